@@ -4,6 +4,7 @@
 use once_cell::sync::Lazy;
 use tracing::warn;
 
+pub mod fs;
 pub mod logging;
 pub mod random;
 pub mod random_util;
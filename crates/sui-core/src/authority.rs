@@ -74,6 +74,7 @@ use sui_types::object::bounded_visitor::BoundedVisitor;
 use sui_types::traffic_control::{
     PolicyConfig, RemoteFirewallConfig, TrafficControlReconfigParams,
 };
+use sui_types::transaction_executor::SimulateOverrides;
 use sui_types::transaction_executor::SimulateTransactionResult;
 use sui_types::transaction_executor::TransactionChecks;
 use tap::TapFallible;
@@ -2200,6 +2201,7 @@ impl AuthorityState {
         &self,
         mut transaction: TransactionData,
         checks: TransactionChecks,
+        overrides: SimulateOverrides,
     ) -> SuiResult<SimulateTransactionResult> {
         if transaction.kind().is_system_tx() {
             return Err(SuiError::UnsupportedFeatureError {
@@ -2217,6 +2219,12 @@ impl AuthorityState {
         // Cheap validity checks for a transaction, including input size limits.
         transaction.validity_check_no_gas_check(epoch_store.protocol_config())?;
 
+        // Injected gas coins replace the gas payment, which then doesn't need to be loaded
+        let gas_coins = Self::simulate_gas_coins(&transaction, &overrides.gas_coins);
+        if !gas_coins.is_empty() {
+            transaction.gas_data_mut().payment.clear();
+        }
+
         let input_object_kinds = transaction.input_objects()?;
         let receiving_object_refs = transaction.receiving_objects();
 
@@ -2237,6 +2245,17 @@ impl AuthorityState {
             epoch_store.epoch(),
         )?;
 
+        if !gas_coins.is_empty() {
+            let mut payment = Vec::with_capacity(gas_coins.len());
+            for gas_object in &gas_coins {
+                payment.push(gas_object.compute_object_reference());
+                input_objects.push(ObjectReadResult::new_from_gas_object(gas_object));
+            }
+            transaction.gas_data_mut().payment = payment;
+        }
+        Self::apply_simulate_overrides(&mut transaction, &mut input_objects, &overrides)?;
+        let simulated = transaction.clone();
+
         // mock a gas object if one was not provided
         let mock_gas_id = if transaction.gas().is_empty() {
             let mock_gas_object = Object::new_move(
@@ -2303,17 +2322,17 @@ impl AuthorityState {
             Some(error) => ExecutionOrEarlyError::Err(error),
             None => ExecutionOrEarlyError::Ok(()),
         };
+        let epoch_data = epoch_store.epoch_start_config().epoch_data();
         let (inner_temp_store, _, effects, execution_result) = executor.dev_inspect_transaction(
             self.get_backing_store().as_ref(),
             protocol_config,
             self.metrics.limits_metrics.clone(),
             false, // expensive_checks
             execution_params,
-            &epoch_store.epoch_start_config().epoch_data().epoch_id(),
-            epoch_store
-                .epoch_start_config()
-                .epoch_data()
-                .epoch_start_timestamp(),
+            &overrides.epoch.unwrap_or(epoch_data.epoch_id()),
+            overrides
+                .epoch_timestamp_ms
+                .unwrap_or(epoch_data.epoch_start_timestamp()),
             checked_input_objects,
             gas_data,
             gas_status,
@@ -2324,6 +2343,7 @@ impl AuthorityState {
         );
 
         Ok(SimulateTransactionResult {
+            transaction: simulated,
             input_objects: inner_temp_store.input_objects,
             output_objects: inner_temp_store.written,
             events: effects.events_digest().map(|_| inner_temp_store.events),
@@ -2333,6 +2353,118 @@ impl AuthorityState {
         })
     }
 
+    /// Create the gas coins injected into a simulation, owned by the gas owner of `transaction`.
+    /// Their IDs are derived from the digest of the transaction as submitted, before its gas
+    /// payment is replaced, so that they are stable across simulations of the same transaction
+    /// and distinct from the objects created by the simulated one.
+    fn simulate_gas_coins(transaction: &TransactionData, balances: &[u64]) -> Vec<Object> {
+        let digest = transaction.digest();
+        let owner = transaction.gas_data().owner;
+        balances
+            .iter()
+            .enumerate()
+            .map(|(creation_num, balance)| {
+                Object::new_move(
+                    MoveObject::new_gas_coin(
+                        OBJECT_START_VERSION,
+                        ObjectID::derive_id(digest, creation_num as u64),
+                        *balance,
+                    ),
+                    Owner::AddressOwner(owner),
+                    TransactionDigest::genesis_marker(),
+                )
+            })
+            .collect()
+    }
+
+    /// Apply the object and clock overrides of a simulation to its loaded input objects, and
+    /// update the references to overridden owned objects in the transaction to match.
+    fn apply_simulate_overrides(
+        transaction: &mut TransactionData,
+        input_objects: &mut InputObjects,
+        overrides: &SimulateOverrides,
+    ) -> SuiResult {
+        let mut replaced_refs = BTreeMap::new();
+        for (object_id, replacement) in &overrides.objects {
+            let Some(input) = input_objects
+                .iter_mut()
+                .find(|input| input.id() == *object_id)
+            else {
+                return Err(SuiError::UnsupportedFeatureError {
+                    error: format!(
+                        "cannot override object {object_id}, which is not an input of the transaction"
+                    ),
+                });
+            };
+            if replacement.id() != *object_id {
+                return Err(SuiError::UnsupportedFeatureError {
+                    error: format!(
+                        "override for object {object_id} has a different id {}",
+                        replacement.id()
+                    ),
+                });
+            }
+
+            // The replacement must be owned in the same way as the object it replaces, because
+            // the input kind (owned or shared) was derived from the transaction and is not
+            // re-checked against the replacement.
+            let same_owner_kind = match input.as_object() {
+                Some(loaded) => {
+                    std::mem::discriminant(&loaded.owner)
+                        == std::mem::discriminant(&replacement.owner)
+                }
+                None => input.is_shared_object() == replacement.owner.is_consensus(),
+            };
+            if !same_owner_kind {
+                return Err(SuiError::UnsupportedFeatureError {
+                    error: format!(
+                        "override for object {object_id} is owned by {}, which is a different kind \
+                         of owner than the input object's",
+                        replacement.owner
+                    ),
+                });
+            }
+
+            if let InputObjectKind::ImmOrOwnedMoveObject(object_ref) = &mut input.input_object_kind
+            {
+                let replacement_ref = replacement.compute_object_reference();
+                replaced_refs.insert(*object_ref, replacement_ref);
+                *object_ref = replacement_ref;
+            }
+            input.object = ObjectReadResultKind::Object(replacement.clone());
+        }
+
+        if let Some(timestamp_ms) = overrides.clock_timestamp_ms {
+            for input in input_objects.iter_mut() {
+                if let ObjectReadResultKind::Object(object) = &mut input.object {
+                    if let Some(clock) = object.data.try_as_move_mut().filter(|o| o.is_clock()) {
+                        clock.set_clock_timestamp_ms_unsafe(timestamp_ms);
+                    }
+                }
+            }
+        }
+
+        let replace = |object_ref: &mut ObjectRef| {
+            if let Some(replacement_ref) = replaced_refs.get(object_ref) {
+                *object_ref = *replacement_ref;
+            }
+        };
+        if let TransactionKind::ProgrammableTransaction(pt) = transaction.kind_mut() {
+            for input in &mut pt.inputs {
+                if let CallArg::Object(ObjectArg::ImmOrOwnedObject(object_ref)) = input {
+                    replace(object_ref);
+                }
+            }
+        }
+        transaction
+            .gas_data_mut()
+            .payment
+            .iter_mut()
+            .for_each(replace);
+
+        Ok(())
+    }
+
     /// The object ID for gas can be any object ID, even for an uncreated object
     #[allow(clippy::collapsible_else_if)]
     #[instrument(skip_all)]
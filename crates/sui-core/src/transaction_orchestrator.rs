@@ -39,7 +39,9 @@ use sui_types::quorum_driver_types::{
 };
 use sui_types::sui_system_state::SuiSystemState;
 use sui_types::transaction::{TransactionData, VerifiedTransaction};
-use sui_types::transaction_executor::{SimulateTransactionResult, TransactionChecks};
+use sui_types::transaction_executor::{
+    SimulateOverrides, SimulateTransactionResult, TransactionChecks,
+};
 use tokio::sync::broadcast::error::RecvError;
 use tokio::sync::broadcast::Receiver;
 use tokio::task::JoinHandle;
@@ -707,8 +709,9 @@ where
         &self,
         transaction: TransactionData,
         checks: TransactionChecks,
+        overrides: SimulateOverrides,
     ) -> Result<SimulateTransactionResult, SuiError> {
         self.validator_state
-            .simulate_transaction(transaction, checks)
+            .simulate_transaction(transaction, checks, overrides)
     }
 }
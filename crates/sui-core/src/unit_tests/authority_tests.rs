@@ -297,6 +297,183 @@ async fn test_dry_run_no_gas_big_transfer() {
     assert_eq!(*dry_run_res.effects.status(), SuiExecutionStatus::Success);
 }
 
+#[tokio::test]
+async fn test_simulate_override_objects() {
+    let (sender, _): (_, AccountKeyPair) = get_key_pair();
+    let recipient = dbg_addr(2);
+    let gas_object_id = ObjectID::random();
+    let coin_id = ObjectID::random();
+    let (_, fullnode, _) = init_state_with_ids_and_object_basics_with_fullnode(vec![
+        (sender, gas_object_id),
+        (sender, coin_id),
+    ])
+    .await;
+
+    let gas_ref = fullnode
+        .get_object(&gas_object_id)
+        .await
+        .unwrap()
+        .compute_object_reference();
+    let coin_ref = fullnode
+        .get_object(&coin_id)
+        .await
+        .unwrap()
+        .compute_object_reference();
+    let mut builder = ProgrammableTransactionBuilder::new();
+    builder.transfer_object(recipient, coin_ref).unwrap();
+    let data = TransactionData::new_programmable(
+        sender,
+        vec![gas_ref],
+        builder.finish(),
+        TEST_ONLY_GAS_UNIT_FOR_TRANSFER * fullnode.reference_gas_price_for_testing().unwrap(),
+        fullnode.reference_gas_price_for_testing().unwrap(),
+    );
+
+    // The replacement is at a different version, so the reference to the coin in the transaction
+    // has to follow it for the input checks to pass
+    let replacement = Object::new_move(
+        MoveObject::new_gas_coin(SequenceNumber::from_u64(7), coin_id, 42),
+        Owner::AddressOwner(sender),
+        TransactionDigest::genesis_marker(),
+    );
+    let overrides = SimulateOverrides {
+        objects: [(coin_id, replacement.clone())].into(),
+        ..Default::default()
+    };
+    let result = fullnode
+        .simulate_transaction(data.clone(), TransactionChecks::Enabled, overrides)
+        .unwrap();
+    assert!(result.effects.status().is_ok());
+    assert_eq!(result.input_objects[&coin_id], replacement);
+    let transferred = &result.output_objects[&coin_id];
+    assert_eq!(transferred.get_coin_value_unsafe(), 42);
+    assert_eq!(transferred.owner, Owner::AddressOwner(recipient));
+
+    // The result holds the transaction that was simulated, which refers to the replacement
+    assert_eq!(
+        *result.effects.transaction_digest(),
+        result.transaction.digest()
+    );
+    assert!(result.transaction.input_objects().unwrap().contains(
+        &InputObjectKind::ImmOrOwnedMoveObject(replacement.compute_object_reference())
+    ));
+
+    // Overrides are never persisted
+    assert_eq!(
+        fullnode
+            .get_object(&coin_id)
+            .await
+            .unwrap()
+            .compute_object_reference(),
+        coin_ref
+    );
+
+    // Only input objects can be overridden
+    let unknown_id = ObjectID::random();
+    let overrides = SimulateOverrides {
+        objects: [(
+            unknown_id,
+            Object::with_id_owner_for_testing(unknown_id, sender),
+        )]
+        .into(),
+        ..Default::default()
+    };
+    let error = fullnode
+        .simulate_transaction(data.clone(), TransactionChecks::Enabled, overrides)
+        .unwrap_err();
+    assert!(matches!(error, SuiError::UnsupportedFeatureError { .. }));
+
+    // A replacement cannot change how the object is owned, e.g. turn an owned input into a
+    // shared object
+    let shared = Object::new_move(
+        MoveObject::new_gas_coin(SequenceNumber::from_u64(7), coin_id, 42),
+        Owner::Shared {
+            initial_shared_version: SequenceNumber::from_u64(7),
+        },
+        TransactionDigest::genesis_marker(),
+    );
+    let overrides = SimulateOverrides {
+        objects: [(coin_id, shared)].into(),
+        ..Default::default()
+    };
+    let error = fullnode
+        .simulate_transaction(data, TransactionChecks::Enabled, overrides)
+        .unwrap_err();
+    let SuiError::UnsupportedFeatureError { error: message } = &error else {
+        panic!("Unexpected error: {error:?}");
+    };
+    assert!(message.contains("different kind of owner"), "{message}");
+}
+
+#[tokio::test]
+async fn test_simulate_override_gas_coins() {
+    let (sender, _): (_, AccountKeyPair) = get_key_pair();
+    let recipient = dbg_addr(2);
+    let gas_object_id = ObjectID::random();
+    let (_, fullnode, _) =
+        init_state_with_ids_and_object_basics_with_fullnode(vec![(sender, gas_object_id)]).await;
+
+    let gas_ref = fullnode
+        .get_object(&gas_object_id)
+        .await
+        .unwrap()
+        .compute_object_reference();
+    let amount = 50_000_000_000u64;
+    let mut builder = ProgrammableTransactionBuilder::new();
+    builder.transfer_sui(recipient, Some(amount));
+    let data = TransactionData::new_programmable(
+        sender,
+        vec![gas_ref],
+        builder.finish(),
+        TEST_ONLY_GAS_UNIT_FOR_TRANSFER * fullnode.reference_gas_price_for_testing().unwrap(),
+        fullnode.reference_gas_price_for_testing().unwrap(),
+    );
+
+    // The injected coins take the place of the gas payment of the transaction
+    let overrides = SimulateOverrides {
+        gas_coins: vec![amount, amount],
+        ..Default::default()
+    };
+    let result = fullnode
+        .simulate_transaction(data.clone(), TransactionChecks::Enabled, overrides)
+        .unwrap();
+    assert!(result.effects.status().is_ok());
+    assert_eq!(result.mock_gas_id, None);
+    assert!(!result.input_objects.contains_key(&gas_object_id));
+
+    // Injected coins are derived from the digest of the transaction as submitted and are merged
+    // into the first one
+    let gas_coin_ids = [
+        ObjectID::derive_id(data.digest(), 0),
+        ObjectID::derive_id(data.digest(), 1),
+    ];
+    assert_eq!(result.effects.gas_object().0 .0, gas_coin_ids[0]);
+    let payment: Vec<_> = result.transaction.gas().iter().map(|r| r.0).collect();
+    assert_eq!(payment, gas_coin_ids);
+    assert!(result
+        .effects
+        .deleted()
+        .iter()
+        .any(|(id, _, _)| *id == gas_coin_ids[1]));
+    let created = result.effects.created();
+    assert_eq!(created.len(), 1);
+    assert!(!gas_coin_ids.contains(&created[0].0 .0));
+    assert_eq!(
+        result.output_objects[&created[0].0 .0].get_coin_value_unsafe(),
+        amount
+    );
+
+    // Simulating the same transaction again injects the same coins
+    let overrides = SimulateOverrides {
+        gas_coins: vec![amount, amount],
+        ..Default::default()
+    };
+    let again = fullnode
+        .simulate_transaction(data, TransactionChecks::Enabled, overrides)
+        .unwrap();
+    assert_eq!(again.effects.gas_object().0 .0, gas_coin_ids[0]);
+}
+
 #[tokio::test]
 async fn test_dev_inspect_object_by_bytes() {
     let (sender, sender_key): (_, AccountKeyPair) = get_key_pair();
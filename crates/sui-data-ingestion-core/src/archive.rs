@@ -0,0 +1,366 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Single file archives of a range of checkpoints, e.g. to use as reproducible indexer test
+//! fixtures.
+//!
+//! An archive is a zstd compressed stream of a header followed by one entry per checkpoint, in
+//! order. Each entry records the digest of the checkpoint summary next to the checkpoint data.
+//! The integrity of checkpoints is checked when they are written and again when they are read:
+//! the summary must match its recorded digest, the contents must match the summary's content
+//! digest, the transactions must match the contents, and each summary must point to the digest of
+//! the previous one. This detects corrupted or incomplete archives, but it does not authenticate
+//! them: the validator signatures on the summaries are not checked against a committee, so an
+//! archive should only be used if it comes from a trusted source.
+//!
+//! The reader ingests directly from an archive when its remote store url is
+//! `archive://<path to the archive>`.
+
+use crate::reader::{CheckpointReader, RemoteStore};
+use crate::ReaderOptions;
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use futures::StreamExt;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, ErrorKind, Read, Write};
+use std::ops::RangeInclusive;
+use std::path::Path;
+use std::sync::Arc;
+use sui_storage::blob::{Blob, BlobEncoding};
+use sui_types::digests::CheckpointDigest;
+use sui_types::effects::TransactionEffectsAPI;
+use sui_types::full_checkpoint_content::CheckpointData;
+use sui_types::message_envelope::Message;
+use sui_types::messages_checkpoint::CheckpointSequenceNumber;
+use tap::pipe::Pipe;
+
+/// Remote store url prefix for reading from a local checkpoint archive.
+pub const ARCHIVE_URL_PREFIX: &str = "archive://";
+
+const MAGIC: [u8; 8] = *b"SUICKPTA";
+const VERSION: u8 = 1;
+const COMPRESSION_LEVEL: i32 = 3;
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArchiveHeader {
+    pub version: u8,
+    pub first_checkpoint: CheckpointSequenceNumber,
+    pub last_checkpoint: CheckpointSequenceNumber,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ArchiveEntry {
+    digest: CheckpointDigest,
+    checkpoint: CheckpointData,
+}
+
+/// Writes the checkpoints of a range to an archive, in order.
+pub struct CheckpointArchiveWriter<W: Write> {
+    encoder: zstd::Encoder<'static, W>,
+    header: ArchiveHeader,
+    next_checkpoint: CheckpointSequenceNumber,
+    previous_digest: Option<CheckpointDigest>,
+}
+
+impl<W: Write> CheckpointArchiveWriter<W> {
+    pub fn new(writer: W, range: RangeInclusive<CheckpointSequenceNumber>) -> Result<Self> {
+        ensure!(!range.is_empty(), "Empty checkpoint range {range:?}");
+        let header = ArchiveHeader {
+            version: VERSION,
+            first_checkpoint: *range.start(),
+            last_checkpoint: *range.end(),
+        };
+        let mut encoder = zstd::Encoder::new(writer, COMPRESSION_LEVEL)?;
+        encoder.write_all(&MAGIC)?;
+        write_frame(&mut encoder, &header)?;
+        Ok(Self {
+            encoder,
+            next_checkpoint: header.first_checkpoint,
+            header,
+            previous_digest: None,
+        })
+    }
+
+    /// Checks the integrity of `checkpoint` and appends it to the archive. Checkpoints must be appended in order.
+    /// Returns the digest of the checkpoint summary.
+    pub fn append(&mut self, checkpoint: &CheckpointData) -> Result<CheckpointDigest> {
+        let sequence_number = checkpoint.checkpoint_summary.sequence_number;
+        ensure!(
+            sequence_number == self.next_checkpoint
+                && sequence_number <= self.header.last_checkpoint,
+            "Expected checkpoint {} but got {sequence_number}",
+            self.next_checkpoint
+        );
+        let digest = check_checkpoint_integrity(checkpoint, self.previous_digest.as_ref())?;
+        // Serialize from a reference, to avoid cloning the checkpoint
+        write_frame(&mut self.encoder, &(digest, checkpoint))?;
+        self.next_checkpoint += 1;
+        self.previous_digest = Some(digest);
+        Ok(digest)
+    }
+
+    /// Completes the archive. Fails if checkpoints of the range are missing.
+    pub fn finish(self) -> Result<W> {
+        ensure!(
+            self.next_checkpoint > self.header.last_checkpoint,
+            "Archive is missing checkpoints {}..={}",
+            self.next_checkpoint,
+            self.header.last_checkpoint
+        );
+        Ok(self.encoder.finish()?)
+    }
+}
+
+/// Iterates over the checkpoints of an archive, checking the integrity of each of them.
+pub struct CheckpointArchiveReader<R: Read> {
+    decoder: zstd::Decoder<'static, BufReader<R>>,
+    header: ArchiveHeader,
+    next_checkpoint: CheckpointSequenceNumber,
+    previous_digest: Option<CheckpointDigest>,
+    failed: bool,
+}
+
+impl CheckpointArchiveReader<File> {
+    pub fn open(path: &Path) -> Result<Self> {
+        let file = File::open(path)
+            .with_context(|| format!("Failed to open checkpoint archive {}", path.display()))?;
+        Self::new(file)
+    }
+
+    /// Opens the archive at `path` to read its checkpoints from `start_checkpoint` on. Fails if
+    /// the archive starts after `start_checkpoint`, or ends before the checkpoint preceding it, as
+    /// checkpoints would be missing in between.
+    pub fn open_at(path: &Path, start_checkpoint: CheckpointSequenceNumber) -> Result<Self> {
+        let reader = Self::open(path)?;
+        let ArchiveHeader {
+            first_checkpoint,
+            last_checkpoint,
+            ..
+        } = reader.header;
+        ensure!(
+            (first_checkpoint..=last_checkpoint.saturating_add(1)).contains(&start_checkpoint),
+            "Cannot read from checkpoint {start_checkpoint}: checkpoint archive {} holds \
+            checkpoints {first_checkpoint}..={last_checkpoint}",
+            path.display()
+        );
+        Ok(reader)
+    }
+}
+
+impl<R: Read> CheckpointArchiveReader<R> {
+    pub fn new(reader: R) -> Result<Self> {
+        let mut decoder = zstd::Decoder::new(reader)?;
+        let mut magic = [0u8; MAGIC.len()];
+        ensure!(
+            decoder.read_exact(&mut magic).is_ok() && magic == MAGIC,
+            "Not a checkpoint archive"
+        );
+        let header: ArchiveHeader = read_frame(&mut decoder)?
+            .ok_or_else(|| anyhow!("Checkpoint archive is missing its header"))?;
+        ensure!(
+            header.version == VERSION,
+            "Unsupported checkpoint archive version {}",
+            header.version
+        );
+        Ok(Self {
+            decoder,
+            next_checkpoint: header.first_checkpoint,
+            header,
+            previous_digest: None,
+            failed: false,
+        })
+    }
+
+    pub fn header(&self) -> &ArchiveHeader {
+        &self.header
+    }
+
+    fn next_checkpoint(&mut self) -> Result<Option<Arc<CheckpointData>>> {
+        let sequence_number = self.next_checkpoint;
+        if sequence_number > self.header.last_checkpoint {
+            return Ok(None);
+        }
+        let ArchiveEntry { digest, checkpoint } = read_frame(&mut self.decoder)?
+            .ok_or_else(|| anyhow!("Checkpoint archive is truncated at {sequence_number}"))?;
+        ensure!(
+            checkpoint.checkpoint_summary.sequence_number == sequence_number,
+            "Expected checkpoint {sequence_number} but got {}",
+            checkpoint.checkpoint_summary.sequence_number
+        );
+        let actual = check_checkpoint_integrity(&checkpoint, self.previous_digest.as_ref())?;
+        ensure!(
+            actual == digest,
+            "Checkpoint {sequence_number} digest mismatch: recorded {digest}, computed {actual}"
+        );
+        self.next_checkpoint += 1;
+        self.previous_digest = Some(digest);
+        Ok(Some(Arc::new(checkpoint)))
+    }
+}
+
+impl<R: Read> Iterator for CheckpointArchiveReader<R> {
+    type Item = Result<Arc<CheckpointData>>;
+
+    /// Stops after the first error, as the checkpoints that follow cannot be checked.
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        let result = self.next_checkpoint().transpose();
+        self.failed = matches!(result, Some(Err(_)));
+        result
+    }
+}
+
+/// Checks that the summary, contents and transactions of `checkpoint` are consistent, and that
+/// it follows the checkpoint with `previous_digest` if known. Returns the digest of the summary.
+///
+/// This is an integrity check only: the signatures on the summary are not verified against the
+/// committee of its epoch.
+pub fn check_checkpoint_integrity(
+    checkpoint: &CheckpointData,
+    previous_digest: Option<&CheckpointDigest>,
+) -> Result<CheckpointDigest> {
+    let summary = checkpoint.checkpoint_summary.data();
+    let sequence_number = summary.sequence_number;
+
+    if let Some(previous_digest) = previous_digest {
+        ensure!(
+            summary.previous_digest.as_ref() == Some(previous_digest),
+            "Checkpoint {sequence_number} does not follow checkpoint {} ({previous_digest})",
+            sequence_number.saturating_sub(1)
+        );
+    }
+
+    let content_digest = *checkpoint.checkpoint_contents.digest();
+    ensure!(
+        content_digest == summary.content_digest,
+        "Checkpoint {sequence_number} contents digest mismatch: summary has {}, contents have {content_digest}",
+        summary.content_digest
+    );
+
+    ensure!(
+        checkpoint.checkpoint_contents.size() == checkpoint.transactions.len(),
+        "Checkpoint {sequence_number} has {} transactions, but its contents have {}",
+        checkpoint.transactions.len(),
+        checkpoint.checkpoint_contents.size()
+    );
+    for (digests, transaction) in checkpoint
+        .checkpoint_contents
+        .iter()
+        .zip(&checkpoint.transactions)
+    {
+        ensure!(
+            *transaction.transaction.digest() == digests.transaction
+                && transaction.effects.transaction_digest() == &digests.transaction
+                && transaction.effects.digest() == digests.effects,
+            "Checkpoint {sequence_number} transaction {} does not match its contents",
+            digests.transaction
+        );
+    }
+
+    Ok(*checkpoint.checkpoint_summary.digest())
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct ArchiveSummary {
+    pub first_checkpoint: CheckpointSequenceNumber,
+    pub last_checkpoint: CheckpointSequenceNumber,
+    pub first_digest: CheckpointDigest,
+    pub last_digest: CheckpointDigest,
+}
+
+/// Fetches the checkpoints in `range` from a remote store (an object store, a full node, or both,
+/// using the same urls as the reader), and writes them to an archive at `output`.
+pub async fn export_archive(
+    remote_store_url: String,
+    remote_store_options: Vec<(String, String)>,
+    range: RangeInclusive<CheckpointSequenceNumber>,
+    concurrency: usize,
+    output: &Path,
+) -> Result<ArchiveSummary> {
+    let store = RemoteStore::new(
+        remote_store_url,
+        remote_store_options,
+        ReaderOptions::default().timeout_secs,
+    )?;
+    let file = File::create(output)
+        .with_context(|| format!("Failed to create checkpoint archive {}", output.display()))?;
+    let mut writer = CheckpointArchiveWriter::new(BufWriter::new(file), range.clone())?;
+
+    let mut checkpoints = range
+        .clone()
+        .map(|sequence_number| CheckpointReader::remote_fetch_checkpoint(&store, sequence_number))
+        .pipe(futures::stream::iter)
+        .buffered(concurrency.max(1));
+
+    let mut first_digest = None;
+    let mut last_digest = None;
+    while let Some(checkpoint) = checkpoints.next().await {
+        let (checkpoint, _) = checkpoint?;
+        let digest = writer.append(&checkpoint)?;
+        first_digest.get_or_insert(digest);
+        last_digest = Some(digest);
+    }
+    writer.finish()?.flush()?;
+
+    Ok(ArchiveSummary {
+        first_checkpoint: *range.start(),
+        last_checkpoint: *range.end(),
+        first_digest: first_digest.expect("range is not empty"),
+        last_digest: last_digest.expect("range is not empty"),
+    })
+}
+
+/// Checks the integrity of the archive at `archive` and writes its checkpoints to `dir`, as
+/// `<sequence number>.chk` files that a reader of a local directory can ingest.
+pub fn unpack_archive(archive: &Path, dir: &Path) -> Result<ArchiveSummary> {
+    std::fs::create_dir_all(dir)?;
+    let reader = CheckpointArchiveReader::open(archive)?;
+    let header = reader.header().clone();
+
+    let mut first_digest = None;
+    let mut last_digest = None;
+    for checkpoint in reader {
+        let checkpoint = checkpoint?;
+        let sequence_number = checkpoint.checkpoint_summary.sequence_number;
+        let bytes = Blob::encode(&checkpoint, BlobEncoding::Bcs)?.to_bytes();
+        std::fs::write(dir.join(format!("{sequence_number}.chk")), bytes)?;
+        let digest = *checkpoint.checkpoint_summary.digest();
+        first_digest.get_or_insert(digest);
+        last_digest = Some(digest);
+    }
+
+    let (Some(first_digest), Some(last_digest)) = (first_digest, last_digest) else {
+        bail!("Checkpoint archive is empty");
+    };
+    Ok(ArchiveSummary {
+        first_checkpoint: header.first_checkpoint,
+        last_checkpoint: header.last_checkpoint,
+        first_digest,
+        last_digest,
+    })
+}
+
+// Frames are BCS values prefixed with their length, as a little endian u32.
+fn write_frame<T: Serialize>(writer: &mut impl Write, value: &T) -> Result<()> {
+    let bytes = bcs::to_bytes(value)?;
+    let len = u32::try_from(bytes.len()).context("Checkpoint archive frame is too large")?;
+    writer.write_all(&len.to_le_bytes())?;
+    writer.write_all(&bytes)?;
+    Ok(())
+}
+
+// Returns `None` at the end of the stream.
+fn read_frame<T: DeserializeOwned>(reader: &mut impl Read) -> Result<Option<T>> {
+    let mut len = [0u8; 4];
+    match reader.read_exact(&mut len) {
+        Ok(()) => {}
+        Err(err) if err.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+        Err(err) => return Err(err.into()),
+    }
+    let mut bytes = vec![0u8; u32::from_le_bytes(len) as usize];
+    reader.read_exact(&mut bytes)?;
+    Ok(Some(bcs::from_bytes(&bytes)?))
+}
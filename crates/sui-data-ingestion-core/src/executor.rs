@@ -1,6 +1,7 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::archive::{CheckpointArchiveReader, ARCHIVE_URL_PREFIX};
 use crate::progress_store::{
     ExecutorProgress, ProgressStore, ProgressStoreWrapper, ShimProgressStore,
 };
@@ -8,12 +9,12 @@ use crate::reader::CheckpointReader;
 use crate::worker_pool::WorkerPool;
 use crate::Worker;
 use crate::{DataIngestionMetrics, ReaderOptions};
-use anyhow::Result;
+use anyhow::{bail, Result};
 use futures::Future;
 use mysten_metrics::spawn_monitored_task;
 use once_cell::sync::Lazy;
 use prometheus::Registry;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::pin::Pin;
 use std::sync::Arc;
 use sui_types::full_checkpoint_content::CheckpointData;
@@ -102,6 +103,13 @@ impl<P: ProgressStore> IndexerExecutor<P> {
     ) -> Result<ExecutorProgress> {
         let mut reader_checkpoint_number = self.progress_store.min_watermark()?;
         let upper_limit = reader_options.upper_limit;
+        if let Some(archive_path) = remote_store_url
+            .as_deref()
+            .and_then(|url| url.strip_prefix(ARCHIVE_URL_PREFIX))
+        {
+            // Fail before starting if the archive can't be read from the watermark
+            CheckpointArchiveReader::open_at(Path::new(archive_path), reader_checkpoint_number)?;
+        }
         let (checkpoint_reader, mut checkpoint_recv, gc_sender, _exit_sender) =
             CheckpointReader::initialize(
                 path,
@@ -110,7 +118,7 @@ impl<P: ProgressStore> IndexerExecutor<P> {
                 remote_store_options,
                 reader_options,
             );
-        spawn_monitored_task!(checkpoint_reader.run());
+        let mut reader_handle = spawn_monitored_task!(checkpoint_reader.run());
 
         for pool in std::mem::take(&mut self.pools) {
             spawn_monitored_task!(pool);
@@ -118,6 +126,10 @@ impl<P: ProgressStore> IndexerExecutor<P> {
         loop {
             tokio::select! {
                 _ = &mut exit_receiver => break,
+                result = &mut reader_handle => {
+                    result??;
+                    bail!("checkpoint reader stopped");
+                }
                 Some((task_name, sequence_number)) = self.pool_progress_receiver.recv() => {
                     self.progress_store.save(task_name.clone(), sequence_number).await?;
                     let seq_number = self.progress_store.min_watermark()?;
@@ -1,6 +1,7 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+mod archive;
 mod executor;
 mod metrics;
 mod progress_store;
@@ -14,6 +15,10 @@ mod worker_pool;
 use std::sync::Arc;
 
 use anyhow::Result;
+pub use archive::{
+    check_checkpoint_integrity, export_archive, unpack_archive, ArchiveHeader, ArchiveSummary,
+    CheckpointArchiveReader, CheckpointArchiveWriter, ARCHIVE_URL_PREFIX,
+};
 use async_trait::async_trait;
 pub use executor::{
     setup_single_workflow, setup_single_workflow_with_options, IndexerExecutor,
@@ -1,9 +1,10 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::archive::{CheckpointArchiveReader, ARCHIVE_URL_PREFIX};
 use crate::create_remote_store_client;
 use crate::executor::MAX_CHECKPOINTS_IN_PROGRESS;
-use anyhow::Result;
+use anyhow::{Context, Result};
 use backoff::backoff::Backoff;
 use futures::StreamExt;
 use mysten_metrics::spawn_monitored_task;
@@ -69,12 +70,34 @@ impl Default for ReaderOptions {
     }
 }
 
-enum RemoteStore {
+pub(crate) enum RemoteStore {
     ObjectStore(Box<dyn ObjectStore>),
     Rest(sui_rpc_api::Client),
     Hybrid(Box<dyn ObjectStore>, sui_rpc_api::Client),
 }
 
+impl RemoteStore {
+    pub(crate) fn new(
+        url: String,
+        remote_store_options: Vec<(String, String)>,
+        timeout_secs: u64,
+    ) -> Result<Self> {
+        Ok(if let Some((fn_url, remote_url)) = url.split_once('|') {
+            let object_store = create_remote_store_client(
+                remote_url.to_string(),
+                remote_store_options,
+                timeout_secs,
+            )?;
+            RemoteStore::Hybrid(object_store, sui_rpc_api::Client::new(fn_url)?)
+        } else if url.ends_with("/rest") {
+            RemoteStore::Rest(sui_rpc_api::Client::new(url)?)
+        } else {
+            let object_store = create_remote_store_client(url, remote_store_options, timeout_secs)?;
+            RemoteStore::ObjectStore(object_store)
+        })
+    }
+}
+
 impl CheckpointReader {
     /// Represents a single iteration of the reader.
     /// Reads files in a local directory, validates them, and forwards `CheckpointData` to the executor.
@@ -143,7 +166,7 @@ impl CheckpointReader {
         }
     }
 
-    async fn remote_fetch_checkpoint(
+    pub(crate) async fn remote_fetch_checkpoint(
         store: &RemoteStore,
         checkpoint_number: CheckpointSequenceNumber,
     ) -> Result<(Arc<CheckpointData>, usize)> {
@@ -190,25 +213,16 @@ impl CheckpointReader {
             .remote_store_url
             .clone()
             .expect("remote store url must be set");
-        let store = if let Some((fn_url, remote_url)) = url.split_once('|') {
-            let object_store = create_remote_store_client(
-                remote_url.to_string(),
-                self.remote_store_options.clone(),
-                self.options.timeout_secs,
-            )
-            .expect("failed to create remote store client");
-            RemoteStore::Hybrid(object_store, sui_rpc_api::Client::new(fn_url).unwrap())
-        } else if url.ends_with("/rest") {
-            RemoteStore::Rest(sui_rpc_api::Client::new(url).unwrap())
-        } else {
-            let object_store = create_remote_store_client(
-                url,
-                self.remote_store_options.clone(),
-                self.options.timeout_secs,
-            )
-            .expect("failed to create remote store client");
-            RemoteStore::ObjectStore(object_store)
-        };
+        if let Some(archive_path) = url.strip_prefix(ARCHIVE_URL_PREFIX) {
+            Self::start_archive_fetcher(PathBuf::from(archive_path), start_checkpoint, sender);
+            return receiver;
+        }
+        let store = RemoteStore::new(
+            url,
+            self.remote_store_options.clone(),
+            self.options.timeout_secs,
+        )
+        .expect("failed to create remote store client");
 
         spawn_monitored_task!(async move {
             let mut checkpoint_stream = (start_checkpoint..u64::MAX)
@@ -226,7 +240,55 @@ impl CheckpointReader {
         receiver
     }
 
-    fn remote_fetch(&mut self) -> Vec<Arc<CheckpointData>> {
+    /// Streams the checkpoints of a local archive, starting at `start_checkpoint`.
+    fn start_archive_fetcher(
+        path: PathBuf,
+        start_checkpoint: CheckpointSequenceNumber,
+        sender: mpsc::Sender<Result<(Arc<CheckpointData>, usize)>>,
+    ) {
+        spawn_monitored_task!(async move {
+            let sender = tokio::task::spawn_blocking(move || {
+                let reader = match CheckpointArchiveReader::open_at(&path, start_checkpoint) {
+                    Ok(reader) => reader,
+                    Err(err) => {
+                        let _ = sender.blocking_send(Err(err));
+                        return sender;
+                    }
+                };
+                for checkpoint in reader {
+                    let checkpoint = checkpoint.and_then(|checkpoint| {
+                        let size = bcs::serialized_size(&*checkpoint)?;
+                        Ok((checkpoint, size))
+                    });
+                    if let Ok((checkpoint, _)) = &checkpoint {
+                        if checkpoint.checkpoint_summary.sequence_number < start_checkpoint {
+                            continue;
+                        }
+                    }
+                    let failed = checkpoint.is_err();
+                    if sender.blocking_send(checkpoint).is_err() {
+                        info!("archive reader dropped");
+                        break;
+                    }
+                    if failed {
+                        break;
+                    }
+                }
+                sender
+            })
+            .await
+            .expect("archive reader panicked");
+            // Once the archive is exhausted, keep the channel open so that the reader waits for
+            // new checkpoints instead of restarting the fetcher.
+            sender.closed().await;
+        });
+    }
+
+    /// Takes the checkpoints fetched so far from the remote store. Errors of the remote store are
+    /// transient and restart the fetcher, except those of an archive: it is read from the start
+    /// again when the fetcher restarts, so an archive that fails its integrity checks would fail
+    /// them forever.
+    fn remote_fetch(&mut self) -> Result<Vec<Arc<CheckpointData>>> {
         let mut checkpoints = vec![];
         if self.remote_fetcher_receiver.is_none() {
             self.remote_fetcher_receiver = Some(self.start_remote_fetcher());
@@ -237,6 +299,9 @@ impl CheckpointReader {
                     self.data_limiter.add(&checkpoint, size);
                     checkpoints.push(checkpoint);
                 }
+                Ok(Err(err)) if self.reads_archive() => {
+                    return Err(err.context("Failed to read checkpoint archive"));
+                }
                 Ok(Err(err)) => {
                     error!("remote reader transient error {:?}", err);
                     self.remote_fetcher_receiver = None;
@@ -250,7 +315,13 @@ impl CheckpointReader {
                 Err(TryRecvError::Empty) => break,
             }
         }
-        checkpoints
+        Ok(checkpoints)
+    }
+
+    fn reads_archive(&self) -> bool {
+        self.remote_store_url
+            .as_deref()
+            .is_some_and(|url| url.starts_with(ARCHIVE_URL_PREFIX))
     }
 
     async fn sync(&mut self) -> Result<()> {
@@ -269,7 +340,7 @@ impl CheckpointReader {
                 || checkpoints[0].checkpoint_summary.sequence_number
                     > self.current_checkpoint_number)
         {
-            checkpoints = self.remote_fetch();
+            checkpoints = self.remote_fetch()?;
             read_source = "remote";
         } else {
             // cancel remote fetcher execution because local reader has made progress
@@ -395,7 +466,7 @@ impl CheckpointReader {
                     self.gc_processed_files(gc_checkpoint_number).expect("Failed to clean the directory");
                 }
                 Ok(Some(_)) | Err(_) = timeout(Duration::from_millis(self.options.tick_internal_ms), inotify_recv.recv())  => {
-                    self.sync().await.expect("Failed to read checkpoint files");
+                    self.sync().await.context("Failed to read checkpoints")?;
                 }
             }
         }
@@ -2,7 +2,10 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::progress_store::ExecutorProgress;
-use crate::{DataIngestionMetrics, FileProgressStore, IndexerExecutor, WorkerPool};
+use crate::{
+    unpack_archive, CheckpointArchiveReader, CheckpointArchiveWriter, DataIngestionMetrics,
+    FileProgressStore, IndexerExecutor, WorkerPool, ARCHIVE_URL_PREFIX,
+};
 use crate::{ReaderOptions, Worker};
 use anyhow::Result;
 use async_trait::async_trait;
@@ -13,7 +16,9 @@ use std::path::PathBuf;
 use std::time::Duration;
 use sui_protocol_config::ProtocolConfig;
 use sui_storage::blob::{Blob, BlobEncoding};
+use sui_types::base_types::ExecutionDigests;
 use sui_types::crypto::KeypairTraits;
+use sui_types::digests::CheckpointDigest;
 use sui_types::full_checkpoint_content::CheckpointData;
 use sui_types::gas::GasCostSummary;
 use sui_types::messages_checkpoint::{
@@ -134,6 +139,15 @@ const RNG_SEED: [u8; 32] = [
 ];
 
 fn mock_checkpoint_data_bytes(seq_number: CheckpointSequenceNumber) -> Vec<u8> {
+    Blob::encode(&mock_checkpoint_data(seq_number, None), BlobEncoding::Bcs)
+        .unwrap()
+        .to_bytes()
+}
+
+fn mock_checkpoint_data(
+    seq_number: CheckpointSequenceNumber,
+    previous_digest: Option<CheckpointDigest>,
+) -> CheckpointData {
     let mut rng = StdRng::from_seed(RNG_SEED);
     let (keys, committee) = make_committee_key(&mut rng);
     let contents = CheckpointContents::new_with_digests_only_for_tests(vec![]);
@@ -143,7 +157,7 @@ fn mock_checkpoint_data_bytes(seq_number: CheckpointSequenceNumber) -> Vec<u8> {
         seq_number,
         0,
         &contents,
-        None,
+        previous_digest,
         GasCostSummary::default(),
         None,
         0,
@@ -158,13 +172,184 @@ fn mock_checkpoint_data_bytes(seq_number: CheckpointSequenceNumber) -> Vec<u8> {
         })
         .collect();
 
-    let checkpoint_data = CheckpointData {
+    CheckpointData {
         checkpoint_summary: CertifiedCheckpointSummary::new(summary, sign_infos, &committee)
             .unwrap(),
         checkpoint_contents: contents,
         transactions: vec![],
+    }
+}
+
+// Checkpoints `range` of a chain starting at genesis
+fn mock_checkpoint_chain(range: std::ops::Range<CheckpointSequenceNumber>) -> Vec<CheckpointData> {
+    let mut previous_digest = None;
+    (0..range.end)
+        .map(|seq_number| {
+            let checkpoint = mock_checkpoint_data(seq_number, previous_digest);
+            previous_digest = Some(*checkpoint.checkpoint_summary.digest());
+            checkpoint
+        })
+        .skip(range.start as usize)
+        .collect()
+}
+
+fn write_archive(path: &std::path::Path, range: std::ops::Range<CheckpointSequenceNumber>) {
+    let file = std::fs::File::create(path).unwrap();
+    let mut writer = CheckpointArchiveWriter::new(file, range.start..=range.end - 1).unwrap();
+    for checkpoint in mock_checkpoint_chain(range) {
+        writer.append(&checkpoint).unwrap();
+    }
+    writer.finish().unwrap();
+}
+
+#[test]
+fn archive_roundtrip() {
+    let dir = temp_dir();
+    let path = dir.join("checkpoints.chkar");
+    write_archive(&path, 5..15);
+
+    let reader = CheckpointArchiveReader::open(&path).unwrap();
+    assert_eq!(reader.header().first_checkpoint, 5);
+    assert_eq!(reader.header().last_checkpoint, 14);
+    let checkpoints: Vec<_> = reader.collect::<Result<_>>().unwrap();
+    let expected = mock_checkpoint_chain(5..15);
+    assert_eq!(checkpoints.len(), expected.len());
+    for (checkpoint, expected) in checkpoints.iter().zip(&expected) {
+        assert_eq!(
+            checkpoint.checkpoint_summary.digest(),
+            expected.checkpoint_summary.digest()
+        );
+    }
+
+    let summary = unpack_archive(&path, &dir.join("unpacked")).unwrap();
+    assert_eq!(summary.first_checkpoint, 5);
+    assert_eq!(summary.last_checkpoint, 14);
+    assert_eq!(
+        summary.last_digest,
+        *expected.last().unwrap().checkpoint_summary.digest()
+    );
+    for seq_number in 5..15 {
+        let bytes = std::fs::read(dir.join("unpacked").join(format!("{seq_number}.chk"))).unwrap();
+        let checkpoint: CheckpointData = Blob::from_bytes(&bytes).unwrap();
+        assert_eq!(checkpoint.checkpoint_summary.sequence_number, seq_number);
+    }
+}
+
+#[test]
+fn archive_rejects_broken_chain() {
+    let mut writer = CheckpointArchiveWriter::new(vec![], 0..=2).unwrap();
+    writer.append(&mock_checkpoint_data(0, None)).unwrap();
+    // Not linked to checkpoint 0
+    let err = writer.append(&mock_checkpoint_data(1, None)).unwrap_err();
+    assert!(err.to_string().contains("does not follow"));
+    // Out of order
+    let err = writer.append(&mock_checkpoint_chain(2..3)[0]).unwrap_err();
+    assert!(err.to_string().contains("Expected checkpoint 1"));
+    // Incomplete
+    assert!(writer.finish().is_err());
+}
+
+#[test]
+fn archive_rejects_corrupted_data() {
+    let dir = temp_dir();
+    let path = dir.join("checkpoints.chkar");
+    write_archive(&path, 0..3);
+
+    // Truncated archive
+    let bytes = std::fs::read(&path).unwrap();
+    let truncated = CheckpointArchiveReader::new(&bytes[..bytes.len() / 2])
+        .and_then(|reader| reader.collect::<Result<Vec<_>>>());
+    assert!(truncated.is_err());
+
+    // Checkpoint with contents that do not match its summary
+    let mut checkpoints = mock_checkpoint_chain(0..2);
+    checkpoints[1].checkpoint_contents =
+        CheckpointContents::new_with_digests_only_for_tests(vec![ExecutionDigests::random()]);
+    let mut writer = CheckpointArchiveWriter::new(vec![], 0..=1).unwrap();
+    writer.append(&checkpoints[0]).unwrap();
+    let err = writer.append(&checkpoints[1]).unwrap_err();
+    assert!(err.to_string().contains("contents digest mismatch"));
+}
+
+// Runs an executor reading from the archive at `archive_path`, with a worker that has processed
+// the checkpoints before `start_checkpoint`.
+async fn run_from_archive(
+    archive_path: &std::path::Path,
+    start_checkpoint: CheckpointSequenceNumber,
+) -> Result<ExecutorProgress> {
+    let mut bundle = create_executor_bundle();
+    std::fs::write(
+        bundle._progress_file.path(),
+        format!(r#"{{"test": {start_checkpoint}}}"#),
+    )
+    .unwrap();
+    add_worker_pool(&mut bundle.executor, TestWorker, 5)
+        .await
+        .unwrap();
+    let options = ReaderOptions {
+        tick_internal_ms: 10,
+        batch_size: 1,
+        ..Default::default()
     };
-    Blob::encode(&checkpoint_data, BlobEncoding::Bcs)
-        .unwrap()
-        .to_bytes()
+    let (sender, recv) = oneshot::channel();
+    let remote_store_url = format!("{ARCHIVE_URL_PREFIX}{}", archive_path.display());
+    let handle = tokio::task::spawn(async move {
+        bundle
+            .executor
+            .run(temp_dir(), Some(remote_store_url), vec![], options, recv)
+            .await
+    });
+    tokio::time::sleep(Duration::from_secs(1)).await;
+    drop(sender);
+    handle.await.unwrap()
+}
+
+#[tokio::test]
+async fn read_from_archive() {
+    let archive_path = temp_dir().join("checkpoints.chkar");
+    write_archive(&archive_path, 0..20);
+
+    let result = run_from_archive(&archive_path, 0).await;
+    assert_eq!(result.unwrap().get("test"), Some(&20));
+}
+
+#[tokio::test]
+async fn read_from_archive_range() {
+    let archive_path = temp_dir().join("checkpoints.chkar");
+    write_archive(&archive_path, 5..15);
+
+    let result = run_from_archive(&archive_path, 8).await;
+    assert_eq!(result.unwrap().get("test"), Some(&15));
+
+    // Nothing left to read, the reader waits for checkpoints from other sources
+    let result = run_from_archive(&archive_path, 15).await;
+    assert_eq!(result.unwrap().get("test"), Some(&15));
+
+    // Checkpoints before the archive or between its end and the watermark are missing
+    for start_checkpoint in [0, 4, 16] {
+        let err = run_from_archive(&archive_path, start_checkpoint)
+            .await
+            .unwrap_err();
+        assert!(
+            err.to_string().contains("holds checkpoints 5..=14"),
+            "{err}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn read_from_corrupted_archive() {
+    let archive_path = temp_dir().join("checkpoints.chkar");
+    write_archive(&archive_path, 0..20);
+    // Cut the archive in the middle of its checkpoints, keeping its header
+    let bytes = zstd::decode_all(std::fs::File::open(&archive_path).unwrap()).unwrap();
+    let truncated = zstd::encode_all(&bytes[..bytes.len() / 2], 3).unwrap();
+    std::fs::write(&archive_path, truncated).unwrap();
+
+    // The reader stops instead of reading the archive again and again
+    let err = run_from_archive(&archive_path, 0).await.unwrap_err();
+    assert!(
+        format!("{err:#}").contains("Failed to read checkpoint archive"),
+        "{err:#}"
+    );
 }
@@ -182,6 +182,16 @@ async fn batch_get_objects() {
                     version: None,
                     read_mask: None,
                 },
+                GetObjectRequest {
+                    object_id: Some("not an object id".to_owned()),
+                    version: None,
+                    read_mask: None,
+                },
+                GetObjectRequest {
+                    object_id: Some("0x5".to_owned()),
+                    version: Some(u64::MAX),
+                    read_mask: None,
+                },
             ],
             read_mask: None,
         })
@@ -201,4 +211,23 @@ async fn batch_get_objects() {
         objects[2].object().unwrap().object_id,
         Some("0x3".parse::<ObjectId>().unwrap().to_string())
     );
+    // Invalid and missing objects are reported per request
+    assert!(objects[3].object().is_none());
+    assert!(objects[4].object().is_none());
+
+    let too_many = (0..=1000)
+        .map(|_| GetObjectRequest {
+            object_id: Some("0x1".to_owned()),
+            version: None,
+            read_mask: None,
+        })
+        .collect();
+    let status = client
+        .batch_get_objects(BatchGetObjectsRequest {
+            requests: too_many,
+            read_mask: None,
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), tonic::Code::InvalidArgument);
 }
@@ -0,0 +1,137 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use sui_macros::sim_test;
+use sui_rpc::proto::sui::rpc::v2beta2::live_data_service_client::LiveDataServiceClient;
+use sui_rpc::proto::sui::rpc::v2beta2::Argument;
+use sui_rpc::proto::sui::rpc::v2beta2::Command;
+use sui_rpc::proto::sui::rpc::v2beta2::Input;
+use sui_rpc::proto::sui::rpc::v2beta2::ProgrammableTransaction;
+use sui_rpc::proto::sui::rpc::v2beta2::SimulateTransactionRequest;
+use sui_rpc::proto::sui::rpc::v2beta2::Transaction;
+use sui_rpc::proto::sui::rpc::v2beta2::TransactionKind;
+use sui_rpc::proto::sui::rpc::v2beta2::TransferObjects;
+use sui_rpc_api::Client;
+use sui_rpc_api::SIMULATE_GAS_COIN_METADATA;
+use sui_rpc_api::SIMULATE_OBJECT_METADATA;
+use sui_types::base_types::SequenceNumber;
+use sui_types::base_types::SuiAddress;
+use sui_types::digests::TransactionDigest;
+use sui_types::effects::TransactionEffectsAPI;
+use sui_types::object::MoveObject;
+use sui_types::object::Object;
+use test_cluster::TestClusterBuilder;
+use tonic::metadata::MetadataValue;
+
+fn transfer_object(sender: SuiAddress, object_id: &str, recipient: SuiAddress) -> Transaction {
+    Transaction {
+        kind: Some(TransactionKind::from(ProgrammableTransaction {
+            inputs: vec![
+                Input {
+                    object_id: Some(object_id.to_owned()),
+                    ..Default::default()
+                },
+                Input {
+                    literal: Some(Box::new(recipient.to_string().into())),
+                    ..Default::default()
+                },
+            ],
+            commands: vec![Command::from(TransferObjects {
+                objects: vec![Argument::new_input(0)],
+                address: Some(Argument::new_input(1)),
+            })],
+        })),
+        sender: Some(sender.to_string()),
+        ..Default::default()
+    }
+}
+
+#[sim_test]
+async fn simulate_transaction_with_overrides() {
+    let test_cluster = TestClusterBuilder::new().build().await;
+
+    let client = Client::new(test_cluster.rpc_url()).unwrap();
+    let mut alpha_client = LiveDataServiceClient::connect(test_cluster.rpc_url().to_owned())
+        .await
+        .unwrap();
+    let recipient = SuiAddress::random_for_testing_only();
+
+    let (sender, mut gas) = test_cluster.wallet.get_one_account().await.unwrap();
+    gas.sort_by_key(|object_ref| object_ref.0);
+    let obj_to_send = gas.first().unwrap().0;
+
+    // Send a coin holding 7 MIST at a later version in place of the real one, paying for gas with
+    // an injected coin
+    let object = client.get_object(obj_to_send).await.unwrap();
+    let replacement = Object::new_move(
+        MoveObject::new_gas_coin(
+            SequenceNumber::from_u64(object.version().value() + 5),
+            obj_to_send,
+            7,
+        ),
+        object.owner().clone(),
+        TransactionDigest::genesis_marker(),
+    );
+    let mut request = tonic::Request::new(SimulateTransactionRequest {
+        transaction: Some(transfer_object(
+            sender,
+            &obj_to_send.to_canonical_string(true),
+            recipient,
+        )),
+        do_gas_selection: Some(true),
+        ..Default::default()
+    });
+    request.metadata_mut().append_bin(
+        SIMULATE_OBJECT_METADATA,
+        MetadataValue::from_bytes(&bcs::to_bytes(&replacement).unwrap()),
+    );
+    request
+        .metadata_mut()
+        .append(SIMULATE_GAS_COIN_METADATA, "1000000000".parse().unwrap());
+
+    let executed_transaction = alpha_client
+        .simulate_transaction(request)
+        .await
+        .unwrap()
+        .into_inner()
+        .transaction
+        .unwrap();
+    let effects: sui_types::effects::TransactionEffects = executed_transaction
+        .effects
+        .unwrap()
+        .bcs
+        .unwrap()
+        .deserialize()
+        .unwrap();
+
+    assert!(effects.status().is_ok());
+    let gas_object_id = effects.gas_object().0 .0;
+    assert!(gas.iter().all(|object_ref| object_ref.0 != gas_object_id));
+    assert!(executed_transaction.balance_changes.iter().any(|change| {
+        change.address.as_deref() == Some(&recipient.to_string())
+            && change.amount.as_deref() == Some("7")
+    }));
+
+    // Nothing was persisted
+    assert_eq!(
+        client.get_object(obj_to_send).await.unwrap().version(),
+        object.version()
+    );
+
+    let mut request = tonic::Request::new(SimulateTransactionRequest {
+        transaction: Some(transfer_object(
+            sender,
+            &obj_to_send.to_canonical_string(true),
+            recipient,
+        )),
+        ..Default::default()
+    });
+    request
+        .metadata_mut()
+        .append(SIMULATE_GAS_COIN_METADATA, "-1".parse().unwrap());
+    let status = alpha_client
+        .simulate_transaction(request)
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), tonic::Code::InvalidArgument);
+}
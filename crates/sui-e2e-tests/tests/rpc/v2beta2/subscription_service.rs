@@ -2,11 +2,14 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::transfer_coin;
+use std::time::Duration;
 use sui_macros::sim_test;
 use sui_rpc::field::FieldMask;
 use sui_rpc::field::FieldMaskUtil;
 use sui_rpc::proto::sui::rpc::v2beta2::subscription_service_client::SubscriptionServiceClient;
 use sui_rpc::proto::sui::rpc::v2beta2::SubscribeCheckpointsRequest;
+use sui_rpc_api::subscription::START_CHECKPOINT_METADATA;
+use sui_rpc_api::subscription::TRANSACTION_FILTER_METADATA;
 use test_cluster::TestClusterBuilder;
 use tokio_stream::StreamExt;
 
@@ -55,3 +58,108 @@ async fn subscribe_checkpoint() {
 
     assert!(count >= 50);
 }
+
+#[sim_test]
+async fn subscribe_checkpoint_with_transaction_filter() {
+    let test_cluster = TestClusterBuilder::new().build().await;
+
+    let sender = test_cluster
+        .wallet
+        .get_all_accounts_and_gas_objects()
+        .await
+        .unwrap()[0]
+        .0;
+
+    let mut client = SubscriptionServiceClient::connect(test_cluster.rpc_url().to_owned())
+        .await
+        .unwrap();
+
+    let mut request = tonic::Request::new(SubscribeCheckpointsRequest {
+        read_mask: Some(FieldMask::from_paths([
+            "sequence_number",
+            "transactions.digest",
+        ])),
+    });
+    request.metadata_mut().insert(
+        TRANSACTION_FILTER_METADATA,
+        format!("sender={sender}").parse().unwrap(),
+    );
+
+    let mut stream = client
+        .subscribe_checkpoints(request)
+        .await
+        .unwrap()
+        .into_inner();
+
+    let transaction_digest = transfer_coin(&test_cluster.wallet).await.to_string();
+
+    // Every checkpoint is still delivered, but only carries the transactions matching the
+    // filter, so system transactions never show up.
+    let found = tokio::time::timeout(Duration::from_secs(60), async {
+        while let Some(item) = stream.next().await {
+            let checkpoint = item.unwrap().checkpoint.unwrap();
+            let digests = checkpoint
+                .transactions
+                .into_iter()
+                .map(|transaction| transaction.digest.unwrap())
+                .collect::<Vec<_>>();
+            if !digests.is_empty() {
+                assert_eq!(digests, [transaction_digest.clone()]);
+                return true;
+            }
+        }
+        false
+    })
+    .await
+    .unwrap();
+    assert!(found);
+
+    let mut request = tonic::Request::new(SubscribeCheckpointsRequest {
+        read_mask: Some(FieldMask::from_str("sequence_number")),
+    });
+    request.metadata_mut().insert(
+        TRANSACTION_FILTER_METADATA,
+        "recipient=0x1".parse().unwrap(),
+    );
+    let status = client.subscribe_checkpoints(request).await.unwrap_err();
+    assert_eq!(status.code(), tonic::Code::InvalidArgument);
+}
+
+#[sim_test]
+async fn subscribe_checkpoint_from_start() {
+    let test_cluster = TestClusterBuilder::new().build().await;
+
+    let _transaction_digest = transfer_coin(&test_cluster.wallet).await;
+
+    let mut client = SubscriptionServiceClient::connect(test_cluster.rpc_url().to_owned())
+        .await
+        .unwrap();
+
+    let mut request = tonic::Request::new(SubscribeCheckpointsRequest {
+        read_mask: Some(FieldMask::from_str("sequence_number")),
+    });
+    request
+        .metadata_mut()
+        .insert(START_CHECKPOINT_METADATA, "0".parse().unwrap());
+
+    let mut stream = client
+        .subscribe_checkpoints(request)
+        .await
+        .unwrap()
+        .into_inner();
+
+    // Checkpoints already executed are backfilled before the live ones, without any gap
+    for expected in 0..50 {
+        let cursor = stream.next().await.unwrap().unwrap().cursor.unwrap();
+        assert_eq!(cursor, expected);
+    }
+
+    let mut request = tonic::Request::new(SubscribeCheckpointsRequest {
+        read_mask: Some(FieldMask::from_str("sequence_number")),
+    });
+    request
+        .metadata_mut()
+        .insert(START_CHECKPOINT_METADATA, "latest".parse().unwrap());
+    let status = client.subscribe_checkpoints(request).await.unwrap_err();
+    assert_eq!(status.code(), tonic::Code::InvalidArgument);
+}
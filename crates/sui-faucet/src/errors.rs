@@ -14,6 +14,15 @@ pub enum FaucetError {
 
     #[error("Internal error: {0}")]
     Internal(String),
+
+    #[error("Invalid number of coins requested: {requested}, must be between 1 and {max}")]
+    InvalidNumCoins { requested: usize, max: usize },
+
+    #[error("Invalid amount requested: {requested} MIST per coin, must be between 1 and {max}")]
+    InvalidAmount { requested: u64, max: u64 },
+
+    #[error("Too many requests, retry after {retry_after_secs} seconds")]
+    TooManyRequests { retry_after_secs: u64 },
 }
 
 impl FaucetError {
@@ -1,11 +1,14 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::RateLimit;
 use clap::Parser;
 use std::net::Ipv4Addr;
 
 pub const DEFAULT_AMOUNT: u64 = 200_000_000_000;
 pub const DEFAULT_NUM_COINS: usize = 5;
+pub const DEFAULT_MAX_AMOUNT: u64 = 1_000_000_000_000;
+pub const DEFAULT_MAX_NUM_COINS: usize = 20;
 
 #[derive(Parser, Clone)]
 #[clap(
@@ -27,6 +30,27 @@ pub struct FaucetConfig {
     #[clap(long, default_value_t = DEFAULT_NUM_COINS)]
     pub num_coins: usize,
 
+    /// Maximum amount of MIST per coin that a multi-coin request can ask for. It does not apply
+    /// to `--amount`.
+    #[clap(long, default_value_t = DEFAULT_MAX_AMOUNT)]
+    pub max_amount: u64,
+
+    /// Maximum number of coins that a multi-coin request can ask for. It does not apply to
+    /// `--num-coins`.
+    #[clap(long, default_value_t = DEFAULT_MAX_NUM_COINS)]
+    pub max_num_coins: usize,
+
+    /// Limit on the requests for the same recipient, as `<max requests>/<window in seconds>`.
+    /// Can be repeated to limit several windows, e.g. `--recipient-rate-limit 5/60
+    /// --recipient-rate-limit 100/86400`.
+    #[clap(long = "recipient-rate-limit")]
+    pub recipient_rate_limits: Vec<RateLimit>,
+
+    /// Limit on the requests from the same IP address, as `<max requests>/<window in seconds>`.
+    /// Can be repeated to limit several windows.
+    #[clap(long = "ip-rate-limit")]
+    pub ip_rate_limits: Vec<RateLimit>,
+
     #[clap(long, default_value_t = 60)]
     pub wallet_client_timeout_secs: u64,
 }
@@ -38,6 +62,10 @@ impl Default for FaucetConfig {
             host_ip: Ipv4Addr::new(127, 0, 0, 1),
             amount: DEFAULT_AMOUNT,
             num_coins: DEFAULT_NUM_COINS,
+            max_amount: DEFAULT_MAX_AMOUNT,
+            max_num_coins: DEFAULT_MAX_NUM_COINS,
+            recipient_rate_limits: vec![],
+            ip_rate_limits: vec![],
             wallet_client_timeout_secs: 60,
         }
     }
@@ -5,6 +5,7 @@ mod app_state;
 mod errors;
 mod faucet_config;
 mod local_faucet;
+mod rate_limiter;
 mod server;
 mod types;
 
@@ -12,5 +13,8 @@ pub use app_state::AppState;
 pub use errors::FaucetError;
 pub use faucet_config::FaucetConfig;
 pub use local_faucet::LocalFaucet;
+pub use rate_limiter::RateLimit;
 pub use server::{create_wallet_context, start_faucet};
-pub use types::{CoinInfo, FaucetRequest, FaucetResponse, FixedAmountRequest, RequestStatus};
+pub use types::{
+    CoinInfo, FaucetRequest, FaucetResponse, FixedAmountRequest, MultiCoinRequest, RequestStatus,
+};
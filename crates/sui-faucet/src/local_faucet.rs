@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use std::fmt;
+use std::net::IpAddr;
 use std::sync::Arc;
 
 use anyhow::bail;
@@ -9,8 +10,10 @@ use tokio::sync::Mutex;
 use tokio::time::Duration;
 use tracing::info;
 
+use crate::rate_limiter::RateLimiter;
 use crate::FaucetConfig;
 use crate::FaucetError;
+use crate::FaucetRequest;
 use sui_sdk::{
     rpc_types::{SuiTransactionBlockResponse, SuiTransactionBlockResponseOptions},
     types::quorum_driver_types::ExecuteTransactionRequestType,
@@ -37,6 +40,9 @@ pub struct LocalFaucet {
     coin_id: Arc<Mutex<ObjectID>>,
     coin_amount: u64,
     num_coins: usize,
+    max_amount: u64,
+    max_num_coins: usize,
+    rate_limiter: RateLimiter,
 }
 
 /// We do not just derive(Debug) because WalletContext and the WriteAheadLog do not implement Debug / are also hard
@@ -64,13 +70,65 @@ impl LocalFaucet {
             coin_id: Arc::new(Mutex::new(*coins[0].id())),
             coin_amount: config.amount,
             num_coins: config.num_coins,
+            max_amount: config.max_amount,
+            max_num_coins: config.max_num_coins,
+            rate_limiter: RateLimiter::new(config.recipient_rate_limits, config.ip_rate_limits),
         }))
     }
 
+    /// Serve a request sent from `ip`, after checking it against the faucet's limits.
+    pub async fn handle_request(
+        &self,
+        request: &FaucetRequest,
+        ip: Option<IpAddr>,
+    ) -> Result<Vec<CoinInfo>, FaucetError> {
+        let (num_coins, amount) = match request {
+            FaucetRequest::FixedAmountRequest(_) => (self.num_coins, self.coin_amount),
+            // Only the values set in a request are limited, the faucet's defaults always apply.
+            FaucetRequest::MultiCoinRequest(request) => {
+                if let Some(num_coins) = request.num_coins {
+                    if num_coins == 0 || num_coins > self.max_num_coins {
+                        return Err(FaucetError::InvalidNumCoins {
+                            requested: num_coins,
+                            max: self.max_num_coins,
+                        });
+                    }
+                }
+                if let Some(amount) = request.amount {
+                    if amount == 0 || amount > self.max_amount {
+                        return Err(FaucetError::InvalidAmount {
+                            requested: amount,
+                            max: self.max_amount,
+                        });
+                    }
+                }
+                (
+                    request.num_coins.unwrap_or(self.num_coins),
+                    request.amount.unwrap_or(self.coin_amount),
+                )
+            }
+        };
+
+        self.rate_limiter.check(request.recipient(), ip)?;
+        self.execute_request(request.recipient(), num_coins, amount)
+            .await
+    }
+
     /// Make transaction and execute it.
     pub async fn local_request_execute_tx(
         &self,
         recipient: SuiAddress,
+    ) -> Result<Vec<CoinInfo>, FaucetError> {
+        self.execute_request(recipient, self.num_coins, self.coin_amount)
+            .await
+    }
+
+    /// Send `num_coins` coins of `amount` MIST each to `recipient`.
+    async fn execute_request(
+        &self,
+        recipient: SuiAddress,
+        num_coins: usize,
+        amount: u64,
     ) -> Result<Vec<CoinInfo>, FaucetError> {
         let gas_price = self
             .wallet
@@ -79,8 +137,8 @@ impl LocalFaucet {
             .map_err(|e| FaucetError::internal(format!("Failed to get gas price: {}", e)))?;
 
         let mut ptb = ProgrammableTransactionBuilder::new();
-        let recipients = vec![recipient; self.num_coins];
-        let amounts = vec![self.coin_amount; self.num_coins];
+        let recipients = vec![recipient; num_coins];
+        let amounts = vec![amount; num_coins];
         ptb.pay_sui(recipients, amounts)
             .map_err(FaucetError::internal)?;
 
@@ -115,7 +173,7 @@ impl LocalFaucet {
             .created()
             .iter()
             .map(|o| CoinInfo {
-                amount: self.coin_amount,
+                amount,
                 id: o.object_id(),
                 transfer_tx_digest: *effects.transaction_digest(),
             })
@@ -260,6 +318,108 @@ mod tests {
         assert_eq!(coins.data.len(), 2 * local_faucet.num_coins);
     }
 
+    #[tokio::test]
+    async fn test_local_faucet_multi_coin_request() {
+        let cluster = TestClusterBuilder::new().build().await;
+        let client = cluster.sui_client().clone();
+
+        let config = FaucetConfig {
+            max_num_coins: 3,
+            recipient_rate_limits: vec!["2/3600".parse().unwrap()],
+            ..Default::default()
+        };
+        let max_amount = config.max_amount;
+        let local_faucet = LocalFaucet::new(cluster.wallet, config).await.unwrap();
+
+        let recipient = SuiAddress::random_for_testing_only();
+        let request = FaucetRequest::new_multi_coin_request(recipient, Some(3), Some(1_000));
+        let coins = local_faucet.handle_request(&request, None).await.unwrap();
+        assert_eq!(coins.len(), 3);
+        assert!(coins.iter().all(|coin| coin.amount == 1_000));
+
+        let coins = client
+            .coin_read_api()
+            .get_coins(recipient, None, None, None)
+            .await
+            .unwrap();
+        assert_eq!(coins.data.len(), 3);
+        assert!(coins.data.iter().all(|coin| coin.balance == 1_000));
+
+        // Requests outside of the configured bounds are rejected
+        let request = FaucetRequest::new_multi_coin_request(recipient, Some(4), None);
+        assert_eq!(
+            local_faucet
+                .handle_request(&request, None)
+                .await
+                .unwrap_err(),
+            FaucetError::InvalidNumCoins {
+                requested: 4,
+                max: 3
+            }
+        );
+        let request = FaucetRequest::new_multi_coin_request(recipient, None, Some(max_amount + 1));
+        assert_eq!(
+            local_faucet
+                .handle_request(&request, None)
+                .await
+                .unwrap_err(),
+            FaucetError::InvalidAmount {
+                requested: max_amount + 1,
+                max: max_amount
+            }
+        );
+
+        // The second valid request is allowed, the third is rate limited
+        let request = FaucetRequest::new_fixed_amount_request(recipient);
+        local_faucet.handle_request(&request, None).await.unwrap();
+        assert!(matches!(
+            local_faucet.handle_request(&request, None).await,
+            Err(FaucetError::TooManyRequests { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_local_faucet_defaults_above_limits() {
+        let cluster = TestClusterBuilder::new().build().await;
+        let client = cluster.sui_client().clone();
+
+        // The default number of coins is above the maximum a request can ask for
+        let config = FaucetConfig {
+            num_coins: 5,
+            max_num_coins: 3,
+            ..Default::default()
+        };
+        let local_faucet = LocalFaucet::new(cluster.wallet, config).await.unwrap();
+
+        let recipient = SuiAddress::random_for_testing_only();
+        let request = FaucetRequest::new_fixed_amount_request(recipient);
+        let coins = local_faucet.handle_request(&request, None).await.unwrap();
+        assert_eq!(coins.len(), 5);
+
+        let request = FaucetRequest::new_multi_coin_request(recipient, None, Some(1_000));
+        let coins = local_faucet.handle_request(&request, None).await.unwrap();
+        assert_eq!(coins.len(), 5);
+
+        let coins = client
+            .coin_read_api()
+            .get_coins(recipient, None, None, None)
+            .await
+            .unwrap();
+        assert_eq!(coins.data.len(), 10);
+
+        let request = FaucetRequest::new_multi_coin_request(recipient, Some(5), None);
+        assert_eq!(
+            local_faucet
+                .handle_request(&request, None)
+                .await
+                .unwrap_err(),
+            FaucetError::InvalidNumCoins {
+                requested: 5,
+                max: 3
+            }
+        );
+    }
+
     #[tokio::test]
     async fn test_find_gas_coins_and_address() {
         let mut cluster = TestClusterBuilder::new().build().await;
@@ -0,0 +1,295 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::FaucetError;
+use std::{
+    collections::{HashMap, VecDeque},
+    hash::Hash,
+    net::IpAddr,
+    str::FromStr,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+use sui_sdk::types::base_types::SuiAddress;
+
+/// At most `max_requests` requests in any window of `window`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RateLimit {
+    pub max_requests: usize,
+    pub window: Duration,
+}
+
+/// Parses `<max requests>/<window in seconds>`, e.g. `10/3600` for 10 requests per hour.
+impl FromStr for RateLimit {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (max_requests, window) = s
+            .split_once('/')
+            .ok_or_else(|| anyhow::anyhow!("Expected <max requests>/<window in seconds>"))?;
+        let max_requests: usize = max_requests.trim().parse()?;
+        let window: u64 = window.trim().parse()?;
+        anyhow::ensure!(
+            max_requests > 0 && window > 0,
+            "Rate limits must allow at least one request in a non-empty window"
+        );
+        Ok(Self {
+            max_requests,
+            window: Duration::from_secs(window),
+        })
+    }
+}
+
+/// Limits the rate of requests per recipient address and per IP address. Each key is limited by
+/// every configured window, so that e.g. bursts and daily totals can be limited separately.
+pub(crate) struct RateLimiter {
+    inner: Mutex<(KeyRateLimiter<SuiAddress>, KeyRateLimiter<IpAddr>)>,
+}
+
+impl RateLimiter {
+    pub(crate) fn new(recipient_limits: Vec<RateLimit>, ip_limits: Vec<RateLimit>) -> Self {
+        Self {
+            inner: Mutex::new((
+                KeyRateLimiter::new(recipient_limits),
+                KeyRateLimiter::new(ip_limits),
+            )),
+        }
+    }
+
+    /// Record a request from `ip` for `recipient`, unless it exceeds a rate limit.
+    pub(crate) fn check(
+        &self,
+        recipient: SuiAddress,
+        ip: Option<IpAddr>,
+    ) -> Result<(), FaucetError> {
+        self.check_at(recipient, ip, Instant::now())
+    }
+
+    fn check_at(
+        &self,
+        recipient: SuiAddress,
+        ip: Option<IpAddr>,
+        now: Instant,
+    ) -> Result<(), FaucetError> {
+        let mut inner = self.inner.lock().unwrap();
+        let (recipients, ips) = &mut *inner;
+        recipients.prune(now);
+        ips.prune(now);
+
+        let retry_after = recipients
+            .retry_after(&recipient, now)
+            .max(ip.and_then(|ip| ips.retry_after(&ip, now)));
+        if let Some(retry_after) = retry_after {
+            return Err(FaucetError::TooManyRequests {
+                // Round up, so that retrying after that many seconds succeeds.
+                retry_after_secs: retry_after.as_millis().div_ceil(1000) as u64,
+            });
+        }
+
+        recipients.record(recipient, now);
+        if let Some(ip) = ip {
+            ips.record(ip, now);
+        }
+        Ok(())
+    }
+}
+
+struct KeyRateLimiter<K> {
+    limits: Vec<RateLimit>,
+    // Time of the requests of each key, oldest first, within the longest window
+    requests: HashMap<K, VecDeque<Instant>>,
+    last_pruned: Option<Instant>,
+}
+
+impl<K: Hash + Eq> KeyRateLimiter<K> {
+    fn new(limits: Vec<RateLimit>) -> Self {
+        Self {
+            limits,
+            requests: HashMap::new(),
+            last_pruned: None,
+        }
+    }
+
+    fn longest_window(&self) -> Option<Duration> {
+        self.limits.iter().map(|limit| limit.window).max()
+    }
+
+    /// Forget the keys without requests in the longest window. This runs at most once per longest
+    /// window, so that the keys that stop sending requests do not accumulate.
+    fn prune(&mut self, now: Instant) {
+        let Some(longest) = self.longest_window() else {
+            return;
+        };
+        if self
+            .last_pruned
+            .is_some_and(|t| now.duration_since(t) < longest)
+        {
+            return;
+        }
+        self.last_pruned = Some(now);
+        self.requests.retain(|_, requests| {
+            drop_expired(requests, now, longest);
+            !requests.is_empty()
+        });
+    }
+
+    /// How long `key` has to wait before it is allowed another request, if it has to.
+    fn retry_after(&mut self, key: &K, now: Instant) -> Option<Duration> {
+        let longest = self.longest_window()?;
+        let requests = self.requests.get_mut(key)?;
+        drop_expired(requests, now, longest);
+        if requests.is_empty() {
+            self.requests.remove(key);
+            return None;
+        }
+
+        self.limits
+            .iter()
+            .filter_map(|limit| {
+                let in_window = requests
+                    .iter()
+                    .rev()
+                    .take_while(|t| now.duration_since(**t) < limit.window)
+                    .count();
+                if in_window < limit.max_requests {
+                    return None;
+                }
+                // Enough requests have to leave the window to make room for one more.
+                let oldest = requests[requests.len() - limit.max_requests];
+                Some(limit.window - now.duration_since(oldest))
+            })
+            .max()
+    }
+
+    fn record(&mut self, key: K, now: Instant) {
+        if !self.limits.is_empty() {
+            self.requests.entry(key).or_default().push_back(now);
+        }
+    }
+}
+
+// Drop the requests older than `window`.
+fn drop_expired(requests: &mut VecDeque<Instant>, now: Instant, window: Duration) {
+    while requests
+        .front()
+        .is_some_and(|t| now.duration_since(*t) >= window)
+    {
+        requests.pop_front();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limits(limits: &[&str]) -> Vec<RateLimit> {
+        limits.iter().map(|l| l.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn test_parse_rate_limit() {
+        assert_eq!(
+            "10/3600".parse::<RateLimit>().unwrap(),
+            RateLimit {
+                max_requests: 10,
+                window: Duration::from_secs(3600),
+            }
+        );
+        assert!("10".parse::<RateLimit>().is_err());
+        assert!("0/60".parse::<RateLimit>().is_err());
+        assert!("10/0".parse::<RateLimit>().is_err());
+        assert!("a/60".parse::<RateLimit>().is_err());
+    }
+
+    #[test]
+    fn test_no_limits() {
+        let limiter = RateLimiter::new(vec![], vec![]);
+        let recipient = SuiAddress::random_for_testing_only();
+        let now = Instant::now();
+        for _ in 0..100 {
+            limiter.check_at(recipient, None, now).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_recipient_limit_tiers() {
+        let limiter = RateLimiter::new(limits(&["2/10", "3/100"]), vec![]);
+        let recipient = SuiAddress::random_for_testing_only();
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+
+        limiter.check_at(recipient, None, at(0)).unwrap();
+        limiter.check_at(recipient, None, at(1)).unwrap();
+        assert_eq!(
+            limiter.check_at(recipient, None, at(2)),
+            Err(FaucetError::TooManyRequests {
+                retry_after_secs: 8
+            })
+        );
+
+        // Other recipients are not affected
+        limiter
+            .check_at(SuiAddress::random_for_testing_only(), None, at(2))
+            .unwrap();
+
+        // The short window has passed, but not the long one
+        limiter.check_at(recipient, None, at(10)).unwrap();
+        assert_eq!(
+            limiter.check_at(recipient, None, at(20)),
+            Err(FaucetError::TooManyRequests {
+                retry_after_secs: 80
+            })
+        );
+        limiter.check_at(recipient, None, at(100)).unwrap();
+    }
+
+    #[test]
+    fn test_ip_limit() {
+        let limiter = RateLimiter::new(vec![], limits(&["1/60"]));
+        let ip: IpAddr = "127.0.0.1".parse().unwrap();
+        let now = Instant::now();
+
+        limiter
+            .check_at(SuiAddress::random_for_testing_only(), Some(ip), now)
+            .unwrap();
+        assert!(limiter
+            .check_at(SuiAddress::random_for_testing_only(), Some(ip), now)
+            .is_err());
+        limiter
+            .check_at(
+                SuiAddress::random_for_testing_only(),
+                Some("127.0.0.2".parse().unwrap()),
+                now,
+            )
+            .unwrap();
+        // Requests without a known IP are only limited per recipient
+        limiter
+            .check_at(SuiAddress::random_for_testing_only(), None, now)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_forget_idle_keys() {
+        let limiter = RateLimiter::new(limits(&["1/10", "2/60"]), limits(&["5/60"]));
+        let ip: IpAddr = "127.0.0.1".parse().unwrap();
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+
+        for _ in 0..10 {
+            limiter
+                .check_at(SuiAddress::random_for_testing_only(), None, at(0))
+                .unwrap();
+        }
+        limiter
+            .check_at(SuiAddress::random_for_testing_only(), Some(ip), at(30))
+            .unwrap();
+        assert_eq!(11, limiter.inner.lock().unwrap().0.requests.len());
+
+        // Once the longest window has passed, only the keys with recent requests are kept
+        let recipient = SuiAddress::random_for_testing_only();
+        limiter.check_at(recipient, None, at(60)).unwrap();
+        let inner = limiter.inner.lock().unwrap();
+        assert_eq!(2, inner.0.requests.len());
+        assert_eq!(1, inner.1.requests.len());
+    }
+}
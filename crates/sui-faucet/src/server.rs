@@ -5,6 +5,7 @@ use crate::types::*;
 use crate::{AppState, FaucetConfig, FaucetError, FaucetRequest};
 use axum::{
     error_handling::HandleErrorLayer,
+    extract::ConnectInfo,
     http::StatusCode,
     response::IntoResponse,
     routing::{get, post},
@@ -31,46 +32,36 @@ async fn health() -> &'static str {
 
 async fn request_local_gas(
     Extension(state): Extension<Arc<AppState>>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
     Json(payload): Json<FaucetRequest>,
-    // ) -> &'static str {
 ) -> impl IntoResponse {
-    let FaucetRequest::FixedAmountRequest(request) = payload;
-    info!("Local request for address: {}", request.recipient);
-    let request = state
-        .faucet
-        .local_request_execute_tx(request.recipient)
-        .await;
-
-    if let Err(e) = request {
-        return (
-            StatusCode::INTERNAL_SERVER_ERROR,
+    info!(
+        "Local request for address: {} from {}",
+        payload.recipient(),
+        addr.ip()
+    );
+    match state.faucet.handle_request(&payload, Some(addr.ip())).await {
+        Ok(coins) => (
+            StatusCode::OK,
             Json(FaucetResponse {
-                status: RequestStatus::Failure(e),
-                coins_sent: None,
+                status: RequestStatus::Success,
+                coins_sent: Some(coins),
             }),
-        );
+        ),
+        Err(e) => (status_code(&e), Json(FaucetResponse::from(e))),
     }
+}
 
-    let Ok(coins) = request else {
-        return (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(FaucetResponse {
-                status: RequestStatus::Failure(FaucetError::internal(format!(
-                    "Failed to execute transaction: {}",
-                    request.unwrap_err()
-                ))),
-                coins_sent: None,
-            }),
-        );
-    };
-
-    (
-        StatusCode::OK,
-        Json(FaucetResponse {
-            status: RequestStatus::Success,
-            coins_sent: Some(coins),
-        }),
-    )
+fn status_code(error: &FaucetError) -> StatusCode {
+    match error {
+        FaucetError::InvalidNumCoins { .. } | FaucetError::InvalidAmount { .. } => {
+            StatusCode::BAD_REQUEST
+        }
+        FaucetError::TooManyRequests { .. } => StatusCode::TOO_MANY_REQUESTS,
+        FaucetError::Wallet(_) | FaucetError::Transfer(_) | FaucetError::Internal(_) => {
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    }
 }
 
 pub fn create_wallet_context(
@@ -201,6 +192,22 @@ mod tests {
             .unwrap();
 
         assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        // Test multi coin request out of bounds
+        let req = FaucetRequest::new_multi_coin_request(recipient, Some(1_000), None);
+        let response = client
+            .post(format!("http://127.0.0.1:{port}/v2/gas",))
+            .json(&req)
+            .send()
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let faucet_response = response.json::<FaucetResponse>().await.unwrap();
+        assert!(matches!(
+            faucet_response.status,
+            RequestStatus::Failure(FaucetError::InvalidNumCoins { .. })
+        ));
         handle.abort();
     }
 }
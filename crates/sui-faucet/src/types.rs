@@ -11,6 +11,7 @@ use sui_sdk::types::{
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum FaucetRequest {
     FixedAmountRequest(FixedAmountRequest),
+    MultiCoinRequest(MultiCoinRequest),
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -18,12 +19,42 @@ pub struct FixedAmountRequest {
     pub recipient: SuiAddress,
 }
 
+/// Request `num_coins` coins of `amount` MIST each. The faucet's defaults are used for the fields
+/// that are not set.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MultiCoinRequest {
+    pub recipient: SuiAddress,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub num_coins: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub amount: Option<u64>,
+}
+
 impl FaucetRequest {
     pub fn new_fixed_amount_request(recipient: impl Into<SuiAddress>) -> Self {
         Self::FixedAmountRequest(FixedAmountRequest {
             recipient: recipient.into(),
         })
     }
+
+    pub fn new_multi_coin_request(
+        recipient: impl Into<SuiAddress>,
+        num_coins: Option<usize>,
+        amount: Option<u64>,
+    ) -> Self {
+        Self::MultiCoinRequest(MultiCoinRequest {
+            recipient: recipient.into(),
+            num_coins,
+            amount,
+        })
+    }
+
+    pub fn recipient(&self) -> SuiAddress {
+        match self {
+            Self::FixedAmountRequest(request) => request.recipient,
+            Self::MultiCoinRequest(request) => request.recipient,
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug)]
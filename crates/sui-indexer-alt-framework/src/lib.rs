@@ -30,9 +30,13 @@ pub use sui_types as types;
 pub mod cluster;
 pub mod ingestion;
 pub mod metrics;
+#[cfg(feature = "parquet")]
+pub mod parquet;
 pub mod pipeline;
 #[cfg(feature = "postgres")]
 pub mod postgres;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
 pub mod task;
 
 /// Command-line arguments for the indexer
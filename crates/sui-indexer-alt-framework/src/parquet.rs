@@ -0,0 +1,57 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use prometheus::Registry;
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    ingestion::{ClientArgs, IngestionConfig},
+    Indexer, IndexerArgs,
+};
+
+pub use sui_parquet_store::*;
+
+/// An opinionated indexer implementation that writes each pipeline's output to Parquet files in a
+/// local directory, with watermarks persisted alongside them.
+impl Indexer<ParquetStore> {
+    /// Create a new instance of the indexer framework, writing Parquet files under `root`.
+    /// `parquet_args`, `indexer_args,`, `client_args`, and `ingestion_config` contain
+    /// configurations for the following, respectively:
+    ///
+    /// - How rows are partitioned into files,
+    /// - What is indexed (which checkpoints, which pipelines, whether to update the watermarks
+    ///   table) and where to serve metrics from,
+    /// - Where to download checkpoints from,
+    /// - Concurrency and buffering parameters for downloading checkpoints.
+    ///
+    /// Pipelines write their rows using [Connection::write_rows], from their handler's `commit`
+    /// function, and can remove old files using [Connection::prune].
+    ///
+    /// After initialization, at least one pipeline must be added using [Self::concurrent_pipeline]
+    /// or [Self::sequential_pipeline], before the indexer is started using [Self::run].
+    pub async fn new_from_parquet(
+        root: impl Into<PathBuf>,
+        parquet_args: ParquetArgs,
+        indexer_args: IndexerArgs,
+        client_args: ClientArgs,
+        ingestion_config: IngestionConfig,
+        registry: &Registry,
+        cancel: CancellationToken,
+    ) -> Result<Self> {
+        let store =
+            ParquetStore::new(root, parquet_args).context("Failed to initialize Parquet store")?;
+
+        Indexer::new(
+            store,
+            indexer_args,
+            client_args,
+            ingestion_config,
+            registry,
+            cancel,
+        )
+        .await
+    }
+}
@@ -0,0 +1,175 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Passphrase based encryption of keystore files.
+//! The plaintext (the content of a plaintext keystore file) is encrypted with AES-256-GCM under a
+//! key derived from the passphrase with scrypt. The KDF parameters, salt and nonce are saved
+//! alongside the ciphertext, so that they can be changed without breaking existing files.
+
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Nonce};
+use anyhow::{anyhow, ensure};
+use fastcrypto::encoding::{Base64, Encoding};
+use rand::{rngs::OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroizing;
+
+/// Environment variable the keystore passphrase is read from, for non-interactive use.
+pub const KEYSTORE_PASSPHRASE_ENV_VAR: &str = "SUI_KEYSTORE_PASSPHRASE";
+
+const VERSION: u8 = 1;
+const KDF: &str = "scrypt";
+const CIPHER: &str = "aes-256-gcm";
+const SALT_LENGTH: usize = 32;
+const NONCE_LENGTH: usize = 12;
+const KEY_LENGTH: usize = 32;
+
+/// The passphrase in `SUI_KEYSTORE_PASSPHRASE`, if it is set.
+pub fn passphrase_from_env() -> Option<Zeroizing<String>> {
+    std::env::var(KEYSTORE_PASSPHRASE_ENV_VAR)
+        .ok()
+        .map(Zeroizing::new)
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ScryptParams {
+    pub log_n: u8,
+    pub r: u32,
+    pub p: u32,
+}
+
+impl Default for ScryptParams {
+    /// N = 2^17, r = 8, p = 1: about 128 MiB of memory per key derivation.
+    fn default() -> Self {
+        Self {
+            log_n: 17,
+            r: 8,
+            p: 1,
+        }
+    }
+}
+
+/// An encrypted keystore file.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct EncryptedKeystore {
+    version: u8,
+    kdf: String,
+    kdf_params: ScryptParams,
+    /// Base64 encoded
+    salt: String,
+    cipher: String,
+    /// Base64 encoded
+    nonce: String,
+    /// Base64 encoded
+    ciphertext: String,
+}
+
+impl EncryptedKeystore {
+    pub fn encrypt(
+        plaintext: &[u8],
+        passphrase: &str,
+        params: ScryptParams,
+    ) -> Result<Self, anyhow::Error> {
+        let mut salt = [0u8; SALT_LENGTH];
+        let mut nonce = [0u8; NONCE_LENGTH];
+        OsRng.fill_bytes(&mut salt);
+        OsRng.fill_bytes(&mut nonce);
+
+        let key = derive_key(passphrase, &salt, &params)?;
+        let ciphertext = Aes256Gcm::new_from_slice(key.as_slice())
+            .map_err(|e| anyhow!("Invalid keystore encryption key: {e}"))?
+            .encrypt(Nonce::from_slice(&nonce), plaintext)
+            .map_err(|_| anyhow!("Cannot encrypt the keystore"))?;
+
+        Ok(Self {
+            version: VERSION,
+            kdf: KDF.to_string(),
+            kdf_params: params,
+            salt: Base64::encode(salt),
+            cipher: CIPHER.to_string(),
+            nonce: Base64::encode(nonce),
+            ciphertext: Base64::encode(ciphertext),
+        })
+    }
+
+    pub fn kdf_params(&self) -> ScryptParams {
+        self.kdf_params
+    }
+
+    /// Decrypt the keystore. Fails if the passphrase is wrong or the file was tampered with.
+    pub fn decrypt(&self, passphrase: &str) -> Result<Zeroizing<Vec<u8>>, anyhow::Error> {
+        ensure!(
+            self.version == VERSION,
+            "Unsupported encrypted keystore version {}",
+            self.version
+        );
+        ensure!(self.kdf == KDF, "Unsupported keystore KDF {}", self.kdf);
+        ensure!(
+            self.cipher == CIPHER,
+            "Unsupported keystore cipher {}",
+            self.cipher
+        );
+
+        let decode = |field: &str, value: &str| {
+            Base64::decode(value).map_err(|e| anyhow!("Invalid {field} in encrypted keystore: {e}"))
+        };
+        let salt = decode("salt", &self.salt)?;
+        let nonce = decode("nonce", &self.nonce)?;
+        let ciphertext = decode("ciphertext", &self.ciphertext)?;
+        ensure!(
+            nonce.len() == NONCE_LENGTH,
+            "Invalid nonce in encrypted keystore"
+        );
+
+        let key = derive_key(passphrase, &salt, &self.kdf_params)?;
+        let plaintext = Aes256Gcm::new_from_slice(key.as_slice())
+            .map_err(|e| anyhow!("Invalid keystore encryption key: {e}"))?
+            .decrypt(Nonce::from_slice(&nonce), ciphertext.as_slice())
+            .map_err(|_| anyhow!("Cannot decrypt the keystore: wrong passphrase"))?;
+        Ok(Zeroizing::new(plaintext))
+    }
+}
+
+fn derive_key(
+    passphrase: &str,
+    salt: &[u8],
+    params: &ScryptParams,
+) -> Result<Zeroizing<[u8; KEY_LENGTH]>, anyhow::Error> {
+    let params = scrypt::Params::new(params.log_n, params.r, params.p)
+        .map_err(|e| anyhow!("Invalid scrypt parameters: {e}"))?;
+    let mut key = Zeroizing::new([0u8; KEY_LENGTH]);
+    scrypt::scrypt(passphrase.as_bytes(), salt, &params, key.as_mut_slice())
+        .map_err(|e| anyhow!("Cannot derive the keystore encryption key: {e}"))?;
+    Ok(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{EncryptedKeystore, ScryptParams};
+
+    const TEST_PARAMS: ScryptParams = ScryptParams {
+        log_n: 4,
+        r: 8,
+        p: 1,
+    };
+
+    #[test]
+    fn encrypt_decrypt_test() {
+        let encrypted =
+            EncryptedKeystore::encrypt(b"[\"key\"]", "passphrase", TEST_PARAMS).unwrap();
+        assert_eq!(
+            b"[\"key\"]".as_slice(),
+            encrypted.decrypt("passphrase").unwrap().as_slice()
+        );
+        assert!(encrypted.decrypt("wrong passphrase").is_err());
+    }
+
+    #[test]
+    fn tampered_ciphertext_test() {
+        let mut encrypted =
+            EncryptedKeystore::encrypt(b"[\"key\"]", "passphrase", TEST_PARAMS).unwrap();
+        let other = EncryptedKeystore::encrypt(b"[\"other\"]", "passphrase", TEST_PARAMS).unwrap();
+        encrypted.ciphertext = other.ciphertext;
+        assert!(encrypted.decrypt("passphrase").is_err());
+    }
+}
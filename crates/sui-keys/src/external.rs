@@ -0,0 +1,513 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! A keystore whose private keys never enter this process. Signing is delegated to an external
+//! signer (a hardware wallet bridge, an HSM, a remote signing service, ...) that is reached either
+//! by spawning a command and talking to it over stdio, or by connecting to a Unix domain socket.
+//!
+//! The signer speaks a line-delimited JSON protocol: each connection (or process) receives one
+//! request on a single line and answers with one response on a single line.
+//!
+//! ```text
+//! -> {"method":"keys"}
+//! <- {"keys":[{"public_key":"<base64 flag || pk>","alias":"optional-alias"}]}
+//! -> {"method":"sign","address":"0x...","message":"<base64 bytes to sign>"}
+//! <- {"signature":"<base64 flag || sig || pk>"}
+//! <- {"error":"reason the request failed"}
+//! ```
+//!
+//! The bytes sent with a `sign` request are already hashed (for `sign_secure`, the Blake2b256
+//! digest of the BCS encoded intent message), so the signer signs them as is.
+
+use crate::keystore::{validate_alias, AccountKeystore, Alias, InMemKeystore};
+use crate::random_names::{random_name, random_names};
+use anyhow::{anyhow, bail, ensure, Context};
+use fastcrypto::encoding::{Base64, Encoding};
+use fastcrypto::hash::HashFunction;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use shared_crypto::intent::{Intent, IntentMessage};
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::{Display, Formatter};
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::OnceLock;
+use sui_types::base_types::SuiAddress;
+use sui_types::crypto::{
+    DefaultHash, EncodeDecodeBase64, PublicKey, Signature, SuiKeyPair, SuiSignature, ToFromBytes,
+};
+
+/// How to reach an external signer.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SignerEndpoint {
+    /// Spawn `command` with `args` for every request. The request is written to its stdin and the
+    /// response is read from its stdout.
+    Command {
+        command: String,
+        #[serde(default)]
+        args: Vec<String>,
+    },
+    /// Connect to a signer listening on a Unix domain socket.
+    Socket { path: PathBuf },
+}
+
+/// A request sent to an external signer.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(tag = "method", rename_all = "snake_case")]
+pub enum SignerRequest {
+    /// List the keys held by the signer.
+    Keys,
+    /// Sign `message` (Base64) with the key of `address`.
+    Sign {
+        address: SuiAddress,
+        message: String,
+    },
+}
+
+/// A response from an external signer.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SignerResponse {
+    Keys(Vec<SignerKey>),
+    /// Base64 encoded `flag || signature || public key`.
+    Signature(String),
+    Error(String),
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct SignerKey {
+    /// Base64 encoded `flag || public key`.
+    pub public_key: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alias: Option<String>,
+}
+
+/// A keystore backed by an external signer.
+/// Keys and their aliases are loaded from the signer the first time they are needed. Aliases that
+/// the signer does not provide are generated, and alias updates only last for the lifetime of the
+/// keystore.
+pub struct ExternalKeystore {
+    endpoint: SignerEndpoint,
+    keys: OnceLock<SignerKeys>,
+}
+
+// The keys held by the signer, by address.
+struct SignerKeys {
+    keys: BTreeMap<SuiAddress, PublicKey>,
+    aliases: BTreeMap<SuiAddress, Alias>,
+}
+
+impl Serialize for ExternalKeystore {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.endpoint.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for ExternalKeystore {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        // Loading a keystore from the config must not reach out to the signer, which may be
+        // unavailable when no key is needed.
+        Ok(ExternalKeystore::lazy(SignerEndpoint::deserialize(
+            deserializer,
+        )?))
+    }
+}
+
+impl AccountKeystore for ExternalKeystore {
+    fn sign_hashed(&self, address: &SuiAddress, msg: &[u8]) -> Result<Signature, signature::Error> {
+        self.sign(address, msg)
+            .map_err(|e| signature::Error::from_source(e.to_string()))
+    }
+
+    fn sign_secure<T>(
+        &self,
+        address: &SuiAddress,
+        msg: &T,
+        intent: Intent,
+    ) -> Result<Signature, signature::Error>
+    where
+        T: Serialize,
+    {
+        // Same digest as `Signature::new_secure`.
+        let intent_msg = IntentMessage::new(intent, msg);
+        let mut hasher = DefaultHash::default();
+        hasher.update(bcs::to_bytes(&intent_msg).map_err(signature::Error::from_source)?);
+        let signature = self.sign_hashed(address, &hasher.finalize().digest)?;
+
+        signature
+            .verify_secure(&intent_msg, *address, signature.scheme())
+            .map_err(|e| {
+                signature::Error::from_source(format!(
+                    "External signer returned an invalid signature for address [{address}]: {e}"
+                ))
+            })?;
+        Ok(signature)
+    }
+
+    fn import(&mut self, _alias: Option<String>, keypair: SuiKeyPair) -> Result<(), anyhow::Error> {
+        let address: SuiAddress = (&keypair.public()).into();
+        bail!(
+            "Cannot import key for address [{address}]: keys are managed by the external signer {}",
+            self.endpoint
+        )
+    }
+
+    fn remove(&mut self, address: SuiAddress) -> Result<(), anyhow::Error> {
+        bail!(
+            "Cannot remove key for address [{address}]: keys are managed by the external signer {}",
+            self.endpoint
+        )
+    }
+
+    fn entries(&self) -> Vec<PublicKey> {
+        self.loaded()
+            .map(|keys| keys.keys.values().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    fn export(&self, address: &SuiAddress) -> Result<&SuiKeyPair, anyhow::Error> {
+        bail!(
+            "Cannot export key for address [{address}]: the private key is held by the external \
+            signer {}",
+            self.endpoint
+        )
+    }
+
+    fn addresses_with_alias(&self) -> Vec<(&SuiAddress, &Alias)> {
+        self.loaded()
+            .map(|keys| keys.aliases.iter().collect())
+            .unwrap_or_default()
+    }
+
+    fn aliases(&self) -> Vec<&Alias> {
+        self.loaded()
+            .map(|keys| keys.aliases.values().collect())
+            .unwrap_or_default()
+    }
+
+    fn aliases_mut(&mut self) -> Vec<&mut Alias> {
+        if self.loaded().is_none() {
+            return vec![];
+        }
+        self.keys
+            .get_mut()
+            .map(|keys| keys.aliases.values_mut().collect())
+            .unwrap_or_default()
+    }
+
+    fn get_alias(&self, address: &SuiAddress) -> Result<String, anyhow::Error> {
+        match self.keys()?.aliases.get(address) {
+            Some(alias) => Ok(alias.alias.clone()),
+            None => bail!("Cannot find alias for address {address}"),
+        }
+    }
+
+    fn create_alias(&self, alias: Option<String>) -> Result<String, anyhow::Error> {
+        match alias {
+            Some(a) if self.alias_exists(&a) => {
+                bail!("Alias {a} already exists. Please choose another alias.")
+            }
+            Some(a) => validate_alias(&a),
+            None => Ok(random_name(
+                &self
+                    .aliases()
+                    .iter()
+                    .map(|x| x.alias.to_string())
+                    .collect::<HashSet<_>>(),
+            )),
+        }
+    }
+
+    fn update_alias(
+        &mut self,
+        old_alias: &str,
+        new_alias: Option<&str>,
+    ) -> Result<String, anyhow::Error> {
+        self.update_alias_value(old_alias, new_alias)
+    }
+}
+
+impl ExternalKeystore {
+    /// Connect to the signer at `endpoint` and load the keys it holds.
+    pub fn new(endpoint: SignerEndpoint) -> Result<Self, anyhow::Error> {
+        let keystore = Self::lazy(endpoint);
+        keystore.keys()?;
+        Ok(keystore)
+    }
+
+    /// A keystore for the signer at `endpoint`, which is only contacted once a key is needed.
+    pub fn lazy(endpoint: SignerEndpoint) -> Self {
+        Self {
+            endpoint,
+            keys: OnceLock::new(),
+        }
+    }
+
+    pub fn endpoint(&self) -> &SignerEndpoint {
+        &self.endpoint
+    }
+
+    // The keys held by the signer, loading them on first use.
+    fn keys(&self) -> Result<&SignerKeys, anyhow::Error> {
+        if let Some(keys) = self.keys.get() {
+            return Ok(keys);
+        }
+        let keys = SignerKeys::load(&self.endpoint)?;
+        Ok(self.keys.get_or_init(|| keys))
+    }
+
+    // For the accessors that cannot fail: a signer that cannot be reached holds no keys.
+    fn loaded(&self) -> Option<&SignerKeys> {
+        self.keys()
+            .inspect_err(|e| tracing::warn!("Cannot load keys: {e:#}"))
+            .ok()
+    }
+
+    fn sign(&self, address: &SuiAddress, msg: &[u8]) -> Result<Signature, anyhow::Error> {
+        ensure!(
+            self.keys()?.keys.contains_key(address),
+            "Cannot find key for address: [{address}]"
+        );
+
+        let request = SignerRequest::Sign {
+            address: *address,
+            message: Base64::encode(msg),
+        };
+        let signature = match send_request(&self.endpoint, &request)? {
+            SignerResponse::Signature(signature) => signature,
+            response => bail!(
+                "Unexpected response from external signer {}: {response:?}",
+                self.endpoint
+            ),
+        };
+
+        let signature = Base64::decode(&signature)
+            .map_err(|e| anyhow!("Invalid signature encoding: {e}"))
+            .and_then(|bytes| {
+                Signature::from_bytes(&bytes).map_err(|e| anyhow!("Invalid signature: {e}"))
+            })
+            .with_context(|| format!("Bad signature from external signer {}", self.endpoint))?;
+
+        // Make sure the signer used the key we asked for.
+        let signer = PublicKey::try_from_bytes(signature.scheme(), signature.public_key_bytes())
+            .map_err(|e| anyhow!("Invalid public key in signature: {e}"))?;
+        ensure!(
+            SuiAddress::from(&signer) == *address,
+            "External signer {} signed with the key of {} instead of [{address}]",
+            self.endpoint,
+            SuiAddress::from(&signer),
+        );
+        Ok(signature)
+    }
+}
+
+impl SignerKeys {
+    // Ask the signer at `endpoint` for its keys, and name those that it has no alias for.
+    fn load(endpoint: &SignerEndpoint) -> Result<Self, anyhow::Error> {
+        let keys = match send_request(endpoint, &SignerRequest::Keys)? {
+            SignerResponse::Keys(keys) => keys,
+            response => bail!("Unexpected response from external signer {endpoint}: {response:?}"),
+        };
+
+        let mut public_keys = BTreeMap::new();
+        let mut aliases = BTreeMap::new();
+        for key in keys {
+            let public_key = PublicKey::decode_base64(&key.public_key).map_err(|e| {
+                anyhow!(
+                    "Invalid public key {} from external signer {endpoint}: {e}",
+                    key.public_key
+                )
+            })?;
+            let address = SuiAddress::from(&public_key);
+            if let Some(alias) = key.alias {
+                aliases.insert(
+                    address,
+                    Alias {
+                        alias: validate_alias(&alias)?,
+                        public_key_base64: key.public_key,
+                    },
+                );
+            }
+            public_keys.insert(address, public_key);
+        }
+
+        let taken = aliases.values().map(|a| a.alias.clone()).collect();
+        let unnamed: Vec<_> = public_keys
+            .iter()
+            .filter(|(address, _)| !aliases.contains_key(*address))
+            .map(|(address, key)| (*address, key.encode_base64()))
+            .collect();
+        let names = random_names(taken, unnamed.len());
+        for ((address, public_key_base64), alias) in unnamed.into_iter().zip(names) {
+            aliases.insert(
+                address,
+                Alias {
+                    alias,
+                    public_key_base64,
+                },
+            );
+        }
+
+        Ok(Self {
+            keys: public_keys,
+            aliases,
+        })
+    }
+}
+
+// Send a single request to the signer and wait for its response.
+fn send_request(
+    endpoint: &SignerEndpoint,
+    request: &SignerRequest,
+) -> Result<SignerResponse, anyhow::Error> {
+    let mut line = serde_json::to_string(request)?;
+    line.push('\n');
+
+    let response = match endpoint {
+        SignerEndpoint::Command { command, args } => {
+            let mut child = Command::new(command)
+                .args(args)
+                .stdin(Stdio::piped())
+                .stdout(Stdio::piped())
+                .spawn()
+                .with_context(|| format!("Cannot start external signer {endpoint}"))?;
+            // Dropping stdin closes it, so that the signer sees the end of the request.
+            child
+                .stdin
+                .take()
+                .ok_or_else(|| anyhow!("Cannot write to external signer {endpoint}"))?
+                .write_all(line.as_bytes())?;
+            let output = child.wait_with_output()?;
+            ensure!(
+                output.status.success(),
+                "External signer {endpoint} exited with {}",
+                output.status
+            );
+            String::from_utf8(output.stdout)?
+        }
+
+        #[cfg(unix)]
+        SignerEndpoint::Socket { path } => {
+            let mut stream = std::os::unix::net::UnixStream::connect(path)
+                .with_context(|| format!("Cannot connect to external signer {endpoint}"))?;
+            stream.write_all(line.as_bytes())?;
+            let mut response = String::new();
+            BufReader::new(stream).read_line(&mut response)?;
+            response
+        }
+
+        #[cfg(not(unix))]
+        SignerEndpoint::Socket { .. } => {
+            bail!("External signers over Unix sockets are not supported on this platform")
+        }
+    };
+
+    let response = response.lines().next().unwrap_or_default();
+    match serde_json::from_str(response)
+        .with_context(|| format!("Invalid response from external signer {endpoint}"))?
+    {
+        SignerResponse::Error(e) => bail!("External signer {endpoint} failed: {e}"),
+        response => Ok(response),
+    }
+}
+
+impl Display for SignerEndpoint {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SignerEndpoint::Command { command, args } if args.is_empty() => {
+                write!(f, "`{command}`")
+            }
+            SignerEndpoint::Command { command, args } => {
+                write!(f, "`{command} {}`", args.join(" "))
+            }
+            SignerEndpoint::Socket { path } => write!(f, "at {}", path.display()),
+        }
+    }
+}
+
+/// A reference implementation of the signer protocol, backed by an in-memory keystore.
+pub struct MockSigner {
+    keystore: InMemKeystore,
+}
+
+impl MockSigner {
+    pub fn new(keystore: InMemKeystore) -> Self {
+        Self { keystore }
+    }
+
+    pub fn keystore(&self) -> &InMemKeystore {
+        &self.keystore
+    }
+
+    pub fn handle(&self, request: SignerRequest) -> SignerResponse {
+        match request {
+            SignerRequest::Keys => SignerResponse::Keys(
+                self.keystore
+                    .entries()
+                    .into_iter()
+                    .map(|public_key| SignerKey {
+                        alias: self.keystore.get_alias(&(&public_key).into()).ok(),
+                        public_key: public_key.encode_base64(),
+                    })
+                    .collect(),
+            ),
+            SignerRequest::Sign { address, message } => {
+                let signature = Base64::decode(&message)
+                    .map_err(|e| e.to_string())
+                    .and_then(|msg| {
+                        self.keystore
+                            .sign_hashed(&address, &msg)
+                            .map_err(|e| e.to_string())
+                    });
+                match signature {
+                    Ok(signature) => SignerResponse::Signature(Base64::encode(signature)),
+                    Err(e) => SignerResponse::Error(e),
+                }
+            }
+        }
+    }
+
+    /// Answer a single request line.
+    pub fn handle_line(&self, line: &str) -> String {
+        let response = match serde_json::from_str(line.trim()) {
+            Ok(request) => self.handle(request),
+            Err(e) => SignerResponse::Error(format!("Invalid request: {e}")),
+        };
+        serde_json::to_string(&response).expect("Signer responses are serializable")
+    }
+
+    /// Serve requests on a Unix domain socket at `path`, from a background thread.
+    #[cfg(unix)]
+    pub fn serve(
+        self,
+        path: impl Into<PathBuf>,
+    ) -> Result<std::thread::JoinHandle<()>, anyhow::Error> {
+        let path = path.into();
+        let listener = std::os::unix::net::UnixListener::bind(&path)
+            .with_context(|| format!("Cannot bind mock signer to {}", path.display()))?;
+        Ok(std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else {
+                    continue;
+                };
+                let mut line = String::new();
+                let Ok(reader) = stream.try_clone() else {
+                    continue;
+                };
+                if BufReader::new(reader).read_line(&mut line).is_err() {
+                    continue;
+                }
+                let mut response = self.handle_line(&line);
+                response.push('\n');
+                let _ = stream.write_all(response.as_bytes());
+            }
+        }))
+    }
+}
@@ -1,6 +1,10 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::encrypted::{
+    passphrase_from_env, EncryptedKeystore, ScryptParams, KEYSTORE_PASSPHRASE_ENV_VAR,
+};
+use crate::external::ExternalKeystore;
 use crate::key_derive::{derive_key_pair_from_path, generate_new_key};
 use crate::key_identity::KeyIdentity;
 use crate::random_names::{random_name, random_names};
@@ -18,17 +22,20 @@ use std::fs;
 use std::fs::File;
 use std::io::BufReader;
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
 use sui_types::base_types::SuiAddress;
 use sui_types::crypto::get_key_pair_from_rng;
 use sui_types::crypto::{
     enum_dispatch, EncodeDecodeBase64, PublicKey, Signature, SignatureScheme, SuiKeyPair,
 };
+use zeroize::Zeroizing;
 
 #[derive(Serialize, Deserialize)]
 #[enum_dispatch(AccountKeystore)]
 pub enum Keystore {
     File(FileBasedKeystore),
     InMem(InMemKeystore),
+    External(ExternalKeystore),
 }
 #[enum_dispatch]
 pub trait AccountKeystore: Send + Sync {
@@ -164,6 +171,11 @@ impl Display for Keystore {
                 writeln!(writer, "Keystore Type : InMem")?;
                 write!(f, "{}", writer)
             }
+            Keystore::External(external) => {
+                writeln!(writer, "Keystore Type : External")?;
+                write!(writer, "Keystore Signer : {}", external.endpoint())?;
+                write!(f, "{}", writer)
+            }
         }
     }
 }
@@ -174,11 +186,29 @@ pub struct Alias {
     pub public_key_base64: String,
 }
 
+/// Called the first time the keys of a locked keystore are needed, to ask for its passphrase.
+pub type PassphrasePrompt = Arc<dyn Fn() -> Result<String, anyhow::Error> + Send + Sync>;
+
 #[derive(Default)]
 pub struct FileBasedKeystore {
-    keys: BTreeMap<SuiAddress, SuiKeyPair>,
+    // Unset until an encrypted keystore file is unlocked
+    keys: OnceLock<BTreeMap<SuiAddress, SuiKeyPair>>,
     aliases: BTreeMap<SuiAddress, Alias>,
     path: Option<PathBuf>,
+    // Passphrase the keystore file is encrypted with, if it is encrypted
+    passphrase: OnceLock<Zeroizing<String>>,
+    kdf_params: ScryptParams,
+    // Content of an encrypted keystore file
+    locked: Option<EncryptedKeystore>,
+    prompt: Option<PassphrasePrompt>,
+}
+
+/// Content of a keystore file: either a list of Base64 encoded keys, or that list encrypted.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum KeystoreFile {
+    Plaintext(Vec<String>),
+    Encrypted(EncryptedKeystore),
 }
 
 impl Serialize for FileBasedKeystore {
@@ -211,9 +241,14 @@ impl AccountKeystore for FileBasedKeystore {
     fn sign_hashed(&self, address: &SuiAddress, msg: &[u8]) -> Result<Signature, signature::Error> {
         Ok(Signature::new_hashed(
             msg,
-            self.keys.get(address).ok_or_else(|| {
-                signature::Error::from_source(format!("Cannot find key for address: [{address}]"))
-            })?,
+            self.keys()
+                .map_err(|e| signature::Error::from_source(e.to_string()))?
+                .get(address)
+                .ok_or_else(|| {
+                    signature::Error::from_source(format!(
+                        "Cannot find key for address: [{address}]"
+                    ))
+                })?,
         ))
     }
     fn sign_secure<T>(
@@ -227,13 +262,19 @@ impl AccountKeystore for FileBasedKeystore {
     {
         Ok(Signature::new_secure(
             &IntentMessage::new(intent, msg),
-            self.keys.get(address).ok_or_else(|| {
-                signature::Error::from_source(format!("Cannot find key for address: [{address}]"))
-            })?,
+            self.keys()
+                .map_err(|e| signature::Error::from_source(e.to_string()))?
+                .get(address)
+                .ok_or_else(|| {
+                    signature::Error::from_source(format!(
+                        "Cannot find key for address: [{address}]"
+                    ))
+                })?,
         ))
     }
 
     fn import(&mut self, alias: Option<String>, keypair: SuiKeyPair) -> Result<(), anyhow::Error> {
+        self.keys()?;
         let address: SuiAddress = (&keypair.public()).into();
         let alias = self.create_alias(alias)?;
         self.aliases.insert(
@@ -243,14 +284,14 @@ impl AccountKeystore for FileBasedKeystore {
                 public_key_base64: keypair.public().encode_base64(),
             },
         );
-        self.keys.insert(address, keypair);
+        self.keys_mut()?.insert(address, keypair);
         self.save()?;
         Ok(())
     }
 
     fn remove(&mut self, address: SuiAddress) -> Result<(), anyhow::Error> {
+        self.keys_mut()?.remove(&address);
         self.aliases.remove(&address);
-        self.keys.remove(&address);
         self.save()?;
         Ok(())
     }
@@ -269,8 +310,17 @@ impl AccountKeystore for FileBasedKeystore {
         self.aliases.values_mut().collect()
     }
 
+    /// The public keys of a locked keystore are read from its aliases, so that its addresses can
+    /// be listed without unlocking it.
     fn entries(&self) -> Vec<PublicKey> {
-        self.keys.values().map(|key| key.public()).collect()
+        match self.keys.get() {
+            Some(keys) => keys.values().map(|key| key.public()).collect(),
+            None => self
+                .aliases
+                .values()
+                .filter_map(|alias| PublicKey::decode_base64(&alias.public_key_base64).ok())
+                .collect(),
+        }
     }
 
     /// This function returns an error if the provided alias already exists. If the alias
@@ -302,7 +352,7 @@ impl AccountKeystore for FileBasedKeystore {
     }
 
     fn export(&self, address: &SuiAddress) -> Result<&SuiKeyPair, anyhow::Error> {
-        match self.keys.get(address) {
+        match self.keys()?.get(address) {
             Some(key) => Ok(key),
             None => Err(anyhow!("Cannot find key for address: [{address}]")),
         }
@@ -322,25 +372,38 @@ impl AccountKeystore for FileBasedKeystore {
 }
 
 impl FileBasedKeystore {
+    /// Load the keystore at `path`. An encrypted keystore is unlocked with the passphrase in
+    /// `SUI_KEYSTORE_PASSPHRASE` if it is set, and is loaded locked otherwise.
     pub fn new(path: &PathBuf) -> Result<Self, anyhow::Error> {
+        let mut passphrase = None;
+        let mut kdf_params = ScryptParams::default();
+        let mut locked = None;
         let keys = if path.exists() {
             let reader =
                 BufReader::new(File::open(path).with_context(|| {
                     format!("Cannot open the keystore file: {}", path.display())
                 })?);
-            let kp_strings: Vec<String> = serde_json::from_reader(reader).with_context(|| {
+            let content: KeystoreFile = serde_json::from_reader(reader).with_context(|| {
                 format!("Cannot deserialize the keystore file: {}", path.display(),)
             })?;
-            kp_strings
-                .iter()
-                .map(|kpstr| {
-                    let key = SuiKeyPair::decode_base64(kpstr);
-                    key.map(|k| (SuiAddress::from(&k.public()), k))
-                })
-                .collect::<Result<BTreeMap<_, _>, _>>()
-                .map_err(|e| anyhow!("Invalid keystore file: {}. {}", path.display(), e))?
+            match content {
+                KeystoreFile::Plaintext(kp_strings) => Some(decode_keys(path, &kp_strings)?),
+                KeystoreFile::Encrypted(encrypted) => {
+                    kdf_params = encrypted.kdf_params();
+                    let keys = match passphrase_from_env() {
+                        Some(env_passphrase) => {
+                            let keys = decrypt_keys(path, &encrypted, &env_passphrase)?;
+                            passphrase = Some(env_passphrase);
+                            Some(keys)
+                        }
+                        None => None,
+                    };
+                    locked = Some(encrypted);
+                    keys
+                }
+            }
         } else {
-            BTreeMap::new()
+            Some(BTreeMap::new())
         };
 
         // check aliases
@@ -376,24 +439,8 @@ impl FileBasedKeystore {
                         e
                     )
                 })?
-        } else if keys.is_empty() {
-            BTreeMap::new()
-        } else {
-            let names: Vec<String> = random_names(HashSet::new(), keys.len());
-            let aliases = keys
-                .iter()
-                .zip(names)
-                .map(|((sui_address, skp), alias)| {
-                    let public_key_base64 = skp.public().encode_base64();
-                    (
-                        *sui_address,
-                        Alias {
-                            alias,
-                            public_key_base64,
-                        },
-                    )
-                })
-                .collect::<BTreeMap<_, _>>();
+        } else if let Some(keys) = keys.as_ref().filter(|keys| !keys.is_empty()) {
+            let aliases = default_aliases(keys);
             let aliases_store = serde_json::to_string_pretty(&aliases.values().collect::<Vec<_>>())
                 .with_context(|| {
                     format!(
@@ -403,15 +450,109 @@ impl FileBasedKeystore {
                 })?;
             fs::write(aliases_path, aliases_store)?;
             aliases
+        } else {
+            BTreeMap::new()
         };
 
         Ok(Self {
-            keys,
+            keys: keys.map(OnceLock::from).unwrap_or_default(),
             aliases,
             path: Some(path.to_path_buf()),
+            passphrase: passphrase.map(OnceLock::from).unwrap_or_default(),
+            kdf_params,
+            locked,
+            prompt: None,
         })
     }
 
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Whether the keystore file is encrypted and has not been unlocked yet. A locked keystore
+    /// lists its addresses, and is unlocked with the passphrase prompt the first time its keys
+    /// are used.
+    pub fn is_locked(&self) -> bool {
+        self.locked.is_some() && self.keys.get().is_none()
+    }
+
+    /// Whether the keystore file is encrypted.
+    pub fn is_encrypted(&self) -> bool {
+        self.is_locked() || self.passphrase.get().is_some()
+    }
+
+    /// Ask for the passphrase with `prompt` when the keys of a locked keystore are first needed,
+    /// i.e. to sign, export, or modify the keystore.
+    pub fn set_passphrase_prompt(&mut self, prompt: PassphrasePrompt) {
+        self.prompt = Some(prompt);
+    }
+
+    /// Decrypt the keys of a locked keystore with `passphrase`.
+    pub fn unlock(&mut self, passphrase: &str) -> Result<(), anyhow::Error> {
+        if !self.is_locked() {
+            return Ok(());
+        }
+        self.decrypt(Zeroizing::new(passphrase.to_string()))?;
+
+        let keys = self.keys.get().expect("keystore is unlocked");
+        if self.aliases.is_empty() && !keys.is_empty() {
+            self.aliases = default_aliases(keys);
+            self.save_aliases()?;
+        }
+        Ok(())
+    }
+
+    /// Encrypt the keystore file with `passphrase`, or store it in plaintext if `passphrase` is
+    /// `None`, and save it. This migrates a plaintext keystore to the encrypted format, and
+    /// changes the passphrase of an encrypted one.
+    pub fn set_passphrase(&mut self, passphrase: Option<&str>) -> Result<(), anyhow::Error> {
+        self.keys()?;
+        self.passphrase = passphrase
+            .map(|p| OnceLock::from(Zeroizing::new(p.to_string())))
+            .unwrap_or_default();
+        self.save_keystore()
+    }
+
+    /// Scrypt parameters used to derive the encryption key when the keystore is next saved.
+    pub fn set_kdf_params(&mut self, kdf_params: ScryptParams) {
+        self.kdf_params = kdf_params;
+    }
+
+    /// The keys of the keystore, unlocking it with the passphrase prompt if it is locked.
+    fn keys(&self) -> Result<&BTreeMap<SuiAddress, SuiKeyPair>, anyhow::Error> {
+        if !self.is_locked() {
+            return Ok(self.keys.get_or_init(BTreeMap::new));
+        }
+        let Some(prompt) = &self.prompt else {
+            bail!(
+                "The keystore {} is encrypted and locked. Unlock it with its passphrase, or set \
+                {KEYSTORE_PASSPHRASE_ENV_VAR}",
+                self.path.as_deref().unwrap_or(Path::new("")).display()
+            );
+        };
+        self.decrypt(Zeroizing::new(prompt()?))
+    }
+
+    fn keys_mut(&mut self) -> Result<&mut BTreeMap<SuiAddress, SuiKeyPair>, anyhow::Error> {
+        self.keys()?;
+        Ok(self.keys.get_mut().expect("keystore is unlocked"))
+    }
+
+    // Decrypt the keys of a locked keystore with `passphrase`, and keep both once they are valid.
+    fn decrypt(
+        &self,
+        passphrase: Zeroizing<String>,
+    ) -> Result<&BTreeMap<SuiAddress, SuiKeyPair>, anyhow::Error> {
+        let encrypted = self.locked.as_ref().expect("keystore is locked");
+        let keys = decrypt_keys(
+            self.path.as_deref().unwrap_or(Path::new("")),
+            encrypted,
+            &passphrase,
+        )?;
+        let _ = self.passphrase.set(passphrase);
+        Ok(self.keys.get_or_init(|| keys))
+    }
+
     pub fn set_path(&mut self, path: &Path) {
         self.path = Some(path.to_path_buf());
     }
@@ -437,15 +578,23 @@ impl FileBasedKeystore {
     /// Keys saved as Base64 with 33 bytes `flag || privkey` ($BASE64_STR).
     /// To see Bech32 format encoding, use `sui keytool export $SUI_ADDRESS` where
     /// $SUI_ADDRESS can be found with `sui keytool list`. Or use `sui keytool convert $BASE64_STR`
+    /// If the keystore has a passphrase, that list of keys is saved encrypted.
     pub fn save_keystore(&self) -> Result<(), anyhow::Error> {
         if let Some(path) = &self.path {
-            let store = serde_json::to_string_pretty(
-                &self
-                    .keys
-                    .values()
-                    .map(|k| k.encode_base64())
-                    .collect::<Vec<_>>(),
-            )
+            let keys = self
+                .keys()?
+                .values()
+                .map(|k| k.encode_base64())
+                .collect::<Vec<_>>();
+            let store = match self.passphrase.get() {
+                None => serde_json::to_string_pretty(&keys),
+                Some(passphrase) => {
+                    let plaintext = Zeroizing::new(serde_json::to_vec(&keys)?);
+                    let encrypted =
+                        EncryptedKeystore::encrypt(&plaintext, passphrase, self.kdf_params)?;
+                    serde_json::to_string_pretty(&encrypted)
+                }
+            }
             .with_context(|| format!("Cannot serialize keystore to file: {}", path.display()))?;
             fs::write(path, store)?;
         }
@@ -458,8 +607,8 @@ impl FileBasedKeystore {
         Ok(())
     }
 
-    pub fn key_pairs(&self) -> Vec<&SuiKeyPair> {
-        self.keys.values().collect()
+    pub fn key_pairs(&self) -> Result<Vec<&SuiKeyPair>, anyhow::Error> {
+        Ok(self.keys()?.values().collect())
     }
 }
 
@@ -612,7 +761,51 @@ impl InMemKeystore {
     }
 }
 
-fn validate_alias(alias: &str) -> Result<String, anyhow::Error> {
+fn decode_keys(
+    path: &Path,
+    kp_strings: &[String],
+) -> Result<BTreeMap<SuiAddress, SuiKeyPair>, anyhow::Error> {
+    kp_strings
+        .iter()
+        .map(|kpstr| {
+            let key = SuiKeyPair::decode_base64(kpstr);
+            key.map(|k| (SuiAddress::from(&k.public()), k))
+        })
+        .collect::<Result<BTreeMap<_, _>, _>>()
+        .map_err(|e| anyhow!("Invalid keystore file: {}. {}", path.display(), e))
+}
+
+fn decrypt_keys(
+    path: &Path,
+    encrypted: &EncryptedKeystore,
+    passphrase: &str,
+) -> Result<BTreeMap<SuiAddress, SuiKeyPair>, anyhow::Error> {
+    let plaintext = encrypted
+        .decrypt(passphrase)
+        .with_context(|| format!("Cannot unlock the keystore file: {}", path.display()))?;
+    let kp_strings: Vec<String> = serde_json::from_slice(&plaintext)
+        .with_context(|| format!("Cannot deserialize the keystore file: {}", path.display()))?;
+    decode_keys(path, &kp_strings)
+}
+
+// Random aliases for `keys`, used when a keystore has no aliases file.
+fn default_aliases(keys: &BTreeMap<SuiAddress, SuiKeyPair>) -> BTreeMap<SuiAddress, Alias> {
+    keys.iter()
+        .zip(random_names(HashSet::new(), keys.len()))
+        .map(|((sui_address, skp), alias)| {
+            let public_key_base64 = skp.public().encode_base64();
+            (
+                *sui_address,
+                Alias {
+                    alias,
+                    public_key_base64,
+                },
+            )
+        })
+        .collect()
+}
+
+pub(crate) fn validate_alias(alias: &str) -> Result<String, anyhow::Error> {
     let re = Regex::new(r"^[A-Za-z][A-Za-z0-9-_\.]*$")
         .map_err(|_| anyhow!("Cannot build the regex needed to validate the alias naming"))?;
     let alias = alias.trim();
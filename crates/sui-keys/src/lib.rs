@@ -1,6 +1,8 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+pub mod encrypted;
+pub mod external;
 pub mod key_derive;
 pub mod key_identity;
 pub mod keypair_file;
@@ -3,12 +3,18 @@
 
 use std::fs;
 use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 
 use fastcrypto::hash::HashFunction;
 use fastcrypto::traits::EncodeDecodeBase64;
 use sui_keys::key_derive::generate_new_key;
+use sui_keys::key_identity::KeyIdentity;
 use tempfile::TempDir;
 
+use shared_crypto::intent::Intent;
+use sui_keys::encrypted::ScryptParams;
+use sui_keys::external::{ExternalKeystore, MockSigner, SignerEndpoint};
 use sui_keys::keystore::{AccountKeystore, Alias, FileBasedKeystore, InMemKeystore, Keystore};
 use sui_types::crypto::{DefaultHash, SignatureScheme, SuiSignatureInner};
 use sui_types::{
@@ -308,6 +314,244 @@ fn remove_key_test() {
     assert!(!aliases_content.contains("test_key"));
 }
 
+// Cheap scrypt parameters, to keep tests fast
+const TEST_KDF_PARAMS: ScryptParams = ScryptParams {
+    log_n: 4,
+    r: 8,
+    p: 1,
+};
+
+#[test]
+fn encrypt_keystore_test() {
+    let temp_dir = TempDir::new().unwrap();
+    let keystore_path = temp_dir.path().join("sui.keystore");
+    let mut keystore = FileBasedKeystore::new(&keystore_path).unwrap();
+    let (address, _, _) = keystore
+        .generate(SignatureScheme::ED25519, None, None, None)
+        .unwrap();
+    let private_key = keystore.export(&address).unwrap().encode_base64();
+    assert!(!keystore.is_encrypted());
+
+    // Migrate the plaintext keystore to the encrypted format
+    keystore.set_kdf_params(TEST_KDF_PARAMS);
+    keystore.set_passphrase(Some("passphrase")).unwrap();
+    assert!(keystore.is_encrypted());
+    let content = fs::read_to_string(&keystore_path).unwrap();
+    assert!(!content.contains(&private_key));
+
+    // The keystore is loaded locked, but still lists its addresses
+    let mut keystore = FileBasedKeystore::new(&keystore_path).unwrap();
+    assert!(keystore.is_locked());
+    assert_eq!(vec![address], keystore.addresses());
+    assert!(keystore.export(&address).is_err());
+    assert!(keystore
+        .import(
+            None,
+            generate_new_key(SignatureScheme::ED25519, None, None)
+                .unwrap()
+                .1
+        )
+        .is_err());
+    assert!(keystore.unlock("wrong passphrase").is_err());
+    assert!(keystore.is_locked());
+
+    keystore.unlock("passphrase").unwrap();
+    assert!(!keystore.is_locked());
+    assert_eq!(vec![address], keystore.addresses());
+    assert_eq!(
+        private_key,
+        keystore.export(&address).unwrap().encode_base64()
+    );
+
+    // Keys added to an unlocked keystore are saved encrypted
+    let (other, _, _) = keystore
+        .generate(SignatureScheme::ED25519, None, None, None)
+        .unwrap();
+    let mut keystore = FileBasedKeystore::new(&keystore_path).unwrap();
+    keystore.unlock("passphrase").unwrap();
+    assert_eq!(2, keystore.addresses().len());
+    assert!(keystore.addresses().contains(&other));
+}
+
+#[test]
+fn unlock_keystore_on_first_use_test() {
+    let temp_dir = TempDir::new().unwrap();
+    let keystore_path = temp_dir.path().join("sui.keystore");
+    let mut keystore = FileBasedKeystore::new(&keystore_path).unwrap();
+    keystore.set_kdf_params(TEST_KDF_PARAMS);
+    keystore.set_passphrase(Some("passphrase")).unwrap();
+    let (address, _, _) = keystore
+        .generate(SignatureScheme::ED25519, None, None, None)
+        .unwrap();
+
+    let prompts = Arc::new(AtomicUsize::new(0));
+    let mut keystore = FileBasedKeystore::new(&keystore_path).unwrap();
+    keystore.set_passphrase_prompt({
+        let prompts = prompts.clone();
+        Arc::new(move || {
+            prompts.fetch_add(1, Ordering::SeqCst);
+            Ok("passphrase".to_string())
+        })
+    });
+
+    // Listing addresses does not ask for the passphrase
+    assert_eq!(vec![address], keystore.addresses());
+    assert_eq!(0, prompts.load(Ordering::SeqCst));
+
+    // Signing unlocks the keystore, once
+    let msg = "hello".to_string();
+    keystore
+        .sign_secure(&address, &msg, Intent::sui_transaction())
+        .unwrap();
+    keystore
+        .sign_secure(&address, &msg, Intent::sui_transaction())
+        .unwrap();
+    assert!(!keystore.is_locked());
+    assert_eq!(1, prompts.load(Ordering::SeqCst));
+}
+
+#[test]
+fn decrypt_keystore_test() {
+    let temp_dir = TempDir::new().unwrap();
+    let keystore_path = temp_dir.path().join("sui.keystore");
+    let mut keystore = FileBasedKeystore::new(&keystore_path).unwrap();
+    keystore.set_kdf_params(TEST_KDF_PARAMS);
+    keystore.set_passphrase(Some("passphrase")).unwrap();
+    let (address, _, _) = keystore
+        .generate(SignatureScheme::ED25519, None, None, None)
+        .unwrap();
+
+    // Locked keystores cannot be decrypted
+    let mut keystore = FileBasedKeystore::new(&keystore_path).unwrap();
+    assert!(keystore.set_passphrase(None).is_err());
+
+    keystore.unlock("passphrase").unwrap();
+    keystore.set_passphrase(None).unwrap();
+    let keystore = FileBasedKeystore::new(&keystore_path).unwrap();
+    assert!(!keystore.is_encrypted());
+    assert_eq!(vec![address], keystore.addresses());
+}
+
+#[cfg(unix)]
+#[test]
+fn external_keystore_sign_test() {
+    let temp_dir = TempDir::new().unwrap();
+    let socket = temp_dir.path().join("signer.sock");
+    let reference = InMemKeystore::new_insecure_for_tests(2);
+    MockSigner::new(InMemKeystore::new_insecure_for_tests(2))
+        .serve(&socket)
+        .unwrap();
+
+    let keystore =
+        Keystore::from(ExternalKeystore::new(SignerEndpoint::Socket { path: socket }).unwrap());
+    assert_eq!(reference.addresses(), keystore.addresses());
+    assert_eq!(2, keystore.aliases().len());
+
+    // Signatures are the same as if the keys were held locally
+    let address = reference.addresses()[0];
+    let msg = "hello".to_string();
+    let expected = reference
+        .sign_secure(&address, &msg, Intent::sui_transaction())
+        .unwrap();
+    let actual = keystore
+        .sign_secure(&address, &msg, Intent::sui_transaction())
+        .unwrap();
+    assert_eq!(expected, actual);
+
+    let hashed = reference.sign_hashed(&address, b"digest").unwrap();
+    assert_eq!(hashed, keystore.sign_hashed(&address, b"digest").unwrap());
+
+    // Unknown addresses cannot sign, and private keys never leave the signer
+    let (unknown, _, _, _) = generate_new_key(SignatureScheme::ED25519, None, None).unwrap();
+    assert!(keystore
+        .sign_secure(&unknown, &msg, Intent::sui_transaction())
+        .is_err());
+    assert!(keystore.export(&address).is_err());
+}
+
+#[cfg(unix)]
+#[test]
+fn external_keystore_manage_keys_test() {
+    let temp_dir = TempDir::new().unwrap();
+    let socket = temp_dir.path().join("signer.sock");
+    MockSigner::new(InMemKeystore::new_insecure_for_tests(1))
+        .serve(&socket)
+        .unwrap();
+
+    let mut keystore =
+        Keystore::from(ExternalKeystore::new(SignerEndpoint::Socket { path: socket }).unwrap());
+    assert!(keystore.to_string().contains("External"));
+    assert!(keystore.to_string().contains("signer.sock"));
+
+    // Keys are managed by the signer
+    assert!(keystore
+        .generate(SignatureScheme::ED25519, None, None, None)
+        .is_err());
+    let address = keystore.addresses()[0];
+    assert!(keystore.remove(address).is_err());
+    assert_eq!(1, keystore.addresses().len());
+
+    // Aliases can still be changed locally
+    let old_alias = keystore.get_alias(&address).unwrap();
+    keystore.update_alias(&old_alias, Some("hardware")).unwrap();
+    assert_eq!("hardware", keystore.get_alias(&address).unwrap());
+    assert_eq!(
+        address,
+        keystore
+            .get_by_identity(KeyIdentity::Alias("hardware".to_string()))
+            .unwrap()
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn external_keystore_command_test() {
+    let (address, keypair) = generate_new_key(SignatureScheme::ED25519, None, None)
+        .map(|(address, kp, _, _)| (address, kp))
+        .unwrap();
+    let response = format!(
+        r#"{{"keys":[{{"public_key":"{}","alias":"ledger"}}]}}"#,
+        keypair.public().encode_base64()
+    );
+    let endpoint = SignerEndpoint::Command {
+        command: "sh".to_string(),
+        args: vec!["-c".to_string(), format!("read -r _ && echo '{response}'")],
+    };
+
+    let keystore = Keystore::from(ExternalKeystore::new(endpoint.clone()).unwrap());
+    assert_eq!(vec![address], keystore.addresses());
+    assert_eq!("ledger", keystore.get_alias(&address).unwrap());
+
+    // The keystore is saved as the endpoint of its signer
+    let json = serde_json::to_string(&keystore).unwrap();
+    let Keystore::External(keystore) = serde_json::from_str(&json).unwrap() else {
+        panic!("Expected an external keystore");
+    };
+    assert_eq!(&endpoint, keystore.endpoint());
+    assert_eq!(vec![address], keystore.addresses());
+
+    // The canned signer cannot sign
+    assert!(keystore.sign_hashed(&address, b"digest").is_err());
+}
+
+#[test]
+fn external_keystore_unreachable_signer_test() {
+    let temp_dir = TempDir::new().unwrap();
+    let endpoint = SignerEndpoint::Socket {
+        path: temp_dir.path().join("missing.sock"),
+    };
+    assert!(ExternalKeystore::new(endpoint.clone()).is_err());
+
+    // Loading the keystore from its config does not contact the signer, only using it does
+    let json = serde_json::to_string(&endpoint).unwrap();
+    let keystore: ExternalKeystore = serde_json::from_str(&json).unwrap();
+    assert_eq!(&endpoint, keystore.endpoint());
+    let (address, _, _, _) = generate_new_key(SignatureScheme::ED25519, None, None).unwrap();
+    assert!(keystore.sign_hashed(&address, b"digest").is_err());
+    assert!(keystore.get_alias(&address).is_err());
+    assert!(keystore.addresses().is_empty());
+}
+
 fn alias_names(aliases: Vec<&Alias>) -> Vec<&str> {
     aliases
         .into_iter()
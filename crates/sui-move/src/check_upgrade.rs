@@ -0,0 +1,41 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use clap::{Parser, ValueEnum};
+use sui_types::{base_types::ObjectID, move_package::UpgradePolicy};
+
+/// Check that the package can upgrade an on-chain package, without building an upgrade
+/// transaction. Requires a connection to the network the package is published on.
+#[derive(Parser)]
+#[group(id = "sui-move-check-upgrade")]
+pub struct CheckUpgrade {
+    /// The ID of the on-chain package to compare with.
+    #[clap(long = "package-id", value_parser = ObjectID::from_hex_literal)]
+    pub package_id: ObjectID,
+    /// The upgrade policy to check against.
+    #[clap(long, value_enum, default_value_t = Policy::Compatible)]
+    pub policy: Policy,
+    /// Also include unpublished dependencies in the package.
+    #[clap(long)]
+    pub with_unpublished_dependencies: bool,
+    /// Print every incompatibility as JSON instead of rendering them as diagnostics.
+    #[clap(long)]
+    pub json: bool,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum Policy {
+    Compatible,
+    Additive,
+    DepOnly,
+}
+
+impl From<Policy> for UpgradePolicy {
+    fn from(policy: Policy) -> Self {
+        match policy {
+            Policy::Compatible => UpgradePolicy::Compatible,
+            Policy::Additive => UpgradePolicy::Additive,
+            Policy::DepOnly => UpgradePolicy::DepOnly,
+        }
+    }
+}
@@ -9,6 +9,7 @@ use sui_move_build::{implicit_deps, set_sui_flavor, SuiPackageHooks};
 use sui_package_management::system_package_versions::latest_system_packages;
 
 pub mod build;
+pub mod check_upgrade;
 pub mod coverage;
 pub mod disassemble;
 pub mod manage_package;
@@ -20,6 +21,7 @@ pub mod unit_test;
 #[derive(Parser)]
 pub enum Command {
     Build(build::Build),
+    CheckUpgrade(check_upgrade::CheckUpgrade),
     Coverage(coverage::Coverage),
     Disassemble(disassemble::Disassemble),
     ManagePackage(manage_package::ManagePackage),
@@ -50,6 +52,9 @@ pub fn execute_move_command(
     move_package::package_hooks::register_package_hooks(Box::new(SuiPackageHooks));
     match command {
         Command::Build(c) => c.execute(package_path, build_config),
+        Command::CheckUpgrade(_) => {
+            anyhow::bail!("`sui move check-upgrade` requires a connection to the network")
+        }
         Command::Coverage(c) => c.execute(package_path, build_config),
         Command::Disassemble(c) => c.execute(package_path, build_config),
         Command::ManagePackage(c) => c.execute(package_path, build_config),
@@ -0,0 +1,474 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs,
+    io::{ErrorKind, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use anyhow::Context;
+use fastcrypto::hash::{Blake2b256, HashFunction};
+use mysten_common::fs::write_atomic;
+use parquet::basic::Compression;
+use tempfile::{NamedTempFile, TempPath};
+use tokio::sync::Mutex as AsyncMutex;
+use tracing::{debug, warn};
+
+use crate::watermark::StoredWatermark;
+
+pub use row::{ParquetRow, ParquetSchema, ParquetValue};
+pub use sui_indexer_alt_framework_store_traits::Store;
+
+mod row;
+pub mod store;
+mod watermark;
+
+/// Prefix for the per-epoch directories that a pipeline's files are partitioned into.
+const EPOCH_DIR_PREFIX: &str = "epoch_";
+
+/// Name of the file holding a pipeline's watermark, inside that pipeline's directory. The leading
+/// underscore causes most data lake tooling to skip it when scanning for data files.
+const WATERMARK_FILE: &str = "_watermark.json";
+
+const PARQUET_SUFFIX: &str = "parquet";
+
+/// Number of bytes of a data file's content digest that are included in its name.
+const DIGEST_PREFIX_LEN: usize = 8;
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct ParquetArgs {
+    /// Maximum width of the checkpoint range covered by a single Parquet file. Each commit writes
+    /// at least one file per epoch it touches, and splits rows further so that no file spans more
+    /// than this many checkpoints.
+    #[arg(long, default_value_t = Self::default().parquet_checkpoints_per_file)]
+    pub parquet_checkpoints_per_file: u64,
+}
+
+/// A store that writes each pipeline's rows to Parquet files on the local filesystem, laid out as:
+///
+/// ```text
+/// {root}/{pipeline}/_watermark.json
+/// {root}/{pipeline}/epoch_{epoch}/{checkpoint_lo}_{checkpoint_hi_exclusive}_{digest}.parquet
+/// ```
+///
+/// File names include a digest of their contents, so re-committing the same batch (e.g. on retry)
+/// overwrites the previous attempt rather than duplicating it. Instances of [ParquetStore] can be
+/// cloned to share access to the same directory.
+///
+/// Sequential pipelines write their files and watermark in the same transaction, so their files
+/// never extend beyond their watermark. Concurrent pipelines write files outside of transactions,
+/// out of order, and their watermark trails behind. In both cases, rows beyond the watermark are
+/// discarded when the pipeline restarts, because the checkpoints they came from will be processed
+/// again: Files that start after the watermark are removed, and files that straddle it are
+/// rewritten to only contain rows from checkpoints up to the watermark.
+#[derive(Clone)]
+pub struct ParquetStore {
+    root: Arc<PathBuf>,
+    checkpoints_per_file: u64,
+
+    /// Serializes read-modify-write operations on watermark files, and the files they cover.
+    lock: Arc<AsyncMutex<()>>,
+
+    /// Pipelines whose data files have been checked for left-overs from a previous run.
+    recovered: Arc<Mutex<HashSet<String>>>,
+}
+
+/// A handle for writing to a [ParquetStore]. When created as part of a transaction, data files and
+/// watermark updates are staged, and only become visible once the transaction succeeds.
+pub struct Connection<'c> {
+    store: &'c ParquetStore,
+    pending: Option<Pending>,
+}
+
+/// Writes made inside a transaction that have not been applied yet.
+#[derive(Default)]
+struct Pending {
+    /// Data files written to a temporary location, paired with their final location.
+    files: Vec<(TempPath, PathBuf)>,
+
+    /// Updated watermarks, by pipeline.
+    watermarks: BTreeMap<String, StoredWatermark>,
+}
+
+/// A data file, identified by the pipeline and checkpoint range it holds rows for.
+struct DataFile {
+    path: PathBuf,
+    checkpoint_lo: u64,
+    checkpoint_hi_exclusive: u64,
+}
+
+impl ParquetArgs {
+    pub fn checkpoints_per_file(&self) -> u64 {
+        self.parquet_checkpoints_per_file.max(1)
+    }
+}
+
+impl ParquetStore {
+    /// Create a store that writes Parquet files under the `root` directory, creating it if it
+    /// does not exist yet.
+    pub fn new(root: impl Into<PathBuf>, args: ParquetArgs) -> anyhow::Result<Self> {
+        let root = root.into();
+        fs::create_dir_all(&root)
+            .with_context(|| format!("Failed to create {}", root.display()))?;
+
+        Ok(Self {
+            root: Arc::new(root),
+            checkpoints_per_file: args.checkpoints_per_file(),
+            lock: Arc::new(AsyncMutex::new(())),
+            recovered: Arc::new(Mutex::new(HashSet::new())),
+        })
+    }
+
+    /// The directory that this store writes to.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// The directory holding `pipeline`'s data files and watermark.
+    pub fn pipeline_dir(&self, pipeline: &str) -> PathBuf {
+        self.root.join(pipeline)
+    }
+
+    /// Paths to all of `pipeline`'s committed data files, ordered by the checkpoint range they
+    /// cover.
+    pub fn files(&self, pipeline: &str) -> anyhow::Result<Vec<PathBuf>> {
+        Ok(self
+            .data_files(pipeline)?
+            .into_iter()
+            .map(|f| f.path)
+            .collect())
+    }
+
+    fn watermark_path(&self, pipeline: &str) -> PathBuf {
+        self.pipeline_dir(pipeline).join(WATERMARK_FILE)
+    }
+
+    fn data_files(&self, pipeline: &str) -> anyhow::Result<Vec<DataFile>> {
+        let mut files = vec![];
+
+        let epochs = match fs::read_dir(self.pipeline_dir(pipeline)) {
+            Ok(epochs) => epochs,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(files),
+            Err(e) => return Err(e.into()),
+        };
+
+        for epoch in epochs {
+            let epoch = epoch?;
+            if !epoch.file_type()?.is_dir()
+                || !epoch
+                    .file_name()
+                    .to_string_lossy()
+                    .starts_with(EPOCH_DIR_PREFIX)
+            {
+                continue;
+            }
+
+            for file in fs::read_dir(epoch.path())? {
+                let path = file?.path();
+                if let Some(file) = DataFile::parse(path) {
+                    files.push(file);
+                }
+            }
+        }
+
+        files.sort_by_key(|f| (f.checkpoint_lo, f.checkpoint_hi_exclusive));
+        Ok(files)
+    }
+}
+
+impl<'c> Connection<'c> {
+    fn new(store: &'c ParquetStore, transactional: bool) -> Self {
+        Self {
+            store,
+            pending: transactional.then(Pending::default),
+        }
+    }
+
+    /// Write `rows` for `pipeline`, returning the number of rows written. Rows are partitioned
+    /// into files by epoch, and then by checkpoint range, so a single call may produce several
+    /// files. Inside a transaction, the files are only moved into place when it commits.
+    pub fn write_rows<R: ParquetRow>(
+        &mut self,
+        pipeline: &str,
+        rows: &[R],
+    ) -> anyhow::Result<usize> {
+        let width = self.store.checkpoints_per_file;
+
+        let mut partitions: BTreeMap<(u64, u64), Vec<&R>> = BTreeMap::new();
+        for row in rows {
+            let key = (row.epoch(), row.checkpoint() / width);
+            partitions.entry(key).or_default().push(row);
+        }
+
+        for ((epoch, _), rows) in partitions {
+            let (lo, hi) = rows.iter().fold((u64::MAX, 0), |(lo, hi), r| {
+                (lo.min(r.checkpoint()), hi.max(r.checkpoint()))
+            });
+
+            let bytes = row::encode(rows.iter().copied(), Compression::SNAPPY)
+                .with_context(|| format!("Failed to encode rows for {pipeline}"))?;
+
+            // The digest is stable across processes and releases, so that a retry after a restart
+            // produces the same file name.
+            let digest = Blake2b256::digest(&bytes);
+            let path = self
+                .store
+                .pipeline_dir(pipeline)
+                .join(format!("{EPOCH_DIR_PREFIX}{epoch}"))
+                .join(format!(
+                    "{lo}_{}_{}.{PARQUET_SUFFIX}",
+                    hi + 1,
+                    hex::encode(&digest.digest[..DIGEST_PREFIX_LEN])
+                ));
+
+            // Files are always written to a temporary location first, so that readers never
+            // observe a partially written file.
+            if let Some(pending) = &mut self.pending {
+                let dir = path.parent().unwrap();
+                fs::create_dir_all(dir)?;
+
+                let mut tmp = NamedTempFile::new_in(dir)?;
+                tmp.write_all(&bytes)
+                    .with_context(|| format!("Failed to write {}", tmp.path().display()))?;
+                pending.files.push((tmp.into_temp_path(), path));
+            } else {
+                write_atomic(&path, &bytes)?;
+            }
+        }
+
+        Ok(rows.len())
+    }
+
+    /// Delete `pipeline`'s data files that only contain rows from checkpoints in the range
+    /// `[from, to_exclusive)`, returning the number of files removed. Files that straddle the
+    /// boundary are kept until a later call covers them entirely.
+    pub fn prune(&mut self, pipeline: &str, from: u64, to_exclusive: u64) -> anyhow::Result<usize> {
+        let mut pruned = 0;
+        for file in self.store.data_files(pipeline)? {
+            if from <= file.checkpoint_lo && file.checkpoint_hi_exclusive <= to_exclusive {
+                file.remove()?;
+                pruned += 1;
+            }
+        }
+
+        Ok(pruned)
+    }
+
+    /// Discard rows that a previous run of `pipeline` wrote beyond its committed watermark. This
+    /// happens once per pipeline, the first time its committer watermark is read, which is when
+    /// the pipeline starts up.
+    async fn recover(&self, pipeline: &str) -> anyhow::Result<()> {
+        let _guard = self.store.lock.lock().await;
+        if !self
+            .store
+            .recovered
+            .lock()
+            .unwrap()
+            .insert(pipeline.to_owned())
+        {
+            return Ok(());
+        }
+
+        let committed = StoredWatermark::read(&self.store.watermark_path(pipeline))?;
+        remove_uncommitted(self.store, pipeline, committed)
+    }
+
+    /// The latest watermark for `pipeline`, taking into account any changes staged in the current
+    /// transaction.
+    fn watermark(&self, pipeline: &str) -> anyhow::Result<Option<StoredWatermark>> {
+        if let Some(watermark) = self
+            .pending
+            .as_ref()
+            .and_then(|p| p.watermarks.get(pipeline))
+        {
+            return Ok(Some(*watermark));
+        }
+
+        StoredWatermark::read(&self.store.watermark_path(pipeline))
+    }
+
+    /// Apply `update` to `pipeline`'s watermark. The update returns whether it modified the
+    /// watermark, in which case the change is either staged (inside a transaction) or persisted.
+    async fn update_watermark(
+        &mut self,
+        pipeline: &str,
+        update: impl FnOnce(&mut Option<StoredWatermark>) -> bool,
+    ) -> anyhow::Result<bool> {
+        let _guard = self.store.lock.lock().await;
+
+        let mut watermark = self.watermark(pipeline)?;
+        if !update(&mut watermark) {
+            return Ok(false);
+        }
+
+        let Some(watermark) = watermark else {
+            return Ok(false);
+        };
+
+        if let Some(pending) = &mut self.pending {
+            pending.watermarks.insert(pipeline.to_owned(), watermark);
+        } else {
+            watermark.write(&self.store.watermark_path(pipeline))?;
+        }
+
+        Ok(true)
+    }
+}
+
+impl Pending {
+    /// Make the transaction's writes visible: Data files are moved into place before watermarks
+    /// are updated. If this fails part way through, files that were moved into place are removed
+    /// again (unless an identical file was already there), and the remaining writes are
+    /// discarded. Data left behind by a crash during the commit is cleaned up when the pipeline
+    /// restarts.
+    async fn commit(self, store: &ParquetStore) -> anyhow::Result<()> {
+        let _guard = store.lock.lock().await;
+
+        let mut moved = vec![];
+        let result = self.apply(store, &mut moved);
+
+        if result.is_err() {
+            for path in moved {
+                if let Err(e) = fs::remove_file(&path) {
+                    warn!(path = %path.display(), "Failed to roll back write: {e}");
+                }
+            }
+
+            self.abort();
+        }
+
+        result
+    }
+
+    /// Move data files into place, followed by watermarks, recording the paths of files that
+    /// did not exist before in `moved`.
+    fn apply(&self, store: &ParquetStore, moved: &mut Vec<PathBuf>) -> anyhow::Result<()> {
+        for (tmp, path) in &self.files {
+            let existed = path.try_exists()?;
+            fs::rename(tmp, path)
+                .with_context(|| format!("Failed to move data into {}", path.display()))?;
+
+            if !existed {
+                moved.push(path.clone());
+            }
+        }
+
+        for (pipeline, watermark) in &self.watermarks {
+            watermark.write(&store.watermark_path(pipeline))?;
+        }
+
+        Ok(())
+    }
+
+    /// Discard the transaction's writes that have not been moved into place.
+    fn abort(self) {
+        for (tmp, _) in self.files {
+            let path = tmp.to_path_buf();
+            match tmp.close() {
+                Err(e) if e.kind() != ErrorKind::NotFound => {
+                    warn!(path = %path.display(), "Failed to clean up aborted write: {e}");
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+impl DataFile {
+    /// Recognize a data file from its path, which must follow the layout described in
+    /// [ParquetStore].
+    fn parse(path: PathBuf) -> Option<Self> {
+        if path.extension()? != PARQUET_SUFFIX {
+            return None;
+        }
+
+        let stem = path.file_stem()?.to_str()?;
+        let mut parts = stem.splitn(3, '_');
+        let checkpoint_lo = parts.next()?.parse().ok()?;
+        let checkpoint_hi_exclusive = parts.next()?.parse().ok()?;
+
+        Some(Self {
+            path,
+            checkpoint_lo,
+            checkpoint_hi_exclusive,
+        })
+    }
+
+    /// Replace this file with one that only contains its rows from checkpoints before
+    /// `checkpoint_hi_exclusive`, which must fall inside the file's checkpoint range.
+    fn truncate(self, checkpoint_hi_exclusive: u64) -> anyhow::Result<()> {
+        debug!(path = %self.path.display(), checkpoint_hi_exclusive, "Truncating data file");
+        let file = fs::File::open(&self.path)
+            .with_context(|| format!("Failed to open {}", self.path.display()))?;
+
+        let bytes = row::truncate(file, checkpoint_hi_exclusive - 1, Compression::SNAPPY)
+            .with_context(|| format!("Failed to truncate {}", self.path.display()))?;
+
+        let digest = Blake2b256::digest(&bytes);
+        let path = self.path.with_file_name(format!(
+            "{}_{checkpoint_hi_exclusive}_{}.{PARQUET_SUFFIX}",
+            self.checkpoint_lo,
+            hex::encode(&digest.digest[..DIGEST_PREFIX_LEN])
+        ));
+
+        write_atomic(&path, &bytes)?;
+        self.remove()
+    }
+
+    fn remove(self) -> anyhow::Result<()> {
+        debug!(path = %self.path.display(), "Removing data file");
+        fs::remove_file(&self.path)
+            .with_context(|| format!("Failed to remove {}", self.path.display()))?;
+
+        // Clean up the epoch directory if this was its last file.
+        if let Some(dir) = self.path.parent() {
+            if fs::read_dir(dir)?.next().is_none() {
+                let _ = fs::remove_dir(dir);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for ParquetArgs {
+    fn default() -> Self {
+        Self {
+            parquet_checkpoints_per_file: 1000,
+        }
+    }
+}
+
+/// Discard rows for `pipeline` from checkpoints after its `committed` watermark. These are left
+/// behind by a concurrent pipeline whose watermark had not caught up with its writes when it
+/// stopped, or by a sequential pipeline that stopped part way through committing, and will be
+/// re-written when the checkpoints they came from are re-processed. Files that start after the
+/// watermark are removed, and files that straddle it are rewritten to only keep the rows up to it.
+fn remove_uncommitted(
+    store: &ParquetStore,
+    pipeline: &str,
+    committed: Option<StoredWatermark>,
+) -> anyhow::Result<()> {
+    let first_uncommitted = committed.map_or(0, |w| w.checkpoint_hi_inclusive + 1);
+
+    let mut removed = vec![];
+    let mut truncated = vec![];
+    for file in store.data_files(pipeline)? {
+        if file.checkpoint_lo >= first_uncommitted {
+            removed.push(file.path.clone());
+            file.remove()?;
+        } else if file.checkpoint_hi_exclusive > first_uncommitted {
+            truncated.push(file.path.clone());
+            file.truncate(first_uncommitted)?;
+        }
+    }
+
+    if !removed.is_empty() || !truncated.is_empty() {
+        warn!(pipeline, ?removed, ?truncated, "Discarded uncommitted data");
+    }
+
+    Ok(())
+}
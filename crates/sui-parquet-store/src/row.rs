@@ -0,0 +1,368 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{collections::HashMap, sync::Arc};
+
+use anyhow::{anyhow, bail, Context};
+use arrow_array::{
+    builder::{BooleanBuilder, GenericStringBuilder, Int64Builder, UInt64Builder},
+    ArrayRef, RecordBatch,
+};
+use parquet::arrow::{arrow_reader::ParquetRecordBatchReaderBuilder, ArrowWriter};
+use parquet::basic::Compression;
+use parquet::file::{properties::WriterProperties, reader::ChunkReader};
+use parquet::format::KeyValue;
+
+type StrBuilder = GenericStringBuilder<i32>;
+
+/// Key of the file-level metadata entry that records how many rows each checkpoint contributed to
+/// a file, as comma-separated `{checkpoint}:{rows}` pairs, in checkpoint order.
+const CHECKPOINT_ROWS_KEY: &str = "sui.checkpoint_rows";
+
+/// A single cell of a row written to a Parquet file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParquetValue {
+    U64(u64),
+    Str(String),
+    Bool(bool),
+    I64(i64),
+    OptionU64(Option<u64>),
+    OptionStr(Option<String>),
+}
+
+/// The columns of a type that is written out as rows of a Parquet file.
+pub trait ParquetSchema {
+    /// Column names, in the order that their values are returned by [Self::get_column].
+    fn schema() -> Vec<String>;
+
+    /// The value of the `idx`-th column of this row. The variant returned for a given column must
+    /// be the same across all rows.
+    fn get_column(&self, idx: usize) -> ParquetValue;
+}
+
+/// A row that can be written to a Parquet file by the [crate::ParquetStore]. Pipelines writing to
+/// Parquet implement this for their `Processor::Value`.
+pub trait ParquetRow: ParquetSchema {
+    /// The epoch this row was produced in, used to partition rows into directories.
+    fn epoch(&self) -> u64;
+
+    /// The checkpoint this row was produced from, used to partition rows into files.
+    fn checkpoint(&self) -> u64;
+}
+
+impl From<u64> for ParquetValue {
+    fn from(value: u64) -> Self {
+        Self::U64(value)
+    }
+}
+
+impl From<i64> for ParquetValue {
+    fn from(value: i64) -> Self {
+        Self::I64(value)
+    }
+}
+
+impl From<String> for ParquetValue {
+    fn from(value: String) -> Self {
+        Self::Str(value)
+    }
+}
+
+impl From<Option<u64>> for ParquetValue {
+    fn from(value: Option<u64>) -> Self {
+        Self::OptionU64(value)
+    }
+}
+
+impl From<Option<String>> for ParquetValue {
+    fn from(value: Option<String>) -> Self {
+        Self::OptionStr(value)
+    }
+}
+
+impl From<bool> for ParquetValue {
+    fn from(value: bool) -> Self {
+        Self::Bool(value)
+    }
+}
+
+enum ColumnBuilder {
+    U64(UInt64Builder),
+    I64(Int64Builder),
+    Bool(BooleanBuilder),
+    Str(StrBuilder),
+}
+
+impl ColumnBuilder {
+    fn for_value(value: &ParquetValue) -> Self {
+        match value {
+            ParquetValue::U64(_) | ParquetValue::OptionU64(_) => Self::U64(UInt64Builder::new()),
+            ParquetValue::I64(_) => Self::I64(Int64Builder::new()),
+            ParquetValue::Bool(_) => Self::Bool(BooleanBuilder::new()),
+            ParquetValue::Str(_) | ParquetValue::OptionStr(_) => Self::Str(StrBuilder::new()),
+        }
+    }
+
+    fn append(&mut self, value: ParquetValue) -> anyhow::Result<()> {
+        match (self, value) {
+            (Self::U64(b), ParquetValue::U64(v)) => b.append_value(v),
+            (Self::I64(b), ParquetValue::I64(v)) => b.append_value(v),
+            (Self::Bool(b), ParquetValue::Bool(v)) => b.append_value(v),
+            (Self::Str(b), ParquetValue::Str(v)) => b.append_value(&v),
+            (Self::U64(b), ParquetValue::OptionU64(v)) => b.append_option(v),
+            (Self::Str(b), ParquetValue::OptionStr(v)) => b.append_option(v),
+            (_, v) => bail!("Unexpected value {v:?}"),
+        }
+
+        Ok(())
+    }
+
+    fn finish(self) -> ArrayRef {
+        match self {
+            Self::U64(mut b) => Arc::new(b.finish()),
+            Self::I64(mut b) => Arc::new(b.finish()),
+            Self::Bool(mut b) => Arc::new(b.finish()),
+            Self::Str(mut b) => Arc::new(b.finish()),
+        }
+    }
+}
+
+/// Encode `rows` as a single Parquet file, compressed with `compression`, returning its contents.
+/// Column types are inferred from the first row. Rows are written in checkpoint order, and the
+/// number of rows from each checkpoint is recorded in the file's metadata, so that the file can
+/// later be [truncate]d to a prefix of its checkpoint range.
+pub(crate) fn encode<'r, R: ParquetRow + 'r>(
+    rows: impl IntoIterator<Item = &'r R>,
+    compression: Compression,
+) -> anyhow::Result<Vec<u8>> {
+    let schema = R::schema();
+    let mut rows: Vec<_> = rows.into_iter().collect();
+    rows.sort_by_key(|r| r.checkpoint());
+
+    let first = rows.first().ok_or_else(|| anyhow!("No rows to encode"))?;
+    let mut builders: Vec<_> = (0..schema.len())
+        .map(|i| ColumnBuilder::for_value(&first.get_column(i)))
+        .collect();
+
+    let mut checkpoint_rows: Vec<(u64, usize)> = vec![];
+    for row in &rows {
+        match checkpoint_rows.last_mut() {
+            Some((cp, count)) if *cp == row.checkpoint() => *count += 1,
+            _ => checkpoint_rows.push((row.checkpoint(), 1)),
+        }
+
+        for (i, builder) in builders.iter_mut().enumerate() {
+            builder
+                .append(row.get_column(i))
+                .map_err(|e| anyhow!("Type mismatch on column {:?}: {e}", schema[i]))?;
+        }
+    }
+
+    let arrays = builders.into_iter().map(ColumnBuilder::finish);
+    let batch = RecordBatch::try_from_iter(schema.into_iter().zip(arrays))?;
+    write(&[batch], &checkpoint_rows, compression)
+}
+
+/// Re-encode the Parquet file in `data`, keeping only the rows from checkpoints up to and
+/// including `checkpoint_hi_inclusive`. The file must have been written by [encode], and must
+/// contain at least one such row.
+pub(crate) fn truncate<T: ChunkReader + 'static>(
+    data: T,
+    checkpoint_hi_inclusive: u64,
+    compression: Compression,
+) -> anyhow::Result<Vec<u8>> {
+    let builder = ParquetRecordBatchReaderBuilder::try_new(data)?;
+
+    let checkpoint_rows = builder
+        .metadata()
+        .file_metadata()
+        .key_value_metadata()
+        .and_then(|kvs| kvs.iter().find(|kv| kv.key == CHECKPOINT_ROWS_KEY))
+        .and_then(|kv| kv.value.as_deref())
+        .context("Missing per-checkpoint row counts")?;
+
+    let checkpoint_rows = parse_checkpoint_rows(checkpoint_rows)?;
+    let checkpoint_rows: Vec<_> = checkpoint_rows
+        .into_iter()
+        .take_while(|(cp, _)| *cp <= checkpoint_hi_inclusive)
+        .collect();
+
+    // Drop metadata carried over from the original file, so it does not describe the original
+    // rows.
+    let schema = Arc::new(
+        builder
+            .schema()
+            .as_ref()
+            .clone()
+            .with_metadata(HashMap::new()),
+    );
+
+    let mut remaining: usize = checkpoint_rows.iter().map(|(_, count)| count).sum();
+    let mut batches = vec![];
+    for batch in builder.build()? {
+        if remaining == 0 {
+            break;
+        }
+
+        let batch = batch?;
+        let len = batch.num_rows().min(remaining);
+        remaining -= len;
+        batches.push(RecordBatch::try_new(
+            schema.clone(),
+            batch.slice(0, len).columns().to_vec(),
+        )?);
+    }
+
+    if batches.is_empty() {
+        bail!("No rows at or below checkpoint {checkpoint_hi_inclusive}");
+    }
+
+    write(&batches, &checkpoint_rows, compression)
+}
+
+/// Write `batches`, which must share a schema, to a single Parquet file, with `checkpoint_rows`
+/// recorded in its metadata.
+fn write(
+    batches: &[RecordBatch],
+    checkpoint_rows: &[(u64, usize)],
+    compression: Compression,
+) -> anyhow::Result<Vec<u8>> {
+    let checkpoint_rows = checkpoint_rows
+        .iter()
+        .map(|(cp, count)| format!("{cp}:{count}"))
+        .collect::<Vec<_>>()
+        .join(",");
+
+    let properties = WriterProperties::builder()
+        .set_compression(compression)
+        .set_key_value_metadata(Some(vec![KeyValue::new(
+            CHECKPOINT_ROWS_KEY.to_owned(),
+            checkpoint_rows,
+        )]))
+        .build();
+
+    let mut buf = vec![];
+    let mut writer = ArrowWriter::try_new(&mut buf, batches[0].schema(), Some(properties))?;
+    for batch in batches {
+        writer.write(batch)?;
+    }
+
+    writer.close()?;
+    Ok(buf)
+}
+
+fn parse_checkpoint_rows(value: &str) -> anyhow::Result<Vec<(u64, usize)>> {
+    value
+        .split(',')
+        .map(|pair| {
+            let (cp, count) = pair
+                .split_once(':')
+                .with_context(|| format!("Invalid per-checkpoint row count {pair:?}"))?;
+            Ok((cp.parse()?, count.parse()?))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow_array::{cast::AsArray, types::UInt64Type, Array};
+
+    use super::*;
+
+    struct Row(u64, Option<String>);
+
+    impl ParquetSchema for Row {
+        fn schema() -> Vec<String> {
+            vec!["cp".to_owned(), "name".to_owned()]
+        }
+
+        fn get_column(&self, idx: usize) -> ParquetValue {
+            match idx {
+                0 => ParquetValue::U64(self.0),
+                1 => ParquetValue::OptionStr(self.1.clone()),
+                _ => unreachable!(),
+            }
+        }
+    }
+
+    impl ParquetRow for Row {
+        fn epoch(&self) -> u64 {
+            0
+        }
+
+        fn checkpoint(&self) -> u64 {
+            self.0
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let rows = vec![Row(1, Some("a".to_owned())), Row(2, None)];
+        let bytes = encode(&rows, Compression::SNAPPY).unwrap();
+
+        let reader = ParquetRecordBatchReaderBuilder::try_new(bytes::Bytes::from(bytes))
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let batches: Vec<_> = reader.map(Result::unwrap).collect();
+        assert_eq!(batches.len(), 1);
+
+        let batch = &batches[0];
+        assert_eq!(batch.num_rows(), 2);
+
+        let cps = batch.column(0).as_primitive::<UInt64Type>();
+        assert_eq!(cps.values(), &[1, 2]);
+
+        let names = batch.column(1).as_string::<i32>();
+        assert_eq!(names.value(0), "a");
+        assert!(names.is_null(1));
+    }
+
+    #[test]
+    fn test_deterministic() {
+        let rows = vec![Row(1, Some("a".to_owned())), Row(2, None)];
+        let a = encode(&rows, Compression::SNAPPY).unwrap();
+        let b = encode(&rows, Compression::SNAPPY).unwrap();
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn test_truncate() {
+        let rows = vec![
+            Row(3, Some("c".to_owned())),
+            Row(1, Some("a".to_owned())),
+            Row(2, None),
+            Row(3, None),
+        ];
+
+        let bytes = encode(&rows, Compression::SNAPPY).unwrap();
+        let bytes = truncate(bytes::Bytes::from(bytes), 2, Compression::SNAPPY).unwrap();
+
+        let reader = ParquetRecordBatchReaderBuilder::try_new(bytes::Bytes::from(bytes.clone()))
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let cps: Vec<u64> = reader
+            .map(Result::unwrap)
+            .flat_map(|b| b.column(0).as_primitive::<UInt64Type>().values().to_vec())
+            .collect();
+        assert_eq!(cps, vec![1, 2]);
+
+        // The truncated file can itself be truncated further.
+        let bytes = truncate(bytes::Bytes::from(bytes), 1, Compression::SNAPPY).unwrap();
+        let reader = ParquetRecordBatchReaderBuilder::try_new(bytes::Bytes::from(bytes))
+            .unwrap()
+            .build()
+            .unwrap();
+        let rows: usize = reader.map(|b| b.unwrap().num_rows()).sum();
+        assert_eq!(rows, 1);
+    }
+
+    #[test]
+    fn test_empty() {
+        let rows: Vec<Row> = vec![];
+        assert!(encode(&rows, Compression::SNAPPY).is_err());
+    }
+}
@@ -0,0 +1,413 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use scoped_futures::ScopedBoxFuture;
+use sui_indexer_alt_framework_store_traits as store;
+
+use crate::watermark::{now_ms, StoredWatermark};
+use crate::{Connection, ParquetStore};
+
+#[async_trait]
+impl store::Connection for Connection<'_> {
+    async fn committer_watermark(
+        &mut self,
+        pipeline: &'static str,
+    ) -> anyhow::Result<Option<store::CommitterWatermark>> {
+        self.recover(pipeline).await?;
+        Ok(self
+            .watermark(pipeline)?
+            .map(|w| store::CommitterWatermark {
+                epoch_hi_inclusive: w.epoch_hi_inclusive,
+                checkpoint_hi_inclusive: w.checkpoint_hi_inclusive,
+                tx_hi: w.tx_hi,
+                timestamp_ms_hi_inclusive: w.timestamp_ms_hi_inclusive,
+            }))
+    }
+
+    async fn reader_watermark(
+        &mut self,
+        pipeline: &'static str,
+    ) -> anyhow::Result<Option<store::ReaderWatermark>> {
+        Ok(self.watermark(pipeline)?.map(|w| store::ReaderWatermark {
+            checkpoint_hi_inclusive: w.checkpoint_hi_inclusive,
+            reader_lo: w.reader_lo,
+        }))
+    }
+
+    async fn pruner_watermark(
+        &mut self,
+        pipeline: &'static str,
+        delay: Duration,
+    ) -> anyhow::Result<Option<store::PrunerWatermark>> {
+        //     |---------- + delay ---------------------|
+        //                             |--- wait_for ---|
+        //     |-----------------------|----------------|
+        //     ^                       ^
+        //     pruner_timestamp        NOW()
+        let now = now_ms() as i64;
+        Ok(self.watermark(pipeline)?.map(|w| store::PrunerWatermark {
+            wait_for_ms: w.pruner_timestamp_ms as i64 + delay.as_millis() as i64 - now,
+            reader_lo: w.reader_lo,
+            pruner_hi: w.pruner_hi,
+        }))
+    }
+
+    async fn set_committer_watermark(
+        &mut self,
+        pipeline: &'static str,
+        watermark: store::CommitterWatermark,
+    ) -> anyhow::Result<bool> {
+        self.update_watermark(pipeline, |stored| {
+            // Only write the new `hi` values if they advance the existing entry.
+            if stored
+                .as_ref()
+                .is_some_and(|s| s.checkpoint_hi_inclusive >= watermark.checkpoint_hi_inclusive)
+            {
+                return false;
+            }
+
+            let stored = stored.get_or_insert_with(StoredWatermark::default);
+            stored.epoch_hi_inclusive = watermark.epoch_hi_inclusive;
+            stored.checkpoint_hi_inclusive = watermark.checkpoint_hi_inclusive;
+            stored.tx_hi = watermark.tx_hi;
+            stored.timestamp_ms_hi_inclusive = watermark.timestamp_ms_hi_inclusive;
+            true
+        })
+        .await
+    }
+
+    async fn set_reader_watermark(
+        &mut self,
+        pipeline: &'static str,
+        reader_lo: u64,
+    ) -> anyhow::Result<bool> {
+        self.update_watermark(pipeline, |stored| match stored {
+            Some(s) if s.reader_lo < reader_lo => {
+                s.reader_lo = reader_lo;
+                s.pruner_timestamp_ms = now_ms();
+                true
+            }
+            _ => false,
+        })
+        .await
+    }
+
+    async fn set_pruner_watermark(
+        &mut self,
+        pipeline: &'static str,
+        pruner_hi: u64,
+    ) -> anyhow::Result<bool> {
+        self.update_watermark(pipeline, |stored| match stored {
+            Some(s) => {
+                s.pruner_hi = pruner_hi;
+                true
+            }
+            None => false,
+        })
+        .await
+    }
+}
+
+#[async_trait]
+impl store::Store for ParquetStore {
+    type Connection<'c> = Connection<'c>;
+
+    async fn connect<'c>(&'c self) -> anyhow::Result<Self::Connection<'c>> {
+        Ok(Connection::new(self, false))
+    }
+}
+
+#[async_trait]
+impl store::TransactionalStore for ParquetStore {
+    async fn transaction<'a, R, F>(&self, f: F) -> anyhow::Result<R>
+    where
+        R: Send + 'a,
+        F: Send + 'a,
+        F: for<'r> FnOnce(
+            &'r mut Self::Connection<'_>,
+        ) -> ScopedBoxFuture<'a, 'r, anyhow::Result<R>>,
+    {
+        let mut conn = Connection::new(self, true);
+        let result = f(&mut conn).await;
+
+        let pending = conn
+            .pending
+            .take()
+            .expect("transaction to have pending writes");
+        match result {
+            Ok(r) => {
+                pending.commit(self).await?;
+                Ok(r)
+            }
+            Err(e) => {
+                pending.abort();
+                Err(e)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow_array::{cast::AsArray, types::UInt64Type};
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+    use scoped_futures::ScopedFutureExt;
+    use store::{Connection as _, Store as _, TransactionalStore as _};
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::{ParquetArgs, ParquetRow, ParquetSchema, ParquetValue};
+
+    struct Row {
+        epoch: u64,
+        checkpoint: u64,
+    }
+
+    impl ParquetSchema for Row {
+        fn schema() -> Vec<String> {
+            vec!["epoch".to_owned(), "checkpoint".to_owned()]
+        }
+
+        fn get_column(&self, idx: usize) -> ParquetValue {
+            match idx {
+                0 => ParquetValue::U64(self.epoch),
+                1 => ParquetValue::U64(self.checkpoint),
+                _ => unreachable!(),
+            }
+        }
+    }
+
+    impl ParquetRow for Row {
+        fn epoch(&self) -> u64 {
+            self.epoch
+        }
+
+        fn checkpoint(&self) -> u64 {
+            self.checkpoint
+        }
+    }
+
+    fn rows(epoch: u64, checkpoints: impl IntoIterator<Item = u64>) -> Vec<Row> {
+        checkpoints
+            .into_iter()
+            .map(|checkpoint| Row { epoch, checkpoint })
+            .collect()
+    }
+
+    /// The checkpoint column of every row written for `pipeline`, in file order.
+    fn checkpoints(store: &ParquetStore, pipeline: &str) -> Vec<u64> {
+        let mut checkpoints = vec![];
+        for path in store.files(pipeline).unwrap() {
+            let file = std::fs::File::open(path).unwrap();
+            let reader = ParquetRecordBatchReaderBuilder::try_new(file)
+                .unwrap()
+                .build()
+                .unwrap();
+
+            for batch in reader {
+                let batch = batch.unwrap();
+                let column = batch.column(1).as_primitive::<UInt64Type>();
+                checkpoints.extend(column.values().iter().copied());
+            }
+        }
+
+        checkpoints
+    }
+
+    fn store(dir: &TempDir) -> ParquetStore {
+        ParquetStore::new(
+            dir.path(),
+            ParquetArgs {
+                parquet_checkpoints_per_file: 10,
+            },
+        )
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_watermarks() {
+        let dir = TempDir::new().unwrap();
+        let store = store(&dir);
+        let mut conn = store.connect().await.unwrap();
+
+        assert!(conn.committer_watermark("p").await.unwrap().is_none());
+        assert!(!conn.set_reader_watermark("p", 5).await.unwrap());
+
+        let watermark = store::CommitterWatermark::new_for_testing(10);
+        assert!(conn.set_committer_watermark("p", watermark).await.unwrap());
+        let watermark = store::CommitterWatermark::new_for_testing(5);
+        assert!(!conn.set_committer_watermark("p", watermark).await.unwrap());
+
+        assert!(conn.set_reader_watermark("p", 5).await.unwrap());
+        assert!(!conn.set_reader_watermark("p", 5).await.unwrap());
+
+        // Watermarks survive re-opening the store.
+        let store = self::store(&dir);
+        let mut conn = store.connect().await.unwrap();
+
+        let reader = conn.reader_watermark("p").await.unwrap().unwrap();
+        assert_eq!(reader.checkpoint_hi_inclusive, 10);
+        assert_eq!(reader.reader_lo, 5);
+
+        let pruner = conn
+            .pruner_watermark("p", Duration::from_secs(60))
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(pruner.wait_for_ms > 50_000 && pruner.wait_for_ms <= 60_000);
+    }
+
+    #[tokio::test]
+    async fn test_partitioning() {
+        let dir = TempDir::new().unwrap();
+        let store = store(&dir);
+        let mut conn = store.connect().await.unwrap();
+
+        // Epoch 0 covers checkpoints 0..15, which are split across two files, and epoch 1
+        // starts part way through the second checkpoint range.
+        let mut values = rows(0, 0..15);
+        values.extend(rows(1, 15..18));
+        assert_eq!(conn.write_rows("p", &values).unwrap(), 18);
+
+        let files: Vec<_> = store
+            .files("p")
+            .unwrap()
+            .into_iter()
+            .map(|p| {
+                let epoch = p.parent().unwrap().file_name().unwrap().to_owned();
+                let name = p.file_name().unwrap().to_str().unwrap().to_owned();
+                let (lo, rest) = name.split_once('_').unwrap();
+                let (hi, _) = rest.split_once('_').unwrap();
+                (epoch.into_string().unwrap(), lo.to_owned(), hi.to_owned())
+            })
+            .collect();
+
+        assert_eq!(
+            files,
+            vec![
+                ("epoch_0".to_owned(), "0".to_owned(), "10".to_owned()),
+                ("epoch_0".to_owned(), "10".to_owned(), "15".to_owned()),
+                ("epoch_1".to_owned(), "15".to_owned(), "18".to_owned()),
+            ]
+        );
+
+        // Re-writing the same rows is idempotent.
+        conn.write_rows("p", &values).unwrap();
+        assert_eq!(store.files("p").unwrap().len(), 3);
+
+        // Only files entirely within the pruned range are removed.
+        assert_eq!(conn.prune("p", 0, 12).unwrap(), 1);
+        assert_eq!(conn.prune("p", 0, 15).unwrap(), 1);
+        assert_eq!(store.files("p").unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_transaction() {
+        let dir = TempDir::new().unwrap();
+        let store = store(&dir);
+
+        // A failed transaction leaves no trace.
+        let result: anyhow::Result<()> = store
+            .transaction(|conn| {
+                async move {
+                    conn.write_rows("p", &rows(0, 0..5))?;
+                    let watermark = store::CommitterWatermark::new_for_testing(4);
+                    conn.set_committer_watermark("p", watermark).await?;
+                    anyhow::bail!("abort");
+                }
+                .scope_boxed()
+            })
+            .await;
+        assert!(result.is_err());
+
+        let mut conn = store.connect().await.unwrap();
+        assert!(conn.committer_watermark("p").await.unwrap().is_none());
+        assert!(store.files("p").unwrap().is_empty());
+
+        // Files written outside a transaction (as concurrent pipelines do) are not touched by a
+        // transaction committing, even if they are ahead of the watermark.
+        conn.write_rows("c", &rows(0, 3..7)).unwrap();
+
+        store
+            .transaction(|conn| {
+                async move {
+                    conn.write_rows("p", &rows(0, 0..5))?;
+
+                    // Changes are visible from inside the transaction.
+                    let watermark = store::CommitterWatermark::new_for_testing(4);
+                    assert!(conn.set_committer_watermark("p", watermark).await?);
+                    let watermark = conn.committer_watermark("p").await?.unwrap();
+                    assert_eq!(watermark.checkpoint_hi_inclusive, 4);
+                    Ok(())
+                }
+                .scope_boxed()
+            })
+            .await
+            .unwrap();
+
+        let watermark = conn.committer_watermark("p").await.unwrap().unwrap();
+        assert_eq!(watermark.checkpoint_hi_inclusive, 4);
+        assert_eq!(store.files("p").unwrap().len(), 1);
+        assert_eq!(store.files("c").unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_recover_on_startup() {
+        let dir = TempDir::new().unwrap();
+        let store = store(&dir);
+        let mut conn = store.connect().await.unwrap();
+
+        let watermark = store::CommitterWatermark::new_for_testing(9);
+        conn.set_committer_watermark("p", watermark).await.unwrap();
+        conn.write_rows("p", &rows(0, 0..10)).unwrap();
+        conn.write_rows("p", &rows(0, 10..15)).unwrap();
+        assert_eq!(store.files("p").unwrap().len(), 2);
+
+        // Data beyond the watermark from the previous run is removed when the pipeline starts
+        // up, and only then.
+        let store = self::store(&dir);
+        let mut conn = store.connect().await.unwrap();
+        let watermark = conn.committer_watermark("p").await.unwrap().unwrap();
+        assert_eq!(watermark.checkpoint_hi_inclusive, 9);
+        assert_eq!(store.files("p").unwrap().len(), 1);
+
+        conn.write_rows("p", &rows(0, 10..15)).unwrap();
+        conn.committer_watermark("p").await.unwrap();
+        assert_eq!(store.files("p").unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_recover_straddling_files() {
+        let dir = TempDir::new().unwrap();
+        let store = store(&dir);
+        let mut conn = store.connect().await.unwrap();
+
+        // A concurrent pipeline wrote checkpoints 0..10, in two overlapping files, but its
+        // watermark only reached checkpoint 7 before it stopped.
+        let watermark = store::CommitterWatermark::new_for_testing(7);
+        conn.set_committer_watermark("p", watermark).await.unwrap();
+        conn.write_rows("p", &rows(0, 0..5)).unwrap();
+        conn.write_rows("p", &rows(0, [5, 6, 7, 8, 9, 9])).unwrap();
+
+        // On restart, the file straddling the watermark is rewritten to drop the rows for
+        // checkpoints 8 and 9, which will be processed again.
+        let store = self::store(&dir);
+        let mut conn = store.connect().await.unwrap();
+        conn.committer_watermark("p").await.unwrap();
+        assert_eq!(checkpoints(&store, "p"), (0..8).collect::<Vec<_>>());
+
+        let files = store.files("p").unwrap();
+        assert_eq!(files.len(), 2);
+        let name = files[1].file_name().unwrap().to_str().unwrap();
+        assert!(name.starts_with("5_8_"), "{name}");
+
+        // Re-processing those checkpoints does not duplicate any rows.
+        conn.write_rows("p", &rows(0, [8, 9, 9])).unwrap();
+        let mut expect: Vec<_> = (0..10).collect();
+        expect.push(9);
+        assert_eq!(checkpoints(&store, "p"), expect);
+    }
+}
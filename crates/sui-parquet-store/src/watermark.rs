@@ -0,0 +1,55 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{
+    fs,
+    io::ErrorKind,
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Context;
+use mysten_common::fs::write_atomic;
+use serde::{Deserialize, Serialize};
+
+/// Watermark for a single pipeline, persisted as JSON alongside that pipeline's Parquet files.
+#[derive(Serialize, Deserialize, Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct StoredWatermark {
+    pub epoch_hi_inclusive: u64,
+    pub checkpoint_hi_inclusive: u64,
+    pub tx_hi: u64,
+    pub timestamp_ms_hi_inclusive: u64,
+    pub reader_lo: u64,
+    /// Wall-clock time (milliseconds since the Unix epoch) at which `reader_lo` was last raised.
+    pub pruner_timestamp_ms: u64,
+    pub pruner_hi: u64,
+}
+
+impl StoredWatermark {
+    /// Read the watermark stored at `path`, returning `None` if it has not been written yet.
+    pub(crate) fn read(path: &Path) -> anyhow::Result<Option<Self>> {
+        let bytes = match fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+        };
+
+        let watermark = serde_json::from_slice(&bytes)
+            .with_context(|| format!("Failed to parse watermark at {}", path.display()))?;
+
+        Ok(Some(watermark))
+    }
+
+    /// Atomically replace the watermark stored at `path`.
+    pub(crate) fn write(&self, path: &Path) -> anyhow::Result<()> {
+        write_atomic(path, &serde_json::to_vec_pretty(self)?)
+    }
+}
+
+/// The current wall-clock time in milliseconds since the Unix epoch.
+pub(crate) fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
@@ -9,14 +9,17 @@ use std::{
 use move_trace_format::format::{MoveTrace, MoveTraceReader};
 use sui_types::{effects::TransactionEffects, gas::GasUsageReport};
 
+use crate::what_if::WhatIfReport;
+
 pub const ARTIFACTS_ENCODING_EXT: &str = "json";
 pub const ARTIFACTS_ENCODING_COMPRESSION_EXT: &str = "json.zst";
 
-pub const ARTIFACTS: [Artifact; 4] = [
+pub const ARTIFACTS: [Artifact; 5] = [
     Artifact::Trace,
     Artifact::TransactionEffects,
     Artifact::TransactionGasReport,
     Artifact::ForkedTransactionEffects,
+    Artifact::WhatIfReport,
 ];
 
 /// The types of artifacts that the replay tool knows about and may output.
@@ -26,6 +29,7 @@ pub enum Artifact {
     TransactionEffects,
     TransactionGasReport,
     ForkedTransactionEffects,
+    WhatIfReport,
 }
 
 /// Encoding types for artifacts that may be output by the replay tool.
@@ -93,6 +97,7 @@ impl Artifact {
             Artifact::TransactionEffects => "transaction_effects",
             Artifact::ForkedTransactionEffects => "forked_transaction_effects",
             Artifact::TransactionGasReport => "transaction_gas_report",
+            Artifact::WhatIfReport => "what_if_report",
         }
     }
 
@@ -102,7 +107,8 @@ impl Artifact {
             Artifact::Trace => EncodingType::JsonCompressed,
             Artifact::ForkedTransactionEffects
             | Artifact::TransactionEffects
-            | Artifact::TransactionGasReport => EncodingType::Json,
+            | Artifact::TransactionGasReport
+            | Artifact::WhatIfReport => EncodingType::Json,
         }
     }
 
@@ -189,6 +195,23 @@ impl ArtifactMember<'_, '_> {
             None
         }
     }
+
+    /// Try to get the WhatIfReport if the artifact type is `WhatIfReport`.
+    /// If the artifact type is not `WhatIfReport` `None` is returned.
+    pub fn try_get_what_if_report(&self) -> Option<anyhow::Result<WhatIfReport>> {
+        if self.artifact_type == Artifact::WhatIfReport {
+            Some(self.get_json().and_then(|json| {
+                serde_json::from_value::<WhatIfReport>(json).map_err(|e| {
+                    anyhow::anyhow!(
+                        "Failed to deserialize what-if report from {}: {e}",
+                        self.artifact_path.display()
+                    )
+                })
+            }))
+        } else {
+            None
+        }
+    }
 }
 
 /// Serialization methods for `ArtifactManager`.
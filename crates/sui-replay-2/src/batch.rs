@@ -0,0 +1,689 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Batch replay.
+//! Replays a list of transactions with a configurable number of workers that share a single
+//! store, and records the outcome of each transaction in a summary that can be saved as JSON or
+//! CSV (e.g. to regression test an execution layer against a large set of transactions).
+//!
+//! Workers share a `SharedCacheStore` on top of the data store, so that objects and packages
+//! loaded for one transaction are not fetched again for the others.
+
+use crate::{
+    artifacts::ArtifactManager,
+    diff_effects,
+    replay_interface::{EpochData, EpochStore, ObjectKey, ObjectStore, TransactionStore},
+    replay_txn::{replay_transaction, ReplayError},
+};
+use anyhow::Context;
+use clap::ValueEnum;
+use lru::LruCache;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    num::NonZeroUsize,
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Condvar, Mutex, PoisonError,
+    },
+    time::Instant,
+};
+use sui_types::{
+    base_types::ObjectID, effects::TransactionEffects, object::Object,
+    supported_protocol_versions::ProtocolConfig, transaction::TransactionData,
+};
+
+// Number of objects, and of user packages, kept in memory by a `SharedCacheStore`.
+const SHARED_OBJECT_CACHE_SIZE: usize = 10_000;
+const SHARED_PACKAGE_CACHE_SIZE: usize = 1_000;
+
+/// Format of the batch replay summary.
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SummaryFormat {
+    #[default]
+    Json,
+    Csv,
+}
+
+/// Outcome of replaying a single transaction.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "outcome", rename_all = "snake_case")]
+pub enum Outcome {
+    /// The replay produced the same effects as on chain.
+    Matched,
+    /// The replay produced different effects, `diff` is a line diff of expected vs actual.
+    EffectsMismatch { diff: String },
+    /// The transaction failed to execute (or its artifacts could not be saved).
+    ExecutionError { error: String },
+    /// The transaction or the data it needs could not be loaded.
+    DataFetchError { error: String },
+}
+
+/// Summary entry for a single transaction in a batch.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TransactionSummary {
+    pub digest: String,
+    #[serde(flatten)]
+    pub outcome: Outcome,
+    pub elapsed_ms: u64,
+}
+
+// Flat row for the CSV summary.
+#[derive(Serialize)]
+struct CsvRow<'a> {
+    digest: &'a str,
+    outcome: &'a str,
+    elapsed_ms: u64,
+    detail: &'a str,
+}
+
+impl Outcome {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Outcome::Matched => "matched",
+            Outcome::EffectsMismatch { .. } => "effects_mismatch",
+            Outcome::ExecutionError { .. } => "execution_error",
+            Outcome::DataFetchError { .. } => "data_fetch_error",
+        }
+    }
+
+    fn detail(&self) -> &str {
+        match self {
+            Outcome::Matched => "",
+            Outcome::EffectsMismatch { diff } => diff,
+            Outcome::ExecutionError { error } | Outcome::DataFetchError { error } => error,
+        }
+    }
+}
+
+impl From<Result<(), ReplayError>> for Outcome {
+    fn from(result: Result<(), ReplayError>) -> Self {
+        match result {
+            Ok(()) => Outcome::Matched,
+            Err(ReplayError::EffectsMismatch {
+                expected, actual, ..
+            }) => Outcome::EffectsMismatch {
+                diff: diff_effects(&expected, &actual),
+            },
+            Err(e @ ReplayError::Execution { .. }) => Outcome::ExecutionError {
+                error: e.to_string(),
+            },
+            Err(e @ ReplayError::DataFetch { .. }) => Outcome::DataFetchError {
+                error: e.to_string(),
+            },
+        }
+    }
+}
+
+/// Replay `digests` with `workers` threads sharing `data_store`.
+/// Summaries are returned in the same order as `digests`. If `terminate_early` is set, workers
+/// stop picking up new transactions after the first failure, and transactions that were not
+/// replayed are left out of the summary.
+/// Workers block on the current Tokio runtime, so this must be called from a thread that is
+/// allowed to block, e.g. from `tokio::task::spawn_blocking`.
+pub fn replay_batch<S>(
+    output_root_dir: &Path,
+    digests: &[String],
+    data_store: &S,
+    trace: bool,
+    terminate_early: bool,
+    overwrite_existing: bool,
+    workers: usize,
+) -> Vec<TransactionSummary>
+where
+    S: TransactionStore + EpochStore + ObjectStore + Sync,
+{
+    let store = SharedCacheStore::new(data_store);
+    let handle = tokio::runtime::Handle::current();
+    let next = AtomicUsize::new(0);
+    let stop = AtomicBool::new(false);
+    let summaries = Mutex::new(BTreeMap::new());
+
+    std::thread::scope(|s| {
+        for _ in 0..workers.max(1) {
+            s.spawn(|| loop {
+                if stop.load(Ordering::Relaxed) {
+                    break;
+                }
+                let idx = next.fetch_add(1, Ordering::Relaxed);
+                let Some(tx_digest) = digests.get(idx) else {
+                    break;
+                };
+
+                let start = Instant::now();
+                let tx_dir = output_root_dir.join(tx_digest);
+                let result = ArtifactManager::new(&tx_dir, overwrite_existing)
+                    .map_err(|error| ReplayError::Execution {
+                        digest: tx_digest.clone(),
+                        error,
+                    })
+                    .and_then(|artifact_manager| {
+                        handle.block_on(replay_transaction(
+                            &artifact_manager,
+                            tx_digest,
+                            &store,
+                            trace,
+                        ))
+                    });
+
+                match &result {
+                    Err(e) if terminate_early => {
+                        ::tracing::error!(
+                            "Error while replaying transaction {}: {:?}",
+                            tx_digest,
+                            e
+                        );
+                        stop.store(true, Ordering::Relaxed);
+                    }
+                    Err(e) => {
+                        ::tracing::error!("Failed to replay transaction {}: {:?}", tx_digest, e);
+                    }
+                    Ok(_) => {
+                        ::tracing::info!("Successfully replayed transaction {}", tx_digest);
+                    }
+                }
+
+                let summary = TransactionSummary {
+                    digest: tx_digest.clone(),
+                    outcome: result.into(),
+                    elapsed_ms: start.elapsed().as_millis() as u64,
+                };
+                summaries.lock().unwrap().insert(idx, summary);
+            });
+        }
+    });
+
+    summaries.into_inner().unwrap().into_values().collect()
+}
+
+/// Save `summaries` to `path` in the given `format`.
+pub fn write_summary(
+    path: &Path,
+    format: SummaryFormat,
+    summaries: &[TransactionSummary],
+) -> anyhow::Result<()> {
+    let file = std::fs::File::create(path)
+        .context(format!("Failed to create summary file {}", path.display()))?;
+    match format {
+        SummaryFormat::Json => serde_json::to_writer_pretty(file, summaries)?,
+        SummaryFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(file);
+            for summary in summaries {
+                writer.serialize(CsvRow {
+                    digest: &summary.digest,
+                    outcome: summary.outcome.name(),
+                    elapsed_ms: summary.elapsed_ms,
+                    detail: summary.outcome.detail(),
+                })?;
+            }
+            writer.flush()?;
+        }
+    }
+    Ok(())
+}
+
+/// Count of transactions per outcome, in a stable order.
+pub fn outcome_counts(summaries: &[TransactionSummary]) -> BTreeMap<&'static str, usize> {
+    let mut counts = BTreeMap::new();
+    for summary in summaries {
+        *counts.entry(summary.outcome.name()).or_default() += 1;
+    }
+    counts
+}
+
+/// A store that keeps the objects most recently loaded through it in memory, to share them across
+/// the transactions of a batch.
+/// User packages are immutable, so they are cached by ID whatever the query. Other objects are
+/// cached by the query that loaded them.
+/// An object missing from the cache is fetched by the first worker needing it, and the workers
+/// needing it while it is fetched wait for that fetch (e.g. for a popular package or shared
+/// object) instead of fetching it too. Different objects are fetched concurrently.
+pub struct SharedCacheStore<'a, S> {
+    inner: &'a S,
+    objects: Mutex<LruCache<ObjectKey, Object>>,
+    packages: Mutex<LruCache<ObjectID, Object>>,
+    in_flight: Mutex<HashMap<ObjectKey, Arc<Fetch>>>,
+}
+
+/// A fetch of an object by one worker, that other workers needing the object wait for.
+#[derive(Default)]
+struct Fetch {
+    state: Mutex<FetchState>,
+    done: Condvar,
+}
+
+#[derive(Default)]
+enum FetchState {
+    #[default]
+    Pending,
+    Fetched(Option<Object>),
+    Failed,
+}
+
+impl Fetch {
+    fn complete(&self, state: FetchState) {
+        *self.state.lock().unwrap_or_else(PoisonError::into_inner) = state;
+        self.done.notify_all();
+    }
+
+    /// Waits for the fetch to complete. Returns `None` if it failed.
+    fn wait(&self) -> Option<Option<Object>> {
+        let state = self
+            .done
+            .wait_while(self.state.lock().unwrap(), |state| {
+                matches!(state, FetchState::Pending)
+            })
+            .unwrap();
+        match &*state {
+            FetchState::Fetched(object) => Some(object.clone()),
+            FetchState::Pending | FetchState::Failed => None,
+        }
+    }
+}
+
+/// The fetches a worker started, with the index of their object in its request. Fetches still
+/// pending when it is dropped, because fetching failed, are completed as failed so that the workers
+/// waiting for them fetch the objects themselves.
+struct Claims<'c> {
+    in_flight: &'c Mutex<HashMap<ObjectKey, Arc<Fetch>>>,
+    claims: Vec<(usize, ObjectKey, Arc<Fetch>)>,
+}
+
+impl Claims<'_> {
+    fn complete(&mut self, fetched: Vec<Option<Object>>, objects: &mut [Option<Object>]) {
+        let mut in_flight = self.in_flight.lock().unwrap();
+        for ((index, key, fetch), fetched) in self.claims.drain(..).zip(fetched) {
+            in_flight.remove(&key);
+            objects[index] = fetched.clone();
+            fetch.complete(FetchState::Fetched(fetched));
+        }
+    }
+}
+
+impl Drop for Claims<'_> {
+    fn drop(&mut self) {
+        let mut in_flight = self
+            .in_flight
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        for (_, key, fetch) in self.claims.drain(..) {
+            in_flight.remove(&key);
+            fetch.complete(FetchState::Failed);
+        }
+    }
+}
+
+impl<'a, S> SharedCacheStore<'a, S> {
+    pub fn new(inner: &'a S) -> Self {
+        Self {
+            inner,
+            objects: Mutex::new(LruCache::new(
+                NonZeroUsize::new(SHARED_OBJECT_CACHE_SIZE).unwrap(),
+            )),
+            packages: Mutex::new(LruCache::new(
+                NonZeroUsize::new(SHARED_PACKAGE_CACHE_SIZE).unwrap(),
+            )),
+            in_flight: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn cached(&self, key: &ObjectKey) -> Option<Object> {
+        if let Some(package) = self.packages.lock().unwrap().get(&key.object_id) {
+            return Some(package.clone());
+        }
+        self.objects.lock().unwrap().get(key).cloned()
+    }
+
+    fn cache(&self, key: &ObjectKey, object: &Object) {
+        if object.is_package() && !is_system_package(&object.id()) {
+            self.packages
+                .lock()
+                .unwrap()
+                .put(object.id(), object.clone());
+        } else {
+            self.objects
+                .lock()
+                .unwrap()
+                .put(key.clone(), object.clone());
+        }
+    }
+
+    // Fill in the objects in `objects` that are not loaded yet from the cache.
+    fn fill_from_cache(&self, keys: &[ObjectKey], objects: &mut [Option<Object>]) {
+        for (key, object) in keys.iter().zip(objects.iter_mut()) {
+            if object.is_none() {
+                *object = self.cached(key);
+            }
+        }
+    }
+}
+
+impl<S: TransactionStore> TransactionStore for SharedCacheStore<'_, S> {
+    fn transaction_data_and_effects(
+        &self,
+        tx_digest: &str,
+    ) -> Result<(TransactionData, TransactionEffects, u64), anyhow::Error> {
+        self.inner.transaction_data_and_effects(tx_digest)
+    }
+}
+
+impl<S: EpochStore> EpochStore for SharedCacheStore<'_, S> {
+    fn epoch_info(&self, epoch: u64) -> Result<EpochData, anyhow::Error> {
+        self.inner.epoch_info(epoch)
+    }
+
+    fn protocol_config(&self, epoch: u64) -> Result<ProtocolConfig, anyhow::Error> {
+        self.inner.protocol_config(epoch)
+    }
+}
+
+impl<S: ObjectStore> ObjectStore for SharedCacheStore<'_, S> {
+    fn get_objects(&self, keys: &[ObjectKey]) -> Result<Vec<Option<Object>>, anyhow::Error> {
+        let mut objects = vec![None; keys.len()];
+        self.fill_from_cache(keys, &mut objects);
+        if objects.iter().all(Option::is_some) {
+            return Ok(objects);
+        }
+
+        // Start fetching the missing objects that no other worker is fetching, and wait for the
+        // others once done. Fetches are cached before they complete, so an object which is not
+        // being fetched is either cached or still missing.
+        let mut claims = Claims {
+            in_flight: &self.in_flight,
+            claims: vec![],
+        };
+        let mut waiting = vec![];
+        {
+            let mut in_flight = self.in_flight.lock().unwrap();
+            for (index, key) in keys.iter().enumerate() {
+                if objects[index].is_some() {
+                    continue;
+                }
+                if let Some(fetch) = in_flight.get(key) {
+                    waiting.push((index, fetch.clone()));
+                } else if let Some(object) = self.cached(key) {
+                    objects[index] = Some(object);
+                } else {
+                    let fetch = Arc::new(Fetch::default());
+                    in_flight.insert(key.clone(), fetch.clone());
+                    claims.claims.push((index, key.clone(), fetch));
+                }
+            }
+        }
+
+        if !claims.claims.is_empty() {
+            let missing: Vec<_> = claims
+                .claims
+                .iter()
+                .map(|(_, key, _)| key.clone())
+                .collect();
+            let fetched = self.inner.get_objects(&missing)?;
+            for (key, object) in missing.iter().zip(&fetched) {
+                if let Some(object) = object {
+                    self.cache(key, object);
+                }
+            }
+            claims.complete(fetched, &mut objects);
+        }
+
+        // Objects whose fetch failed in another worker are fetched again
+        let mut failed = vec![];
+        for (index, fetch) in waiting {
+            match fetch.wait() {
+                Some(object) => objects[index] = object,
+                None => failed.push(index),
+            }
+        }
+        if !failed.is_empty() {
+            let missing: Vec<_> = failed.iter().map(|index| keys[*index].clone()).collect();
+            let fetched = self.inner.get_objects(&missing)?;
+            for ((index, key), object) in failed.into_iter().zip(&missing).zip(fetched) {
+                if let Some(object) = &object {
+                    self.cache(key, object);
+                }
+                objects[index] = object;
+            }
+        }
+
+        Ok(objects)
+    }
+}
+
+// System packages are upgraded in place, so their ID alone does not identify a version.
+fn is_system_package(object_id: &ObjectID) -> bool {
+    sui_types::SYSTEM_PACKAGE_ADDRESSES.contains(object_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::replay_interface::VersionQuery;
+    use sui_types::base_types::{SequenceNumber, SuiAddress};
+    use sui_types::digests::TransactionDigest;
+    use sui_types::object::{MoveObject, Owner};
+
+    /// A store for transactions that each transfer their own coin and all pay for gas with the
+    /// same coin. It counts how often each object is fetched from it, and how many fetches run at
+    /// the same time. It has no epoch data, so replays stop before execution.
+    struct CountingStore {
+        transactions: BTreeMap<String, TransactionData>,
+        gas: ObjectKey,
+        objects: BTreeMap<ObjectID, Object>,
+        fetches: Mutex<BTreeMap<ObjectKey, usize>>,
+        concurrent_fetches: AtomicUsize,
+        max_concurrent_fetches: AtomicUsize,
+    }
+
+    impl CountingStore {
+        fn new(transactions: usize) -> Self {
+            let sender = SuiAddress::random_for_testing_only();
+            let coin = || {
+                Object::new_move(
+                    MoveObject::new_gas_coin(
+                        SequenceNumber::from_u64(5),
+                        ObjectID::random(),
+                        1_000,
+                    ),
+                    Owner::AddressOwner(sender),
+                    TransactionDigest::genesis_marker(),
+                )
+            };
+            let gas = coin();
+            let mut objects = BTreeMap::from([(gas.id(), gas.clone())]);
+
+            let transactions = (0..transactions)
+                .map(|_| {
+                    let coin = coin();
+                    let txn_data = TransactionData::new_transfer(
+                        SuiAddress::random_for_testing_only(),
+                        coin.compute_object_reference(),
+                        sender,
+                        gas.compute_object_reference(),
+                        10_000_000,
+                        1_000,
+                    );
+                    objects.insert(coin.id(), coin);
+                    (TransactionDigest::random().to_string(), txn_data)
+                })
+                .collect();
+
+            Self {
+                transactions,
+                gas: ObjectKey {
+                    object_id: gas.id(),
+                    version_query: VersionQuery::Version(gas.version().value()),
+                },
+                objects,
+                fetches: Mutex::new(BTreeMap::new()),
+                concurrent_fetches: AtomicUsize::new(0),
+                max_concurrent_fetches: AtomicUsize::new(0),
+            }
+        }
+    }
+
+    impl TransactionStore for CountingStore {
+        fn transaction_data_and_effects(
+            &self,
+            tx_digest: &str,
+        ) -> Result<(TransactionData, TransactionEffects, u64), anyhow::Error> {
+            let txn_data = self.transactions[tx_digest].clone();
+            Ok((txn_data, TransactionEffects::default(), 1))
+        }
+    }
+
+    impl EpochStore for CountingStore {
+        fn epoch_info(&self, epoch: u64) -> Result<EpochData, anyhow::Error> {
+            anyhow::bail!("No data for epoch {epoch}")
+        }
+
+        fn protocol_config(&self, _epoch: u64) -> Result<ProtocolConfig, anyhow::Error> {
+            Ok(ProtocolConfig::get_for_max_version_UNSAFE())
+        }
+    }
+
+    impl ObjectStore for CountingStore {
+        fn get_objects(&self, keys: &[ObjectKey]) -> Result<Vec<Option<Object>>, anyhow::Error> {
+            let mut fetches = self.fetches.lock().unwrap();
+            for key in keys {
+                *fetches.entry(key.clone()).or_default() += 1;
+            }
+            drop(fetches);
+
+            // Take as long as a round trip to a remote store
+            let concurrent = self.concurrent_fetches.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_concurrent_fetches
+                .fetch_max(concurrent, Ordering::SeqCst);
+            std::thread::sleep(std::time::Duration::from_millis(50));
+            self.concurrent_fetches.fetch_sub(1, Ordering::SeqCst);
+
+            Ok(keys
+                .iter()
+                .map(|key| self.objects.get(&key.object_id).cloned())
+                .collect())
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn parallel_workers_share_objects() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = CountingStore::new(8);
+        let digests: Vec<_> = store.transactions.keys().cloned().collect();
+
+        let (summaries, store, digests) = tokio::task::spawn_blocking(move || {
+            let summaries = replay_batch(dir.path(), &digests, &store, false, false, false, 4);
+            (summaries, store, digests)
+        })
+        .await
+        .unwrap();
+
+        // Every transaction is summarized, in order.
+        let summarized: Vec<_> = summaries.iter().map(|s| s.digest.clone()).collect();
+        assert_eq!(summarized, digests);
+        for summary in &summaries {
+            assert!(
+                matches!(summary.outcome, Outcome::ExecutionError { .. }),
+                "{:?}",
+                summary.outcome
+            );
+        }
+
+        // The gas coin is used by every transaction, but only fetched once, like the coins each
+        // transaction transfers.
+        let fetches = store.fetches.into_inner().unwrap();
+        assert_eq!(fetches.get(&store.gas), Some(&1));
+        for object in store.objects.values() {
+            let key = ObjectKey {
+                object_id: object.id(),
+                version_query: VersionQuery::Version(object.version().value()),
+            };
+            assert_eq!(fetches.get(&key), Some(&1), "{key:?}");
+        }
+
+        // Workers fetch different objects at the same time.
+        assert!(store.max_concurrent_fetches.into_inner() > 1);
+    }
+
+    fn summaries() -> Vec<TransactionSummary> {
+        let summary = |digest: &str, outcome| TransactionSummary {
+            digest: digest.to_string(),
+            outcome,
+            elapsed_ms: 10,
+        };
+        vec![
+            summary("a", Outcome::Matched),
+            summary(
+                "b",
+                Outcome::EffectsMismatch {
+                    diff: "- status: success\n+ status: failure".to_string(),
+                },
+            ),
+            summary(
+                "c",
+                Outcome::DataFetchError {
+                    error: "object not found".to_string(),
+                },
+            ),
+            summary("d", Outcome::Matched),
+        ]
+    }
+
+    #[test]
+    fn json_summary_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("summary.json");
+        let summaries = summaries();
+        write_summary(&path, SummaryFormat::Json, &summaries).unwrap();
+
+        let json: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+        assert_eq!(json[1]["digest"], "b");
+        assert_eq!(json[1]["outcome"], "effects_mismatch");
+        assert_eq!(json[2]["error"], "object not found");
+
+        let read: Vec<TransactionSummary> = serde_json::from_value(json).unwrap();
+        assert_eq!(read, summaries);
+    }
+
+    #[test]
+    fn csv_summary() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("summary.csv");
+        write_summary(&path, SummaryFormat::Csv, &summaries()).unwrap();
+
+        let mut reader = csv::Reader::from_path(&path).unwrap();
+        assert_eq!(
+            reader.headers().unwrap().iter().collect::<Vec<_>>(),
+            ["digest", "outcome", "elapsed_ms", "detail"]
+        );
+        let rows: Vec<Vec<String>> = reader
+            .records()
+            .map(|r| r.unwrap().iter().map(str::to_string).collect())
+            .collect();
+        assert_eq!(rows.len(), 4);
+        assert_eq!(rows[0], ["a", "matched", "10", ""]);
+        assert_eq!(
+            rows[1],
+            [
+                "b",
+                "effects_mismatch",
+                "10",
+                "- status: success\n+ status: failure"
+            ]
+        );
+        assert_eq!(rows[2], ["c", "data_fetch_error", "10", "object not found"]);
+    }
+
+    #[test]
+    fn count_outcomes() {
+        let counts = outcome_counts(&summaries());
+        assert_eq!(
+            counts.into_iter().collect::<Vec<_>>(),
+            [
+                ("data_fetch_error", 1),
+                ("effects_mismatch", 1),
+                ("matched", 2)
+            ]
+        );
+    }
+}
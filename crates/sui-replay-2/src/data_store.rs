@@ -6,9 +6,12 @@
 //! `crates/sui-indexer-alt-graphql/schema.graphql`.
 //! The `DataStore` carries an epoch cache and soon a system package cache that can help
 //! when replaying multiple transactions.
+//! An optional `ObjectCache` records every epoch and object fetched, so that transactions can
+//! later be replayed offline (`local_store.rs`).
 
 use crate::{
     gql_queries,
+    object_cache::ObjectCache,
     replay_interface::{EpochData, EpochStore, ObjectKey, ObjectStore, TransactionStore},
     Node,
 };
@@ -41,6 +44,8 @@ pub struct DataStore {
     // Keep the epoch data considering its small size and footprint
     epoch_map: RwLock<BTreeMap<EpochId, EpochData>>,
     // TODO: define a system package map?
+    // On-disk cache populated with the data fetched, for later offline replays
+    object_cache: Option<ObjectCache>,
     /// The binary's version passed to the User-Agent header in GQL query requests
     version: String,
 }
@@ -72,6 +77,9 @@ impl EpochStore for DataStore {
             return Ok(epoch_data.clone());
         }
         let epoch_data = block_on!(self.epoch(epoch))?;
+        if let Some(cache) = &self.object_cache {
+            cache.put_epoch(&epoch_data)?;
+        }
         self.epoch_map
             .write()
             .unwrap()
@@ -91,7 +99,15 @@ impl EpochStore for DataStore {
 
 impl ObjectStore for DataStore {
     fn get_objects(&self, keys: &[ObjectKey]) -> Result<Vec<Option<Object>>, anyhow::Error> {
-        block_on!(self.objects(keys))
+        let objects = block_on!(self.objects(keys))?;
+        if let Some(cache) = &self.object_cache {
+            for (key, object) in keys.iter().zip(&objects) {
+                if let Some(object) = object {
+                    cache.put_object(key, object)?;
+                }
+            }
+        }
+        Ok(objects)
     }
 }
 
@@ -114,11 +130,18 @@ impl DataStore {
             client,
             node,
             epoch_map,
+            object_cache: None,
             rpc,
             version: version.to_string(),
         })
     }
 
+    /// Record all epochs and objects fetched by this store in `object_cache`.
+    pub fn with_object_cache(mut self, object_cache: ObjectCache) -> Self {
+        self.object_cache = Some(object_cache);
+        self
+    }
+
     pub fn node(&self) -> &Node {
         &self.node
     }
@@ -2,5 +2,6 @@
 // SPDX-License-Identifier: Apache-2.0
 
 mod gas_report;
+mod what_if_report;
 
 pub struct Pretty<'a, T>(pub &'a T);
@@ -0,0 +1,123 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::displays::Pretty;
+use crate::what_if::{ObjectChangeSummary, WhatIfReport};
+use std::fmt::{Display, Formatter};
+use sui_types::base_types::SequenceNumber;
+use tabled::{
+    builder::Builder as TableBuilder,
+    settings::{style::HorizontalLine, Style as TableStyle},
+};
+
+impl Display for Pretty<'_, WhatIfReport> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let Pretty(report) = self;
+        writeln!(f, "What-if replay of transaction {}", report.digest)?;
+        for package in &report.packages {
+            writeln!(f, "  package {package} replaced by local build")?;
+        }
+        for (name, value) in &report.protocol_config {
+            writeln!(f, "  protocol config {name} = {value}")?;
+        }
+        if report.is_unchanged() {
+            writeln!(f, "Effects are unchanged")?;
+        }
+        summary_table(f, report)?;
+        object_changes_table(f, report)?;
+        if !report.events.is_empty() {
+            writeln!(f, "Events emitted")?;
+            for event in &report.events {
+                writeln!(f, "  {event}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn summary_table(f: &mut Formatter, report: &WhatIfReport) -> std::fmt::Result {
+    let mut builder = TableBuilder::default();
+    macro_rules! record {
+        ($name:expr, $expected:expr, $actual:expr) => {
+            builder.push_record(vec![
+                $name.to_string(),
+                $expected.to_string(),
+                $actual.to_string(),
+            ]);
+        };
+    }
+    record!("", "Expected", "Actual");
+    record!(
+        "Status",
+        format!("{:?}", report.status.expected),
+        format!("{:?}", report.status.actual)
+    );
+    let (expected, actual) = (&report.gas.expected, &report.gas.actual);
+    record!(
+        "Computation Cost",
+        expected.computation_cost,
+        actual.computation_cost
+    );
+    record!("Storage Cost", expected.storage_cost, actual.storage_cost);
+    record!(
+        "Storage Rebate",
+        expected.storage_rebate,
+        actual.storage_rebate
+    );
+    record!(
+        "Non-Refundable Storage Fee",
+        expected.non_refundable_storage_fee,
+        actual.non_refundable_storage_fee
+    );
+    record!(
+        "Events Digest",
+        format!("{:?}", report.events_digest.expected),
+        format!("{:?}", report.events_digest.actual)
+    );
+
+    let mut table = builder.build();
+    table.with(TableStyle::rounded().horizontals([HorizontalLine::new(
+        1,
+        TableStyle::modern().get_horizontal(),
+    )]));
+    write!(f, "\n{}\n", table)
+}
+
+fn object_changes_table(f: &mut Formatter, report: &WhatIfReport) -> std::fmt::Result {
+    if report.object_changes.is_empty() {
+        return Ok(());
+    }
+
+    let mut builder = TableBuilder::default();
+    builder.push_record(vec!["Object ID", "Expected", "Actual"]);
+    for change in &report.object_changes {
+        builder.push_record(vec![
+            change.object_id.to_string(),
+            object_change(&change.expected),
+            object_change(&change.actual),
+        ]);
+    }
+    let mut table = builder.build();
+
+    table.with(TableStyle::rounded().horizontals([HorizontalLine::new(
+        1,
+        TableStyle::modern().get_horizontal(),
+    )]));
+    write!(f, "\n{}\n", table)
+}
+
+fn object_change(change: &Option<ObjectChangeSummary>) -> String {
+    let Some(change) = change else {
+        return "unchanged".to_string();
+    };
+    let version = |v: Option<SequenceNumber>| v.map_or("-".to_string(), |v| v.value().to_string());
+    format!(
+        "{:?} {} -> {} {}",
+        change.id_operation,
+        version(change.input_version),
+        version(change.output_version),
+        change
+            .output_digest
+            .map_or("-".to_string(), |d| d.to_string()),
+    )
+}
@@ -12,11 +12,15 @@
 //! the store (BackingPackageStore, ObjectStore, ChildObjectResolver)
 
 use crate::{
-    replay_interface::{EpochStore, ObjectKey, ObjectStore, VersionQuery},
+    replay_interface::{EpochData, EpochStore, ObjectKey, ObjectStore, VersionQuery},
     replay_txn::{get_input_objects_for_replay, ReplayTransaction},
 };
 use anyhow::Context;
-use move_core_types::{language_storage::ModuleId, resolver::ModuleResolver};
+use move_core_types::{
+    annotated_value::MoveTypeLayout,
+    language_storage::{ModuleId, StructTag},
+    resolver::ModuleResolver,
+};
 use move_trace_format::format::MoveTraceBuilder;
 use std::{
     cell::RefCell,
@@ -25,6 +29,7 @@ use std::{
     sync::Arc,
 };
 use sui_execution::Executor;
+use sui_json_rpc_types::DevInspectResults;
 use sui_types::{
     base_types::{ObjectID, ObjectRef, SequenceNumber, VersionNumber},
     committee::EpochId,
@@ -33,12 +38,12 @@ use sui_types::{
     error::{ExecutionError, SuiError, SuiResult},
     execution_params::{get_early_execution_error, ExecutionOrEarlyError},
     gas::SuiGasStatus,
-    inner_temporary_store::InnerTemporaryStore,
+    inner_temporary_store::{InnerTemporaryStore, PackageStoreWithFallback},
     metrics::LimitsMetrics,
     object::Object,
     storage::{BackingPackageStore, ChildObjectResolver, PackageObject, ParentSync},
     supported_protocol_versions::ProtocolConfig,
-    transaction::{CheckedInputObjects, TransactionDataAPI},
+    transaction::{CheckedInputObjects, TransactionData, TransactionDataAPI},
 };
 use tracing::{debug, trace};
 
@@ -153,6 +158,75 @@ pub fn execute_transaction_to_effects(
     ))
 }
 
+// Dev-inspect a transaction that was not executed on chain, with all checks enabled.
+// Objects are taken from `object_cache` (loaded by the caller) and dynamic loads go to
+// `object_store` at `checkpoint`.
+// Return effects, events and the values returned by each command.
+pub fn dev_inspect_transaction(
+    executor: &ReplayExecutor,
+    txn_data: TransactionData,
+    epoch_data: &EpochData,
+    checkpoint: u64,
+    object_store: &dyn ObjectStore,
+    object_cache: BTreeMap<ObjectID, BTreeMap<u64, Object>>,
+) -> Result<DevInspectResults, anyhow::Error> {
+    debug!("Start dev inspect");
+    let digest = txn_data.digest();
+    let input_objects = get_input_objects_for_replay(&txn_data, &digest, &object_cache)?;
+    let protocol_config = &executor.protocol_config;
+    let gas_status = SuiGasStatus::new(
+        txn_data.gas_data().budget,
+        txn_data.gas_data().price,
+        epoch_data.rgp,
+        protocol_config,
+    )?;
+    let store: ReplayStore<'_> = ReplayStore {
+        checkpoint,
+        store: object_store,
+        object_cache: RefCell::new(object_cache),
+    };
+    let input_objects = CheckedInputObjects::new_for_replay(input_objects);
+    let early_execution_error = get_early_execution_error(&digest, &input_objects, &HashSet::new());
+    let execution_params = match early_execution_error {
+        Some(error) => ExecutionOrEarlyError::Err(error),
+        None => ExecutionOrEarlyError::Ok(()),
+    };
+    let (inner_store, _gas_status, effects, result) = executor.executor.dev_inspect_transaction(
+        &store,
+        protocol_config,
+        executor.metrics.clone(),
+        false, // expensive checks
+        execution_params,
+        &epoch_data.epoch_id,
+        epoch_data.start_timestamp,
+        input_objects,
+        txn_data.gas_data().clone(),
+        gas_status,
+        txn_data.kind().clone(),
+        txn_data.sender(),
+        digest,
+        false, // skip all checks
+    );
+    debug!("End dev inspect");
+
+    // packages published by the transaction are only in the temporary store
+    let mut layout_resolver =
+        executor
+            .executor
+            .type_layout_resolver(Box::new(PackageStoreWithFallback::new(
+                &inner_store,
+                &store,
+            )));
+    Ok(DevInspectResults::new(
+        effects.clone(),
+        inner_store.events.clone(),
+        result,
+        bcs::to_bytes(&txn_data)?,
+        bcs::to_bytes(&effects)?,
+        layout_resolver.as_mut(),
+    )?)
+}
+
 impl ReplayExecutor {
     pub fn new(
         protocol_config: ProtocolConfig,
@@ -171,6 +245,26 @@ impl ReplayExecutor {
             metrics,
         })
     }
+
+    // Annotated layout of `struct_tag`, resolving packages through `object_store` at
+    // `checkpoint`.
+    pub fn struct_layout(
+        &self,
+        struct_tag: &StructTag,
+        object_store: &dyn ObjectStore,
+        checkpoint: u64,
+    ) -> Result<MoveTypeLayout, anyhow::Error> {
+        let store = ReplayStore {
+            checkpoint,
+            store: object_store,
+            object_cache: RefCell::new(BTreeMap::new()),
+        };
+        let layout = self
+            .executor
+            .type_layout_resolver(Box::new(&store))
+            .get_annotated_layout(struct_tag)?;
+        Ok(layout.into_layout())
+    }
 }
 
 //
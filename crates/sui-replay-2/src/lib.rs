@@ -2,10 +2,13 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::artifacts::{Artifact, ArtifactManager};
+use crate::batch::{outcome_counts, replay_batch, write_summary, Outcome, SummaryFormat};
 use crate::build::BuildCmdConfig;
 use crate::data_store::DataStore;
 use crate::displays::Pretty;
-use crate::replay_txn::replay_transaction;
+use crate::local_store::LocalStore;
+use crate::object_cache::ObjectCache;
+use crate::what_if::WhatIfCmdConfig;
 use anyhow::{anyhow, bail};
 use clap::{Parser, Subcommand};
 use similar::{ChangeTag, TextDiff};
@@ -17,16 +20,21 @@ use sui_types::effects::TransactionEffects;
 use sui_types::supported_protocol_versions::Chain;
 
 pub mod artifacts;
+pub mod batch;
 pub mod build;
 pub mod data_store;
 pub mod displays;
 pub mod execution;
 pub mod gql_queries;
+pub mod local_store;
+pub mod object_cache;
 pub mod replay_interface;
 pub mod replay_txn;
+pub mod simulate;
 pub mod tracing;
+pub mod what_if;
 
-const DEFAULT_OUTPUT_DIR: &str = ".replay";
+pub(crate) const DEFAULT_OUTPUT_DIR: &str = ".replay";
 
 /// Arguments to the replay tool.
 /// It allows to replay a single transaction by digest or
@@ -54,6 +62,8 @@ pub enum Commands {
     /// Build and prepare replay data
     #[clap(alias = "b")]
     Build(BuildCmdConfig),
+    /// Replay a transaction against modified packages or protocol config
+    WhatIf(WhatIfCmdConfig),
 }
 
 /// Arguments for the (implicit) replay command.
@@ -66,8 +76,18 @@ pub struct ReplayConfig {
     #[arg(long)]
     pub digests_path: Option<PathBuf>,
     /// RPC of the fullnode used to replay the transaction.
+    /// When replaying from `--ingestion-path` it only selects the chain (protocol config).
     #[arg(long, short, default_value = "mainnet")]
     pub node: Node,
+    /// Replay offline from a local directory of checkpoints (`<seq>.chk` files, as written for
+    /// local ingestion) instead of querying the RPC.
+    #[arg(long)]
+    pub ingestion_path: Option<PathBuf>,
+    /// Directory of cached objects and epochs. When replaying against the RPC, every object and
+    /// epoch fetched is saved to it. When replaying from `--ingestion-path`, it provides the
+    /// objects that are not in the checkpoints (e.g. packages and objects only read).
+    #[arg(long)]
+    pub object_cache: Option<PathBuf>,
     /// Whether to trace the transaction execution. Generated traces will be saved in the output
     /// directory (or `<cur_dir>/.replay/<digest>` if none provided).
     #[arg(long = "trace", default_value = "false")]
@@ -75,6 +95,16 @@ pub struct ReplayConfig {
     /// Terminate a batch replay early if an error occurs when replaying one of the transactions.
     #[arg(long, default_value = "false")]
     pub terminate_early: bool,
+    /// Number of transactions replayed in parallel. Workers share the objects and packages they
+    /// load.
+    #[arg(long, default_value = "1")]
+    pub workers: usize,
+    /// Save a summary of the outcome of each transaction replayed to this file.
+    #[arg(long)]
+    pub summary_path: Option<PathBuf>,
+    /// Format of the summary saved to `--summary-path`.
+    #[arg(long, value_enum, default_value_t = SummaryFormat::Json)]
+    pub summary_format: SummaryFormat,
     /// The output directory for the replay artifacts. Defaults `<cur_dir>/.replay/<digest>`.
     #[arg(long, short)]
     pub output_dir: Option<PathBuf>,
@@ -125,10 +155,15 @@ impl FromStr for Node {
 pub async fn handle_replay_config(config: &ReplayConfig, version: &str) -> anyhow::Result<PathBuf> {
     let ReplayConfig {
         node,
+        ingestion_path,
+        object_cache,
         digest,
         digests_path,
         trace,
         mut terminate_early,
+        workers,
+        summary_path,
+        summary_format,
         output_dir,
         show_effects: _,
         overwrite_existing,
@@ -177,25 +212,75 @@ pub async fn handle_replay_config(config: &ReplayConfig, version: &str) -> anyho
 
     ::tracing::debug!("Binary version: {version}");
 
-    // `DataStore` implements `TransactionStore`, `EpochStore` and `ObjectStore`
-    let data_store = DataStore::new(node.clone(), version)
-        .map_err(|e| anyhow!("Failed to create data store: {:?}", e))?;
+    let object_cache = object_cache
+        .as_ref()
+        .map(ObjectCache::new)
+        .transpose()
+        .map_err(|e| anyhow!("Failed to open object cache: {:?}", e))?;
 
-    // load and replay transactions
-    for tx_digest in digests {
-        let tx_dir = output_root_dir.join(&tx_digest);
-        let artifact_manager =
-            ArtifactManager::new(&tx_dir, *overwrite_existing /* overrides_allowed */)?;
-        match replay_transaction(&artifact_manager, &tx_digest, &data_store, *trace).await {
-            Err(e) if terminate_early => {
-                ::tracing::error!("Error while replaying transaction {}: {:?}", tx_digest, e);
-                bail!("Replay terminated due to error: {}", e);
+    // Replays block on the data store, so the batch runs on the blocking thread pool rather than
+    // holding up the runtime's workers.
+    let summaries = {
+        let output_root_dir = output_root_dir.clone();
+        let ingestion_path = ingestion_path.clone();
+        let node = node.clone();
+        let version = version.to_owned();
+        let (trace, overwrite_existing, workers) = (*trace, *overwrite_existing, *workers);
+        tokio::task::spawn_blocking(move || -> anyhow::Result<_> {
+            // `DataStore` and `LocalStore` implement `TransactionStore`, `EpochStore` and
+            // `ObjectStore`
+            if let Some(ingestion_path) = ingestion_path {
+                let local_store = LocalStore::new(ingestion_path, node.chain(), object_cache)
+                    .map_err(|e| anyhow!("Failed to create local store: {:?}", e))?;
+                Ok(replay_batch(
+                    &output_root_dir,
+                    &digests,
+                    &local_store,
+                    trace,
+                    terminate_early,
+                    overwrite_existing,
+                    workers,
+                ))
+            } else {
+                let mut data_store = DataStore::new(node, &version)
+                    .map_err(|e| anyhow!("Failed to create data store: {:?}", e))?;
+                if let Some(object_cache) = object_cache {
+                    data_store = data_store.with_object_cache(object_cache);
+                }
+                Ok(replay_batch(
+                    &output_root_dir,
+                    &digests,
+                    &data_store,
+                    trace,
+                    terminate_early,
+                    overwrite_existing,
+                    workers,
+                ))
             }
-            Err(e) => {
-                ::tracing::error!("Failed to replay transaction {}: {:?}", tx_digest, e);
-            }
-            Ok(_) => {
-                ::tracing::info!("Successfully replayed transaction {}", tx_digest);
+        })
+        .await
+        .map_err(|e| anyhow!("Batch replay failed: {e}"))??
+    };
+
+    let counts = outcome_counts(&summaries);
+    ::tracing::info!("Replayed {} transactions: {:?}", summaries.len(), counts);
+
+    if let Some(summary_path) = summary_path {
+        write_summary(summary_path, *summary_format, &summaries)?;
+    }
+
+    if terminate_early {
+        if let Some(failed) = summaries.iter().find(|s| s.outcome != Outcome::Matched) {
+            match &failed.outcome {
+                Outcome::EffectsMismatch { .. } => bail!(
+                    "Replay terminated due to error: Transaction effects do not match expected \
+                    effects for transaction {}",
+                    failed.digest
+                ),
+                Outcome::ExecutionError { error } | Outcome::DataFetchError { error } => {
+                    bail!("Replay terminated due to error: {}", error)
+                }
+                Outcome::Matched => unreachable!(),
             }
         }
     }
@@ -0,0 +1,420 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! An offline implementation of the replay interfaces: `TransactionStore`, `EpochStore`, and
+//! `ObjectStore`.
+//! The `LocalStore` reads `CheckpointData` blobs from a local ingestion directory (`<seq>.chk`
+//! files, the same format read by `sui-data-ingestion-core`'s local reader) and, optionally, an
+//! `ObjectCache` populated by a previous online replay.
+//!
+//! Checkpoints only carry the objects a transaction modified (and their outputs), so objects that
+//! were only read (packages, read-only shared objects, dynamic fields) are expected to come from
+//! the object cache. Lookups always try the cache first, as it records the exact answers given by
+//! the RPC, and fall back to the objects found in the checkpoints.
+
+use crate::{
+    object_cache::ObjectCache,
+    replay_interface::{
+        EpochData, EpochStore, ObjectKey, ObjectStore, TransactionStore, VersionQuery,
+    },
+};
+use anyhow::{anyhow, bail, Context};
+use lru::LruCache;
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    fs,
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+use sui_storage::blob::Blob;
+use sui_types::{
+    base_types::ObjectID,
+    committee::ProtocolVersion,
+    digests::TransactionDigest,
+    effects::{TransactionEffects, TransactionEffectsAPI},
+    full_checkpoint_content::CheckpointData,
+    object::Object,
+    supported_protocol_versions::{Chain, ProtocolConfig},
+    transaction::TransactionData,
+};
+use tracing::debug;
+
+type CheckpointSequenceNumber = u64;
+type ObjectVersion = u64;
+
+// Number of decoded checkpoints kept in memory.
+const CHECKPOINT_CACHE_SIZE: usize = 64;
+
+pub struct LocalStore {
+    path: PathBuf,
+    chain: Chain,
+    object_cache: Option<ObjectCache>,
+    index: Mutex<Index>,
+    // Checkpoints most recently decoded
+    checkpoints: Mutex<LruCache<CheckpointSequenceNumber, Arc<CheckpointData>>>,
+}
+
+// What is known about the checkpoints read so far. Checkpoints are read in order, and only as far
+// as needed to answer a query.
+#[derive(Default)]
+struct Index {
+    // Checkpoints not read yet, in order
+    unread: VecDeque<CheckpointSequenceNumber>,
+    // Checkpoint each transaction was executed in
+    transactions: HashMap<TransactionDigest, CheckpointSequenceNumber>,
+    // Every version of an object found in the checkpoints, mapped to the earliest checkpoint it
+    // was seen in. That is an upper bound on the checkpoint the version was created in.
+    objects: BTreeMap<ObjectID, BTreeMap<ObjectVersion, CheckpointSequenceNumber>>,
+    // Epochs that start within the checkpoints available locally
+    epochs: BTreeMap<u64, EpochData>,
+}
+
+impl TransactionStore for LocalStore {
+    fn transaction_data_and_effects(
+        &self,
+        tx_digest: &str,
+    ) -> Result<(TransactionData, TransactionEffects, u64), anyhow::Error> {
+        let digest: TransactionDigest = tx_digest
+            .parse()
+            .context(format!("Transaction digest malformed: {}", tx_digest))?;
+        let checkpoint = self
+            .read_until(|index| index.transactions.get(&digest).copied())?
+            .ok_or_else(|| {
+                anyhow!(
+                    "Transaction {} not found in checkpoints at {}",
+                    tx_digest,
+                    self.path.display()
+                )
+            })?;
+
+        let data = self.checkpoint(checkpoint)?;
+        let txn = data
+            .transactions
+            .iter()
+            .find(|txn| *txn.effects.transaction_digest() == digest)
+            .ok_or_else(|| {
+                anyhow!("Transaction {tx_digest} not found in checkpoint {checkpoint}")
+            })?;
+
+        Ok((
+            txn.transaction.transaction_data().clone(),
+            txn.effects.clone(),
+            checkpoint,
+        ))
+    }
+}
+
+impl EpochStore for LocalStore {
+    fn epoch_info(&self, epoch: u64) -> Result<EpochData, anyhow::Error> {
+        if let Some(epoch_data) = self.read_until(|index| index.epochs.get(&epoch).cloned())? {
+            return Ok(epoch_data);
+        }
+        if let Some(epoch_data) = self
+            .object_cache
+            .as_ref()
+            .map(|cache| cache.epoch(epoch))
+            .transpose()?
+            .flatten()
+        {
+            return Ok(epoch_data);
+        }
+        bail!(
+            "Cannot find epoch info for epoch {epoch}: the start of the epoch is not in the local \
+            checkpoints and the epoch is not in the object cache"
+        )
+    }
+
+    fn protocol_config(&self, epoch: u64) -> Result<ProtocolConfig, anyhow::Error> {
+        let epoch = self.epoch_info(epoch)?;
+        Ok(ProtocolConfig::get_for_version(
+            ProtocolVersion::new(epoch.protocol_version),
+            self.chain,
+        ))
+    }
+}
+
+impl ObjectStore for LocalStore {
+    fn get_objects(&self, keys: &[ObjectKey]) -> Result<Vec<Option<Object>>, anyhow::Error> {
+        keys.iter().map(|key| self.object(key)).collect()
+    }
+}
+
+impl LocalStore {
+    /// Open the checkpoints (`<seq>.chk` files) in `path`. Checkpoints are only read when a
+    /// query needs them.
+    pub fn new(
+        path: impl Into<PathBuf>,
+        chain: Chain,
+        object_cache: Option<ObjectCache>,
+    ) -> Result<Self, anyhow::Error> {
+        let path = path.into();
+        let unread: VecDeque<_> = checkpoint_files(&path)?.into();
+        debug!(
+            "Local store created with {} checkpoints at {}",
+            unread.len(),
+            path.display()
+        );
+
+        Ok(Self {
+            path,
+            chain,
+            object_cache,
+            index: Mutex::new(Index {
+                unread,
+                ..Default::default()
+            }),
+            checkpoints: Mutex::new(LruCache::new(
+                NonZeroUsize::new(CHECKPOINT_CACHE_SIZE).unwrap(),
+            )),
+        })
+    }
+
+    pub fn chain(&self) -> Chain {
+        self.chain
+    }
+
+    // Read checkpoints into the index until `lookup` finds what it is looking for in it, or there
+    // are no checkpoints left to read.
+    fn read_until<T>(
+        &self,
+        lookup: impl Fn(&Index) -> Option<T>,
+    ) -> Result<Option<T>, anyhow::Error> {
+        let mut index = self.index.lock().unwrap();
+        loop {
+            if let Some(found) = lookup(&index) {
+                return Ok(Some(found));
+            }
+            let Some(&checkpoint) = index.unread.front() else {
+                return Ok(None);
+            };
+            let data = self.checkpoint(checkpoint)?;
+            index.add(checkpoint, &data)?;
+            index.unread.pop_front();
+        }
+    }
+
+    fn object(&self, key: &ObjectKey) -> Result<Option<Object>, anyhow::Error> {
+        if let Some(object) = self
+            .object_cache
+            .as_ref()
+            .map(|cache| cache.object(key))
+            .transpose()?
+            .flatten()
+        {
+            return Ok(Some(object));
+        }
+
+        // An exact version is found as soon as the checkpoint it is in has been read, other
+        // queries are only answered once every checkpoint that could change the answer has been.
+        let found = self.read_until(|index| {
+            let versions = index.objects.get(&key.object_id);
+            match key.version_query {
+                VersionQuery::Version(version) => versions
+                    .and_then(|v| v.get_key_value(&version))
+                    .map(|(v, cp)| Some((*v, *cp))),
+                VersionQuery::AtCheckpoint(checkpoint)
+                    if index.unread.front().is_some_and(|next| *next <= checkpoint) =>
+                {
+                    None
+                }
+                VersionQuery::AtCheckpoint(checkpoint) => Some(versions.and_then(|v| {
+                    v.iter()
+                        .rev()
+                        .find(|(_, seen)| **seen <= checkpoint)
+                        .map(|(v, cp)| (*v, *cp))
+                })),
+                _ if !index.unread.is_empty() => None,
+                VersionQuery::RootVersion(version) => Some(
+                    versions
+                        .and_then(|v| v.range(..=version).next_back())
+                        .map(|(v, cp)| (*v, *cp)),
+                ),
+                VersionQuery::ImmutableOrLatest => Some(
+                    versions
+                        .and_then(|v| v.last_key_value())
+                        .map(|(v, cp)| (*v, *cp)),
+                ),
+            }
+        })?;
+        let Some((version, checkpoint)) = found.flatten() else {
+            return Ok(None);
+        };
+
+        let data = self.checkpoint(checkpoint)?;
+        Ok(data
+            .all_objects()
+            .into_iter()
+            .find(|object| object.id() == key.object_id && object.version().value() == version)
+            .cloned())
+    }
+
+    fn checkpoint(
+        &self,
+        checkpoint: CheckpointSequenceNumber,
+    ) -> Result<Arc<CheckpointData>, anyhow::Error> {
+        if let Some(data) = self.checkpoints.lock().unwrap().get(&checkpoint) {
+            return Ok(data.clone());
+        }
+        let data = read_checkpoint(&self.path, checkpoint)?;
+        self.checkpoints
+            .lock()
+            .unwrap()
+            .put(checkpoint, data.clone());
+        Ok(data)
+    }
+}
+
+impl Index {
+    fn add(
+        &mut self,
+        checkpoint: CheckpointSequenceNumber,
+        data: &CheckpointData,
+    ) -> Result<(), anyhow::Error> {
+        for txn in &data.transactions {
+            self.transactions
+                .insert(*txn.effects.transaction_digest(), checkpoint);
+        }
+
+        for object in data.all_objects() {
+            self.objects
+                .entry(object.id())
+                .or_default()
+                .entry(object.version().value())
+                .or_insert(checkpoint);
+        }
+
+        if let Some(epoch) = data.epoch_info()? {
+            let missing = |field: &str| {
+                anyhow!(
+                    "Missing {field} for epoch {} in checkpoint {checkpoint}",
+                    epoch.epoch
+                )
+            };
+            self.epochs.insert(
+                epoch.epoch,
+                EpochData {
+                    epoch_id: epoch.epoch,
+                    protocol_version: epoch
+                        .protocol_version
+                        .ok_or_else(|| missing("protocol version"))?,
+                    rgp: epoch
+                        .reference_gas_price
+                        .ok_or_else(|| missing("reference gas price"))?,
+                    start_timestamp: epoch
+                        .start_timestamp_ms
+                        .ok_or_else(|| missing("start timestamp"))?,
+                },
+            );
+        }
+
+        Ok(())
+    }
+}
+
+// Sequence numbers of all the checkpoint files in `path`, in order.
+fn checkpoint_files(path: &Path) -> Result<Vec<CheckpointSequenceNumber>, anyhow::Error> {
+    let entries =
+        fs::read_dir(path).context(format!("Failed to read checkpoints at {}", path.display()))?;
+
+    let mut checkpoints = vec![];
+    for entry in entries {
+        let entry = entry?;
+        let file_name = entry.file_name();
+        let Some(seq) = file_name
+            .to_str()
+            .and_then(|name| name.strip_suffix(".chk"))
+            .and_then(|seq| seq.parse().ok())
+        else {
+            continue;
+        };
+        checkpoints.push(seq);
+    }
+
+    checkpoints.sort();
+    Ok(checkpoints)
+}
+
+fn read_checkpoint(
+    path: &Path,
+    checkpoint: CheckpointSequenceNumber,
+) -> Result<Arc<CheckpointData>, anyhow::Error> {
+    let file = path.join(format!("{checkpoint}.chk"));
+    let bytes = fs::read(&file).context(format!("Failed to read {}", file.display()))?;
+    Blob::from_bytes::<Arc<CheckpointData>>(&bytes)
+        .context(format!("Failed to decode checkpoint {}", file.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sui_storage::blob::BlobEncoding;
+    use sui_types::test_checkpoint_data_builder::TestCheckpointDataBuilder;
+
+    #[test]
+    fn read_checkpoints_lazily() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut builder = TestCheckpointDataBuilder::new(0)
+            .start_transaction(0)
+            .create_owned_object(0)
+            .finish_transaction();
+        let checkpoint0 = builder.build_checkpoint();
+        builder = builder
+            .start_transaction(0)
+            .mutate_owned_object(0)
+            .finish_transaction();
+        let checkpoint1 = builder.build_checkpoint();
+        let checkpoints = [checkpoint0, checkpoint1];
+        for (seq, data) in checkpoints.iter().enumerate() {
+            let blob = Blob::encode(data, BlobEncoding::Bcs).unwrap();
+            fs::write(dir.path().join(format!("{seq}.chk")), blob.to_bytes()).unwrap();
+        }
+
+        let store = LocalStore::new(dir.path(), Chain::Unknown, None).unwrap();
+        assert_eq!(store.index.lock().unwrap().unread.len(), 2);
+
+        // Finding the first transaction only reads the first checkpoint.
+        let first = checkpoints[0].transactions[0].transaction.digest();
+        let (_, effects, checkpoint) = store
+            .transaction_data_and_effects(&first.to_string())
+            .unwrap();
+        assert_eq!(*effects.transaction_digest(), *first);
+        assert_eq!(checkpoint, 0);
+        assert_eq!(store.index.lock().unwrap().unread.len(), 1);
+
+        let object_id = TestCheckpointDataBuilder::derive_object_id(0);
+        let output_version = |data: &CheckpointData| {
+            data.transactions[0]
+                .output_objects
+                .iter()
+                .find(|o| o.id() == object_id)
+                .unwrap()
+                .version()
+                .value()
+        };
+        let created = output_version(&checkpoints[0]);
+        let mutated = output_version(&checkpoints[1]);
+
+        let key = |version_query| ObjectKey {
+            object_id,
+            version_query,
+        };
+        let version = |key| {
+            store
+                .get_objects(&[key])
+                .unwrap()
+                .pop()
+                .unwrap()
+                .map(|o: Object| o.version().value())
+        };
+
+        assert_eq!(version(key(VersionQuery::AtCheckpoint(0))), Some(created));
+        assert_eq!(version(key(VersionQuery::ImmutableOrLatest)), Some(mutated));
+        assert_eq!(version(key(VersionQuery::Version(created))), Some(created));
+        assert_eq!(version(key(VersionQuery::Version(mutated + 1))), None);
+        assert!(store.index.lock().unwrap().unread.is_empty());
+
+        let missing = TransactionDigest::random().to_string();
+        assert!(store.transaction_data_and_effects(&missing).is_err());
+    }
+}
@@ -4,7 +4,8 @@
 use clap::*;
 use core::panic;
 use sui_replay_2::{
-    build::handle_build_command, handle_replay_config, print_effects_or_fork, Commands, Config,
+    build::handle_build_command, handle_replay_config, print_effects_or_fork,
+    what_if::handle_what_if_command, Commands, Config,
 };
 use tracing::debug;
 
@@ -24,6 +25,9 @@ async fn main() -> anyhow::Result<()> {
         Some(Commands::Build(build_config)) => {
             handle_build_command(build_config)?;
         }
+        Some(Commands::WhatIf(what_if_config)) => {
+            handle_what_if_command(&config.replay, what_if_config, VERSION).await?;
+        }
         None => {
             let output_root = handle_replay_config(&config.replay, VERSION).await?;
 
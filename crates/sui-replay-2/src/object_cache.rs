@@ -0,0 +1,214 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! An on-disk cache of the objects and epoch data used by replay.
+//! The cache is populated when replaying against a live RPC endpoint and can later be used,
+//! together with a local checkpoint directory, to replay transactions without network access.
+//!
+//! Layout of the cache directory:
+//! ```text
+//! <root>/epochs/<epoch>.json                    -- `EpochData` as JSON
+//! <root>/objects/<object_id>/<version>.bcs      -- BCS encoded `Object`
+//! <root>/objects/<object_id>/<query>            -- version returned for a non exact query
+//! ```
+//! Non exact queries (`RootVersion`, `AtCheckpoint` and `ImmutableOrLatest`) are recorded with
+//! the version the store returned for them, so that a replay from the cache resolves objects
+//! exactly as the original replay did.
+
+use crate::replay_interface::{EpochData, ObjectKey, VersionQuery};
+use anyhow::Context;
+use mysten_common::fs::write_atomic;
+use std::{
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+use sui_types::{base_types::ObjectID, object::Object};
+
+const EPOCHS_DIR: &str = "epochs";
+const OBJECTS_DIR: &str = "objects";
+
+#[derive(Clone, Debug)]
+pub struct ObjectCache {
+    root: PathBuf,
+}
+
+impl ObjectCache {
+    /// Open (or create) an object cache rooted at `root`.
+    pub fn new(root: impl Into<PathBuf>) -> Result<Self, anyhow::Error> {
+        let root = root.into();
+        fs::create_dir_all(root.join(EPOCHS_DIR))
+            .and_then(|_| fs::create_dir_all(root.join(OBJECTS_DIR)))
+            .context(format!(
+                "Failed to create object cache at {}",
+                root.display()
+            ))?;
+        Ok(Self { root })
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Return the `EpochData` for `epoch` if it is in the cache.
+    pub fn epoch(&self, epoch: u64) -> Result<Option<EpochData>, anyhow::Error> {
+        let path = self.epoch_path(epoch);
+        let Some(bytes) = read_if_exists(&path)? else {
+            return Ok(None);
+        };
+        let epoch_data = serde_json::from_slice(&bytes)
+            .context(format!("Failed to parse epoch data at {}", path.display()))?;
+        Ok(Some(epoch_data))
+    }
+
+    pub fn put_epoch(&self, epoch_data: &EpochData) -> Result<(), anyhow::Error> {
+        let bytes = serde_json::to_vec_pretty(epoch_data)?;
+        write_atomic(&self.epoch_path(epoch_data.epoch_id), &bytes)
+    }
+
+    /// Return the object that answers `key`, if the cache has it.
+    pub fn object(&self, key: &ObjectKey) -> Result<Option<Object>, anyhow::Error> {
+        let version = match &key.version_query {
+            VersionQuery::Version(version) => *version,
+            query => {
+                let path = self.object_dir(&key.object_id).join(query_file_name(query));
+                let Some(bytes) = read_if_exists(&path)? else {
+                    return Ok(None);
+                };
+                String::from_utf8(bytes)?
+                    .trim()
+                    .parse()
+                    .context(format!("Malformed object version at {}", path.display()))?
+            }
+        };
+
+        let path = self.object_path(&key.object_id, version);
+        let Some(bytes) = read_if_exists(&path)? else {
+            return Ok(None);
+        };
+        let object = bcs::from_bytes(&bytes).context(format!(
+            "Failed to deserialize object at {}",
+            path.display()
+        ))?;
+        Ok(Some(object))
+    }
+
+    /// Record `object` as the answer to `key`.
+    pub fn put_object(&self, key: &ObjectKey, object: &Object) -> Result<(), anyhow::Error> {
+        let version = object.version().value();
+        let path = self.object_path(&key.object_id, version);
+        if !path.exists() {
+            write_atomic(&path, &bcs::to_bytes(object)?)?;
+        }
+
+        if !matches!(key.version_query, VersionQuery::Version(_)) {
+            let path = self
+                .object_dir(&key.object_id)
+                .join(query_file_name(&key.version_query));
+            write_atomic(&path, version.to_string().as_bytes())?;
+        }
+
+        Ok(())
+    }
+
+    fn epoch_path(&self, epoch: u64) -> PathBuf {
+        self.root.join(EPOCHS_DIR).join(format!("{epoch}.json"))
+    }
+
+    fn object_dir(&self, object_id: &ObjectID) -> PathBuf {
+        self.root.join(OBJECTS_DIR).join(object_id.to_string())
+    }
+
+    fn object_path(&self, object_id: &ObjectID, version: u64) -> PathBuf {
+        self.object_dir(object_id).join(format!("{version}.bcs"))
+    }
+}
+
+fn query_file_name(query: &VersionQuery) -> String {
+    match query {
+        VersionQuery::Version(v) => format!("{v}.bcs"),
+        VersionQuery::RootVersion(v) => format!("root_version_{v}"),
+        VersionQuery::AtCheckpoint(cp) => format!("at_checkpoint_{cp}"),
+        VersionQuery::ImmutableOrLatest => "latest".to_string(),
+    }
+}
+
+fn read_if_exists(path: &Path) -> Result<Option<Vec<u8>>, anyhow::Error> {
+    match fs::read(path) {
+        Ok(bytes) => Ok(Some(bytes)),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e).context(format!("Failed to read {}", path.display())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = ObjectCache::new(dir.path()).unwrap();
+
+        let object = Object::immutable_with_id_for_testing(ObjectID::random());
+        let object_id = object.id();
+        let version = object.version().value();
+        let key = |version_query| ObjectKey {
+            object_id,
+            version_query,
+        };
+
+        assert!(cache
+            .object(&key(VersionQuery::Version(version)))
+            .unwrap()
+            .is_none());
+
+        // A non exact query is answered with the version it was recorded with.
+        cache
+            .put_object(&key(VersionQuery::ImmutableOrLatest), &object)
+            .unwrap();
+        assert_eq!(
+            cache
+                .object(&key(VersionQuery::ImmutableOrLatest))
+                .unwrap()
+                .unwrap(),
+            object
+        );
+        assert_eq!(
+            cache
+                .object(&key(VersionQuery::Version(version)))
+                .unwrap()
+                .unwrap(),
+            object
+        );
+        assert!(cache
+            .object(&key(VersionQuery::RootVersion(version)))
+            .unwrap()
+            .is_none());
+        assert!(cache
+            .object(&key(VersionQuery::Version(version + 1)))
+            .unwrap()
+            .is_none());
+
+        let epoch = EpochData {
+            epoch_id: 7,
+            protocol_version: 42,
+            rgp: 1000,
+            start_timestamp: 1_700_000_000_000,
+        };
+        cache.put_epoch(&epoch).unwrap();
+
+        // Entries survive re-opening the cache, and no temporary files are left behind.
+        let cache = ObjectCache::new(dir.path()).unwrap();
+        let cached = cache.epoch(7).unwrap().unwrap();
+        assert_eq!(cached.protocol_version, 42);
+        assert_eq!(cached.rgp, 1000);
+        assert!(cache.epoch(8).unwrap().is_none());
+
+        let files: Vec<_> = fs::read_dir(cache.object_dir(&object_id))
+            .unwrap()
+            .map(|e| e.unwrap().file_name().into_string().unwrap())
+            .collect();
+        assert_eq!(files.len(), 2, "{files:?}");
+    }
+}
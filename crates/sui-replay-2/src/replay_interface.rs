@@ -12,7 +12,10 @@
 //! and work with them.
 //!
 //! A `DataStore` with reasonable defaults is provided for convenience (`data_store.rs`).
+//! A `LocalStore` replays offline from a local checkpoint directory and an object cache
+//! (`local_store.rs`).
 
+use serde::{Deserialize, Serialize};
 use sui_types::{
     base_types::ObjectID, effects::TransactionEffects, object::Object,
     supported_protocol_versions::ProtocolConfig, transaction::TransactionData,
@@ -33,7 +36,7 @@ pub trait TransactionStore {
 }
 
 /// Epoch data required to reaplay a transaction.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct EpochData {
     pub epoch_id: u64,
     pub protocol_version: u64,
@@ -56,7 +59,7 @@ pub trait EpochStore {
 
 /// Query for an object.
 /// Specifies an `ObjectID` and the rule to retrieve it.
-#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct ObjectKey {
     pub object_id: ObjectID,
     pub version_query: VersionQuery,
@@ -67,7 +70,7 @@ pub struct ObjectKey {
 /// `RootVersion` request an object at a given version at most (<=)
 /// `AtCheckpoint` request an object at a given checkpoint. Useful for unknown `Version).
 /// `ImmutableOrLatest` requests an object assumed to be unversionalbe in the system (e.g. user packages)
-#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum VersionQuery {
     Version(u64),
     RootVersion(u64),
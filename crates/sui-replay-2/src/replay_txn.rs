@@ -12,12 +12,11 @@
 
 use crate::{
     artifacts::{Artifact, ArtifactManager},
-    data_store::DataStore,
     execution::{execute_transaction_to_effects, ReplayExecutor},
     replay_interface::{EpochStore, ObjectKey, ObjectStore, TransactionStore, VersionQuery},
     tracing::save_trace_output,
 };
-use anyhow::{anyhow, bail, Context};
+use anyhow::{anyhow, Context};
 use move_trace_format::format::MoveTraceBuilder;
 use std::collections::{btree_map::Entry, BTreeMap, BTreeSet};
 use sui_types::{base_types::SequenceNumber, TypeTag};
@@ -52,38 +51,72 @@ pub struct ReplayTransaction {
     pub object_cache: BTreeMap<ObjectID, BTreeMap<ObjectVersion, Object>>,
 }
 
+/// Ways replaying a single transaction can fail.
+#[derive(thiserror::Error, Debug)]
+pub enum ReplayError {
+    /// The transaction, or the objects and epoch data needed to execute it, could not be loaded.
+    #[error("Failed to load transaction {digest}: {error:?}")]
+    DataFetch {
+        digest: String,
+        error: anyhow::Error,
+    },
+    /// The transaction could not be executed, or its artifacts could not be saved.
+    #[error("Failed to execute transaction {digest}: {error:?}")]
+    Execution {
+        digest: String,
+        error: anyhow::Error,
+    },
+    /// The transaction executed, but produced different effects than on chain.
+    #[error("Transaction effects do not match expected effects for transaction {digest}")]
+    EffectsMismatch {
+        digest: String,
+        expected: Box<TransactionEffects>,
+        actual: Box<TransactionEffects>,
+    },
+}
+
 //
 // Run a single transaction and print results to stdout
 //
-pub(crate) async fn replay_transaction(
+pub(crate) async fn replay_transaction<S>(
     artifact_manager: &ArtifactManager<'_>,
     tx_digest: &str,
-    data_store: &DataStore,
+    data_store: &S,
     trace: bool,
-) -> anyhow::Result<()> {
-    // load a `ReplayTranaction`
-    let replay_txn = match ReplayTransaction::load(tx_digest, data_store, data_store, data_store) {
-        Ok(replay_txn) => replay_txn,
-        Err(e) => {
-            bail!("Failed to load transaction {}: {:?}", tx_digest, e);
-        }
+) -> Result<(), ReplayError>
+where
+    S: TransactionStore + EpochStore + ObjectStore,
+{
+    let execution_error = |error| ReplayError::Execution {
+        digest: tx_digest.to_string(),
+        error,
     };
 
+    // load a `ReplayTranaction`
+    let replay_txn = ReplayTransaction::load(tx_digest, data_store, data_store, data_store)
+        .map_err(|error| ReplayError::DataFetch {
+            digest: tx_digest.to_string(),
+            error,
+        })?;
+
     // replay the transaction
     let mut trace_builder_opt = trace.then(MoveTraceBuilder::new);
 
     let (result, context_and_effects) =
-        execute_transaction_to_effects(replay_txn, data_store, data_store, &mut trace_builder_opt)?;
+        execute_transaction_to_effects(replay_txn, data_store, data_store, &mut trace_builder_opt)
+            .map_err(execution_error)?;
 
     // TODO: make tracing better abstracted? different tracers?
     if let Some(trace_builder) = trace_builder_opt {
-        save_trace_output(artifact_manager, trace_builder, &context_and_effects).map_err(|e| {
-            anyhow!(
-                "transaction {} failed to build a trace output path -> {:?}",
-                tx_digest,
-                e
-            )
-        })?;
+        save_trace_output(artifact_manager, trace_builder, &context_and_effects)
+            .map_err(|e| {
+                anyhow!(
+                    "transaction {} failed to build a trace output path -> {:?}",
+                    tx_digest,
+                    e
+                )
+            })
+            .map_err(execution_error)?;
     }
 
     // Save results
@@ -97,24 +130,36 @@ pub(crate) async fn replay_transaction(
     artifact_manager
         .member(Artifact::TransactionEffects)
         .serialize_artifact(&context_and_effects.execution_effects)
-        .transpose()?
+        .transpose()
+        .map_err(execution_error)?
         .unwrap();
 
     artifact_manager
         .member(Artifact::TransactionGasReport)
         .serialize_artifact(&context_and_effects.gas_status.gas_usage_report())
-        .transpose()?
+        .transpose()
+        .map_err(execution_error)?
         .unwrap();
 
     verify_txn_and_save_forked_effects(
         artifact_manager,
         &context_and_effects.expected_effects,
         &context_and_effects.execution_effects,
-    )?;
+    )
+    .map_err(execution_error)?;
+
+    if context_and_effects.execution_effects != context_and_effects.expected_effects {
+        return Err(ReplayError::EffectsMismatch {
+            digest: tx_digest.to_string(),
+            expected: Box::new(context_and_effects.expected_effects),
+            actual: Box::new(context_and_effects.execution_effects),
+        });
+    }
 
     Ok(())
 }
 
+// Save the effects of the replay as forked if they differ from the expected effects.
 fn verify_txn_and_save_forked_effects(
     artifact_manager: &ArtifactManager<'_>,
     expected_effects: &TransactionEffects,
@@ -130,13 +175,8 @@ fn verify_txn_and_save_forked_effects(
             .serialize_artifact(effects)
             .transpose()?
             .unwrap();
-        bail!(
-            "Transaction effects do not match expected effects for transaction {}",
-            effects.transaction_digest()
-        );
-    } else {
-        Ok(())
     }
+    Ok(())
 }
 
 impl ReplayTransaction {
@@ -211,14 +251,25 @@ fn load_transaction_objects(
     effects: &TransactionEffects,
     checkpoint: u64,
     object_store: &dyn ObjectStore,
+) -> Result<BTreeMap<ObjectID, BTreeMap<ObjectVersion, Object>>, anyhow::Error> {
+    // get the ids and versions of the input objects to load
+    let object_keys = get_txn_object_keys(txn_data, effects)?;
+    load_objects_and_packages(txn_data, &object_keys, checkpoint, object_store)
+}
+
+// Load the objects at `object_keys` and all the packages required by the transaction
+// and by the types of those objects.
+pub(crate) fn load_objects_and_packages(
+    txn_data: &TransactionData,
+    object_keys: &[ObjectKey],
+    checkpoint: u64,
+    object_store: &dyn ObjectStore,
 ) -> Result<BTreeMap<ObjectID, BTreeMap<ObjectVersion, Object>>, anyhow::Error> {
     // collect all package ids required by the transaction
     let mut packages = get_packages(txn_data)?;
 
-    // get the ids and versions of the input objects to load
     // load the objects and collect the package ids of the type parameters
-    let object_keys = get_txn_object_keys(txn_data, effects)?;
-    let (mut object_cache, tp_pkgs) = load_objects(&object_keys, object_store)?;
+    let (mut object_cache, tp_pkgs) = load_objects(object_keys, object_store)?;
     packages.extend(&tp_pkgs);
 
     // load the packages and add them to the object cache
@@ -380,7 +431,9 @@ fn get_txn_object_keys(
 // That includes:
 // - the gas coins
 // -- all `CallArg::Object` to PTBs
-fn get_input_ids(txn_data: &TransactionData) -> Result<BTreeSet<ObjectKey>, anyhow::Error> {
+pub(crate) fn get_input_ids(
+    txn_data: &TransactionData,
+) -> Result<BTreeSet<ObjectKey>, anyhow::Error> {
     // grab all coins
     let mut object_keys: BTreeSet<ObjectKey> = txn_data
         .gas_data()
@@ -0,0 +1,498 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Local simulation of a transaction that has not been executed.
+//! The transaction is dev-inspected against the state of a store at a checkpoint, with parts of
+//! that state overridden: the contents of objects, packages replaced by local builds, the
+//! balance of the gas coin and the timestamp of the clock.
+//!
+//! Overrides are applied by `OverrideStore`, a wrapper around any store implementing the replay
+//! interfaces which answers object queries for overridden objects with their overridden version
+//! (as `WhatIfStore` does for packages), so the rest of the execution runs unchanged.
+
+use crate::{
+    execution::{dev_inspect_transaction, ReplayExecutor},
+    replay_interface::{EpochStore, ObjectKey, ObjectStore, VersionQuery},
+    replay_txn::{get_input_ids, load_objects_and_packages},
+    what_if::build_substitute,
+};
+use anyhow::{anyhow, bail, ensure, Context};
+use fastcrypto::encoding::{Base64, Encoding};
+use move_core_types::language_storage::StructTag;
+use serde::Deserialize;
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+use sui_json::SuiJsonValue;
+use sui_json_rpc_types::DevInspectResults;
+use sui_types::{
+    base_types::ObjectID,
+    object::{MoveObject, Object},
+    supported_protocol_versions::ProtocolConfig,
+    transaction::{TransactionData, TransactionDataAPI},
+    SUI_CLOCK_OBJECT_ID,
+};
+use tracing::debug;
+
+/// Changes to the state a transaction is simulated against, usually read from a JSON file:
+///
+/// ```json
+/// {
+///   "objects": { "0x...": { "json": { "id": "0x...", "value": "100" } } },
+///   "packages": { "0x...": "../my_package" },
+///   "gasBalance": 1000000000,
+///   "clockTimestampMs": 1700000000000
+/// }
+/// ```
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct StateOverrides {
+    /// Replacement contents of existing objects. The type, owner and version are unchanged.
+    #[serde(default)]
+    pub objects: BTreeMap<ObjectID, ObjectContents>,
+    /// Packages replaced by the package built from a local path, which must be built against
+    /// the same addresses as the package it replaces.
+    #[serde(default)]
+    pub packages: BTreeMap<ObjectID, PathBuf>,
+    /// MIST added to the balance of the first gas coin.
+    pub gas_balance: Option<u64>,
+    /// Timestamp of the `Clock` object (`0x6`).
+    pub clock_timestamp_ms: Option<u64>,
+}
+
+/// The contents of a Move object, including its `id` field.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ObjectContents {
+    /// The value of the object as JSON, in the format accepted for `sui client call` arguments.
+    Json(serde_json::Value),
+    /// The BCS bytes of the value of the object, Base64 encoded.
+    Bcs(String),
+}
+
+impl StateOverrides {
+    /// Read overrides from a JSON file. Relative package paths are resolved against the directory
+    /// of the file.
+    pub fn from_file(path: &Path) -> Result<Self, anyhow::Error> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Cannot read overrides from {}", path.display()))?;
+        let mut overrides: Self = serde_json::from_str(&content)
+            .with_context(|| format!("Invalid overrides in {}", path.display()))?;
+        let base_dir = path.parent().unwrap_or(Path::new("."));
+        for package_path in overrides.packages.values_mut() {
+            *package_path = base_dir.join(&*package_path);
+        }
+        Ok(overrides)
+    }
+}
+
+/// A store that answers queries for overridden objects and packages with their overridden
+/// version, on top of the store it wraps.
+pub struct OverrideStore<'a, S> {
+    inner: &'a S,
+    objects: BTreeMap<ObjectID, Object>,
+}
+
+impl<S: ObjectStore> ObjectStore for OverrideStore<'_, S> {
+    fn get_objects(&self, keys: &[ObjectKey]) -> Result<Vec<Option<Object>>, anyhow::Error> {
+        // Query the wrapped store only for the objects that are not overridden
+        let inner_keys: Vec<_> = keys
+            .iter()
+            .filter(|key| !self.objects.contains_key(&key.object_id))
+            .cloned()
+            .collect();
+        let mut inner_objects = self.inner.get_objects(&inner_keys)?.into_iter();
+
+        keys.iter()
+            .map(|key| match self.objects.get(&key.object_id) {
+                Some(object) => Ok(Some(object.clone())),
+                None => inner_objects
+                    .next()
+                    .ok_or_else(|| anyhow!("Missing object for {:?}", key)),
+            })
+            .collect()
+    }
+}
+
+impl<'a, S: ObjectStore> OverrideStore<'a, S> {
+    /// Apply `overrides` to the state of `inner` at `checkpoint`, for simulating `txn_data`.
+    pub fn new(
+        inner: &'a S,
+        executor: &ReplayExecutor,
+        protocol_config: &ProtocolConfig,
+        checkpoint: u64,
+        txn_data: &TransactionData,
+        overrides: &StateOverrides,
+    ) -> Result<Self, anyhow::Error> {
+        let mut store = Self {
+            inner,
+            objects: BTreeMap::new(),
+        };
+
+        // packages first, so that the layouts of overridden objects come from local builds
+        for (package_id, path) in &overrides.packages {
+            let on_chain = store.get_at_checkpoint(*package_id, checkpoint)?;
+            let package =
+                build_substitute(&on_chain, path, protocol_config.max_move_package_size())
+                    .with_context(|| format!("Failed to substitute package {package_id}"))?;
+            store.objects.insert(*package_id, package);
+        }
+
+        let mut objects = BTreeMap::new();
+        for (object_id, contents) in &overrides.objects {
+            let mut object = store.get_at_checkpoint(*object_id, checkpoint)?;
+            let move_object = object
+                .data
+                .try_as_move()
+                .ok_or_else(|| anyhow!("Cannot override the contents of package {object_id}"))?;
+            let bytes = match contents {
+                ObjectContents::Bcs(bcs) => Base64::decode(bcs)
+                    .map_err(|e| anyhow!("Invalid Base64 contents for {object_id}: {e}"))?,
+                ObjectContents::Json(json) => {
+                    let struct_tag: StructTag = move_object.type_().clone().into();
+                    let layout = executor.struct_layout(&struct_tag, &store, checkpoint)?;
+                    SuiJsonValue::new(json.clone())?
+                        .to_bcs_bytes(&layout)
+                        .with_context(|| format!("Invalid JSON contents for {object_id}"))?
+                }
+            };
+            ensure!(
+                MoveObject::id_opt(&bytes).ok() == Some(*object_id),
+                "The contents of {object_id} must start with its ID"
+            );
+            // SAFETY: the contents are only used for a local simulation and are checked by the
+            // VM when they are deserialized
+            let new_object = unsafe {
+                MoveObject::new_from_execution(
+                    move_object.type_().clone(),
+                    move_object.has_public_transfer(),
+                    move_object.version(),
+                    bytes,
+                    protocol_config,
+                    /* system_mutation */ false,
+                )?
+            };
+            *object.data.try_as_move_mut().unwrap() = new_object;
+            objects.insert(*object_id, object);
+        }
+        store.objects.extend(objects);
+
+        if let Some(gas_balance) = overrides.gas_balance {
+            let Some((gas_id, gas_version, _)) = txn_data.gas_data().payment.first() else {
+                bail!("Cannot override the gas balance of a transaction without gas payment");
+            };
+            let mut gas = store
+                .get_one(ObjectKey {
+                    object_id: *gas_id,
+                    version_query: VersionQuery::Version(gas_version.value()),
+                })?
+                .ok_or_else(|| anyhow!("Gas coin {gas_id} not found"))?;
+            ensure!(gas.is_gas_coin(), "Gas payment {gas_id} is not a SUI coin");
+            let coin = gas.data.try_as_move_mut().unwrap();
+            let balance = coin
+                .get_coin_value_unsafe()
+                .checked_add(gas_balance)
+                .ok_or_else(|| anyhow!("Gas balance of {gas_id} overflows"))?;
+            coin.set_coin_value_unsafe(balance);
+            store.objects.insert(*gas_id, gas);
+        }
+
+        if let Some(timestamp_ms) = overrides.clock_timestamp_ms {
+            let mut clock = store.get_at_checkpoint(SUI_CLOCK_OBJECT_ID, checkpoint)?;
+            clock
+                .data
+                .try_as_move_mut()
+                .unwrap()
+                .set_clock_timestamp_ms_unsafe(timestamp_ms);
+            store.objects.insert(SUI_CLOCK_OBJECT_ID, clock);
+        }
+
+        Ok(store)
+    }
+
+    fn get_one(&self, key: ObjectKey) -> Result<Option<Object>, anyhow::Error> {
+        Ok(self.get_objects(&[key])?.into_iter().next().flatten())
+    }
+
+    fn get_at_checkpoint(
+        &self,
+        object_id: ObjectID,
+        checkpoint: u64,
+    ) -> Result<Object, anyhow::Error> {
+        self.get_one(ObjectKey {
+            object_id,
+            version_query: VersionQuery::AtCheckpoint(checkpoint),
+        })?
+        .ok_or_else(|| anyhow!("Object {object_id} not found at checkpoint {checkpoint}"))
+    }
+}
+
+/// Dev-inspect `txn_data` in `epoch` against the state of `store` at `checkpoint`, with
+/// `overrides` applied. Owned inputs are read at the version in the transaction, shared inputs
+/// and packages at `checkpoint`.
+pub fn simulate_transaction<S>(
+    store: &S,
+    epoch: u64,
+    checkpoint: u64,
+    txn_data: TransactionData,
+    overrides: &StateOverrides,
+) -> Result<DevInspectResults, anyhow::Error>
+where
+    S: EpochStore + ObjectStore,
+{
+    let epoch_data = store.epoch_info(epoch)?;
+    let protocol_config = store.protocol_config(epoch)?;
+    let executor = ReplayExecutor::new(protocol_config.clone(), None)?;
+    let store = OverrideStore::new(
+        store,
+        &executor,
+        &protocol_config,
+        checkpoint,
+        &txn_data,
+        overrides,
+    )?;
+
+    let mut object_keys: Vec<_> = get_input_ids(&txn_data)?.into_iter().collect();
+    object_keys.extend(
+        txn_data
+            .shared_input_objects()
+            .into_iter()
+            .map(|shared| ObjectKey {
+                object_id: shared.id,
+                version_query: VersionQuery::AtCheckpoint(checkpoint),
+            }),
+    );
+    let object_cache = load_objects_and_packages(&txn_data, &object_keys, checkpoint, &store)?;
+    debug!(
+        "Simulating {} with {} objects loaded",
+        txn_data.digest(),
+        object_cache.len()
+    );
+
+    dev_inspect_transaction(
+        &executor,
+        txn_data,
+        &epoch_data,
+        checkpoint,
+        &store,
+        object_cache,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sui_types::base_types::{SequenceNumber, SuiAddress};
+    use sui_types::clock::Clock;
+    use sui_types::digests::TransactionDigest;
+    use sui_types::gas_coin::GasCoin;
+    use sui_types::id::UID;
+    use sui_types::object::Owner;
+
+    /// Objects at a single version, whatever the version queried.
+    struct MemoryStore(BTreeMap<ObjectID, Object>);
+
+    impl ObjectStore for MemoryStore {
+        fn get_objects(&self, keys: &[ObjectKey]) -> Result<Vec<Option<Object>>, anyhow::Error> {
+            Ok(keys
+                .iter()
+                .map(|key| self.0.get(&key.object_id).cloned())
+                .collect())
+        }
+    }
+
+    fn gas_coin(owner: SuiAddress, value: u64) -> Object {
+        Object::new_move(
+            MoveObject::new_gas_coin(SequenceNumber::from_u64(5), ObjectID::random(), value),
+            Owner::AddressOwner(owner),
+            TransactionDigest::genesis_marker(),
+        )
+    }
+
+    fn clock(timestamp_ms: u64) -> Object {
+        let contents = bcs::to_bytes(&Clock {
+            id: UID::new(SUI_CLOCK_OBJECT_ID),
+            timestamp_ms,
+        })
+        .unwrap();
+        // SAFETY: the contents are those of a `Clock`
+        let clock = unsafe {
+            MoveObject::new_from_execution_with_limit(
+                Clock::type_().into(),
+                false,
+                SequenceNumber::from_u64(1),
+                contents,
+                256,
+            )
+            .unwrap()
+        };
+        Object::new_move(
+            clock,
+            Owner::Shared {
+                initial_shared_version: SequenceNumber::from_u64(1),
+            },
+            TransactionDigest::genesis_marker(),
+        )
+    }
+
+    fn coin_value(object: &Object) -> u64 {
+        object.data.try_as_move().unwrap().get_coin_value_unsafe()
+    }
+
+    fn latest(object_id: ObjectID) -> ObjectKey {
+        ObjectKey {
+            object_id,
+            version_query: VersionQuery::ImmutableOrLatest,
+        }
+    }
+
+    struct Fixture {
+        sender: SuiAddress,
+        gas: Object,
+        other: Object,
+        store: MemoryStore,
+        executor: ReplayExecutor,
+        protocol_config: ProtocolConfig,
+    }
+
+    impl Fixture {
+        fn new() -> Self {
+            let sender = SuiAddress::random_for_testing_only();
+            let gas = gas_coin(sender, 1_000);
+            let other = gas_coin(sender, 2_000);
+            let store = MemoryStore(
+                [gas.clone(), other.clone(), clock(10)]
+                    .into_iter()
+                    .map(|object| (object.id(), object))
+                    .collect(),
+            );
+            let protocol_config = ProtocolConfig::get_for_max_version_UNSAFE();
+            let executor = ReplayExecutor::new(protocol_config.clone(), None).unwrap();
+            Self {
+                sender,
+                gas,
+                other,
+                store,
+                executor,
+                protocol_config,
+            }
+        }
+
+        fn txn_data(&self) -> TransactionData {
+            TransactionData::new_transfer_sui(
+                SuiAddress::ZERO,
+                self.sender,
+                Some(1),
+                self.gas.compute_object_reference(),
+                1_000_000,
+                1_000,
+            )
+        }
+
+        fn override_store(
+            &self,
+            overrides: &StateOverrides,
+        ) -> Result<OverrideStore<'_, MemoryStore>, anyhow::Error> {
+            OverrideStore::new(
+                &self.store,
+                &self.executor,
+                &self.protocol_config,
+                0,
+                &self.txn_data(),
+                overrides,
+            )
+        }
+    }
+
+    #[test]
+    fn override_object_contents() {
+        let fixture = Fixture::new();
+        let id = fixture.other.id();
+        let overrides = StateOverrides {
+            objects: BTreeMap::from([(
+                id,
+                ObjectContents::Bcs(Base64::encode(GasCoin::new(id, 42).to_bcs_bytes())),
+            )]),
+            ..Default::default()
+        };
+        let store = fixture.override_store(&overrides).unwrap();
+
+        let object = store.get_one(latest(id)).unwrap().unwrap();
+        assert_eq!(coin_value(&object), 42);
+        assert_eq!(object.type_(), fixture.other.type_());
+        assert_eq!(object.owner, fixture.other.owner);
+        assert_eq!(object.version(), fixture.other.version());
+
+        // The contents must be those of the object overridden.
+        let overrides = StateOverrides {
+            objects: BTreeMap::from([(
+                id,
+                ObjectContents::Bcs(Base64::encode(
+                    GasCoin::new(ObjectID::random(), 42).to_bcs_bytes(),
+                )),
+            )]),
+            ..Default::default()
+        };
+        assert!(fixture.override_store(&overrides).is_err());
+    }
+
+    #[test]
+    fn override_gas_balance() {
+        let fixture = Fixture::new();
+        let overrides = StateOverrides {
+            gas_balance: Some(500),
+            ..Default::default()
+        };
+        let store = fixture.override_store(&overrides).unwrap();
+
+        let gas = store.get_one(latest(fixture.gas.id())).unwrap().unwrap();
+        assert_eq!(coin_value(&gas), 1_500);
+        assert_eq!(gas.version(), fixture.gas.version());
+        let other = store.get_one(latest(fixture.other.id())).unwrap().unwrap();
+        assert_eq!(coin_value(&other), 2_000);
+    }
+
+    #[test]
+    fn override_clock() {
+        let fixture = Fixture::new();
+        let overrides = StateOverrides {
+            clock_timestamp_ms: Some(1_700_000_000_000),
+            ..Default::default()
+        };
+        let store = fixture.override_store(&overrides).unwrap();
+
+        let clock = store.get_one(latest(SUI_CLOCK_OBJECT_ID)).unwrap().unwrap();
+        let clock: Clock = bcs::from_bytes(clock.data.try_as_move().unwrap().contents()).unwrap();
+        assert_eq!(clock.timestamp_ms(), 1_700_000_000_000);
+    }
+
+    #[test]
+    fn get_objects_keeps_order() {
+        let fixture = Fixture::new();
+        let overrides = StateOverrides {
+            gas_balance: Some(500),
+            ..Default::default()
+        };
+        let store = fixture.override_store(&overrides).unwrap();
+
+        // Overridden objects are interleaved with objects of the wrapped store, or missing.
+        let missing = ObjectID::random();
+        let ids = [
+            fixture.other.id(),
+            fixture.gas.id(),
+            missing,
+            SUI_CLOCK_OBJECT_ID,
+        ];
+        let keys: Vec<_> = ids.iter().map(|id| latest(*id)).collect();
+        let objects = store.get_objects(&keys).unwrap();
+        assert_eq!(
+            objects
+                .iter()
+                .map(|object| object.as_ref().map(|o| o.id()))
+                .collect::<Vec<_>>(),
+            vec![Some(ids[0]), Some(ids[1]), None, Some(ids[3])],
+        );
+        assert_eq!(coin_value(objects[1].as_ref().unwrap()), 1_500);
+    }
+}
@@ -0,0 +1,727 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! "What-if" replay.
+//! Re-execute a historical transaction with some of the packages it uses replaced by locally
+//! built ones, and/or with some protocol config constants overridden, then report how the
+//! resulting effects differ from the effects produced on chain.
+//!
+//! Substitutions are applied by `WhatIfStore`, a wrapper around any store implementing the
+//! replay interfaces. It answers object queries for substituted packages with the local build
+//! and applies the overrides to the protocol config of every epoch, so the rest of the replay
+//! runs unchanged.
+
+use crate::{
+    artifacts::{Artifact, ArtifactManager},
+    data_store::DataStore,
+    displays::Pretty,
+    execution::execute_transaction_to_effects,
+    local_store::LocalStore,
+    object_cache::ObjectCache,
+    replay_interface::{
+        EpochData, EpochStore, ObjectKey, ObjectStore, TransactionStore, VersionQuery,
+    },
+    replay_txn::ReplayTransaction,
+    ReplayConfig, DEFAULT_OUTPUT_DIR,
+};
+use anyhow::{anyhow, bail, Context};
+use clap::Parser;
+use move_package::BuildConfig as MoveBuildConfig;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+use sui_move_build::{implicit_deps, set_sui_flavor, BuildConfig};
+use sui_package_management::system_package_versions::latest_system_packages;
+use sui_protocol_config::ProtocolConfigValue;
+use sui_types::{
+    base_types::{ObjectID, SequenceNumber},
+    digests::{ObjectDigest, TransactionDigest, TransactionEventsDigest},
+    effects::{IDOperation, ObjectChange, TransactionEffects, TransactionEffectsAPI},
+    execution_status::ExecutionStatus,
+    gas::GasCostSummary,
+    move_package::{MovePackage, TypeOrigin},
+    object::Object,
+    supported_protocol_versions::ProtocolConfig,
+    transaction::TransactionData,
+};
+use tracing::debug;
+
+/// Arguments for the what-if sub-command.
+/// The data source (`--node`, `--ingestion-path`, `--object-cache`) and `--output-dir` are
+/// taken from the replay arguments.
+#[derive(Parser, Clone, Debug)]
+#[clap(
+    name = "what-if",
+    about = "Replay a transaction against modified packages or protocol config and diff the effects.",
+    rename_all = "kebab-case"
+)]
+pub struct WhatIfCmdConfig {
+    /// Transaction digest to replay.
+    #[arg(long, short)]
+    pub digest: String,
+    /// Replace an on-chain package with a locally built one, as `<package-id>=<path>`.
+    /// The local package must be built against the same addresses as the on-chain package
+    /// (e.g. via `published-at` or its named address). Can be repeated.
+    #[arg(long = "package", value_parser = parse_package_substitution)]
+    pub packages: Vec<(ObjectID, PathBuf)>,
+    /// Override a protocol config constant, as `<name>=<value>` (e.g. `max_gas_price=1000`).
+    /// Feature flags cannot be overridden. Can be repeated.
+    #[arg(long = "protocol-config", value_parser = parse_protocol_config_override)]
+    pub protocol_config: Vec<(String, String)>,
+}
+
+/// The value of a property of the effects, on chain (`expected`) and in the what-if replay
+/// (`actual`).
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Change<T> {
+    pub expected: T,
+    pub actual: T,
+}
+
+/// How a single object was changed by a transaction.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ObjectChangeSummary {
+    pub input_version: Option<SequenceNumber>,
+    pub output_version: Option<SequenceNumber>,
+    pub output_digest: Option<ObjectDigest>,
+    pub id_operation: IDOperation,
+}
+
+impl From<ObjectChange> for ObjectChangeSummary {
+    fn from(change: ObjectChange) -> Self {
+        Self {
+            input_version: change.input_version,
+            output_version: change.output_version,
+            output_digest: change.output_digest,
+            id_operation: change.id_operation,
+        }
+    }
+}
+
+/// An object whose change differs between the on-chain and the what-if effects.
+/// `None` means the object was not changed by that execution.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ObjectChangeDiff {
+    pub object_id: ObjectID,
+    pub expected: Option<ObjectChangeSummary>,
+    pub actual: Option<ObjectChangeSummary>,
+}
+
+/// Structured diff between the on-chain effects of a transaction and the effects of a what-if
+/// replay of it.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WhatIfReport {
+    pub digest: TransactionDigest,
+    /// Packages replaced by a local build.
+    pub packages: Vec<ObjectID>,
+    /// Protocol config constants overridden.
+    pub protocol_config: BTreeMap<String, String>,
+    pub status: Change<ExecutionStatus>,
+    pub gas: Change<GasCostSummary>,
+    /// Only objects whose change differs are listed.
+    pub object_changes: Vec<ObjectChangeDiff>,
+    pub events_digest: Change<Option<TransactionEventsDigest>>,
+    /// Types of the events emitted by the what-if replay.
+    pub events: Vec<String>,
+}
+
+impl WhatIfReport {
+    /// Compare on-chain and what-if effects.
+    pub fn new(
+        expected: &TransactionEffects,
+        actual: &TransactionEffects,
+        events: Vec<String>,
+        packages: Vec<ObjectID>,
+        protocol_config: BTreeMap<String, String>,
+    ) -> Self {
+        let mut changes: BTreeMap<ObjectID, Change<Option<ObjectChangeSummary>>> = BTreeMap::new();
+        for change in expected.object_changes() {
+            changes
+                .entry(change.id)
+                .or_insert(Change {
+                    expected: None,
+                    actual: None,
+                })
+                .expected = Some(change.into());
+        }
+        for change in actual.object_changes() {
+            changes
+                .entry(change.id)
+                .or_insert(Change {
+                    expected: None,
+                    actual: None,
+                })
+                .actual = Some(change.into());
+        }
+
+        let object_changes = changes
+            .into_iter()
+            .filter(|(_, change)| change.expected != change.actual)
+            .map(
+                |(object_id, Change { expected, actual })| ObjectChangeDiff {
+                    object_id,
+                    expected,
+                    actual,
+                },
+            )
+            .collect();
+
+        Self {
+            digest: *expected.transaction_digest(),
+            packages,
+            protocol_config,
+            status: Change {
+                expected: expected.status().clone(),
+                actual: actual.status().clone(),
+            },
+            gas: Change {
+                expected: expected.gas_cost_summary().clone(),
+                actual: actual.gas_cost_summary().clone(),
+            },
+            object_changes,
+            events_digest: Change {
+                expected: expected.events_digest().copied(),
+                actual: actual.events_digest().copied(),
+            },
+            events,
+        }
+    }
+
+    /// True if the what-if replay produced the same effects as on chain.
+    pub fn is_unchanged(&self) -> bool {
+        self.status.expected == self.status.actual
+            && self.gas.expected == self.gas.actual
+            && self.object_changes.is_empty()
+            && self.events_digest.expected == self.events_digest.actual
+    }
+}
+
+/// A store that substitutes packages and overrides protocol config values on top of the store
+/// it wraps.
+pub struct WhatIfStore<'a, S> {
+    inner: &'a S,
+    packages: BTreeMap<ObjectID, Object>,
+    protocol_config: Vec<(String, String)>,
+}
+
+impl<S: TransactionStore> TransactionStore for WhatIfStore<'_, S> {
+    fn transaction_data_and_effects(
+        &self,
+        tx_digest: &str,
+    ) -> Result<(TransactionData, TransactionEffects, u64), anyhow::Error> {
+        self.inner.transaction_data_and_effects(tx_digest)
+    }
+}
+
+impl<S: EpochStore> EpochStore for WhatIfStore<'_, S> {
+    fn epoch_info(&self, epoch: u64) -> Result<EpochData, anyhow::Error> {
+        self.inner.epoch_info(epoch)
+    }
+
+    fn protocol_config(&self, epoch: u64) -> Result<ProtocolConfig, anyhow::Error> {
+        let mut protocol_config = self.inner.protocol_config(epoch)?;
+        apply_protocol_config_overrides(&mut protocol_config, &self.protocol_config)?;
+        Ok(protocol_config)
+    }
+}
+
+impl<S: ObjectStore> ObjectStore for WhatIfStore<'_, S> {
+    fn get_objects(&self, keys: &[ObjectKey]) -> Result<Vec<Option<Object>>, anyhow::Error> {
+        // Query the wrapped store only for the objects that are not substituted
+        let inner_keys: Vec<_> = keys
+            .iter()
+            .filter(|key| !self.packages.contains_key(&key.object_id))
+            .cloned()
+            .collect();
+        let mut inner_objects = self.inner.get_objects(&inner_keys)?.into_iter();
+
+        keys.iter()
+            .map(|key| match self.packages.get(&key.object_id) {
+                Some(package) => Ok(Some(package.clone())),
+                None => inner_objects
+                    .next()
+                    .ok_or_else(|| anyhow!("Missing object for {:?}", key)),
+            })
+            .collect()
+    }
+}
+
+impl<'a, S> WhatIfStore<'a, S>
+where
+    S: TransactionStore + EpochStore + ObjectStore,
+{
+    /// Build the packages at `packages` and prepare them to replace their on-chain counterparts
+    /// when replaying `tx_digest`.
+    pub fn new(
+        inner: &'a S,
+        tx_digest: &str,
+        packages: &[(ObjectID, PathBuf)],
+        protocol_config: Vec<(String, String)>,
+    ) -> Result<Self, anyhow::Error> {
+        let (_, effects, checkpoint) = inner.transaction_data_and_effects(tx_digest)?;
+        let max_move_package_size = inner
+            .protocol_config(effects.executed_epoch())?
+            .max_move_package_size();
+
+        let keys: Vec<_> = packages
+            .iter()
+            .map(|(object_id, _)| ObjectKey {
+                object_id: *object_id,
+                version_query: VersionQuery::AtCheckpoint(checkpoint),
+            })
+            .collect();
+        let on_chain = inner.get_objects(&keys)?;
+
+        let mut substitutes = BTreeMap::new();
+        for ((package_id, path), on_chain) in packages.iter().zip(on_chain) {
+            let on_chain = on_chain.ok_or_else(|| {
+                anyhow!("Package {package_id} not found at checkpoint {checkpoint}")
+            })?;
+            let package = build_substitute(&on_chain, path, max_move_package_size)
+                .with_context(|| format!("Failed to substitute package {package_id}"))?;
+            substitutes.insert(*package_id, package);
+        }
+
+        Ok(Self {
+            inner,
+            packages: substitutes,
+            protocol_config,
+        })
+    }
+}
+
+pub async fn handle_what_if_command(
+    replay_config: &ReplayConfig,
+    config: WhatIfCmdConfig,
+    version: &str,
+) -> anyhow::Result<WhatIfReport> {
+    let ReplayConfig {
+        node,
+        ingestion_path,
+        object_cache,
+        output_dir,
+        overwrite_existing,
+        ..
+    } = replay_config;
+
+    let output_root_dir = if let Some(dir) = output_dir {
+        dir.to_path_buf()
+    } else {
+        let current_dir =
+            std::env::current_dir().map_err(|e| anyhow!("Failed to get current directory: {e}"))?;
+        current_dir.join(DEFAULT_OUTPUT_DIR)
+    };
+
+    let object_cache = object_cache
+        .as_ref()
+        .map(ObjectCache::new)
+        .transpose()
+        .map_err(|e| anyhow!("Failed to open object cache: {:?}", e))?;
+
+    let report = if let Some(ingestion_path) = ingestion_path {
+        let local_store = LocalStore::new(ingestion_path, node.chain(), object_cache)
+            .map_err(|e| anyhow!("Failed to create local store: {:?}", e))?;
+        what_if(&output_root_dir, &config, &local_store, *overwrite_existing)?
+    } else {
+        let mut data_store = DataStore::new(node.clone(), version)
+            .map_err(|e| anyhow!("Failed to create data store: {:?}", e))?;
+        if let Some(object_cache) = object_cache {
+            data_store = data_store.with_object_cache(object_cache);
+        }
+        what_if(&output_root_dir, &config, &data_store, *overwrite_existing)?
+    };
+
+    println!("{}", Pretty(&report));
+    Ok(report)
+}
+
+// Replay the transaction through a `WhatIfStore`, save the report as an artifact and return it.
+fn what_if<S>(
+    output_root_dir: &Path,
+    config: &WhatIfCmdConfig,
+    data_store: &S,
+    overwrite_existing: bool,
+) -> anyhow::Result<WhatIfReport>
+where
+    S: TransactionStore + EpochStore + ObjectStore,
+{
+    let WhatIfCmdConfig {
+        digest,
+        packages,
+        protocol_config,
+    } = config;
+
+    let store = WhatIfStore::new(data_store, digest, packages, protocol_config.clone())?;
+    let replay_txn = ReplayTransaction::load(digest, &store, &store, &store)
+        .map_err(|e| anyhow!("Failed to load transaction {}: {:?}", digest, e))?;
+
+    let (result, context_and_effects) =
+        execute_transaction_to_effects(replay_txn, &store, &store, &mut None)?;
+    debug!("What-if execution of {}: {:?}", digest, result);
+
+    let events = context_and_effects
+        .inner_store
+        .events
+        .data
+        .iter()
+        .map(|event| event.type_.to_canonical_string(/* with_prefix */ true))
+        .collect();
+
+    let report = WhatIfReport::new(
+        &context_and_effects.expected_effects,
+        &context_and_effects.execution_effects,
+        events,
+        packages.iter().map(|(id, _)| *id).collect(),
+        protocol_config.iter().cloned().collect(),
+    );
+
+    let tx_dir = output_root_dir.join(digest);
+    let artifact_manager = ArtifactManager::new(&tx_dir, overwrite_existing)?;
+    artifact_manager
+        .member(Artifact::WhatIfReport)
+        .serialize_artifact(&report)
+        .transpose()?
+        .unwrap();
+
+    Ok(report)
+}
+
+// Build the package at `path` and turn it into a package object with the same ID and version as
+// `on_chain`, so that it can transparently replace it.
+// Type origins of types already on chain are preserved, new types originate in this package.
+// The linkage table is taken from the on-chain package: the local build must use the same
+// dependencies.
+pub(crate) fn build_substitute(
+    on_chain: &Object,
+    path: &Path,
+    max_move_package_size: u64,
+) -> Result<Object, anyhow::Error> {
+    let on_chain_package = on_chain
+        .data
+        .try_as_package()
+        .ok_or_else(|| anyhow!("Object {} is not a package", on_chain.id()))?;
+
+    let mut move_build_config = MoveBuildConfig::default();
+    if let Some(err_msg) = set_sui_flavor(&mut move_build_config) {
+        bail!(err_msg);
+    }
+    move_build_config.implicit_dependencies = implicit_deps(latest_system_packages());
+    let compiled = BuildConfig {
+        config: move_build_config,
+        run_bytecode_verifier: true,
+        print_diags_to_stderr: true,
+        chain_id: None,
+    }
+    .build(path)?;
+
+    let mut module_map = BTreeMap::new();
+    let mut type_origin_table = on_chain_package.type_origin_table().clone();
+    for module in compiled.get_modules() {
+        let module_name = module.self_id().name().to_string();
+        let mut bytes = vec![];
+        module.serialize_with_version(module.version, &mut bytes)?;
+        module_map.insert(module_name.clone(), bytes);
+
+        for datatype in module.datatype_handles() {
+            let datatype_name = module.identifier_at(datatype.name).to_string();
+            let known = type_origin_table
+                .iter()
+                .any(|o| o.module_name == module_name && o.datatype_name == datatype_name);
+            if !known && module.self_handle_idx() == datatype.module {
+                type_origin_table.push(TypeOrigin {
+                    module_name: module_name.clone(),
+                    datatype_name,
+                    package: on_chain.id(),
+                });
+            }
+        }
+    }
+
+    let package = MovePackage::new(
+        on_chain.id(),
+        on_chain.version(),
+        module_map,
+        max_move_package_size,
+        type_origin_table,
+        on_chain_package.linkage_table().clone(),
+    )?;
+    Ok(Object::new_from_package(
+        package,
+        on_chain.previous_transaction,
+    ))
+}
+
+fn apply_protocol_config_overrides(
+    protocol_config: &mut ProtocolConfig,
+    overrides: &[(String, String)],
+) -> Result<(), anyhow::Error> {
+    let attrs = protocol_config.attr_map();
+    let features = protocol_config.feature_map();
+    for (name, value) in overrides {
+        let valid = match attrs.get(name) {
+            None if features.contains_key(name) => {
+                bail!("{name} is a feature flag, and feature flags cannot be overridden")
+            }
+            None => bail!("Unknown protocol config constant {name}"),
+            Some(None) => bail!(
+                "Protocol config constant {name} is not set in protocol version {} and cannot be \
+                overridden",
+                protocol_config.version.as_u64()
+            ),
+            Some(Some(ProtocolConfigValue::u16(_))) => value.parse::<u16>().is_ok(),
+            Some(Some(ProtocolConfigValue::u32(_))) => value.parse::<u32>().is_ok(),
+            Some(Some(ProtocolConfigValue::u64(_))) => value.parse::<u64>().is_ok(),
+            Some(Some(ProtocolConfigValue::bool(_))) => value.parse::<bool>().is_ok(),
+        };
+        if !valid {
+            bail!("Invalid value {value} for protocol config constant {name}");
+        }
+        protocol_config.set_attr_for_testing(name.clone(), value.clone());
+    }
+    Ok(())
+}
+
+fn parse_package_substitution(s: &str) -> Result<(ObjectID, PathBuf), String> {
+    let (id, path) = s
+        .split_once('=')
+        .ok_or_else(|| format!("Expected <package-id>=<path>, got {s}"))?;
+    let id = ObjectID::from_hex_literal(id).map_err(|e| format!("Invalid package ID {id}: {e}"))?;
+    Ok((id, PathBuf::from(path)))
+}
+
+fn parse_protocol_config_override(s: &str) -> Result<(String, String), String> {
+    let (name, value) = s
+        .split_once('=')
+        .ok_or_else(|| format!("Expected <name>=<value>, got {s}"))?;
+    Ok((name.trim().to_string(), value.trim().to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sui_protocol_config::{Chain, ProtocolVersion};
+    use sui_types::effects::EffectsObjectChange;
+    use sui_types::execution_status::ExecutionFailureStatus;
+
+    fn effects(
+        digest: TransactionDigest,
+        status: ExecutionStatus,
+        gas: GasCostSummary,
+        created: &[&Object],
+        events_digest: Option<TransactionEventsDigest>,
+    ) -> TransactionEffects {
+        let changed_objects = created
+            .iter()
+            .map(|object| {
+                (
+                    object.id(),
+                    EffectsObjectChange::new(None, Some(object), true, false),
+                )
+            })
+            .collect();
+
+        TransactionEffects::new_from_execution_v2(
+            status,
+            0,
+            gas,
+            vec![],
+            Default::default(),
+            digest,
+            SequenceNumber::from_u64(2),
+            changed_objects,
+            None,
+            events_digest,
+            vec![],
+        )
+    }
+
+    fn report(expected: &TransactionEffects, actual: &TransactionEffects) -> WhatIfReport {
+        WhatIfReport::new(expected, actual, vec![], vec![], BTreeMap::new())
+    }
+
+    #[test]
+    fn test_report_unchanged() {
+        let digest = TransactionDigest::random();
+        let object = Object::immutable_with_id_for_testing(ObjectID::random());
+        let events = Some(TransactionEventsDigest::random());
+        let gas = GasCostSummary::new(100, 200, 50, 5);
+
+        let expected = effects(
+            digest,
+            ExecutionStatus::Success,
+            gas.clone(),
+            &[&object],
+            events,
+        );
+        let actual = effects(digest, ExecutionStatus::Success, gas, &[&object], events);
+
+        let report = report(&expected, &actual);
+        assert_eq!(report.digest, digest);
+        assert!(report.object_changes.is_empty());
+        assert!(report.is_unchanged());
+    }
+
+    #[test]
+    fn test_report_gas() {
+        let digest = TransactionDigest::random();
+        let expected_gas = GasCostSummary::new(100, 200, 50, 5);
+        let actual_gas = GasCostSummary::new(150, 200, 50, 5);
+
+        let expected = effects(
+            digest,
+            ExecutionStatus::Success,
+            expected_gas.clone(),
+            &[],
+            None,
+        );
+        let actual = effects(
+            digest,
+            ExecutionStatus::Success,
+            actual_gas.clone(),
+            &[],
+            None,
+        );
+
+        let report = report(&expected, &actual);
+        assert_eq!(report.gas.expected, expected_gas);
+        assert_eq!(report.gas.actual, actual_gas);
+        assert!(!report.is_unchanged());
+    }
+
+    #[test]
+    fn test_report_object_changes() {
+        let digest = TransactionDigest::random();
+        let gas = GasCostSummary::default();
+        let shared = Object::immutable_with_id_for_testing(ObjectID::random());
+        let only_expected = Object::immutable_with_id_for_testing(ObjectID::random());
+        let only_actual = Object::immutable_with_id_for_testing(ObjectID::random());
+
+        let expected = effects(
+            digest,
+            ExecutionStatus::Success,
+            gas.clone(),
+            &[&shared, &only_expected],
+            None,
+        );
+        let actual = effects(
+            digest,
+            ExecutionStatus::Success,
+            gas,
+            &[&shared, &only_actual],
+            None,
+        );
+
+        let report = report(&expected, &actual);
+        assert!(!report.is_unchanged());
+
+        // Objects changed in the same way by both executions are not reported.
+        let mut diffs: BTreeMap<_, _> = report
+            .object_changes
+            .into_iter()
+            .map(|diff| (diff.object_id, (diff.expected, diff.actual)))
+            .collect();
+        assert_eq!(diffs.len(), 2);
+
+        let (expected, actual) = diffs.remove(&only_expected.id()).unwrap();
+        assert_eq!(expected.unwrap().id_operation, IDOperation::Created);
+        assert!(actual.is_none());
+
+        let (expected, actual) = diffs.remove(&only_actual.id()).unwrap();
+        assert!(expected.is_none());
+        assert_eq!(actual.unwrap().output_digest, Some(only_actual.digest()));
+    }
+
+    #[test]
+    fn test_report_events() {
+        let digest = TransactionDigest::random();
+        let gas = GasCostSummary::default();
+        let events = Some(TransactionEventsDigest::random());
+
+        let expected = effects(digest, ExecutionStatus::Success, gas.clone(), &[], events);
+        let actual = effects(digest, ExecutionStatus::Success, gas, &[], None);
+
+        let report = report(&expected, &actual);
+        assert_eq!(report.events_digest.expected, events);
+        assert_eq!(report.events_digest.actual, None);
+        assert!(!report.is_unchanged());
+    }
+
+    #[test]
+    fn test_report_status() {
+        let digest = TransactionDigest::random();
+        let gas = GasCostSummary::default();
+        let failure = ExecutionStatus::new_failure(ExecutionFailureStatus::InsufficientGas, None);
+
+        let expected = effects(digest, ExecutionStatus::Success, gas.clone(), &[], None);
+        let actual = effects(digest, failure.clone(), gas, &[], None);
+
+        let report = report(&expected, &actual);
+        assert_eq!(report.status.expected, ExecutionStatus::Success);
+        assert_eq!(report.status.actual, failure);
+        assert!(!report.is_unchanged());
+    }
+
+    #[test]
+    fn test_parse_package_substitution() {
+        let (id, path) = parse_package_substitution("0x2=../my/package").unwrap();
+        assert_eq!(id, ObjectID::from_single_byte(2));
+        assert_eq!(path, PathBuf::from("../my/package"));
+
+        let err = parse_package_substitution("0x2").unwrap_err();
+        assert!(err.contains("Expected <package-id>=<path>"), "{err}");
+
+        let err = parse_package_substitution("not-an-id=../my/package").unwrap_err();
+        assert!(err.contains("Invalid package ID not-an-id"), "{err}");
+    }
+
+    #[test]
+    fn test_parse_protocol_config_override() {
+        assert_eq!(
+            parse_protocol_config_override(" max_gas_price = 1000 ").unwrap(),
+            ("max_gas_price".to_owned(), "1000".to_owned()),
+        );
+
+        let err = parse_protocol_config_override("max_gas_price").unwrap_err();
+        assert!(err.contains("Expected <name>=<value>"), "{err}");
+    }
+
+    #[test]
+    fn test_apply_protocol_config_overrides() {
+        let mut config = ProtocolConfig::get_for_max_version_UNSAFE();
+        let overrides = vec![("max_gas_price".to_owned(), "1234".to_owned())];
+        apply_protocol_config_overrides(&mut config, &overrides).unwrap();
+        assert_eq!(config.max_gas_price(), 1234);
+    }
+
+    #[test]
+    fn test_apply_protocol_config_override_errors() {
+        let err = |config: &ProtocolConfig, name: &str, value: &str| {
+            let overrides = vec![(name.to_owned(), value.to_owned())];
+            apply_protocol_config_overrides(&mut config.clone(), &overrides)
+                .unwrap_err()
+                .to_string()
+        };
+
+        let config = ProtocolConfig::get_for_max_version_UNSAFE();
+
+        let msg = err(&config, "not_a_constant", "1");
+        assert!(msg.contains("Unknown protocol config constant"), "{msg}");
+
+        let msg = err(&config, "max_gas_price", "lots");
+        assert!(msg.contains("Invalid value lots"), "{msg}");
+
+        let msg = err(&config, "package_upgrades", "false");
+        assert!(msg.contains("feature flags cannot be overridden"), "{msg}");
+
+        // A constant that was introduced after the first protocol version cannot be set for it.
+        let config = ProtocolConfig::get_for_version(ProtocolVersion::MIN, Chain::Unknown);
+        let msg = err(
+            &config,
+            "max_gas_price_rgp_factor_for_aborted_transactions",
+            "2",
+        );
+        assert!(msg.contains("is not set in protocol version 1"), "{msg}");
+    }
+}
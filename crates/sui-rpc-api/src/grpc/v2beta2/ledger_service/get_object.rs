@@ -1,6 +1,7 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+use super::MAX_BATCH_SIZE;
 use crate::error::ObjectNotFoundError;
 use crate::ErrorReason;
 use crate::RpcError;
@@ -26,39 +27,43 @@ pub fn validate_get_object_requests(
     requests: Vec<(Option<String>, Option<u64>)>,
     read_mask: Option<FieldMask>,
 ) -> ValidationResult {
-    let read_mask = {
-        let read_mask = read_mask.unwrap_or_else(|| FieldMask::from_str(READ_MASK_DEFAULT));
-        read_mask.validate::<Object>().map_err(|path| {
-            FieldViolation::new("read_mask")
-                .with_description(format!("invalid read_mask path: {path}"))
-                .with_reason(ErrorReason::FieldInvalid)
-        })?;
-        FieldMaskTree::from(read_mask)
-    };
+    let read_mask = validate_read_mask(read_mask)?;
     let requests = requests
         .into_iter()
         .enumerate()
-        .map(|(idx, (object_id, version))| {
-            let object_id = object_id
-                .as_ref()
-                .ok_or_else(|| {
-                    FieldViolation::new("object_id")
-                        .with_reason(ErrorReason::FieldMissing)
-                        .nested_at("requests", idx)
-                })?
-                .parse()
-                .map_err(|e| {
-                    FieldViolation::new("object_id")
-                        .with_description(format!("invalid object_id: {e}"))
-                        .with_reason(ErrorReason::FieldInvalid)
-                        .nested_at("requests", idx)
-                })?;
-            Ok((object_id, version))
-        })
+        .map(|(idx, (object_id, version))| Ok((parse_object_id(object_id, idx)?, version)))
         .collect::<Result<_, RpcError>>()?;
     Ok((requests, read_mask))
 }
 
+fn validate_read_mask(read_mask: Option<FieldMask>) -> Result<FieldMaskTree, RpcError> {
+    let read_mask = read_mask.unwrap_or_else(|| FieldMask::from_str(READ_MASK_DEFAULT));
+    read_mask.validate::<Object>().map_err(|path| {
+        FieldViolation::new("read_mask")
+            .with_description(format!("invalid read_mask path: {path}"))
+            .with_reason(ErrorReason::FieldInvalid)
+    })?;
+    Ok(FieldMaskTree::from(read_mask))
+}
+
+fn parse_object_id(object_id: Option<String>, idx: usize) -> Result<ObjectId, RpcError> {
+    let object_id = object_id
+        .as_ref()
+        .ok_or_else(|| {
+            FieldViolation::new("object_id")
+                .with_reason(ErrorReason::FieldMissing)
+                .nested_at("requests", idx)
+        })?
+        .parse()
+        .map_err(|e| {
+            FieldViolation::new("object_id")
+                .with_description(format!("invalid object_id: {e}"))
+                .with_reason(ErrorReason::FieldInvalid)
+                .nested_at("requests", idx)
+        })?;
+    Ok(object_id)
+}
+
 #[tracing::instrument(skip(service))]
 pub fn get_object(
     service: &RpcService,
@@ -84,14 +89,24 @@ pub fn batch_get_objects(
         read_mask,
     }: BatchGetObjectsRequest,
 ) -> Result<BatchGetObjectsResponse, RpcError> {
-    let requests = requests
-        .into_iter()
-        .map(|req| (req.object_id, req.version))
-        .collect();
-    let (requests, read_mask) = validate_get_object_requests(requests, read_mask)?;
+    if requests.len() > MAX_BATCH_SIZE {
+        return Err(FieldViolation::new("requests")
+            .with_description(format!(
+                "number of requests exceeds the maximum of {MAX_BATCH_SIZE}"
+            ))
+            .with_reason(ErrorReason::FieldInvalid)
+            .into());
+    }
+
+    let read_mask = validate_read_mask(read_mask)?;
+    // Invalid object ids are reported for the request they appear in, like missing objects
     let objects = requests
         .into_iter()
-        .map(|(object_id, version)| get_object_impl(service, object_id, version, &read_mask))
+        .enumerate()
+        .map(|(idx, request)| {
+            let object_id = parse_object_id(request.object_id, idx)?;
+            get_object_impl(service, object_id, request.version, &read_mask)
+        })
         .map(|result| match result {
             Ok(object) => GetObjectResult::new_object(object),
             Err(error) => GetObjectResult::new_error(error.into_status_proto()),
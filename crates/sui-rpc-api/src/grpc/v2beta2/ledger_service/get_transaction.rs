@@ -1,6 +1,7 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+use super::MAX_BATCH_SIZE;
 use crate::ErrorReason;
 use crate::RpcError;
 use crate::RpcService;
@@ -74,6 +75,15 @@ pub fn batch_get_transactions(
     service: &RpcService,
     BatchGetTransactionsRequest { digests, read_mask }: BatchGetTransactionsRequest,
 ) -> Result<BatchGetTransactionsResponse, RpcError> {
+    if digests.len() > MAX_BATCH_SIZE {
+        return Err(FieldViolation::new("digests")
+            .with_description(format!(
+                "number of digests exceeds the maximum of {MAX_BATCH_SIZE}"
+            ))
+            .with_reason(ErrorReason::FieldInvalid)
+            .into());
+    }
+
     let read_mask = {
         let read_mask = read_mask.unwrap_or_else(|| FieldMask::from_str(READ_MASK_DEFAULT));
         read_mask
@@ -25,6 +25,9 @@ mod get_service_info;
 mod get_transaction;
 pub use get_epoch::protocol_config_to_proto;
 
+// Maximum number of items which can be requested in a single batch request
+const MAX_BATCH_SIZE: usize = 1000;
+
 #[tonic::async_trait]
 impl LedgerService for RpcService {
     async fn get_service_info(
@@ -21,7 +21,14 @@ mod get_coin_info;
 mod list_balances;
 mod list_dynamic_fields;
 mod list_owned_objects;
+mod select_coins;
 mod simulate;
+pub use select_coins::SelectedCoin;
+pub use simulate::SIMULATE_CLOCK_TIMESTAMP_MS_METADATA;
+pub use simulate::SIMULATE_EPOCH_METADATA;
+pub use simulate::SIMULATE_EPOCH_TIMESTAMP_MS_METADATA;
+pub use simulate::SIMULATE_GAS_COIN_METADATA;
+pub use simulate::SIMULATE_OBJECT_METADATA;
 
 #[tonic::async_trait]
 impl LiveDataService for RpcService {
@@ -74,7 +81,8 @@ impl LiveDataService for RpcService {
         &self,
         request: tonic::Request<SimulateTransactionRequest>,
     ) -> Result<tonic::Response<SimulateTransactionResponse>, tonic::Status> {
-        simulate::simulate_transaction(self, request.into_inner())
+        let overrides = simulate::simulate_overrides(request.metadata())?;
+        simulate::simulate_transaction(self, request.into_inner(), overrides)
             .map(tonic::Response::new)
             .map_err(Into::into)
     }
@@ -0,0 +1,107 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::Result;
+use crate::RpcError;
+use crate::RpcService;
+use move_core_types::language_storage::StructTag;
+use sui_types::base_types::ObjectID;
+use sui_types::base_types::ObjectRef;
+use sui_types::base_types::SuiAddress;
+use sui_types::coin::Coin;
+
+/// Maximum number of coins which can be selected to cover an amount
+const MAX_SELECTED_COINS: usize = 1000;
+
+/// A coin selected by `RpcService::select_coins`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SelectedCoin {
+    pub object_ref: ObjectRef,
+    pub balance: u64,
+}
+
+impl RpcService {
+    /// Select the fewest coins of `coin_type` (e.g. `0x2::sui::SUI`) owned by `owner`, other than
+    /// those in `exclude`, whose balances add up to at least `amount`.
+    ///
+    /// The owner index orders the coins of a type from the largest balance to the smallest, so
+    /// coins are taken in index order until the amount is covered. Coins owned through consensus
+    /// are skipped as they can't be used as owned inputs.
+    ///
+    /// This is not served over gRPC yet: it is meant to back a `SelectCoins` RPC once the
+    /// `LiveDataService` definition of the `sui-rpc` crate has one.
+    #[tracing::instrument(skip(self))]
+    pub fn select_coins(
+        &self,
+        owner: SuiAddress,
+        coin_type: StructTag,
+        amount: u64,
+        exclude: &[ObjectID],
+    ) -> Result<Vec<SelectedCoin>> {
+        let indexes = self
+            .reader
+            .inner()
+            .indexes()
+            .ok_or_else(RpcError::not_found)?;
+
+        let mut selected = vec![];
+        let mut selected_amount: u64 = 0;
+        let iter =
+            indexes.owned_objects_iter(owner, Some(Coin::type_(coin_type.clone().into())), None)?;
+        for info in iter {
+            if selected_amount >= amount {
+                break;
+            }
+
+            let info = info.map_err(|e| RpcError::new(tonic::Code::Internal, e.to_string()))?;
+            if exclude.contains(&info.object_id) {
+                continue;
+            }
+
+            let Some(object) = self
+                .reader
+                .inner()
+                .get_object_by_key(&info.object_id, info.version)
+            else {
+                tracing::debug!(
+                    "unable to find coin {}:{} while selecting coins",
+                    info.object_id,
+                    info.version
+                );
+                continue;
+            };
+            if object.is_consensus() {
+                continue;
+            }
+
+            if selected.len() >= MAX_SELECTED_COINS {
+                return Err(RpcError::new(
+                    tonic::Code::FailedPrecondition,
+                    format!(
+                        "more than {MAX_SELECTED_COINS} coins of type {coin_type} \
+                        are needed to cover amount {amount}"
+                    ),
+                ));
+            }
+
+            let balance = info.balance.unwrap_or_default();
+            selected_amount = selected_amount.saturating_add(balance);
+            selected.push(SelectedCoin {
+                object_ref: object.compute_object_reference(),
+                balance,
+            });
+        }
+
+        if selected_amount >= amount {
+            Ok(selected)
+        } else {
+            Err(RpcError::new(
+                tonic::Code::FailedPrecondition,
+                format!(
+                    "insufficient balance of coin type {coin_type} in account {owner} \
+                    to cover amount {amount}"
+                ),
+            ))
+        }
+    }
+}
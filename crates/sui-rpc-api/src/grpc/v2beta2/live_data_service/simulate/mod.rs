@@ -25,15 +25,104 @@ use sui_types::base_types::ObjectID;
 use sui_types::base_types::ObjectRef;
 use sui_types::base_types::SuiAddress;
 use sui_types::effects::TransactionEffectsAPI;
+use sui_types::object::Object;
 use sui_types::transaction::TransactionDataAPI;
+use sui_types::transaction_executor::SimulateOverrides;
 use sui_types::transaction_executor::SimulateTransactionResult;
 use sui_types::transaction_executor::TransactionChecks;
+use tonic::metadata::Ascii;
 
 mod resolve;
 
+/// Key of the gRPC request metadata holding the BCS of an object replacing an input object of a
+/// simulated transaction. Binary metadata, which can be repeated.
+pub const SIMULATE_OBJECT_METADATA: &str = "x-sui-simulate-object-bin";
+/// Key of the gRPC request metadata holding the balance of a gas coin injected into a simulation
+/// in place of the gas payment. Can be repeated to inject several coins.
+pub const SIMULATE_GAS_COIN_METADATA: &str = "x-sui-simulate-gas-coin";
+/// Key of the gRPC request metadata holding the timestamp of the `Clock` in a simulation.
+pub const SIMULATE_CLOCK_TIMESTAMP_MS_METADATA: &str = "x-sui-simulate-clock-timestamp-ms";
+/// Key of the gRPC request metadata holding the epoch a transaction is simulated in.
+pub const SIMULATE_EPOCH_METADATA: &str = "x-sui-simulate-epoch";
+/// Key of the gRPC request metadata holding the start timestamp of the epoch a transaction is
+/// simulated in.
+pub const SIMULATE_EPOCH_TIMESTAMP_MS_METADATA: &str = "x-sui-simulate-epoch-timestamp-ms";
+
+/// The overrides of a simulation, from the `SIMULATE_*_METADATA` entries of the request.
+pub fn simulate_overrides(metadata: &tonic::metadata::MetadataMap) -> Result<SimulateOverrides> {
+    let objects = metadata
+        .get_all_bin(SIMULATE_OBJECT_METADATA)
+        .iter()
+        .map(|value| {
+            value
+                .to_bytes()
+                .map_err(anyhow::Error::from)
+                .and_then(|bytes| Ok(bcs::from_bytes::<Object>(&bytes)?))
+                .map(|object| (object.id(), object))
+                .map_err(|e| {
+                    FieldViolation::new(SIMULATE_OBJECT_METADATA)
+                        .with_description(format!("invalid object: {e}"))
+                        .with_reason(ErrorReason::FieldInvalid)
+                        .into()
+                })
+        })
+        .collect::<Result<_>>()?;
+
+    let gas_coins = metadata
+        .get_all(SIMULATE_GAS_COIN_METADATA)
+        .iter()
+        .map(|value| parse_u64_metadata(SIMULATE_GAS_COIN_METADATA, value))
+        .collect::<Result<_>>()?;
+
+    let optional = |key: &'static str| {
+        metadata
+            .get(key)
+            .map(|value| parse_u64_metadata(key, value))
+            .transpose()
+    };
+
+    Ok(SimulateOverrides {
+        objects,
+        gas_coins,
+        clock_timestamp_ms: optional(SIMULATE_CLOCK_TIMESTAMP_MS_METADATA)?,
+        epoch: optional(SIMULATE_EPOCH_METADATA)?,
+        epoch_timestamp_ms: optional(SIMULATE_EPOCH_TIMESTAMP_MS_METADATA)?,
+    })
+}
+
+fn parse_u64_metadata(
+    key: &'static str,
+    value: &tonic::metadata::MetadataValue<Ascii>,
+) -> Result<u64> {
+    value
+        .to_str()
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .ok_or_else(|| {
+            FieldViolation::new(key)
+                .with_description("expected an unsigned integer")
+                .with_reason(ErrorReason::FieldInvalid)
+                .into()
+        })
+}
+
+impl RpcService {
+    /// Simulate a transaction as the `SimulateTransaction` RPC does, against the current state with
+    /// `overrides` applied. Overrides are used for budget estimation as well, and injected gas
+    /// coins take the place of gas selection.
+    pub fn simulate_transaction_with_overrides(
+        &self,
+        request: SimulateTransactionRequest,
+        overrides: SimulateOverrides,
+    ) -> Result<SimulateTransactionResponse> {
+        simulate_transaction(self, request, overrides)
+    }
+}
+
 pub fn simulate_transaction(
     service: &RpcService,
     request: SimulateTransactionRequest,
+    overrides: SimulateOverrides,
 ) -> Result<SimulateTransactionResponse> {
     let executor = service
         .executor
@@ -101,7 +190,11 @@ pub fn simulate_transaction(
     if request.do_gas_selection() && checks.enabled() {
         let budget = {
             let simulation_result = executor
-                .simulate_transaction(transaction.clone(), TransactionChecks::Enabled)
+                .simulate_transaction(
+                    transaction.clone(),
+                    TransactionChecks::Enabled,
+                    overrides.clone(),
+                )
                 .map_err(anyhow::Error::from)?;
 
             let estimate = estimate_gas_budget_from_gas_cost(
@@ -112,8 +205,9 @@ pub fn simulate_transaction(
             estimate
         };
 
-        // If the user didn't provide any gas payment we need to do gas selection now
-        if transaction.gas_data().payment.is_empty() {
+        // If the user didn't provide any gas payment or gas coins to inject we need to do gas
+        // selection now
+        if transaction.gas_data().payment.is_empty() && overrides.gas_coins.is_empty() {
             let input_objects = transaction
                 .input_objects()
                 .map_err(anyhow::Error::from)?
@@ -136,7 +230,10 @@ pub fn simulate_transaction(
         }
     }
 
+    // The transaction in the response is the one that was simulated, including the changes made
+    // to it by overrides (e.g. references to overridden objects, or injected gas coins).
     let SimulateTransactionResult {
+        transaction,
         input_objects,
         output_objects,
         events,
@@ -144,7 +241,7 @@ pub fn simulate_transaction(
         execution_result,
         mock_gas_id: _,
     } = executor
-        .simulate_transaction(transaction.clone(), checks)
+        .simulate_transaction(transaction, checks, overrides)
         .map_err(anyhow::Error::from)?;
 
     let transaction = if let Some(submask) = read_mask.subtree("transaction") {
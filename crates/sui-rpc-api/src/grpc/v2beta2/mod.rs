@@ -8,6 +8,12 @@ mod signature_verification_service;
 mod subscription_service;
 mod transaction_execution_service;
 pub use ledger_service::protocol_config_to_proto;
+pub use live_data_service::SelectedCoin;
+pub use live_data_service::SIMULATE_CLOCK_TIMESTAMP_MS_METADATA;
+pub use live_data_service::SIMULATE_EPOCH_METADATA;
+pub use live_data_service::SIMULATE_EPOCH_TIMESTAMP_MS_METADATA;
+pub use live_data_service::SIMULATE_GAS_COIN_METADATA;
+pub use live_data_service::SIMULATE_OBJECT_METADATA;
 
 fn render_json(
     service: &crate::RpcService,
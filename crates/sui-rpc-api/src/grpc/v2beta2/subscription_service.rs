@@ -2,17 +2,27 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use std::pin::Pin;
+use tokio_stream::StreamExt;
 
-use crate::subscription::SubscriptionServiceHandle;
+use crate::subscription::subscribe_checkpoints;
+use crate::subscription::CheckpointFilter;
+use crate::subscription::TransactionFilter;
+use crate::subscription::START_CHECKPOINT_METADATA;
+use crate::subscription::TRANSACTION_FILTER_METADATA;
+use crate::ErrorReason;
+use crate::RpcError;
+use crate::RpcService;
 use sui_rpc::field::FieldMaskTree;
 use sui_rpc::merge::Merge;
+use sui_rpc::proto::google::rpc::bad_request::FieldViolation;
 use sui_rpc::proto::sui::rpc::v2beta2::subscription_service_server::SubscriptionService;
 use sui_rpc::proto::sui::rpc::v2beta2::Checkpoint;
 use sui_rpc::proto::sui::rpc::v2beta2::SubscribeCheckpointsRequest;
 use sui_rpc::proto::sui::rpc::v2beta2::SubscribeCheckpointsResponse;
+use sui_types::messages_checkpoint::CheckpointSequenceNumber;
 
 #[tonic::async_trait]
-impl SubscriptionService for SubscriptionServiceHandle {
+impl SubscriptionService for RpcService {
     /// Server streaming response type for the SubscribeCheckpoints method.
     type SubscribeCheckpointsStream = Pin<
         Box<
@@ -25,17 +35,34 @@ impl SubscriptionService for SubscriptionServiceHandle {
         &self,
         request: tonic::Request<SubscribeCheckpointsRequest>,
     ) -> Result<tonic::Response<Self::SubscribeCheckpointsStream>, tonic::Status> {
+        let filter = checkpoint_filter(request.metadata())?;
+        let start = start_checkpoint(request.metadata())?;
         let read_mask = request.into_inner().read_mask.unwrap_or_default();
         let read_mask = FieldMaskTree::from(read_mask);
 
-        let Some(mut receiver) = self.register_subscription().await else {
-            return Err(tonic::Status::unavailable(
-                "too many existing subscriptions",
+        let Some(subscription_service_handle) = self.subscription_service_handle.clone() else {
+            return Err(tonic::Status::unimplemented(
+                "subscriptions are not enabled on this node",
             ));
         };
 
+        let mut checkpoints = subscribe_checkpoints(
+            subscription_service_handle,
+            self.reader.clone(),
+            start,
+            filter,
+        )
+        .await?;
+
         let response = Box::pin(async_stream::stream! {
-            while let Some(checkpoint) = receiver.recv().await {
+            while let Some(checkpoint) = checkpoints.next().await {
+                let checkpoint = match checkpoint {
+                    Ok(checkpoint) => checkpoint,
+                    Err(e) => {
+                        yield Err(e.into());
+                        break;
+                    }
+                };
                 let cursor = checkpoint.checkpoint_summary.sequence_number;
 
                 let checkpoint = Checkpoint::merge_from(
@@ -55,3 +82,48 @@ impl SubscriptionService for SubscriptionServiceHandle {
         Ok(tonic::Response::new(response))
     }
 }
+
+/// The filter of a subscription, from the `TRANSACTION_FILTER_METADATA` entries of the request.
+fn checkpoint_filter(
+    metadata: &tonic::metadata::MetadataMap,
+) -> Result<CheckpointFilter, RpcError> {
+    metadata
+        .get_all(TRANSACTION_FILTER_METADATA)
+        .iter()
+        .map(|value| {
+            value
+                .to_str()
+                .map_err(anyhow::Error::from)
+                .and_then(str::parse::<TransactionFilter>)
+                .map_err(|e| {
+                    FieldViolation::new(TRANSACTION_FILTER_METADATA)
+                        .with_description(format!("invalid transaction filter: {e}"))
+                        .with_reason(ErrorReason::FieldInvalid)
+                        .into()
+                })
+        })
+        .collect::<Result<Vec<_>, RpcError>>()
+        .map(CheckpointFilter::new)
+}
+
+/// The checkpoint a subscription starts from, from the `START_CHECKPOINT_METADATA` entry of the
+/// request.
+fn start_checkpoint(
+    metadata: &tonic::metadata::MetadataMap,
+) -> Result<Option<CheckpointSequenceNumber>, RpcError> {
+    metadata
+        .get(START_CHECKPOINT_METADATA)
+        .map(|value| {
+            value
+                .to_str()
+                .ok()
+                .and_then(|value| value.parse().ok())
+                .ok_or_else(|| {
+                    FieldViolation::new(START_CHECKPOINT_METADATA)
+                        .with_description("invalid start checkpoint: expected a sequence number")
+                        .with_reason(ErrorReason::FieldInvalid)
+                        .into()
+                })
+        })
+        .transpose()
+}
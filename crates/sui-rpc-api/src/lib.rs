@@ -22,6 +22,12 @@ pub mod subscription;
 
 pub use crate::grpc::v2beta::ledger_service;
 pub use crate::grpc::v2beta2::protocol_config_to_proto;
+pub use crate::grpc::v2beta2::SelectedCoin;
+pub use crate::grpc::v2beta2::SIMULATE_CLOCK_TIMESTAMP_MS_METADATA;
+pub use crate::grpc::v2beta2::SIMULATE_EPOCH_METADATA;
+pub use crate::grpc::v2beta2::SIMULATE_EPOCH_TIMESTAMP_MS_METADATA;
+pub use crate::grpc::v2beta2::SIMULATE_GAS_COIN_METADATA;
+pub use crate::grpc::v2beta2::SIMULATE_OBJECT_METADATA;
 pub use client::Client;
 pub use config::Config;
 pub use error::{
@@ -197,9 +203,9 @@ impl RpcService {
                 .add_service(reflection_v1)
                 .add_service(reflection_v1alpha);
 
-            if let Some(subscription_service_handle) = self.subscription_service_handle.clone() {
+            if self.subscription_service_handle.is_some() {
                 let subscription_service2 =
-sui_rpc::proto::sui::rpc::v2beta2::subscription_service_server::SubscriptionServiceServer::new(subscription_service_handle);
+sui_rpc::proto::sui::rpc::v2beta2::subscription_service_server::SubscriptionServiceServer::new(self.clone());
                 health_reporter
                     .set_service_status(
                         service_name(&subscription_service2),
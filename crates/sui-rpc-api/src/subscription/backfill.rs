@@ -0,0 +1,306 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{CheckpointFilter, SubscriptionServiceHandle, REPLAY_BUFFER_SIZE};
+use crate::error::CheckpointNotFoundError;
+use crate::reader::StateReader;
+use crate::RpcError;
+use std::pin::Pin;
+use std::sync::Arc;
+use sui_types::full_checkpoint_content::CheckpointData;
+use sui_types::messages_checkpoint::CheckpointSequenceNumber;
+use tokio::sync::mpsc;
+use tonic::Code;
+
+// A resuming subscriber reads checkpoints from the checkpoint store until it is this close to the
+// latest checkpoint, and then registers with the subscription service, whose replay buffer holds
+// the checkpoints in between.
+const LIVE_DISTANCE: u64 = REPLAY_BUFFER_SIZE as u64 / 2;
+
+pub(crate) type CheckpointStream =
+    Pin<Box<dyn tokio_stream::Stream<Item = Result<Arc<CheckpointData>, RpcError>> + Send>>;
+
+/// The checkpoints that resuming subscribers are back-filled from.
+pub(crate) trait CheckpointStore: Send + Sync + 'static {
+    /// The lowest checkpoint whose contents and objects are available.
+    fn lowest_available_checkpoint(&self) -> Result<CheckpointSequenceNumber, RpcError>;
+
+    fn latest_checkpoint(&self) -> Result<CheckpointSequenceNumber, RpcError>;
+
+    fn checkpoint(
+        &self,
+        sequence_number: CheckpointSequenceNumber,
+    ) -> Result<CheckpointData, RpcError>;
+}
+
+impl CheckpointStore for StateReader {
+    fn lowest_available_checkpoint(&self) -> Result<CheckpointSequenceNumber, RpcError> {
+        Ok(self
+            .inner()
+            .get_lowest_available_checkpoint()?
+            .max(self.inner().get_lowest_available_checkpoint_objects()?))
+    }
+
+    fn latest_checkpoint(&self) -> Result<CheckpointSequenceNumber, RpcError> {
+        Ok(self.inner().get_latest_checkpoint_sequence_number()?)
+    }
+
+    fn checkpoint(
+        &self,
+        sequence_number: CheckpointSequenceNumber,
+    ) -> Result<CheckpointData, RpcError> {
+        let summary = self
+            .inner()
+            .get_checkpoint_by_sequence_number(sequence_number)
+            .ok_or(CheckpointNotFoundError::sequence_number(sequence_number))?;
+        let contents = self
+            .inner()
+            .get_checkpoint_contents_by_sequence_number(sequence_number)
+            .ok_or(CheckpointNotFoundError::sequence_number(sequence_number))?;
+        Ok(self.inner().get_checkpoint_data(summary, contents)?)
+    }
+}
+
+/// Subscribe to the checkpoints matching `filter`, starting from the checkpoint `start` if set and
+/// from the next checkpoint otherwise.
+///
+/// Checkpoints before the live tip are back-filled from the checkpoint store, so the stream has no
+/// gaps: the stream yields every checkpoint from `start` in order, until the subscriber falls too
+/// far behind and is dropped by the subscription service.
+pub(crate) async fn subscribe_checkpoints(
+    handle: SubscriptionServiceHandle,
+    store: impl CheckpointStore,
+    start: Option<CheckpointSequenceNumber>,
+    filter: CheckpointFilter,
+) -> Result<CheckpointStream, RpcError> {
+    let start = match start {
+        None => Start::Live(register(
+            handle.register_filtered_subscription(filter.clone()).await,
+        )?),
+        Some(start) => {
+            let lowest_available_checkpoint = store.lowest_available_checkpoint()?;
+            if start < lowest_available_checkpoint {
+                return Err(RpcError::new(
+                    Code::OutOfRange,
+                    format!(
+                        "checkpoint {start} is not available, \
+                        the lowest available checkpoint is {lowest_available_checkpoint}"
+                    ),
+                ));
+            }
+            Start::Resume(start)
+        }
+    };
+
+    let stream = async_stream::try_stream! {
+        let (mut receiver, mut next) = match start {
+            Start::Live(receiver) => (receiver, None),
+            Start::Resume(mut next) => {
+                // Read from the store until the replay buffer can cover the rest
+                loop {
+                    let latest = store.latest_checkpoint()?;
+                    if next.saturating_add(LIVE_DISTANCE) > latest {
+                        break;
+                    }
+                    while next <= latest {
+                        yield load_checkpoint(&store, next, &filter)?;
+                        next += 1;
+                    }
+                }
+
+                let receiver = register(handle.resume_subscription(next, filter.clone()).await)?;
+                (receiver, Some(next))
+            }
+        };
+
+        while let Some(checkpoint) = receiver.recv().await {
+            let sequence_number = checkpoint.checkpoint_summary.sequence_number;
+
+            if let Some(next) = next {
+                // Already read from the store
+                if sequence_number < next {
+                    continue;
+                }
+
+                // Fill in the checkpoints the replay buffer no longer held
+                for sequence_number in next..sequence_number {
+                    yield load_checkpoint(&store, sequence_number, &filter)?;
+                }
+            }
+
+            next = Some(sequence_number + 1);
+            yield checkpoint;
+        }
+    };
+
+    Ok(Box::pin(stream))
+}
+
+enum Start {
+    Live(mpsc::Receiver<Arc<CheckpointData>>),
+    Resume(CheckpointSequenceNumber),
+}
+
+fn register<T>(receiver: Option<T>) -> Result<T, RpcError> {
+    receiver.ok_or_else(|| RpcError::new(Code::Unavailable, "too many existing subscriptions"))
+}
+
+fn load_checkpoint(
+    store: &impl CheckpointStore,
+    sequence_number: CheckpointSequenceNumber,
+    filter: &CheckpointFilter,
+) -> Result<Arc<CheckpointData>, RpcError> {
+    let checkpoint = store.checkpoint(sequence_number)?;
+
+    Ok(Arc::new(if filter.is_empty() {
+        checkpoint
+    } else {
+        filter.apply(&checkpoint)
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::subscription::SubscriptionService;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::time::Duration;
+    use sui_types::test_checkpoint_data_builder::TestCheckpointDataBuilder;
+    use tokio_stream::StreamExt;
+
+    /// Checkpoints up to `latest`. The store may hold later checkpoints, as the store of a node
+    /// that is behind its subscription service would.
+    struct TestStore {
+        checkpoints: Arc<Vec<CheckpointData>>,
+        lowest: CheckpointSequenceNumber,
+        latest: Arc<AtomicU64>,
+    }
+
+    impl CheckpointStore for TestStore {
+        fn lowest_available_checkpoint(&self) -> Result<CheckpointSequenceNumber, RpcError> {
+            Ok(self.lowest)
+        }
+
+        fn latest_checkpoint(&self) -> Result<CheckpointSequenceNumber, RpcError> {
+            Ok(self.latest.load(Ordering::SeqCst))
+        }
+
+        fn checkpoint(
+            &self,
+            sequence_number: CheckpointSequenceNumber,
+        ) -> Result<CheckpointData, RpcError> {
+            Ok(self
+                .checkpoints
+                .get(sequence_number as usize)
+                .cloned()
+                .ok_or(CheckpointNotFoundError::sequence_number(sequence_number))?)
+        }
+    }
+
+    fn build_checkpoints(count: u64) -> Arc<Vec<CheckpointData>> {
+        let mut builder = TestCheckpointDataBuilder::new(0);
+        Arc::new((0..count).map(|_| builder.build_checkpoint()).collect())
+    }
+
+    /// Send `checkpoints` to the subscription service, waiting for each to be handled (and added
+    /// to the replay buffer) by receiving it on `probe`.
+    async fn feed(
+        sender: &mpsc::Sender<CheckpointData>,
+        probe: &mut mpsc::Receiver<Arc<CheckpointData>>,
+        checkpoints: &[CheckpointData],
+    ) {
+        for checkpoint in checkpoints {
+            sender.send(checkpoint.clone()).await.unwrap();
+            probe.recv().await.unwrap();
+        }
+    }
+
+    async fn next_sequence_numbers(
+        stream: &mut CheckpointStream,
+        count: usize,
+    ) -> Vec<CheckpointSequenceNumber> {
+        let mut sequence_numbers = vec![];
+        while sequence_numbers.len() < count {
+            let checkpoint = stream.next().await.unwrap().unwrap();
+            sequence_numbers.push(checkpoint.checkpoint_summary.sequence_number);
+        }
+        sequence_numbers
+    }
+
+    async fn assert_no_more(stream: &mut CheckpointStream) {
+        let next = tokio::time::timeout(Duration::from_millis(100), stream.next()).await;
+        assert!(next.is_err(), "Unexpected checkpoint");
+    }
+
+    #[tokio::test]
+    async fn backfill_then_live() {
+        let checkpoints = build_checkpoints(210);
+        let (sender, handle) = SubscriptionService::build(&prometheus::Registry::new());
+        let mut probe = handle.register_subscription().await.unwrap();
+        feed(&sender, &mut probe, &checkpoints[..200]).await;
+
+        let latest = Arc::new(AtomicU64::new(199));
+        let store = TestStore {
+            checkpoints: checkpoints.clone(),
+            lowest: 0,
+            latest: latest.clone(),
+        };
+        let mut stream =
+            subscribe_checkpoints(handle, store, Some(10), CheckpointFilter::default())
+                .await
+                .unwrap();
+
+        // Read from the store, then from the subscription service once caught up.
+        let mut received = next_sequence_numbers(&mut stream, 190).await;
+        for checkpoint in &checkpoints[200..] {
+            feed(&sender, &mut probe, std::slice::from_ref(checkpoint)).await;
+            latest.store(
+                checkpoint.checkpoint_summary.sequence_number,
+                Ordering::SeqCst,
+            );
+        }
+        received.extend(next_sequence_numbers(&mut stream, 10).await);
+
+        assert_eq!(received, (10..210).collect::<Vec<_>>());
+        assert_no_more(&mut stream).await;
+    }
+
+    #[tokio::test]
+    async fn backfill_beyond_replay_buffer() {
+        let checkpoints = build_checkpoints(300);
+        let (sender, handle) = SubscriptionService::build(&prometheus::Registry::new());
+        let mut probe = handle.register_subscription().await.unwrap();
+        feed(&sender, &mut probe, &checkpoints).await;
+
+        // The replay buffer only holds the last checkpoints, the earlier ones come from the store,
+        // including those after the latest checkpoint it reports.
+        let store = TestStore {
+            checkpoints: checkpoints.clone(),
+            lowest: 0,
+            latest: Arc::new(AtomicU64::new(49)),
+        };
+        let mut stream =
+            subscribe_checkpoints(handle.clone(), store, Some(0), CheckpointFilter::default())
+                .await
+                .unwrap();
+
+        assert_eq!(
+            next_sequence_numbers(&mut stream, 300).await,
+            (0..300).collect::<Vec<_>>()
+        );
+        assert_no_more(&mut stream).await;
+
+        // Checkpoints pruned from the store cannot be resumed from.
+        let store = TestStore {
+            checkpoints,
+            lowest: 100,
+            latest: Arc::new(AtomicU64::new(299)),
+        };
+        let Err(error) =
+            subscribe_checkpoints(handle, store, Some(50), CheckpointFilter::default()).await
+        else {
+            panic!("Expected an error resuming from a pruned checkpoint");
+        };
+        assert_eq!(tonic::Status::from(error).code(), Code::OutOfRange);
+    }
+}
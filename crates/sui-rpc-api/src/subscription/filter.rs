@@ -0,0 +1,282 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use anyhow::{anyhow, bail};
+use move_core_types::language_storage::StructTag;
+use std::str::FromStr;
+use sui_types::base_types::{ObjectID, SuiAddress};
+use sui_types::full_checkpoint_content::{CheckpointData, CheckpointTransaction};
+use sui_types::object::{Object, Owner};
+use sui_types::parse_sui_struct_tag;
+use sui_types::transaction::TransactionDataAPI;
+
+/// Selects the transactions of each checkpoint that are delivered to a subscriber.
+///
+/// A transaction is delivered if it matches any of the filters, and an empty `CheckpointFilter`
+/// matches every transaction. Every checkpoint is delivered with its summary and contents, even
+/// if none of its transactions match, so that subscribers always know the cursor to resume from.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CheckpointFilter {
+    filters: Vec<TransactionFilter>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TransactionFilter {
+    /// Transactions sent by the address.
+    Sender(SuiAddress),
+    /// Transactions sent or sponsored by the address, or reading or writing objects it owns.
+    AffectedAddress(SuiAddress),
+    /// Transactions reading or writing the object.
+    AffectedObject(ObjectID),
+    /// Transactions calling a Move function in the package, optionally restricted to a module and
+    /// a function of that module.
+    MoveCall {
+        package: ObjectID,
+        module: Option<String>,
+        function: Option<String>,
+    },
+    /// Transactions emitting an event of the type. A type without type parameters matches all
+    /// instantiations of the type.
+    EventType(StructTag),
+    /// Transactions of the kind, named as by `TransactionKind::name`, e.g.
+    /// "ProgrammableTransaction".
+    Kind(String),
+}
+
+impl CheckpointFilter {
+    pub fn new(filters: Vec<TransactionFilter>) -> Self {
+        Self { filters }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.filters.is_empty()
+    }
+
+    pub fn matches(&self, transaction: &CheckpointTransaction) -> bool {
+        self.is_empty() || self.filters.iter().any(|f| f.matches(transaction))
+    }
+
+    /// A copy of `checkpoint` with only the transactions matching this filter.
+    pub fn apply(&self, checkpoint: &CheckpointData) -> CheckpointData {
+        CheckpointData {
+            checkpoint_summary: checkpoint.checkpoint_summary.clone(),
+            checkpoint_contents: checkpoint.checkpoint_contents.clone(),
+            transactions: checkpoint
+                .transactions
+                .iter()
+                .filter(|transaction| self.matches(transaction))
+                .cloned()
+                .collect(),
+        }
+    }
+}
+
+impl TransactionFilter {
+    pub fn matches(&self, transaction: &CheckpointTransaction) -> bool {
+        let data = transaction.transaction.transaction_data();
+        match self {
+            Self::Sender(address) => data.sender() == *address,
+            Self::AffectedAddress(address) => {
+                data.sender() == *address
+                    || data.gas_owner() == *address
+                    || objects(transaction).any(|o| owner_address(o.owner()) == Some(*address))
+            }
+            Self::AffectedObject(object_id) => objects(transaction).any(|o| o.id() == *object_id),
+            Self::MoveCall {
+                package,
+                module,
+                function,
+            } => data.move_calls().into_iter().any(|(p, m, f)| {
+                p == package
+                    && module.as_deref().is_none_or(|module| module == m)
+                    && function.as_deref().is_none_or(|function| function == f)
+            }),
+            Self::EventType(event_type) => transaction.events.iter().any(|events| {
+                events.data.iter().any(|event| {
+                    if event_type.type_params.is_empty() {
+                        event.type_.address == event_type.address
+                            && event.type_.module == event_type.module
+                            && event.type_.name == event_type.name
+                    } else {
+                        event.type_ == *event_type
+                    }
+                })
+            }),
+            Self::Kind(kind) => data.kind().name() == kind,
+        }
+    }
+}
+
+/// Parses a filter written `<kind>=<value>`, one of:
+///
+/// - `sender=<address>`
+/// - `affected-address=<address>`
+/// - `affected-object=<object id>`
+/// - `move-call=<package>[::<module>[::<function>]]`
+/// - `event-type=<struct tag>`
+/// - `kind=<transaction kind>`
+impl FromStr for TransactionFilter {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (kind, value) = s
+            .split_once('=')
+            .ok_or_else(|| anyhow!("expected '<kind>=<value>', got '{s}'"))?;
+        let value = value.trim();
+        Ok(match kind.trim() {
+            "sender" => Self::Sender(SuiAddress::from_str(value)?),
+            "affected-address" => Self::AffectedAddress(SuiAddress::from_str(value)?),
+            "affected-object" => Self::AffectedObject(ObjectID::from_str(value)?),
+            "move-call" => {
+                let mut parts = value.split("::");
+                let package = ObjectID::from_str(parts.next().unwrap_or_default())?;
+                let module = parts.next().map(str::to_owned);
+                let function = parts.next().map(str::to_owned);
+                if parts.next().is_some() {
+                    bail!("expected '<package>[::<module>[::<function>]]', got '{value}'");
+                }
+                Self::MoveCall {
+                    package,
+                    module,
+                    function,
+                }
+            }
+            "event-type" => Self::EventType(parse_sui_struct_tag(value)?),
+            "kind" => Self::Kind(value.to_owned()),
+            kind => bail!("unknown transaction filter '{kind}'"),
+        })
+    }
+}
+
+fn objects(transaction: &CheckpointTransaction) -> impl Iterator<Item = &Object> {
+    transaction
+        .input_objects
+        .iter()
+        .chain(transaction.output_objects.iter())
+}
+
+fn owner_address(owner: &Owner) -> Option<SuiAddress> {
+    match owner {
+        Owner::AddressOwner(address) | Owner::ConsensusAddressOwner { owner: address, .. } => {
+            Some(*address)
+        }
+        Owner::ObjectOwner(_) | Owner::Shared { .. } | Owner::Immutable => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sui_types::test_checkpoint_data_builder::TestCheckpointDataBuilder;
+
+    #[test]
+    fn filter_checkpoint_transactions() {
+        let package = ObjectID::from_single_byte(0x42);
+        let checkpoint = TestCheckpointDataBuilder::new(0)
+            .start_transaction(0)
+            .create_owned_object(0)
+            .finish_transaction()
+            .start_transaction(1)
+            .add_move_call(package, "module", "function")
+            .finish_transaction()
+            .start_transaction(2)
+            .transfer_object(0, 1)
+            .finish_transaction()
+            .build_checkpoint();
+
+        let sender = TestCheckpointDataBuilder::derive_address;
+        let object = TestCheckpointDataBuilder::derive_object_id(0);
+        let filtered = |filters| {
+            CheckpointFilter::new(filters)
+                .apply(&checkpoint)
+                .transactions
+                .iter()
+                .map(|t| t.transaction.transaction_data().sender())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(filtered(vec![]).len(), 3);
+        assert_eq!(
+            filtered(vec![TransactionFilter::Sender(sender(1))]),
+            [sender(1)]
+        );
+        assert_eq!(
+            filtered(vec![TransactionFilter::AffectedObject(object)]),
+            [sender(0), sender(2)]
+        );
+        assert_eq!(
+            filtered(vec![TransactionFilter::AffectedAddress(sender(1))]),
+            [sender(1), sender(2)]
+        );
+        assert_eq!(
+            filtered(vec![TransactionFilter::MoveCall {
+                package,
+                module: Some("module".to_owned()),
+                function: None,
+            }]),
+            [sender(1)]
+        );
+        assert!(filtered(vec![TransactionFilter::Kind("Genesis".to_owned())]).is_empty());
+
+        let filtered =
+            CheckpointFilter::new(vec![TransactionFilter::Sender(sender(3))]).apply(&checkpoint);
+        assert!(filtered.transactions.is_empty());
+        assert_eq!(
+            filtered.checkpoint_summary.sequence_number,
+            checkpoint.checkpoint_summary.sequence_number
+        );
+    }
+
+    #[test]
+    fn parse_transaction_filters() {
+        let address = SuiAddress::from_str("0x42").unwrap();
+        let package = ObjectID::from_single_byte(0x2);
+        let parse = |s: &str| s.parse::<TransactionFilter>().unwrap();
+
+        assert_eq!(parse("sender=0x42"), TransactionFilter::Sender(address));
+        assert_eq!(
+            parse("affected-address=0x42"),
+            TransactionFilter::AffectedAddress(address)
+        );
+        assert_eq!(
+            parse("affected-object=0x2"),
+            TransactionFilter::AffectedObject(package)
+        );
+        assert_eq!(
+            parse("move-call=0x2"),
+            TransactionFilter::MoveCall {
+                package,
+                module: None,
+                function: None,
+            }
+        );
+        assert_eq!(
+            parse("move-call=0x2::coin::join"),
+            TransactionFilter::MoveCall {
+                package,
+                module: Some("coin".to_owned()),
+                function: Some("join".to_owned()),
+            }
+        );
+        assert_eq!(
+            parse("event-type=0x2::coin::CoinMetadata<0x2::sui::SUI>"),
+            TransactionFilter::EventType(
+                parse_sui_struct_tag("0x2::coin::CoinMetadata<0x2::sui::SUI>").unwrap()
+            )
+        );
+        assert_eq!(
+            parse("kind=ProgrammableTransaction"),
+            TransactionFilter::Kind("ProgrammableTransaction".to_owned())
+        );
+
+        for invalid in [
+            "sender",
+            "sender=foo",
+            "recipient=0x42",
+            "move-call=0x2::coin::join::extra",
+            "event-type=coin",
+        ] {
+            assert!(invalid.parse::<TransactionFilter>().is_err(), "{invalid}");
+        }
+    }
+}
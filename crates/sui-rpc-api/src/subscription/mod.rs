@@ -1,21 +1,66 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+mod backfill;
+mod filter;
+
 use crate::metrics::SubscriptionMetrics;
+pub(crate) use backfill::subscribe_checkpoints;
+pub use filter::{CheckpointFilter, TransactionFilter};
+use std::collections::VecDeque;
 use std::sync::Arc;
 use sui_types::full_checkpoint_content::CheckpointData;
+use sui_types::messages_checkpoint::CheckpointSequenceNumber;
 use tokio::sync::mpsc;
 use tokio::sync::oneshot;
 use tracing::info;
 use tracing::trace;
 
+/// Key of the gRPC request metadata holding a transaction filter of a checkpoint subscription,
+/// written as parsed by `TransactionFilter::from_str`. The key can be repeated, to deliver the
+/// transactions matching any of the filters.
+pub const TRANSACTION_FILTER_METADATA: &str = "x-sui-transaction-filter";
+
+/// Key of the gRPC request metadata holding the sequence number of the checkpoint a subscription
+/// starts from. Checkpoints older than the live stream are backfilled from the store; without it
+/// the subscription starts at the next executed checkpoint.
+pub const START_CHECKPOINT_METADATA: &str = "x-sui-start-checkpoint";
+
 const CHECKPOINT_MAILBOX_SIZE: usize = 1024;
 const MAILBOX_SIZE: usize = 128;
 const SUBSCRIPTION_CHANNEL_SIZE: usize = 256;
 const MAX_SUBSCRIBERS: usize = 1024;
+// Number of the most recent checkpoints kept to be replayed to subscribers resuming from a
+// checkpoint. This must be smaller than SUBSCRIPTION_CHANNEL_SIZE so that a full replay fits in the
+// channel of a new subscriber.
+const REPLAY_BUFFER_SIZE: usize = 128;
 
 struct SubscriptionRequest {
     sender: oneshot::Sender<mpsc::Receiver<Arc<CheckpointData>>>,
+    filter: CheckpointFilter,
+    start: Option<CheckpointSequenceNumber>,
+}
+
+struct Subscriber {
+    sender: mpsc::Sender<Arc<CheckpointData>>,
+    filter: CheckpointFilter,
+}
+
+impl Subscriber {
+    // Subscribers with a filter are sent a copy of the checkpoint holding only the transactions
+    // they are interested in.
+    fn send(
+        &self,
+        checkpoint: &Arc<CheckpointData>,
+    ) -> Result<(), mpsc::error::TrySendError<Arc<CheckpointData>>> {
+        let checkpoint = if self.filter.is_empty() {
+            Arc::clone(checkpoint)
+        } else {
+            Arc::new(self.filter.apply(checkpoint))
+        };
+
+        self.sender.try_send(checkpoint)
+    }
 }
 
 #[derive(Clone)]
@@ -25,8 +70,42 @@ pub struct SubscriptionServiceHandle {
 
 impl SubscriptionServiceHandle {
     pub async fn register_subscription(&self) -> Option<mpsc::Receiver<Arc<CheckpointData>>> {
+        self.register_filtered_subscription(CheckpointFilter::default())
+            .await
+    }
+
+    /// Register a subscription to checkpoints which only carry the transactions matching `filter`.
+    pub async fn register_filtered_subscription(
+        &self,
+        filter: CheckpointFilter,
+    ) -> Option<mpsc::Receiver<Arc<CheckpointData>>> {
+        self.register(filter, None).await
+    }
+
+    /// Register a subscription which is first sent the checkpoints from `start` onwards that are
+    /// still held in the replay buffer of the service, before the checkpoints that follow them.
+    ///
+    /// Checkpoints older than the replay buffer are not sent, so the first checkpoint received can
+    /// be after `start`. Use `subscribe_checkpoints` to back-fill those from the checkpoint store.
+    pub async fn resume_subscription(
+        &self,
+        start: CheckpointSequenceNumber,
+        filter: CheckpointFilter,
+    ) -> Option<mpsc::Receiver<Arc<CheckpointData>>> {
+        self.register(filter, Some(start)).await
+    }
+
+    async fn register(
+        &self,
+        filter: CheckpointFilter,
+        start: Option<CheckpointSequenceNumber>,
+    ) -> Option<mpsc::Receiver<Arc<CheckpointData>>> {
         let (sender, reciever) = oneshot::channel();
-        let request = SubscriptionRequest { sender };
+        let request = SubscriptionRequest {
+            sender,
+            filter,
+            start,
+        };
         self.sender.send(request).await.ok()?;
 
         reciever.await.ok()
@@ -39,7 +118,9 @@ pub struct SubscriptionService {
     // Expectation is that checkpoints are recieved in-order
     checkpoint_mailbox: mpsc::Receiver<CheckpointData>,
     mailbox: mpsc::Receiver<SubscriptionRequest>,
-    subscribers: Vec<mpsc::Sender<Arc<CheckpointData>>>,
+    subscribers: Vec<Subscriber>,
+    // The most recent checkpoints, oldest first
+    replay_buffer: VecDeque<Arc<CheckpointData>>,
 
     metrics: SubscriptionMetrics,
 }
@@ -57,6 +138,7 @@ impl SubscriptionService {
                 checkpoint_mailbox,
                 mailbox,
                 subscribers: Vec::new(),
+                replay_buffer: VecDeque::with_capacity(REPLAY_BUFFER_SIZE),
                 metrics,
             }
             .start(),
@@ -118,10 +200,15 @@ impl SubscriptionService {
 
         let checkpoint = Arc::new(checkpoint);
 
+        if self.replay_buffer.len() >= REPLAY_BUFFER_SIZE {
+            self.replay_buffer.pop_front();
+        }
+        self.replay_buffer.push_back(Arc::clone(&checkpoint));
+
         // Try to send the latest checkpoint to all subscribers. If a subscriber's channel is full
         // then they are likely too slow so we drop them.
         self.subscribers.retain(|subscriber| {
-            match subscriber.try_send(Arc::clone(&checkpoint)) {
+            match subscriber.send(&checkpoint) {
                 Ok(()) => {
                     trace!("succesfully enqueued checkpont for subscriber");
                     true // Retain this subscriber
@@ -147,11 +234,28 @@ impl SubscriptionService {
         }
 
         let (sender, reciever) = mpsc::channel(SUBSCRIPTION_CHANNEL_SIZE);
+        let subscriber = Subscriber {
+            sender,
+            filter: request.filter,
+        };
+
+        // Replay the buffered checkpoints the subscriber is resuming from. The channel is empty and
+        // larger than the buffer so this can't fail.
+        if let Some(start) = request.start {
+            for checkpoint in self
+                .replay_buffer
+                .iter()
+                .filter(|checkpoint| *checkpoint.checkpoint_summary.sequence_number() >= start)
+            {
+                let _ = subscriber.send(checkpoint);
+            }
+        }
+
         match request.sender.send(reciever) {
             Ok(()) => {
                 trace!("succesfully registered new subscriber");
                 self.metrics.inflight_subscribers.inc();
-                self.subscribers.push(sender);
+                self.subscribers.push(subscriber);
             }
             Err(e) => {
                 trace!("failed to register new subscriber: {e:?}");
@@ -0,0 +1,179 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Verified, resumable download of snapshot files.
+//! Every file is checked against the sha3 digest recorded for it in the MANIFEST, and downloaded
+//! again (up to a bounded number of attempts) if the download fails or the digest does not
+//! match. The object files restored so far are recorded in a progress file next to the local
+//! copy of the MANIFEST, so that an interrupted restore only downloads the remaining files. That
+//! progress is discarded if the restore resumes to an empty DB.
+
+use anyhow::{anyhow, Result};
+use bytes::Bytes;
+use fastcrypto::encoding::{Encoding, Hex};
+use mysten_common::fs::write_atomic;
+use object_store::path::Path;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fs;
+use std::num::{NonZeroU64, NonZeroUsize};
+use std::path::PathBuf;
+use sui_storage::compute_sha3_checksum_for_bytes;
+use sui_storage::object_store::ObjectStoreGetExt;
+use tokio::sync::Mutex;
+use tokio::time::{Duration, Instant};
+use tracing::warn;
+
+const PROGRESS_FILE_NAME: &str = "DOWNLOAD_PROGRESS";
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+#[derive(Clone, Debug)]
+pub struct DownloadOptions {
+    /// Number of files to download concurrently.
+    pub concurrency: NonZeroUsize,
+    /// Limit on the average download rate over all files, unlimited if not set.
+    pub max_bytes_per_sec: Option<NonZeroU64>,
+    /// Number of times to try downloading a file before giving up. A file is downloaded again if
+    /// the download fails, or if the file does not match its digest.
+    pub max_attempts: usize,
+}
+
+impl DownloadOptions {
+    pub fn new(concurrency: NonZeroUsize) -> Self {
+        Self {
+            concurrency,
+            max_bytes_per_sec: None,
+            max_attempts: 10,
+        }
+    }
+}
+
+/// Paces downloads so that their average rate stays below a limit. As files are downloaded whole,
+/// a download waits after it completes until the limit allows for its size.
+pub struct BandwidthLimiter {
+    max_bytes_per_sec: Option<NonZeroU64>,
+    next_start: Mutex<Instant>,
+}
+
+impl BandwidthLimiter {
+    pub fn new(max_bytes_per_sec: Option<NonZeroU64>) -> Self {
+        Self {
+            max_bytes_per_sec,
+            next_start: Mutex::new(Instant::now()),
+        }
+    }
+
+    pub async fn consume(&self, bytes: usize) {
+        let Some(max_bytes_per_sec) = self.max_bytes_per_sec else {
+            return;
+        };
+        let duration = Duration::from_secs_f64(bytes as f64 / max_bytes_per_sec.get() as f64);
+        let until = {
+            let mut next_start = self.next_start.lock().await;
+            *next_start = (*next_start).max(Instant::now()) + duration;
+            *next_start
+        };
+        tokio::time::sleep_until(until).await;
+    }
+}
+
+/// Download the file at `path`, retrying until it matches `sha3_digest`.
+pub async fn download_verified(
+    remote_object_store: &dyn ObjectStoreGetExt,
+    path: &Path,
+    sha3_digest: &[u8; 32],
+    limiter: &BandwidthLimiter,
+    max_attempts: usize,
+) -> Result<Bytes> {
+    let mut delay = Duration::from_secs(1);
+    let mut attempt = 1;
+    loop {
+        let result = match remote_object_store.get_bytes(path).await {
+            Ok(bytes) => {
+                limiter.consume(bytes.len()).await;
+                let computed = compute_sha3_checksum_for_bytes(bytes.clone())?;
+                if computed == *sha3_digest {
+                    Ok(bytes)
+                } else {
+                    Err(anyhow!(
+                        "Checksum of {path}: {} doesn't match manifest: {}",
+                        Hex::encode(computed),
+                        Hex::encode(sha3_digest)
+                    ))
+                }
+            }
+            Err(err) => Err(err),
+        };
+        match result {
+            Ok(bytes) => return Ok(bytes),
+            Err(err) if attempt < max_attempts => {
+                warn!("Download of {path} failed (attempt {attempt}): {err:?}");
+                tokio::time::sleep(delay).await;
+                delay = (delay * 2).min(MAX_RETRY_DELAY);
+                attempt += 1;
+            }
+            Err(err) => {
+                return Err(err.context(format!(
+                    "Failed to download {path} after {attempt} attempts"
+                )))
+            }
+        }
+    }
+}
+
+/// The object files of a snapshot that were restored, persisted after each file.
+#[derive(Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DownloadProgress {
+    /// Hex encoded sha3 digest of the MANIFEST the progress is for.
+    manifest_digest: String,
+    /// (bucket, part) of the restored object files.
+    object_files: BTreeSet<(u32, u32)>,
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+impl DownloadProgress {
+    /// Load the progress in `epoch_dir`. Progress recorded for another MANIFEST is discarded.
+    pub fn load(epoch_dir: &std::path::Path, manifest_digest: &[u8; 32]) -> Result<Self> {
+        let path = epoch_dir.join(PROGRESS_FILE_NAME);
+        let manifest_digest = Hex::encode(manifest_digest);
+        let progress = match fs::read(&path) {
+            Ok(bytes) => serde_json::from_slice::<Self>(&bytes)
+                .ok()
+                .filter(|progress| progress.manifest_digest == manifest_digest),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => None,
+            Err(err) => return Err(err.into()),
+        };
+        Ok(Self {
+            path,
+            ..progress.unwrap_or(Self {
+                manifest_digest,
+                ..Default::default()
+            })
+        })
+    }
+
+    pub fn contains_object_file(&self, bucket: u32, part: u32) -> bool {
+        self.object_files.contains(&(bucket, part))
+    }
+
+    pub fn num_object_files(&self) -> usize {
+        self.object_files.len()
+    }
+
+    /// Record that an object file was restored.
+    pub fn add_object_file(&mut self, bucket: u32, part: u32) -> Result<()> {
+        self.object_files.insert((bucket, part));
+        self.save()
+    }
+
+    /// Forget the object files restored so far, e.g. when the DB they were restored to was reset.
+    pub fn clear_object_files(&mut self) -> Result<()> {
+        self.object_files.clear();
+        self.save()
+    }
+
+    fn save(&self) -> Result<()> {
+        write_atomic(&self.path, &serde_json::to_vec(self)?)
+    }
+}
@@ -1,6 +1,7 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::download::{download_verified, BandwidthLimiter, DownloadOptions, DownloadProgress};
 use crate::{
     FileMetadata, FileType, Manifest, MAGIC_BYTES, MANIFEST_FILE_MAGIC, OBJECT_FILE_MAGIC,
     OBJECT_ID_BYTES, OBJECT_REF_BYTES, REFERENCE_FILE_MAGIC, SEQUENCE_NUM_BYTES, SHA3_BYTES,
@@ -28,9 +29,10 @@ use sui_core::authority::authority_store_tables::{AuthorityPerpetualTables, Live
 use sui_core::authority::AuthorityStore;
 use sui_indexer_alt_framework::task::TrySpawnStreamExt;
 use sui_storage::blob::{Blob, BlobEncoding};
+use sui_storage::compute_sha3_checksum;
 use sui_storage::object_store::http::HttpDownloaderBuilder;
-use sui_storage::object_store::util::{copy_file, copy_files, path_to_filesystem};
-use sui_storage::object_store::{ObjectStoreGetExt, ObjectStoreListExt, ObjectStorePutExt};
+use sui_storage::object_store::util::{copy_file, path_to_filesystem};
+use sui_storage::object_store::{ObjectStoreGetExt, ObjectStorePutExt};
 use sui_types::base_types::{ObjectDigest, ObjectID, ObjectRef, SequenceNumber};
 use sui_types::global_state_hash::GlobalStateHash;
 use tokio::sync::Mutex;
@@ -52,6 +54,9 @@ pub struct StateSnapshotReaderV1 {
     object_files: BTreeMap<u32, BTreeMap<u32, FileMetadata>>,
     m: MultiProgress,
     concurrency: usize,
+    max_attempts: usize,
+    limiter: Arc<BandwidthLimiter>,
+    progress: Arc<std::sync::Mutex<DownloadProgress>>,
 }
 
 impl StateSnapshotReaderV1 {
@@ -62,6 +67,29 @@ impl StateSnapshotReaderV1 {
         download_concurrency: NonZeroUsize,
         m: MultiProgress,
         skip_reset_local_store: bool,
+    ) -> Result<Self> {
+        Self::new_with_options(
+            epoch,
+            remote_store_config,
+            local_store_config,
+            DownloadOptions::new(download_concurrency),
+            m,
+            skip_reset_local_store,
+        )
+        .await
+    }
+
+    /// If `skip_reset_local_store` is set, the files downloaded by a previous (possibly
+    /// interrupted) restore of the same snapshot are kept: only the reference files that are
+    /// missing or do not match their digest are downloaded again, and the object files that were
+    /// already restored are skipped.
+    pub async fn new_with_options(
+        epoch: u64,
+        remote_store_config: &ObjectStoreConfig,
+        local_store_config: &ObjectStoreConfig,
+        options: DownloadOptions,
+        m: MultiProgress,
+        skip_reset_local_store: bool,
     ) -> Result<Self> {
         let epoch_dir = format!("epoch_{}", epoch);
         let remote_object_store = if remote_store_config.no_sign_request {
@@ -71,8 +99,6 @@ impl StateSnapshotReaderV1 {
         };
         let local_object_store: Arc<dyn ObjectStorePutExt> =
             local_store_config.make().map(Arc::new)?;
-        let local_object_store_list: Arc<dyn ObjectStoreListExt> =
-            local_store_config.make().map(Arc::new)?;
         let local_staging_dir_root = local_store_config
             .directory
             .as_ref()
@@ -94,10 +120,10 @@ impl StateSnapshotReaderV1 {
             &local_object_store,
         )
         .await?;
-        let manifest = Self::read_manifest(path_to_filesystem(
-            local_staging_dir_root.clone(),
-            &manifest_file_path,
-        )?)?;
+        let local_manifest_path =
+            path_to_filesystem(local_staging_dir_root.clone(), &manifest_file_path)?;
+        let manifest_digest = compute_sha3_checksum(&local_manifest_path)?;
+        let manifest = Self::read_manifest(local_manifest_path)?;
         let snapshot_version = manifest.snapshot_version();
         if snapshot_version != 1u8 {
             return Err(anyhow!("Unexpected snapshot version: {}", snapshot_version));
@@ -130,35 +156,31 @@ impl StateSnapshotReaderV1 {
             }
         }
         let epoch_dir_path = Path::from(epoch_dir);
-        let files: Vec<Path> = ref_files
+        let files: Vec<FileMetadata> = ref_files
             .values()
-            .flat_map(|entry| {
-                let files: Vec<_> = entry
-                    .values()
-                    .map(|file_metadata| file_metadata.file_path(&epoch_dir_path))
-                    .collect();
-                files
-            })
+            .flat_map(|entry| entry.values().cloned())
             .collect();
 
-        let files_to_download = if skip_reset_local_store {
-            let mut list_stream = local_object_store_list
-                .list_objects(Some(&epoch_dir_path))
-                .await;
-            let mut existing_files = std::collections::HashSet::new();
-            while let Some(Ok(meta)) = list_stream.next().await {
-                existing_files.insert(meta.location);
-            }
-            let mut missing_files = Vec::new();
-            for file in &files {
-                if !existing_files.contains(file) {
-                    missing_files.push(file.clone());
-                }
-            }
-            missing_files
+        let files_to_download: Vec<FileMetadata> = if skip_reset_local_store {
+            // Only keep the local files that match the manifest
+            files
+                .into_iter()
+                .filter(|file_metadata| {
+                    !file_metadata
+                        .local_file_path(&local_staging_dir_root, &epoch_dir_path)
+                        .and_then(|path| compute_sha3_checksum(&path))
+                        .is_ok_and(|digest| digest == file_metadata.sha3_digest)
+                })
+                .collect()
         } else {
             files
         };
+        let progress = DownloadProgress::load(
+            &path_to_filesystem(local_staging_dir_root.clone(), &epoch_dir_path)?,
+            &manifest_digest,
+        )?;
+        let limiter = Arc::new(BandwidthLimiter::new(options.max_bytes_per_sec));
+        let max_attempts = options.max_attempts;
         let progress_bar = m.add(
             ProgressBar::new(files_to_download.len() as u64).with_style(
                 ProgressStyle::with_template(
@@ -167,15 +189,33 @@ impl StateSnapshotReaderV1 {
                 .unwrap(),
             ),
         );
-        copy_files(
-            &files_to_download,
-            &files_to_download,
-            &remote_object_store,
-            &local_object_store,
-            download_concurrency,
-            Some(progress_bar.clone()),
-        )
-        .await?;
+        futures::stream::iter(files_to_download.iter())
+            .map(|file_metadata| {
+                let path = file_metadata.file_path(&epoch_dir_path);
+                let remote_object_store = &remote_object_store;
+                let local_object_store = &local_object_store;
+                let limiter = &limiter;
+                async move {
+                    let bytes = download_verified(
+                        remote_object_store.as_ref(),
+                        &path,
+                        &file_metadata.sha3_digest,
+                        limiter,
+                        max_attempts,
+                    )
+                    .await?;
+                    local_object_store.put_bytes(&path, bytes).await?;
+                    Ok::<Path, anyhow::Error>(path)
+                }
+            })
+            .boxed()
+            .buffer_unordered(options.concurrency.get())
+            .try_for_each(|path| {
+                progress_bar.inc(1);
+                progress_bar.set_message(format!("file: {}", path));
+                futures::future::ready(Ok(()))
+            })
+            .await?;
         progress_bar.finish_with_message("Missing ref files download complete");
         Ok(StateSnapshotReaderV1 {
             epoch,
@@ -185,7 +225,10 @@ impl StateSnapshotReaderV1 {
             ref_files,
             object_files,
             m,
-            concurrency: download_concurrency.get(),
+            concurrency: options.concurrency.get(),
+            max_attempts: options.max_attempts,
+            limiter,
+            progress: Arc::new(std::sync::Mutex::new(progress)),
         })
     }
 
@@ -383,17 +426,31 @@ impl StateSnapshotReaderV1 {
         let epoch_dir = self.epoch_dir();
         let concurrency = self.concurrency;
         let remote_object_store = self.remote_object_store.clone();
-        let input_files: Vec<_> = self
-            .object_files
-            .iter()
-            .flat_map(|(bucket, parts)| {
-                parts
-                    .clone()
-                    .into_iter()
-                    .map(|entry| (bucket, entry))
-                    .collect::<Vec<_>>()
-            })
-            .collect();
+        let progress = self.progress.clone();
+        // The progress is only valid for the DB the objects were restored to. If that DB was
+        // reset since, all the object files are restored again.
+        if perpetual_db.database_is_empty()? && progress.lock().unwrap().num_object_files() > 0 {
+            info!("Discarding the restore progress, as the objects it records are not in the DB");
+            progress.lock().unwrap().clear_object_files()?;
+        }
+        let num_restored = progress.lock().unwrap().num_object_files();
+        let input_files: Vec<_> = {
+            let progress = progress.lock().unwrap();
+            self.object_files
+                .iter()
+                .flat_map(|(bucket, parts)| parts.iter().map(move |entry| (*bucket, entry)))
+                .filter(|(bucket, (part_num, _))| {
+                    !progress.contains_object_file(*bucket, **part_num)
+                })
+                .collect()
+        };
+        if num_restored > 0 {
+            info!(
+                "Resuming restore: {} object files already restored, {} left",
+                num_restored,
+                input_files.len()
+            );
+        }
         let obj_progress_bar = self.m.add(
             ProgressBar::new(input_files.len() as u64).with_style(
                 ProgressStyle::with_template(
@@ -405,6 +462,8 @@ impl StateSnapshotReaderV1 {
         let obj_progress_bar_clone = obj_progress_bar.clone();
         let instant = Instant::now();
         let downloaded_bytes = AtomicUsize::new(0);
+        let limiter = self.limiter.clone();
+        let max_attempts = self.max_attempts;
 
         let ret = Abortable::new(
             async move {
@@ -413,18 +472,26 @@ impl StateSnapshotReaderV1 {
                         let epoch_dir_clone = epoch_dir.clone();
                         let remote_object_store_clone = remote_object_store.clone();
                         let sha3_digests_clone = sha3_digests.clone();
+                        let limiter = limiter.clone();
                         async move {
-                            // Download object file with retries
-                            let (bytes, sha3_digest) = download_bytes(
-                                remote_object_store_clone,
-                                file_metadata,
-                                epoch_dir_clone,
-                                sha3_digests_clone,
-                                bucket,
-                                part_num,
-                                None,
+                            // Download object file with retries, until it matches the manifest
+                            let bytes = download_verified(
+                                remote_object_store_clone.as_ref(),
+                                &file_metadata.file_path(&epoch_dir_clone),
+                                &file_metadata.sha3_digest,
+                                &limiter,
+                                max_attempts,
                             )
-                            .await;
+                            .await?;
+                            let sha3_digest = *sha3_digests_clone
+                                .lock()
+                                .await
+                                .get(bucket)
+                                .context(format!("No ref digests for bucket: {bucket}"))?
+                                .get(*part_num)
+                                .context(format!(
+                                    "No ref digest for bucket: {bucket}, part: {part_num}"
+                                ))?;
                             Ok::<(Bytes, FileMetadata, [u8; 32]), anyhow::Error>((
                                 bytes,
                                 (*file_metadata).clone(),
@@ -437,14 +504,21 @@ impl StateSnapshotReaderV1 {
                     .try_for_each(|(bytes, file_metadata, sha3_digest)| {
                         let bytes_len = bytes.len();
                         let result: Result<(), anyhow::Error> =
-                            LiveObjectIter::new(&file_metadata, bytes).map(|obj_iter| {
-                                AuthorityStore::bulk_insert_live_objects(
-                                    perpetual_db,
-                                    obj_iter,
-                                    &sha3_digest,
-                                )
-                                .expect("Failed to insert live objects");
-                            });
+                            LiveObjectIter::new(&file_metadata, bytes)
+                                .map(|obj_iter| {
+                                    AuthorityStore::bulk_insert_live_objects(
+                                        perpetual_db,
+                                        obj_iter,
+                                        &sha3_digest,
+                                    )
+                                    .expect("Failed to insert live objects");
+                                })
+                                .and_then(|_| {
+                                    progress.lock().unwrap().add_object_file(
+                                        file_metadata.bucket_num,
+                                        file_metadata.part_num,
+                                    )
+                                });
                         downloaded_bytes.fetch_add(bytes_len, Ordering::Relaxed);
                         obj_progress_bar_clone.inc(1);
                         obj_progress_bar_clone.set_message(format!(
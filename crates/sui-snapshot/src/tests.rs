@@ -1,6 +1,7 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::download::DownloadOptions;
 use crate::reader::StateSnapshotReaderV1;
 use crate::writer::StateSnapshotWriterV1;
 use crate::FileCompression;
@@ -181,3 +182,168 @@ async fn test_snapshot_empty_db() -> Result<(), anyhow::Error> {
     )?;
     Ok(())
 }
+
+async fn write_snapshot(
+    num_objects: u64,
+) -> Result<(Arc<AuthorityPerpetualTables>, ObjectStoreConfig), anyhow::Error> {
+    let db_path = temp_dir();
+    let local_store_config = ObjectStoreConfig {
+        object_store: Some(ObjectStoreType::File),
+        directory: Some(temp_dir().join("local_dir")),
+        ..Default::default()
+    };
+    let remote_store_config = ObjectStoreConfig {
+        object_store: Some(ObjectStoreType::File),
+        directory: Some(temp_dir().join("remote_dir")),
+        ..Default::default()
+    };
+    let snapshot_writer = StateSnapshotWriterV1::new(
+        &local_store_config,
+        &remote_store_config,
+        FileCompression::Zstd,
+        NonZeroUsize::new(1).unwrap(),
+    )
+    .await?;
+    let perpetual_db = Arc::new(AuthorityPerpetualTables::open(&db_path, None));
+    insert_keys(&perpetual_db, num_objects)?;
+    let root_accumulator =
+        ECMHLiveObjectSetDigest::from(accumulate_live_object_set(&perpetual_db, true).digest());
+    snapshot_writer
+        .write_internal(0, true, perpetual_db.clone(), root_accumulator)
+        .await?;
+    Ok((perpetual_db, remote_store_config))
+}
+
+async fn restore_snapshot(
+    remote_store_config: &ObjectStoreConfig,
+    restored_local: &std::path::Path,
+    db_path: &std::path::Path,
+    options: DownloadOptions,
+    skip_reset_local_store: bool,
+) -> Result<AuthorityPerpetualTables, anyhow::Error> {
+    let local_store_restore_config = ObjectStoreConfig {
+        object_store: Some(ObjectStoreType::File),
+        directory: Some(restored_local.to_path_buf()),
+        ..Default::default()
+    };
+    let mut snapshot_reader = StateSnapshotReaderV1::new_with_options(
+        0,
+        remote_store_config,
+        &local_store_restore_config,
+        options,
+        MultiProgress::new(),
+        skip_reset_local_store,
+    )
+    .await?;
+    let restored_perpetual_db = AuthorityPerpetualTables::open(db_path, None);
+    let (_abort_handle, abort_registration) = AbortHandle::new_pair();
+    snapshot_reader
+        .read(&restored_perpetual_db, abort_registration, None)
+        .await?;
+    Ok(restored_perpetual_db)
+}
+
+fn files_with_extension(dir: &std::path::Path, extension: &str) -> Vec<std::path::PathBuf> {
+    let mut files: Vec<_> = std::fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == extension))
+        .collect();
+    files.sort();
+    files
+}
+
+#[tokio::test]
+async fn test_snapshot_resume() -> Result<(), anyhow::Error> {
+    let (perpetual_db, remote_store_config) = write_snapshot(1000).await?;
+    let restored_local = temp_dir().join("local_dir_restore");
+    let db_path = temp_dir();
+    let options = DownloadOptions::new(NonZeroUsize::new(2).unwrap());
+    let restored_db = restore_snapshot(
+        &remote_store_config,
+        &restored_local,
+        &db_path,
+        options.clone(),
+        false,
+    )
+    .await?;
+    compare_live_objects(&perpetual_db, &restored_db, true)?;
+    drop(restored_db);
+
+    // Corrupt a downloaded reference file, and pretend the restore of an object file was
+    // interrupted
+    let epoch_dir = restored_local.join("epoch_0");
+    let ref_file = files_with_extension(&epoch_dir, "ref")[0].clone();
+    let ref_bytes = std::fs::read(&ref_file)?;
+    std::fs::write(&ref_file, b"corrupted")?;
+    let progress_path = epoch_dir.join("DOWNLOAD_PROGRESS");
+    let mut progress: serde_json::Value = serde_json::from_slice(&std::fs::read(&progress_path)?)?;
+    let object_files = progress["object_files"].as_array_mut().unwrap();
+    let num_object_files = object_files.len();
+    assert!(num_object_files > 1);
+    object_files.pop();
+    std::fs::write(&progress_path, serde_json::to_vec(&progress)?)?;
+
+    // Only the missing object file is restored
+    let resumed_db = restore_snapshot(
+        &remote_store_config,
+        &restored_local,
+        &db_path,
+        options.clone(),
+        true,
+    )
+    .await?;
+    compare_live_objects(&perpetual_db, &resumed_db, true)?;
+    assert_eq!(std::fs::read(&ref_file)?, ref_bytes);
+    let progress: serde_json::Value = serde_json::from_slice(&std::fs::read(&progress_path)?)?;
+    assert_eq!(
+        progress["object_files"].as_array().unwrap().len(),
+        num_object_files
+    );
+    drop(resumed_db);
+
+    // Pretend the restore was interrupted again, but resume it to a new DB: the progress no
+    // longer applies, and all the object files are restored
+    let mut progress = progress;
+    progress["object_files"].as_array_mut().unwrap().pop();
+    std::fs::write(&progress_path, serde_json::to_vec(&progress)?)?;
+    let new_db = restore_snapshot(
+        &remote_store_config,
+        &restored_local,
+        &temp_dir(),
+        options,
+        true,
+    )
+    .await?;
+    compare_live_objects(&perpetual_db, &new_db, true)?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_snapshot_corrupted_object_file() -> Result<(), anyhow::Error> {
+    let (_, remote_store_config) = write_snapshot(100).await?;
+    let remote_epoch_dir = remote_store_config
+        .directory
+        .as_ref()
+        .unwrap()
+        .join("epoch_0");
+    let obj_file = files_with_extension(&remote_epoch_dir, "obj")[0].clone();
+    std::fs::write(&obj_file, b"corrupted")?;
+
+    let options = DownloadOptions {
+        max_attempts: 1,
+        ..DownloadOptions::new(NonZeroUsize::new(1).unwrap())
+    };
+    let err = restore_snapshot(
+        &remote_store_config,
+        &temp_dir().join("local_dir_restore"),
+        &temp_dir(),
+        options,
+        false,
+    )
+    .await
+    .err()
+    .expect("Restore should fail");
+    assert!(format!("{err:?}").contains("doesn't match manifest"));
+    Ok(())
+}
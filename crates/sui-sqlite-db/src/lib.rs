@@ -0,0 +1,353 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::ops::{Deref, DerefMut};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{anyhow, Context};
+use diesel::migration::{Migration, MigrationSource, MigrationVersion};
+use diesel::sqlite::{Sqlite, SqliteConnection};
+use diesel::Connection as _;
+use diesel::ConnectionError;
+use diesel_async::pooled_connection::ManagerConfig;
+use diesel_async::sync_connection_wrapper::SyncConnectionWrapper;
+use diesel_async::{
+    pooled_connection::{
+        bb8::{Pool, PooledConnection},
+        AsyncDieselConnectionManager,
+    },
+    AsyncConnection, SimpleAsyncConnection,
+};
+use futures::FutureExt;
+use tracing::info;
+
+mod model;
+
+pub use sui_field_count::FieldCount;
+
+pub mod schema;
+pub mod store;
+pub mod temp;
+
+use diesel_migrations::{embed_migrations, EmbeddedMigrations};
+
+pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations");
+
+/// SQLite does not offer an async driver, so each connection is a synchronous connection that
+/// runs its queries on tokio's blocking thread pool.
+pub type SqliteConnectionWrapper = SyncConnectionWrapper<SqliteConnection>;
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct DbArgs {
+    /// Number of connections to keep in the pool. SQLite only supports a single writer at a time,
+    /// so additional connections are only useful for concurrent reads.
+    #[arg(long, default_value_t = Self::default().db_connection_pool_size)]
+    pub db_connection_pool_size: u32,
+
+    /// Time spent waiting for a connection from the pool to become available, in milliseconds.
+    #[arg(long, default_value_t = Self::default().db_connection_timeout_ms)]
+    pub db_connection_timeout_ms: u64,
+
+    /// Time a connection will wait for a lock held by another connection to be released before
+    /// failing with `SQLITE_BUSY`, in milliseconds.
+    #[arg(long, default_value_t = Self::default().db_busy_timeout_ms)]
+    pub db_busy_timeout_ms: u64,
+}
+
+#[derive(Clone)]
+pub struct Db {
+    pool: Pool<SqliteConnectionWrapper>,
+
+    /// Location of the database file, used to open dedicated connections (e.g. for migrations).
+    path: Arc<PathBuf>,
+}
+
+/// Wrapper struct over the remote `PooledConnection` type for dealing with the `Store` trait.
+pub struct Connection<'a>(PooledConnection<'a, SqliteConnectionWrapper>);
+
+impl DbArgs {
+    pub fn connection_timeout(&self) -> Duration {
+        Duration::from_millis(self.db_connection_timeout_ms)
+    }
+
+    pub fn busy_timeout(&self) -> Duration {
+        Duration::from_millis(self.db_busy_timeout_ms)
+    }
+}
+
+impl Db {
+    /// Construct a new DB connection pool for the SQLite database at `path` that supports writes
+    /// and reads. The database file is created if it does not already exist. Instances of [Db] can
+    /// be cloned to share access to the same pool.
+    pub async fn for_write(path: impl AsRef<Path>, config: DbArgs) -> anyhow::Result<Self> {
+        Self::new(path.as_ref(), config, false).await
+    }
+
+    /// Construct a new DB connection pool for the SQLite database at `path` whose connections
+    /// reject writes. Instances of [Db] can be cloned to share access to the same pool.
+    pub async fn for_read(path: impl AsRef<Path>, config: DbArgs) -> anyhow::Result<Self> {
+        Self::new(path.as_ref(), config, true).await
+    }
+
+    /// Retrieves a connection from the pool. Can fail with a timeout if a connection cannot be
+    /// established before the [DbArgs::connection_timeout] has elapsed.
+    pub async fn connect(&self) -> anyhow::Result<Connection<'_>> {
+        Ok(Connection(self.pool.get().await?))
+    }
+
+    /// Statistics about the connection pool
+    pub fn state(&self) -> bb8::State {
+        self.pool.state()
+    }
+
+    /// Path to the database file backing this pool.
+    pub fn path(&self) -> &Path {
+        self.path.as_path()
+    }
+
+    /// Run migrations on the database. Use Diesel's `embed_migrations!` macro to generate the
+    /// `migrations` parameter for your indexer.
+    pub async fn run_migrations(
+        &self,
+        migrations: Option<&'static EmbeddedMigrations>,
+    ) -> anyhow::Result<Vec<MigrationVersion<'static>>> {
+        use diesel_migrations::MigrationHarness;
+
+        let merged_migrations = merge_migrations(migrations);
+        let path = self.path.clone();
+
+        info!("Running migrations ...");
+        let finished_migrations = tokio::task::spawn_blocking(move || {
+            let mut conn = SqliteConnection::establish(&database_url(&path)?)
+                .context("Failed to open database for migrations")?;
+
+            conn.run_pending_migrations(merged_migrations)
+                .map(|versions| versions.iter().map(MigrationVersion::as_owned).collect())
+                .map_err(|e| anyhow!("Failed to run migrations: {:?}", e))
+        })
+        .await??;
+
+        info!("Migrations complete.");
+        Ok(finished_migrations)
+    }
+
+    async fn new(path: &Path, args: DbArgs, read_only: bool) -> anyhow::Result<Self> {
+        let path = Arc::new(path.to_owned());
+        let pool = pool(&database_url(&path)?, args, read_only).await?;
+        Ok(Self { pool, path })
+    }
+}
+
+impl Default for DbArgs {
+    fn default() -> Self {
+        Self {
+            db_connection_pool_size: 4,
+            db_connection_timeout_ms: 60_000,
+            db_busy_timeout_ms: 5_000,
+        }
+    }
+}
+
+impl<'a> Deref for Connection<'a> {
+    type Target = PooledConnection<'a, SqliteConnectionWrapper>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for Connection<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+/// SQLite connections are addressed by file path rather than URL. In-memory databases are rejected
+/// because every connection in the pool would see its own, separate database.
+fn database_url(path: &Path) -> anyhow::Result<String> {
+    let url = path
+        .to_str()
+        .with_context(|| format!("Database path is not valid UTF-8: {}", path.display()))?;
+
+    if url.is_empty() || url == ":memory:" {
+        return Err(anyhow!(
+            "SQLite store requires a database file, got {url:?}"
+        ));
+    }
+
+    Ok(url.to_owned())
+}
+
+async fn pool(
+    database_url: &str,
+    args: DbArgs,
+    read_only: bool,
+) -> anyhow::Result<Pool<SqliteConnectionWrapper>> {
+    let busy_timeout = args.busy_timeout();
+
+    let mut config = ManagerConfig::default();
+    config.custom_setup = Box::new(move |url| {
+        async move {
+            let mut conn = SqliteConnectionWrapper::establish(url).await?;
+
+            // WAL mode allows readers to proceed while a writer is active, and the busy timeout
+            // makes concurrent writers queue up behind each other instead of failing immediately.
+            conn.batch_execute(&format!(
+                "PRAGMA journal_mode = WAL; \
+                 PRAGMA synchronous = NORMAL; \
+                 PRAGMA foreign_keys = ON; \
+                 PRAGMA busy_timeout = {};",
+                busy_timeout.as_millis()
+            ))
+            .await
+            .map_err(ConnectionError::CouldntSetupConfiguration)?;
+
+            if read_only {
+                conn.batch_execute("PRAGMA query_only = ON;")
+                    .await
+                    .map_err(ConnectionError::CouldntSetupConfiguration)?;
+            }
+
+            Ok(conn)
+        }
+        .boxed()
+    });
+
+    let manager = AsyncDieselConnectionManager::new_with_config(database_url, config);
+
+    Ok(Pool::builder()
+        .max_size(args.db_connection_pool_size)
+        .connection_timeout(args.connection_timeout())
+        .build(manager)
+        .await?)
+}
+
+/// Returns new migrations derived from the combination of provided migrations and migrations
+/// defined in this crate.
+pub fn merge_migrations(
+    migrations: Option<&'static EmbeddedMigrations>,
+) -> impl MigrationSource<Sqlite> + Send + Sync + 'static {
+    struct Migrations(Option<&'static EmbeddedMigrations>);
+    impl MigrationSource<Sqlite> for Migrations {
+        fn migrations(&self) -> diesel::migration::Result<Vec<Box<dyn Migration<Sqlite>>>> {
+            let mut migrations = MIGRATIONS.migrations()?;
+            if let Some(more_migrations) = self.0 {
+                migrations.extend(more_migrations.migrations()?);
+            }
+            Ok(migrations)
+        }
+    }
+
+    Migrations(migrations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diesel::prelude::QueryableByName;
+    use diesel_async::RunQueryDsl;
+
+    #[derive(Debug, QueryableByName)]
+    struct CountResult {
+        #[diesel(sql_type = diesel::sql_types::BigInt)]
+        cnt: i64,
+    }
+
+    #[tokio::test]
+    async fn temp_db_smoketest() {
+        telemetry_subscribers::init_for_testing();
+        let temp_db = temp::TempDb::new().unwrap();
+        let db = Db::for_write(temp_db.path(), DbArgs::default())
+            .await
+            .unwrap();
+        let mut conn = db.connect().await.unwrap();
+
+        let cnt: CountResult = diesel::sql_query("SELECT 1 AS cnt")
+            .get_result(&mut conn)
+            .await
+            .unwrap();
+
+        assert_eq!(cnt.cnt, 1);
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_rejected() {
+        assert!(Db::for_write(":memory:", DbArgs::default()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_run_migrations() {
+        let temp_db = temp::TempDb::new().unwrap();
+        let db = Db::for_write(temp_db.path(), DbArgs::default())
+            .await
+            .unwrap();
+
+        let versions = db.run_migrations(None).await.unwrap();
+        assert_eq!(versions.len(), 1);
+
+        // Migrations that have already been applied are not re-run.
+        let versions = db.run_migrations(None).await.unwrap();
+        assert!(versions.is_empty());
+
+        let mut conn = db.connect().await.unwrap();
+        let cnt: CountResult = diesel::sql_query(
+            "SELECT COUNT(*) AS cnt FROM sqlite_master WHERE type = 'table' AND name = 'watermarks'",
+        )
+        .get_result(&mut conn)
+        .await
+        .unwrap();
+        assert_eq!(cnt.cnt, 1);
+    }
+
+    #[tokio::test]
+    async fn test_read_only() {
+        let temp_db = temp::TempDb::new().unwrap();
+
+        let writer = Db::for_write(temp_db.path(), DbArgs::default())
+            .await
+            .unwrap();
+        let reader = Db::for_read(temp_db.path(), DbArgs::default())
+            .await
+            .unwrap();
+
+        {
+            // Create a table
+            let mut conn = writer.connect().await.unwrap();
+            diesel::sql_query("CREATE TABLE test_table (id INTEGER PRIMARY KEY)")
+                .execute(&mut conn)
+                .await
+                .unwrap();
+        }
+
+        {
+            // Try an insert into it using the read-only connection, which should fail
+            let mut conn = reader.connect().await.unwrap();
+            let result = diesel::sql_query("INSERT INTO test_table (id) VALUES (1)")
+                .execute(&mut conn)
+                .await;
+            assert!(result.is_err());
+        }
+
+        {
+            // Then try to write to it using the write connection, which should succeed
+            let mut conn = writer.connect().await.unwrap();
+            diesel::sql_query("INSERT INTO test_table (id) VALUES (1)")
+                .execute(&mut conn)
+                .await
+                .unwrap();
+        }
+
+        {
+            // Finally, try to read from it using the read-only connection, which should now return
+            // results.
+            let mut conn = reader.connect().await.unwrap();
+            let cnt: CountResult = diesel::sql_query("SELECT COUNT(*) AS cnt FROM test_table")
+                .get_result(&mut conn)
+                .await
+                .unwrap();
+            assert_eq!(cnt.cnt, 1);
+        }
+    }
+}
@@ -0,0 +1,319 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use diesel::prelude::*;
+use diesel::sql_types::BigInt;
+use diesel::ExpressionMethods;
+use diesel::OptionalExtension;
+use diesel_async::{AsyncConnection, RunQueryDsl};
+use scoped_futures::ScopedBoxFuture;
+use sui_indexer_alt_framework_store_traits as store;
+
+use crate::model::StoredWatermark;
+use crate::schema::watermarks;
+use crate::{Connection, Db};
+
+pub use sui_indexer_alt_framework_store_traits::Store;
+
+/// The database's current time, in milliseconds since the Unix epoch. SQLite's `julianday` has
+/// sub-second precision on all supported versions, unlike `unixepoch` or `CURRENT_TIMESTAMP`.
+const NOW_MS: &str = "CAST((julianday('now') - 2440587.5) * 86400000 AS BIGINT)";
+
+#[async_trait]
+impl store::Connection for Connection<'_> {
+    async fn committer_watermark(
+        &mut self,
+        pipeline: &'static str,
+    ) -> anyhow::Result<Option<store::CommitterWatermark>> {
+        let watermark: Option<(i64, i64, i64, i64)> = watermarks::table
+            .select((
+                watermarks::epoch_hi_inclusive,
+                watermarks::checkpoint_hi_inclusive,
+                watermarks::tx_hi,
+                watermarks::timestamp_ms_hi_inclusive,
+            ))
+            .filter(watermarks::pipeline.eq(pipeline))
+            .first(self)
+            .await
+            .optional()?;
+
+        if let Some(watermark) = watermark {
+            Ok(Some(store::CommitterWatermark {
+                epoch_hi_inclusive: watermark.0 as u64,
+                checkpoint_hi_inclusive: watermark.1 as u64,
+                tx_hi: watermark.2 as u64,
+                timestamp_ms_hi_inclusive: watermark.3 as u64,
+            }))
+        } else {
+            Ok(None)
+        }
+    }
+
+    async fn reader_watermark(
+        &mut self,
+        pipeline: &'static str,
+    ) -> anyhow::Result<Option<store::ReaderWatermark>> {
+        let watermark: Option<(i64, i64)> = watermarks::table
+            .select((watermarks::checkpoint_hi_inclusive, watermarks::reader_lo))
+            .filter(watermarks::pipeline.eq(pipeline))
+            .first(self)
+            .await
+            .optional()?;
+
+        if let Some(watermark) = watermark {
+            Ok(Some(store::ReaderWatermark {
+                checkpoint_hi_inclusive: watermark.0 as u64,
+                reader_lo: watermark.1 as u64,
+            }))
+        } else {
+            Ok(None)
+        }
+    }
+
+    async fn pruner_watermark(
+        &mut self,
+        pipeline: &'static str,
+        delay: Duration,
+    ) -> anyhow::Result<Option<store::PrunerWatermark>> {
+        //     |---------- + delay ---------------------|
+        //                             |--- wait_for ---|
+        //     |-----------------------|----------------|
+        //     ^                       ^
+        //     pruner_timestamp        NOW()
+        let wait_for = diesel::dsl::sql::<BigInt>("CAST(")
+            .bind::<BigInt, _>(delay.as_millis() as i64)
+            .sql(&format!(" + pruner_timestamp_ms - {NOW_MS} AS BIGINT)"));
+
+        let watermark: Option<(i64, i64, i64)> = watermarks::table
+            .select((wait_for, watermarks::pruner_hi, watermarks::reader_lo))
+            .filter(watermarks::pipeline.eq(pipeline))
+            .first(self)
+            .await
+            .optional()?;
+
+        if let Some(watermark) = watermark {
+            Ok(Some(store::PrunerWatermark {
+                wait_for_ms: watermark.0,
+                pruner_hi: watermark.1 as u64,
+                reader_lo: watermark.2 as u64,
+            }))
+        } else {
+            Ok(None)
+        }
+    }
+
+    async fn set_committer_watermark(
+        &mut self,
+        pipeline: &'static str,
+        watermark: store::CommitterWatermark,
+    ) -> anyhow::Result<bool> {
+        // Create a StoredWatermark directly from CommitterWatermark
+        let stored_watermark = StoredWatermark {
+            pipeline: pipeline.to_string(),
+            epoch_hi_inclusive: watermark.epoch_hi_inclusive as i64,
+            checkpoint_hi_inclusive: watermark.checkpoint_hi_inclusive as i64,
+            tx_hi: watermark.tx_hi as i64,
+            timestamp_ms_hi_inclusive: watermark.timestamp_ms_hi_inclusive as i64,
+            reader_lo: 0,
+            pruner_timestamp_ms: 0,
+            pruner_hi: 0,
+        };
+
+        // SQLite does not support a `WHERE` clause on `ON CONFLICT DO UPDATE` through diesel, so
+        // try to insert a fresh entry first, and if one already exists, only write the new `hi`
+        // values if they advance it.
+        let inserted = diesel::insert_into(watermarks::table)
+            .values(&stored_watermark)
+            .on_conflict_do_nothing()
+            .execute(&mut *self)
+            .await?;
+
+        if inserted > 0 {
+            return Ok(true);
+        }
+
+        Ok(diesel::update(watermarks::table)
+            .set((
+                watermarks::epoch_hi_inclusive.eq(stored_watermark.epoch_hi_inclusive),
+                watermarks::checkpoint_hi_inclusive.eq(stored_watermark.checkpoint_hi_inclusive),
+                watermarks::tx_hi.eq(stored_watermark.tx_hi),
+                watermarks::timestamp_ms_hi_inclusive
+                    .eq(stored_watermark.timestamp_ms_hi_inclusive),
+            ))
+            .filter(watermarks::pipeline.eq(pipeline))
+            .filter(
+                watermarks::checkpoint_hi_inclusive.lt(stored_watermark.checkpoint_hi_inclusive),
+            )
+            .execute(self)
+            .await?
+            > 0)
+    }
+
+    async fn set_reader_watermark(
+        &mut self,
+        pipeline: &'static str,
+        reader_lo: u64,
+    ) -> anyhow::Result<bool> {
+        Ok(diesel::update(watermarks::table)
+            .set((
+                watermarks::reader_lo.eq(reader_lo as i64),
+                watermarks::pruner_timestamp_ms.eq(diesel::dsl::sql::<BigInt>(NOW_MS)),
+            ))
+            .filter(watermarks::pipeline.eq(pipeline))
+            .filter(watermarks::reader_lo.lt(reader_lo as i64))
+            .execute(self)
+            .await?
+            > 0)
+    }
+
+    async fn set_pruner_watermark(
+        &mut self,
+        pipeline: &'static str,
+        pruner_hi: u64,
+    ) -> anyhow::Result<bool> {
+        Ok(diesel::update(watermarks::table)
+            .set(watermarks::pruner_hi.eq(pruner_hi as i64))
+            .filter(watermarks::pipeline.eq(pipeline))
+            .execute(self)
+            .await?
+            > 0)
+    }
+}
+
+#[async_trait]
+impl store::Store for Db {
+    type Connection<'c> = Connection<'c>;
+
+    async fn connect<'c>(&'c self) -> anyhow::Result<Self::Connection<'c>> {
+        Ok(Connection(self.pool.get().await?))
+    }
+}
+
+#[async_trait]
+impl store::TransactionalStore for Db {
+    async fn transaction<'a, R, F>(&self, f: F) -> anyhow::Result<R>
+    where
+        R: Send + 'a,
+        F: Send + 'a,
+        F: for<'r> FnOnce(
+            &'r mut Self::Connection<'_>,
+        ) -> ScopedBoxFuture<'a, 'r, anyhow::Result<R>>,
+    {
+        let mut conn = self.connect().await?;
+        AsyncConnection::transaction(&mut conn, |conn| f(conn)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use scoped_futures::ScopedFutureExt;
+    use store::{Connection as _, TransactionalStore as _};
+
+    use super::*;
+    use crate::{temp::TempDb, DbArgs};
+
+    async fn db() -> (Db, TempDb) {
+        let temp_db = TempDb::new().unwrap();
+        let db = Db::for_write(temp_db.path(), DbArgs::default())
+            .await
+            .unwrap();
+        db.run_migrations(None).await.unwrap();
+        (db, temp_db)
+    }
+
+    #[tokio::test]
+    async fn test_committer_watermark_only_advances() {
+        let (db, _temp_db) = db().await;
+        let mut conn = db.connect().await.unwrap();
+
+        assert!(conn.committer_watermark("p").await.unwrap().is_none());
+
+        let watermark = store::CommitterWatermark::new_for_testing(10);
+        assert!(conn.set_committer_watermark("p", watermark).await.unwrap());
+
+        // Lower or equal watermarks are ignored.
+        let watermark = store::CommitterWatermark::new_for_testing(5);
+        assert!(!conn.set_committer_watermark("p", watermark).await.unwrap());
+        let watermark = store::CommitterWatermark::new_for_testing(10);
+        assert!(!conn.set_committer_watermark("p", watermark).await.unwrap());
+
+        let watermark = conn.committer_watermark("p").await.unwrap().unwrap();
+        assert_eq!(watermark.checkpoint_hi_inclusive, 10);
+    }
+
+    #[tokio::test]
+    async fn test_reader_and_pruner_watermarks() {
+        let (db, _temp_db) = db().await;
+        let mut conn = db.connect().await.unwrap();
+
+        let watermark = store::CommitterWatermark::new_for_testing(100);
+        conn.set_committer_watermark("p", watermark).await.unwrap();
+
+        assert!(conn.set_reader_watermark("p", 50).await.unwrap());
+        assert!(!conn.set_reader_watermark("p", 40).await.unwrap());
+
+        let reader = conn.reader_watermark("p").await.unwrap().unwrap();
+        assert_eq!(reader.checkpoint_hi_inclusive, 100);
+        assert_eq!(reader.reader_lo, 50);
+
+        // The reader watermark was just updated, so the pruner has to wait roughly the full delay.
+        let delay = Duration::from_secs(60);
+        let pruner = conn.pruner_watermark("p", delay).await.unwrap().unwrap();
+        assert_eq!(pruner.reader_lo, 50);
+        assert_eq!(pruner.pruner_hi, 0);
+        assert!(pruner.wait_for_ms > 50_000 && pruner.wait_for_ms <= 60_000);
+
+        // With no delay, pruning can start immediately.
+        let pruner = conn
+            .pruner_watermark("p", Duration::ZERO)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(pruner.wait_for().is_none());
+
+        assert!(conn.set_pruner_watermark("p", 50).await.unwrap());
+        let pruner = conn
+            .pruner_watermark("p", Duration::ZERO)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(pruner.pruner_hi, 50);
+    }
+
+    #[tokio::test]
+    async fn test_transaction_rollback() {
+        let (db, _temp_db) = db().await;
+
+        let result: anyhow::Result<()> = db
+            .transaction(|conn| {
+                async move {
+                    let watermark = store::CommitterWatermark::new_for_testing(10);
+                    conn.set_committer_watermark("p", watermark).await?;
+                    anyhow::bail!("abort");
+                }
+                .scope_boxed()
+            })
+            .await;
+        assert!(result.is_err());
+
+        let mut conn = db.connect().await.unwrap();
+        assert!(conn.committer_watermark("p").await.unwrap().is_none());
+
+        db.transaction(|conn| {
+            async move {
+                let watermark = store::CommitterWatermark::new_for_testing(10);
+                conn.set_committer_watermark("p", watermark).await?;
+                Ok(())
+            }
+            .scope_boxed()
+        })
+        .await
+        .unwrap();
+
+        let watermark = conn.committer_watermark("p").await.unwrap().unwrap();
+        assert_eq!(watermark.checkpoint_hi_inclusive, 10);
+    }
+}
@@ -0,0 +1,32 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+
+/// A temporary, local SQLite database file.
+pub struct TempDb {
+    path: PathBuf,
+
+    // Directory holding the database file, along with SQLite's write-ahead log and shared memory
+    // files.
+    //
+    // On drop the directory will be cleaned and its contents deleted.
+    _dir: tempfile::TempDir,
+}
+
+impl TempDb {
+    /// Create a new temporary database location. The database file itself is created when the
+    /// first connection to it is opened.
+    pub fn new() -> Result<Self> {
+        let dir = tempfile::TempDir::new()?;
+        let path = dir.path().join("sqlite.db");
+        Ok(Self { path, _dir: dir })
+    }
+
+    /// Path to the database file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
@@ -12,10 +12,12 @@ use anyhow::Result;
 use consensus_core::storage::{rocksdb_store::RocksDBStore, Store};
 use consensus_core::{BlockAPI, CommitAPI, CommitRange};
 use futures::{future::join_all, StreamExt};
+use std::num::NonZeroU64;
 use std::path::PathBuf;
 use std::{collections::BTreeMap, env, sync::Arc};
 use sui_config::genesis::Genesis;
 use sui_core::authority_client::AuthorityAPI;
+use sui_data_ingestion_core::{export_archive, unpack_archive};
 use sui_protocol_config::Chain;
 use sui_replay::{execute_replay_command, ReplayToolCommand};
 use sui_sdk::{rpc_types::SuiTransactionBlockResponseOptions, SuiClient, SuiClientBuilder};
@@ -196,6 +198,41 @@ pub enum ToolCommand {
         sequence_number: Option<CheckpointSequenceNumber>,
     },
 
+    /// Export a range of checkpoints from a remote store or a full node into a single compressed
+    /// archive. The integrity of checkpoints is checked as they are exported.
+    #[command(name = "export-checkpoints")]
+    ExportCheckpoints {
+        /// Checkpoint store to read from: an object store url, a full node url ending in `/rest`,
+        /// or `<full node url>|<object store url>` to fall back on the object store.
+        #[arg(long = "remote-store-url")]
+        remote_store_url: String,
+        /// Options for the object store, e.g. `aws_region=us-west-2`.
+        #[arg(long = "remote-store-option", value_parser = parse_key_value)]
+        remote_store_options: Vec<(String, String)>,
+        #[arg(long = "start")]
+        start: CheckpointSequenceNumber,
+        /// Last checkpoint to export, inclusive.
+        #[arg(long = "end")]
+        end: CheckpointSequenceNumber,
+        #[arg(long = "output")]
+        output: PathBuf,
+        /// Number of checkpoints to fetch concurrently.
+        #[arg(long = "concurrency", default_value_t = 10)]
+        concurrency: usize,
+    },
+
+    /// Check the integrity of a checkpoint archive and unpack it into a directory of `.chk` files
+    /// that can be ingested as a local checkpoint directory. The signatures of the checkpoints are
+    /// not verified, so only import archives from a trusted source. To ingest from the archive
+    /// directly, use `archive://<path>` as the remote store url of the reader.
+    #[command(name = "import-checkpoints")]
+    ImportCheckpoints {
+        #[arg(long = "archive")]
+        archive: PathBuf,
+        #[arg(long = "path")]
+        path: PathBuf,
+    },
+
     #[command(name = "anemo")]
     Anemo {
         #[command(next_help_heading = "foo", flatten)]
@@ -340,6 +377,13 @@ pub enum ToolCommand {
         /// downloaded, and (if --verify is provided) will be verified via committee signature.
         #[clap(long = "all-checkpoints")]
         all_checkpoints: bool,
+        /// Resume an interrupted restore to the same path: only the snapshot files that are
+        /// missing, or that do not match the digests in the snapshot MANIFEST, are downloaded.
+        #[clap(long = "resume")]
+        resume: bool,
+        /// Limit on the average snapshot download rate, in bytes per second.
+        #[clap(long = "max-download-bytes-per-sec")]
+        max_download_bytes_per_sec: Option<NonZeroU64>,
     },
 
     #[clap(name = "replay")]
@@ -382,6 +426,12 @@ pub enum ToolCommand {
     },
 }
 
+fn parse_key_value(s: &str) -> Result<(String, String), String> {
+    s.split_once('=')
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .ok_or_else(|| format!("Expected <key>=<value>, got {s}"))
+}
+
 async fn check_locked_object(
     sui_client: &Arc<SuiClient>,
     committee: Arc<BTreeMap<AuthorityPublicKeyBytes, u64>>,
@@ -655,6 +705,42 @@ impl ToolCommand {
                     println!("Content: {:?}\n", contents);
                 }
             }
+            ToolCommand::ExportCheckpoints {
+                remote_store_url,
+                remote_store_options,
+                start,
+                end,
+                output,
+                concurrency,
+            } => {
+                let summary = export_archive(
+                    remote_store_url,
+                    remote_store_options,
+                    start..=end,
+                    concurrency,
+                    &output,
+                )
+                .await?;
+                println!(
+                    "Exported checkpoints {}..={} to {}",
+                    summary.first_checkpoint,
+                    summary.last_checkpoint,
+                    output.display()
+                );
+                println!("First checkpoint digest: {}", summary.first_digest);
+                println!("Last checkpoint digest: {}", summary.last_digest);
+            }
+            ToolCommand::ImportCheckpoints { archive, path } => {
+                let summary = unpack_archive(&archive, &path)?;
+                println!(
+                    "Imported checkpoints {}..={} to {}",
+                    summary.first_checkpoint,
+                    summary.last_checkpoint,
+                    path.display()
+                );
+                println!("First checkpoint digest: {}", summary.first_digest);
+                println!("Last checkpoint digest: {}", summary.last_digest);
+            }
             ToolCommand::Anemo { args } => {
                 let config = crate::make_anemo_config();
                 anemo_cli::run(config, args).await
@@ -680,6 +766,8 @@ impl ToolCommand {
                 latest,
                 verbose,
                 all_checkpoints,
+                resume,
+                max_download_bytes_per_sec,
             } => {
                 if !verbose {
                     tracing_handle
@@ -812,6 +900,8 @@ impl ToolCommand {
                     network,
                     verify,
                     all_checkpoints,
+                    resume,
+                    max_download_bytes_per_sec,
                 )
                 .await?;
             }
@@ -9,7 +9,7 @@ use futures::future::AbortHandle;
 use itertools::Itertools;
 use std::collections::BTreeMap;
 use std::fmt::Write;
-use std::num::NonZeroUsize;
+use std::num::{NonZeroU64, NonZeroUsize};
 use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::sync::Arc;
@@ -51,6 +51,7 @@ use sui_core::authority::AuthorityStore;
 use sui_core::checkpoints::CheckpointStore;
 use sui_core::epoch::committee_store::CommitteeStore;
 use sui_core::storage::RocksDbStore;
+use sui_snapshot::download::DownloadOptions;
 use sui_snapshot::reader::StateSnapshotReaderV1;
 use sui_snapshot::setup_db_state;
 use sui_storage::object_store::util::{copy_file, exists, get_path};
@@ -825,6 +826,8 @@ pub async fn download_formal_snapshot(
     network: Chain,
     verify: SnapshotVerifyMode,
     all_checkpoints: bool,
+    resume: bool,
+    max_download_bytes_per_sec: Option<NonZeroU64>,
 ) -> Result<(), anyhow::Error> {
     let m = MultiProgress::new();
     m.println(format!(
@@ -832,7 +835,8 @@ pub async fn download_formal_snapshot(
         epoch, network, verify,
     ))?;
     let path = path.join("staging").to_path_buf();
-    if path.exists() {
+    // When resuming, keep the objects restored so far
+    if path.exists() && !resume {
         fs::remove_dir_all(path.clone())?;
     }
     let perpetual_db = Arc::new(AuthorityPerpetualTables::open(&path.join("store"), None));
@@ -860,7 +864,7 @@ pub async fn download_formal_snapshot(
     let (_abort_handle, abort_registration) = AbortHandle::new_pair();
     let perpetual_db_clone = perpetual_db.clone();
     let snapshot_dir = path.parent().unwrap().join("snapshot");
-    if snapshot_dir.exists() {
+    if snapshot_dir.exists() && !resume {
         fs::remove_dir_all(snapshot_dir.clone())?;
     }
     let snapshot_dir_clone = snapshot_dir.clone();
@@ -876,13 +880,17 @@ pub async fn download_formal_snapshot(
             directory: Some(snapshot_dir_clone.to_path_buf()),
             ..Default::default()
         };
-        let mut reader = StateSnapshotReaderV1::new(
+        let options = DownloadOptions {
+            max_bytes_per_sec: max_download_bytes_per_sec,
+            ..DownloadOptions::new(NonZeroUsize::new(num_parallel_downloads).unwrap())
+        };
+        let mut reader = StateSnapshotReaderV1::new_with_options(
             epoch,
             &snapshot_store_config,
             &local_store_config,
-            NonZeroUsize::new(num_parallel_downloads).unwrap(),
+            options,
             m_clone,
-            false, // skip_reset_local_store
+            resume, // skip_reset_local_store
         )
         .await
         .unwrap_or_else(|err| panic!("Failed to create reader: {}", err));
@@ -0,0 +1,1011 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! A pool of gas coins for submitting many transactions concurrently from one address.
+//!
+//! Selecting gas through the [`DataReader`] for every transaction lets concurrent transactions
+//! pick the same coin, or a stale version of it, and lock or equivocate it. Instead, the pool
+//! splits a number of coins off a primary coin and leases each of them to one transaction at a
+//! time. A coin's reference and balance are tracked from the effects of the transactions it pays
+//! for, so it can be used again right away, without waiting for its new version to be indexed.
+//! Coins whose balance falls below a threshold are periodically merged back into the primary coin,
+//! which also pays for the transactions maintaining the pool.
+//!
+//! Leased coins must only pay for gas: the pool does not know about balance taken from the gas coin
+//! by the transaction's commands.
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use anyhow::{bail, ensure};
+use async_trait::async_trait;
+use sui_json_rpc_types::SuiObjectDataOptions;
+use sui_types::base_types::{ObjectID, ObjectRef, SuiAddress};
+use sui_types::effects::{TransactionEffects, TransactionEffectsAPI};
+use sui_types::gas_coin::GasCoin;
+use sui_types::object::Owner;
+use sui_types::programmable_transaction_builder::ProgrammableTransactionBuilder;
+use sui_types::transaction::{Argument, Command, ObjectArg, TransactionData, TransactionKind};
+use tokio::sync::Notify;
+use tracing::warn;
+
+use crate::{gas_coin_value, DataReader};
+
+/// Maximum number of coins merged by one transaction, including its gas coins.
+const MAX_MERGED_COINS: usize = 256;
+
+/// Signs and executes the transactions that split and merge the pool's coins.
+#[async_trait]
+pub trait TransactionExecutor {
+    /// Sign `tx_data` as the pool's owner, execute it and return its effects once it is final.
+    async fn execute(&self, tx_data: TransactionData) -> Result<TransactionEffects, anyhow::Error>;
+}
+
+#[derive(Clone, Debug)]
+pub struct GasPoolConfig {
+    /// Number of coins the pool keeps to lease.
+    pub num_coins: usize,
+    /// Balance of each coin split off the primary coin.
+    pub coin_balance: u64,
+    /// Coins with a lower balance are merged back into the primary coin.
+    pub dust_threshold: u64,
+    /// Gas budget of the transactions splitting and merging coins.
+    pub maintenance_gas_budget: u64,
+}
+
+impl GasPoolConfig {
+    pub fn new(num_coins: usize, coin_balance: u64) -> Self {
+        Self {
+            num_coins,
+            coin_balance,
+            dust_threshold: coin_balance / 10,
+            maintenance_gas_budget: 500_000_000,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct PooledCoin {
+    object_ref: ObjectRef,
+    balance: u64,
+}
+
+impl PooledCoin {
+    /// Update the coin after it paid for a transaction with these effects.
+    fn charge(&mut self, effects: &TransactionEffects) {
+        self.object_ref = effects.gas_object().0;
+        let net_gas_usage = effects.gas_cost_summary().net_gas_usage();
+        self.balance = self.balance.saturating_add_signed(-net_gas_usage);
+    }
+}
+
+#[derive(Default)]
+struct PoolState {
+    /// The coin the pool's coins are split from and merged into, unless maintenance is running.
+    primary: Option<PooledCoin>,
+    available: Vec<PooledCoin>,
+    leased: usize,
+    /// Coins whose reference or balance is unknown, to be read again.
+    stale: Vec<ObjectID>,
+}
+
+struct Shared {
+    state: Mutex<PoolState>,
+    /// Notified when a coin is returned to the pool.
+    returned: Notify,
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, PoolState> {
+        self.state.lock().unwrap()
+    }
+
+    fn give_back(&self, coin: Option<PooledCoin>, stale: Option<ObjectID>) {
+        {
+            let mut state = self.lock();
+            state.leased -= 1;
+            state.available.extend(coin);
+            state.stale.extend(stale);
+        }
+        self.returned.notify_waiters();
+    }
+}
+
+pub struct GasPool {
+    owner: SuiAddress,
+    config: GasPoolConfig,
+    reader: Arc<dyn DataReader + Sync + Send>,
+    executor: Arc<dyn TransactionExecutor + Sync + Send>,
+    shared: Arc<Shared>,
+}
+
+impl GasPool {
+    /// Create a pool of the gas coins of `owner`. All of its coins are merged into the largest
+    /// one, the primary coin, and `config.num_coins` coins are split off it.
+    pub async fn new(
+        reader: Arc<dyn DataReader + Sync + Send>,
+        executor: Arc<dyn TransactionExecutor + Sync + Send>,
+        owner: SuiAddress,
+        config: GasPoolConfig,
+    ) -> Result<Self, anyhow::Error> {
+        ensure!(
+            config.num_coins > 0,
+            "The gas pool must have at least one coin"
+        );
+        let pool = Self {
+            owner,
+            config,
+            reader,
+            executor,
+            shared: Arc::new(Shared {
+                state: Mutex::new(PoolState::default()),
+                returned: Notify::new(),
+            }),
+        };
+
+        let mut coins = vec![];
+        for info in pool
+            .reader
+            .get_owned_objects(owner, GasCoin::type_())
+            .await?
+        {
+            coins.extend(pool.fetch_coin(info.object_id).await?);
+        }
+        coins.sort_by_key(|coin| std::cmp::Reverse(coin.balance));
+        coins.truncate(MAX_MERGED_COINS);
+        ensure!(!coins.is_empty(), "Address {owner} has no gas coins");
+
+        let total: u64 = coins.iter().map(|coin| coin.balance).sum();
+        let required = pool.config.num_coins as u64 * pool.config.coin_balance
+            + pool.config.maintenance_gas_budget;
+        ensure!(
+            total >= required,
+            "The gas coins of {owner} have a balance of {total}, but the gas pool needs {required}"
+        );
+
+        // Paying with all the coins merges them into the first one.
+        let gas_payment = coins.iter().map(|coin| coin.object_ref).collect();
+        let primary = PooledCoin {
+            balance: total,
+            ..coins[0]
+        };
+        let (primary, created) = pool
+            .split(primary, gas_payment, pool.config.num_coins)
+            .await?;
+        let mut state = pool.shared.lock();
+        state.primary = Some(primary);
+        state.available = created;
+        drop(state);
+        Ok(pool)
+    }
+
+    pub fn owner(&self) -> SuiAddress {
+        self.owner
+    }
+
+    /// Number of coins that can be leased right away.
+    pub fn num_available(&self) -> usize {
+        self.shared.lock().available.len()
+    }
+
+    /// Lease a coin with a balance of at least `gas_budget`, waiting for one to be returned if
+    /// they are all leased.
+    pub async fn lease(&self, gas_budget: u64) -> Result<GasLease, anyhow::Error> {
+        loop {
+            // Created before checking the pool, so that a coin returned in between is not missed.
+            let returned = self.shared.returned.notified();
+            let refresh = {
+                let mut state = self.shared.lock();
+                if let Some(ix) = state
+                    .available
+                    .iter()
+                    .position(|coin| coin.balance >= gas_budget)
+                {
+                    let coin = state.available.swap_remove(ix);
+                    state.leased += 1;
+                    return Ok(GasLease {
+                        coin: Some(coin),
+                        owner: self.owner,
+                        shared: self.shared.clone(),
+                    });
+                }
+                if state.leased == 0 && state.stale.is_empty() {
+                    bail!(
+                        "No coin in the gas pool of {} has a balance of at least {gas_budget}",
+                        self.owner
+                    );
+                }
+                !state.stale.is_empty()
+            };
+            if refresh {
+                self.refresh_stale().await?;
+            } else {
+                returned.await;
+            }
+        }
+    }
+
+    /// Run [`Self::maintain`] every `interval`, forever.
+    pub async fn run_maintenance(&self, interval: Duration) {
+        let mut interval = tokio::time::interval(interval);
+        loop {
+            interval.tick().await;
+            if let Err(e) = self.maintain().await {
+                warn!("Gas pool maintenance failed: {e:?}");
+            }
+        }
+    }
+
+    /// Read the coins whose state is unknown again, merge the coins with a balance below the
+    /// dust threshold into the primary coin, and split coins off it until the pool has
+    /// `num_coins` coins again.
+    pub async fn maintain(&self) -> Result<(), anyhow::Error> {
+        self.refresh_stale().await?;
+
+        // Another maintenance is running if the primary coin was taken.
+        let Some(primary) = self.shared.lock().primary.take() else {
+            return Ok(());
+        };
+        let result = self.merge_and_split(primary).await;
+        let primary = match &result {
+            Ok(primary) => *primary,
+            // The primary coin may have been used by a transaction whose effects are unknown.
+            Err(_) => self
+                .fetch_coin(primary.object_ref.0)
+                .await
+                .ok()
+                .flatten()
+                .unwrap_or(primary),
+        };
+        self.shared.lock().primary = Some(primary);
+        result.map(|_| ())
+    }
+
+    async fn merge_and_split(&self, mut primary: PooledCoin) -> Result<PooledCoin, anyhow::Error> {
+        // Coins beyond what one transaction can merge are left for the next maintenance.
+        let dust: Vec<_> = {
+            let mut state = self.shared.lock();
+            let mut dust = vec![];
+            state.available.retain(|coin| {
+                let is_dust =
+                    coin.balance < self.config.dust_threshold && dust.len() < MAX_MERGED_COINS - 1;
+                if is_dust {
+                    dust.push(*coin);
+                }
+                !is_dust
+            });
+            dust
+        };
+        if !dust.is_empty() {
+            primary = self.merge(primary, &dust).await?;
+        }
+
+        let pooled = {
+            let state = self.shared.lock();
+            state.available.len() + state.leased
+        };
+        let needed = self.config.num_coins.saturating_sub(pooled);
+        let affordable = primary
+            .balance
+            .saturating_sub(self.config.maintenance_gas_budget)
+            / self.config.coin_balance.max(1);
+        let count = needed.min(affordable as usize);
+        if count < needed {
+            warn!(
+                "The primary gas coin of {} can only fund {count} of the {needed} missing pool coins",
+                self.owner
+            );
+        }
+        if count > 0 {
+            let (new_primary, created) =
+                self.split(primary, vec![primary.object_ref], count).await?;
+            primary = new_primary;
+            self.shared.lock().available.extend(created);
+            self.shared.returned.notify_waiters();
+        }
+        Ok(primary)
+    }
+
+    /// Merge the `dust` coins into `primary`. Coins that could not be merged go back to the pool.
+    async fn merge(
+        &self,
+        mut primary: PooledCoin,
+        dust: &[PooledCoin],
+    ) -> Result<PooledCoin, anyhow::Error> {
+        let mut builder = ProgrammableTransactionBuilder::new();
+        let coins = dust
+            .iter()
+            .map(|coin| builder.obj(ObjectArg::ImmOrOwnedObject(coin.object_ref)))
+            .collect::<Result<_, _>>()?;
+        builder.command(Command::MergeCoins(Argument::GasCoin, coins));
+        let result = self
+            .execute(
+                vec![primary.object_ref],
+                TransactionKind::programmable(builder.finish()),
+            )
+            .await;
+
+        let effects = match result {
+            Ok(effects) => effects,
+            Err(e) => {
+                self.shared
+                    .lock()
+                    .stale
+                    .extend(dust.iter().map(|coin| coin.object_ref.0));
+                return Err(e);
+            }
+        };
+        primary.charge(&effects);
+        if effects.status().is_ok() {
+            primary.balance += dust.iter().map(|coin| coin.balance).sum::<u64>();
+            return Ok(primary);
+        }
+
+        // The merged coins were not changed, apart from their version.
+        let mutated = mutated_refs(&effects);
+        self.shared.lock().available.extend(dust.iter().map(|coin| {
+            PooledCoin {
+                object_ref: mutated
+                    .get(&coin.object_ref.0)
+                    .copied()
+                    .unwrap_or(coin.object_ref),
+                ..*coin
+            }
+        }));
+        bail!(
+            "Failed to merge gas coins into {}: {:?}",
+            primary.object_ref.0,
+            effects.status()
+        )
+    }
+
+    /// Split `count` coins of `coin_balance` off `primary`, paying with `gas_payment`.
+    async fn split(
+        &self,
+        mut primary: PooledCoin,
+        gas_payment: Vec<ObjectRef>,
+        count: usize,
+    ) -> Result<(PooledCoin, Vec<PooledCoin>), anyhow::Error> {
+        let mut builder = ProgrammableTransactionBuilder::new();
+        builder.pay_sui(
+            vec![self.owner; count],
+            vec![self.config.coin_balance; count],
+        )?;
+        let effects = self
+            .execute(gas_payment, TransactionKind::programmable(builder.finish()))
+            .await?;
+        primary.charge(&effects);
+        ensure!(
+            effects.status().is_ok(),
+            "Failed to split gas coins off {}: {:?}",
+            primary.object_ref.0,
+            effects.status()
+        );
+
+        primary.balance -= count as u64 * self.config.coin_balance;
+        let created = effects
+            .created()
+            .into_iter()
+            .filter(|(_, owner)| *owner == Owner::AddressOwner(self.owner))
+            .map(|(object_ref, _)| PooledCoin {
+                object_ref,
+                balance: self.config.coin_balance,
+            })
+            .collect();
+        Ok((primary, created))
+    }
+
+    async fn execute(
+        &self,
+        gas_payment: Vec<ObjectRef>,
+        kind: TransactionKind,
+    ) -> Result<TransactionEffects, anyhow::Error> {
+        let gas_price = self.reader.get_reference_gas_price().await?;
+        let tx_data = TransactionData::new_with_gas_coins(
+            kind,
+            self.owner,
+            gas_payment,
+            self.config.maintenance_gas_budget,
+            gas_price,
+        );
+        self.executor.execute(tx_data).await
+    }
+
+    async fn refresh_stale(&self) -> Result<(), anyhow::Error> {
+        let stale = std::mem::take(&mut self.shared.lock().stale);
+        if stale.is_empty() {
+            return Ok(());
+        }
+        let mut refreshed = vec![];
+        for (ix, object_id) in stale.iter().enumerate() {
+            match self.fetch_coin(*object_id).await {
+                Ok(coin) => refreshed.extend(coin),
+                Err(e) => {
+                    self.shared.lock().stale.extend(&stale[ix..]);
+                    self.shared.lock().available.extend(refreshed);
+                    return Err(e);
+                }
+            }
+        }
+        self.shared.lock().available.extend(refreshed);
+        self.shared.returned.notify_waiters();
+        Ok(())
+    }
+
+    /// Read the gas coin `object_id`, if it still exists and belongs to the pool's owner.
+    async fn fetch_coin(&self, object_id: ObjectID) -> Result<Option<PooledCoin>, anyhow::Error> {
+        let response = self
+            .reader
+            .get_object_with_options(
+                object_id,
+                SuiObjectDataOptions::new().with_bcs().with_owner(),
+            )
+            .await?;
+        let Ok(obj) = response.object() else {
+            return Ok(None);
+        };
+        if obj.owner != Some(Owner::AddressOwner(self.owner)) {
+            return Ok(None);
+        }
+        Ok(Some(PooledCoin {
+            object_ref: obj.object_ref(),
+            balance: gas_coin_value(obj)?,
+        }))
+    }
+}
+
+/// A gas coin leased from a [`GasPool`], to pay for one transaction at a time.
+///
+/// Return it with [`Self::release`] once the transaction it paid for was executed, or with
+/// [`Self::release_unused`] if it was not submitted. A lease that is dropped instead returns its
+/// coin to be read again, as its state is unknown.
+pub struct GasLease {
+    coin: Option<PooledCoin>,
+    owner: SuiAddress,
+    shared: Arc<Shared>,
+}
+
+impl GasLease {
+    pub fn object_ref(&self) -> ObjectRef {
+        self.coin().object_ref
+    }
+
+    pub fn balance(&self) -> u64 {
+        self.coin().balance
+    }
+
+    /// Transaction data for `kind`, paid for with the leased coin. The pool's owner sponsors the
+    /// transaction if `sender` is another address.
+    pub fn transaction_data(
+        &self,
+        sender: SuiAddress,
+        kind: TransactionKind,
+        gas_budget: u64,
+        gas_price: u64,
+    ) -> TransactionData {
+        TransactionData::new_with_gas_coins_allow_sponsor(
+            kind,
+            sender,
+            vec![self.object_ref()],
+            gas_budget,
+            gas_price,
+            self.owner,
+        )
+    }
+
+    /// Return the coin to the pool, updated from the effects of the transaction it paid for.
+    pub fn release(mut self, effects: &TransactionEffects) {
+        let mut coin = self.coin.take().unwrap();
+        let (gas_ref, gas_owner) = effects.gas_object();
+        if gas_ref.0 != coin.object_ref.0 {
+            // Not the transaction this coin paid for.
+            self.shared.give_back(None, Some(coin.object_ref.0));
+        } else if gas_owner != Owner::AddressOwner(self.owner) {
+            // The coin was transferred away.
+            self.shared.give_back(None, None);
+        } else {
+            coin.charge(effects);
+            self.shared.give_back(Some(coin), None);
+        }
+    }
+
+    /// Return the coin to the pool, unchanged as no transaction was executed with it.
+    pub fn release_unused(mut self) {
+        let coin = self.coin.take();
+        self.shared.give_back(coin, None);
+    }
+
+    fn coin(&self) -> &PooledCoin {
+        self.coin.as_ref().unwrap()
+    }
+}
+
+impl Drop for GasLease {
+    fn drop(&mut self) {
+        if let Some(coin) = self.coin.take() {
+            self.shared.give_back(None, Some(coin.object_ref.0));
+        }
+    }
+}
+
+fn mutated_refs(effects: &TransactionEffects) -> BTreeMap<ObjectID, ObjectRef> {
+    effects
+        .mutated()
+        .into_iter()
+        .map(|(object_ref, _)| (object_ref.0, object_ref))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use move_core_types::language_storage::StructTag;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use sui_json_rpc_types::SuiObjectResponse;
+    use sui_types::base_types::{random_object_ref, ObjectInfo, SequenceNumber};
+    use sui_types::digests::{ObjectDigest, TransactionDigest};
+    use sui_types::execution_status::{ExecutionFailureStatus, ExecutionStatus};
+    use sui_types::gas::GasCostSummary;
+    use sui_types::object::{MoveObject, Object, ObjectRead};
+    use sui_types::transaction::{CallArg, TransactionDataAPI};
+
+    /// Gas charged by the mock chain for every transaction.
+    const GAS_COST: u64 = 1_000;
+    const SUI: u64 = 1_000_000_000;
+
+    /// Gas coins on a mock chain, that only executes the transactions splitting and merging them.
+    #[derive(Default)]
+    struct MockChain {
+        objects: Mutex<BTreeMap<ObjectID, Object>>,
+        /// Transactions merging coins fail while set.
+        fail_merges: AtomicBool,
+        executed: Mutex<Vec<TransactionData>>,
+    }
+
+    impl MockChain {
+        fn new(owner: SuiAddress, balances: &[u64]) -> Arc<Self> {
+            let chain = Self::default();
+            for balance in balances {
+                let coin = Object::new_gas_with_balance_and_owner_for_testing(*balance, owner);
+                chain.objects.lock().unwrap().insert(coin.id(), coin);
+            }
+            Arc::new(chain)
+        }
+
+        /// References and balances of the coins of `owner`.
+        fn coins(&self, owner: SuiAddress) -> BTreeMap<ObjectRef, u64> {
+            self.objects
+                .lock()
+                .unwrap()
+                .values()
+                .filter(|object| object.owner == Owner::AddressOwner(owner))
+                .map(|object| (object.compute_object_reference(), balance(object)))
+                .collect()
+        }
+
+        /// Execute a transaction that pays with `gas`, is charged `gas_cost`, and runs `commands`.
+        fn apply(
+            &self,
+            sender: SuiAddress,
+            gas: &[ObjectRef],
+            gas_cost: u64,
+            inputs: &[CallArg],
+            commands: &[Command],
+        ) -> Result<TransactionEffects, anyhow::Error> {
+            let mut objects = self.objects.lock().unwrap();
+            let input_refs: Vec<_> = inputs
+                .iter()
+                .filter_map(|input| match input {
+                    CallArg::Object(ObjectArg::ImmOrOwnedObject(object_ref)) => Some(*object_ref),
+                    _ => None,
+                })
+                .collect();
+            for object_ref in gas.iter().chain(&input_refs) {
+                ensure!(
+                    objects
+                        .get(&object_ref.0)
+                        .map(|object| object.compute_object_reference())
+                        == Some(*object_ref),
+                    "Object {} is not available at version {}",
+                    object_ref.0,
+                    object_ref.1
+                );
+            }
+            let modified_at_versions: Vec<_> = gas
+                .iter()
+                .chain(&input_refs)
+                .map(|(id, version, _)| (*id, *version))
+                .collect();
+            let version =
+                SequenceNumber::lamport_increment(modified_at_versions.iter().map(|(_, v)| *v));
+            let owner = Owner::AddressOwner(sender);
+
+            // Paying with several coins merges them into the first one.
+            let mut gas_balance = gas.iter().map(|r| balance(&objects[&r.0])).sum::<u64>();
+            gas_balance = gas_balance
+                .checked_sub(gas_cost)
+                .ok_or_else(|| anyhow::anyhow!("Insufficient gas"))?;
+            let mut deleted: Vec<_> = gas[1..].to_vec();
+
+            let mut status = ExecutionStatus::Success;
+            let mut created = vec![];
+            let mut mutated = vec![];
+            for command in commands {
+                match command {
+                    Command::SplitCoins(Argument::GasCoin, amounts) => {
+                        for amount in amounts {
+                            let Argument::Input(ix) = amount else {
+                                bail!("Unexpected split amount {amount:?}");
+                            };
+                            let CallArg::Pure(bytes) = &inputs[*ix as usize] else {
+                                bail!("Unexpected split amount {amount:?}");
+                            };
+                            let amount: u64 = bcs::from_bytes(bytes)?;
+                            gas_balance -= amount;
+                            created.push(new_coin(ObjectID::random(), version, amount, owner));
+                        }
+                    }
+                    Command::MergeCoins(Argument::GasCoin, _)
+                        if self.fail_merges.load(Ordering::SeqCst) =>
+                    {
+                        // The coins are left as they are, but the transaction bumps their version.
+                        status = ExecutionStatus::new_failure(
+                            ExecutionFailureStatus::CoinBalanceOverflow,
+                            Some(0),
+                        );
+                        created.clear();
+                        mutated = input_refs
+                            .iter()
+                            .map(|(id, _, _)| new_coin(*id, version, balance(&objects[id]), owner))
+                            .collect();
+                        break;
+                    }
+                    Command::MergeCoins(Argument::GasCoin, _) => {
+                        for object_ref in &input_refs {
+                            gas_balance += balance(&objects[&object_ref.0]);
+                            deleted.push(*object_ref);
+                        }
+                    }
+                    Command::TransferObjects(_, _) => {}
+                    command => bail!("Unexpected command {command:?}"),
+                }
+            }
+
+            let gas_coin = new_coin(gas[0].0, version, gas_balance, owner);
+            let gas_object = (gas_coin.compute_object_reference(), owner);
+            let deleted: Vec<_> = deleted
+                .iter()
+                .map(|(id, _, _)| (*id, version, ObjectDigest::OBJECT_DIGEST_DELETED))
+                .collect();
+            for (id, _, _) in &deleted {
+                objects.remove(id);
+            }
+            let refs = |coins: &[Object]| -> Vec<_> {
+                coins
+                    .iter()
+                    .map(|coin| (coin.compute_object_reference(), owner))
+                    .collect()
+            };
+            let (created_refs, mutated_refs) = (refs(&created), refs(&mutated));
+            for coin in created.into_iter().chain(mutated).chain([gas_coin]) {
+                objects.insert(coin.id(), coin);
+            }
+
+            Ok(TransactionEffects::new_from_execution_v1(
+                status,
+                0,
+                GasCostSummary::new(gas_cost, 0, 0, 0),
+                modified_at_versions,
+                vec![],
+                TransactionDigest::random(),
+                created_refs,
+                mutated_refs.into_iter().chain([gas_object]).collect(),
+                vec![],
+                deleted,
+                vec![],
+                vec![],
+                gas_object,
+                None,
+                vec![],
+            ))
+        }
+    }
+
+    #[async_trait]
+    impl DataReader for MockChain {
+        async fn get_owned_objects(
+            &self,
+            address: SuiAddress,
+            _object_type: StructTag,
+        ) -> Result<Vec<ObjectInfo>, anyhow::Error> {
+            Ok(self
+                .objects
+                .lock()
+                .unwrap()
+                .values()
+                .filter(|object| object.owner == Owner::AddressOwner(address))
+                .map(ObjectInfo::from_object)
+                .collect())
+        }
+
+        async fn get_object_with_options(
+            &self,
+            object_id: ObjectID,
+            options: SuiObjectDataOptions,
+        ) -> Result<SuiObjectResponse, anyhow::Error> {
+            let read = match self.objects.lock().unwrap().get(&object_id) {
+                Some(object) => ObjectRead::Exists(
+                    object.compute_object_reference(),
+                    object.clone(),
+                    Some(GasCoin::layout()),
+                ),
+                None => ObjectRead::NotExists(object_id),
+            };
+            (read, options).try_into()
+        }
+
+        async fn get_reference_gas_price(&self) -> Result<u64, anyhow::Error> {
+            Ok(1_000)
+        }
+    }
+
+    #[async_trait]
+    impl TransactionExecutor for MockChain {
+        async fn execute(
+            &self,
+            tx_data: TransactionData,
+        ) -> Result<TransactionEffects, anyhow::Error> {
+            self.executed.lock().unwrap().push(tx_data.clone());
+            let TransactionKind::ProgrammableTransaction(pt) = tx_data.kind() else {
+                bail!("Unexpected transaction kind");
+            };
+            self.apply(
+                tx_data.sender(),
+                tx_data.gas(),
+                GAS_COST,
+                &pt.inputs,
+                &pt.commands,
+            )
+        }
+    }
+
+    fn new_coin(id: ObjectID, version: SequenceNumber, value: u64, owner: Owner) -> Object {
+        Object::new_move(
+            MoveObject::new_gas_coin(version, id, value),
+            owner,
+            TransactionDigest::genesis_marker(),
+        )
+    }
+
+    fn balance(object: &Object) -> u64 {
+        GasCoin::try_from(object).unwrap().value()
+    }
+
+    async fn new_pool(chain: &Arc<MockChain>, owner: SuiAddress, config: GasPoolConfig) -> GasPool {
+        GasPool::new(chain.clone(), chain.clone(), owner, config)
+            .await
+            .unwrap()
+    }
+
+    fn available(pool: &GasPool) -> BTreeMap<ObjectRef, u64> {
+        pool.shared
+            .lock()
+            .available
+            .iter()
+            .map(|coin| (coin.object_ref, coin.balance))
+            .collect()
+    }
+
+    /// The pool's primary and available coins, as the chain knows them.
+    fn assert_in_sync(pool: &GasPool, chain: &MockChain) {
+        let mut coins = available(pool);
+        let primary = pool.shared.lock().primary.unwrap();
+        coins.insert(primary.object_ref, primary.balance);
+        assert_eq!(coins, chain.coins(pool.owner()));
+    }
+
+    #[tokio::test]
+    async fn test_new_merges_and_splits() {
+        let owner = SuiAddress::random_for_testing_only();
+        let chain = MockChain::new(owner, &[10 * SUI, 3 * SUI, 1_000]);
+        let pool = new_pool(&chain, owner, GasPoolConfig::new(4, SUI)).await;
+
+        // One transaction, paying with all the coins, splits the pool's coins
+        let executed = chain.executed.lock().unwrap().clone();
+        assert_eq!(executed.len(), 1);
+        assert_eq!(executed[0].gas().len(), 3);
+
+        assert_eq!(pool.num_available(), 4);
+        assert!(available(&pool).values().all(|balance| *balance == SUI));
+        let primary = pool.shared.lock().primary.unwrap();
+        assert_eq!(primary.balance, 13 * SUI + 1_000 - 4 * SUI - GAS_COST);
+        assert_in_sync(&pool, &chain);
+    }
+
+    #[tokio::test]
+    async fn test_new_insufficient_balance() {
+        let owner = SuiAddress::random_for_testing_only();
+        let chain = MockChain::new(owner, &[SUI, SUI]);
+        assert!(GasPool::new(
+            chain.clone(),
+            chain.clone(),
+            owner,
+            GasPoolConfig::new(2, SUI)
+        )
+        .await
+        .is_err());
+        assert!(chain.executed.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_lease_waits_for_returned_coin() {
+        let owner = SuiAddress::random_for_testing_only();
+        let chain = MockChain::new(owner, &[10 * SUI]);
+        let pool = new_pool(&chain, owner, GasPoolConfig::new(1, SUI)).await;
+
+        let first = pool.lease(1_000).await.unwrap();
+        let coin = first.object_ref();
+        let second = pool.lease(1_000);
+        tokio::pin!(second);
+        assert!(
+            tokio::time::timeout(Duration::from_millis(100), &mut second)
+                .await
+                .is_err()
+        );
+
+        first.release_unused();
+        let second = second.await.unwrap();
+        assert_eq!(second.object_ref(), coin);
+
+        // A budget no coin can cover fails once no coin is leased
+        second.release_unused();
+        assert!(pool.lease(2 * SUI).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_maintain_merges_dust_and_refills() {
+        let owner = SuiAddress::random_for_testing_only();
+        let chain = MockChain::new(owner, &[10 * SUI]);
+        let config = GasPoolConfig::new(2, SUI);
+        let dust_threshold = config.dust_threshold;
+        let pool = new_pool(&chain, owner, config).await;
+
+        // A transaction leaves the leased coin with dust
+        let lease = pool.lease(1_000).await.unwrap();
+        let dust = lease.object_ref();
+        let effects = chain.apply(owner, &[dust], SUI - 1_000, &[], &[]).unwrap();
+        lease.release(&effects);
+        assert_in_sync(&pool, &chain);
+
+        // A lease dropped without being released is read again
+        let lease = pool.lease(1_000).await.unwrap();
+        let dropped = lease.object_ref();
+        drop(lease);
+        assert_eq!(pool.num_available(), 1);
+
+        pool.maintain().await.unwrap();
+        assert_eq!(pool.num_available(), 2);
+        assert!(available(&pool)
+            .iter()
+            .all(|((id, _, _), balance)| *id != dust.0 && *balance >= dust_threshold));
+        assert!(available(&pool).contains_key(&dropped));
+        assert_in_sync(&pool, &chain);
+        // Merging the dust, then splitting a coin to replace it
+        assert_eq!(chain.executed.lock().unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_failed_merge_requeues_coins() {
+        let owner = SuiAddress::random_for_testing_only();
+        let chain = MockChain::new(owner, &[10 * SUI]);
+        let pool = new_pool(&chain, owner, GasPoolConfig::new(2, SUI)).await;
+
+        let lease = pool.lease(1_000).await.unwrap();
+        let dust = lease.object_ref();
+        let effects = chain.apply(owner, &[dust], SUI - 1_000, &[], &[]).unwrap();
+        lease.release(&effects);
+
+        chain.fail_merges.store(true, Ordering::SeqCst);
+        assert!(pool.maintain().await.is_err());
+
+        // The dust coin is back in the pool, at its new version, and the primary coin was charged
+        assert_eq!(pool.num_available(), 2);
+        assert!(available(&pool).keys().any(|(id, _, _)| *id == dust.0));
+        assert!(pool.shared.lock().stale.is_empty());
+        assert_in_sync(&pool, &chain);
+
+        // It is merged once merges succeed again
+        chain.fail_merges.store(false, Ordering::SeqCst);
+        pool.maintain().await.unwrap();
+        assert!(available(&pool).keys().all(|(id, _, _)| *id != dust.0));
+        assert_eq!(pool.num_available(), 2);
+        assert_in_sync(&pool, &chain);
+    }
+
+    fn pool_with(coins: &[u64]) -> Arc<Shared> {
+        let available = coins
+            .iter()
+            .map(|balance| PooledCoin {
+                object_ref: random_object_ref(),
+                balance: *balance,
+            })
+            .collect();
+        Arc::new(Shared {
+            state: Mutex::new(PoolState {
+                available,
+                leased: 1,
+                ..Default::default()
+            }),
+            returned: Notify::new(),
+        })
+    }
+
+    fn lease(shared: &Arc<Shared>) -> GasLease {
+        let mut state = shared.lock();
+        let coin = state.available.pop();
+        GasLease {
+            coin,
+            owner: SuiAddress::ZERO,
+            shared: shared.clone(),
+        }
+    }
+
+    #[test]
+    fn test_release_unused() {
+        let shared = pool_with(&[100]);
+        let lease = lease(&shared);
+        let coin = *lease.coin();
+        lease.release_unused();
+
+        let state = shared.lock();
+        assert_eq!(state.available, vec![coin]);
+        assert_eq!(state.leased, 0);
+        assert!(state.stale.is_empty());
+    }
+
+    #[test]
+    fn test_dropped_lease_is_stale() {
+        let shared = pool_with(&[100]);
+        let lease = lease(&shared);
+        let id = lease.object_ref().0;
+        drop(lease);
+
+        let state = shared.lock();
+        assert!(state.available.is_empty());
+        assert_eq!(state.leased, 0);
+        assert_eq!(state.stale, vec![id]);
+    }
+
+    #[test]
+    fn test_release_with_effects() {
+        let shared = pool_with(&[1_000]);
+        let lease = lease(&shared);
+        let (id, version, _) = lease.object_ref();
+
+        let gas_ref = (id, version.next(), ObjectDigest::random());
+        let effects = TransactionEffects::new_from_execution_v1(
+            ExecutionStatus::Success,
+            0,
+            GasCostSummary::new(300, 200, 100, 0),
+            vec![(id, version)],
+            vec![],
+            TransactionDigest::random(),
+            vec![],
+            vec![(gas_ref, Owner::AddressOwner(SuiAddress::ZERO))],
+            vec![],
+            vec![],
+            vec![],
+            vec![],
+            (gas_ref, Owner::AddressOwner(SuiAddress::ZERO)),
+            None,
+            vec![],
+        );
+        lease.release(&effects);
+
+        let state = shared.lock();
+        assert_eq!(state.available.len(), 1);
+        assert_eq!(state.available[0].balance, 600);
+        assert_eq!(state.available[0].object_ref, gas_ref);
+    }
+}
@@ -17,8 +17,8 @@ use move_core_types::identifier::Identifier;
 use move_core_types::language_storage::{StructTag, TypeTag};
 use sui_json::{is_receiving_argument, resolve_move_function_args, ResolvedCallArg, SuiJsonValue};
 use sui_json_rpc_types::{
-    RPCTransactionRequestParams, SuiData, SuiObjectDataOptions, SuiObjectResponse, SuiRawData,
-    SuiTypeTag,
+    RPCTransactionRequestParams, SuiData, SuiObjectData, SuiObjectDataOptions, SuiObjectResponse,
+    SuiRawData, SuiTypeTag,
 };
 use sui_protocol_config::ProtocolConfig;
 use sui_types::base_types::{ObjectID, ObjectInfo, ObjectRef, ObjectType, SuiAddress};
@@ -34,6 +34,9 @@ use sui_types::transaction::{
 };
 use sui_types::{coin, fp_ensure, SUI_FRAMEWORK_PACKAGE_ID, SUI_SYSTEM_PACKAGE_ID};
 
+pub mod gas_pool;
+pub mod offline;
+
 #[async_trait]
 pub trait DataReader {
     async fn get_owned_objects(
@@ -81,15 +84,7 @@ impl TransactionBuilder {
                     .get_object_with_options(obj.object_id, SuiObjectDataOptions::new().with_bcs())
                     .await?;
                 let obj = response.object()?;
-                let gas: GasCoin = bcs::from_bytes(
-                    &obj.bcs
-                        .as_ref()
-                        .ok_or_else(|| anyhow!("bcs field is unexpectedly empty"))?
-                        .try_as_move()
-                        .ok_or_else(|| anyhow!("Cannot parse move object to gas object"))?
-                        .bcs_bytes,
-                )?;
-                if !input_objects.contains(&obj.object_id) && gas.value() >= gas_budget {
+                if !input_objects.contains(&obj.object_id) && gas_coin_value(obj)? >= gas_budget {
                     return Ok(obj.object_ref());
                 }
             }
@@ -1017,6 +1012,23 @@ impl TransactionBuilder {
             .map(|(oref, _)| oref)
     }
 
+    pub async fn get_reference_gas_price(&self) -> anyhow::Result<u64> {
+        self.0.get_reference_gas_price().await
+    }
+
+    pub async fn get_object_owner(&self, object_id: ObjectID) -> anyhow::Result<SuiAddress> {
+        let object = self
+            .0
+            .get_object_with_options(object_id, SuiObjectDataOptions::new().with_owner())
+            .await?
+            .into_object()?;
+
+        Ok(object
+            .owner
+            .ok_or_else(|| anyhow!("Owner field is None"))?
+            .get_owner_address()?)
+    }
+
     async fn get_object_ref_and_type(
         &self,
         object_id: ObjectID,
@@ -1030,3 +1042,16 @@ impl TransactionBuilder {
         Ok((object.object_ref(), object.object_type()?))
     }
 }
+
+/// The balance of a gas coin, read with its BCS contents.
+fn gas_coin_value(obj: &SuiObjectData) -> Result<u64, anyhow::Error> {
+    let gas: GasCoin = bcs::from_bytes(
+        &obj.bcs
+            .as_ref()
+            .ok_or_else(|| anyhow!("bcs field is unexpectedly empty"))?
+            .try_as_move()
+            .ok_or_else(|| anyhow!("Cannot parse move object to gas object"))?
+            .bcs_bytes,
+    )?;
+    Ok(gas.value())
+}
@@ -0,0 +1,211 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! A [`DataReader`] backed by a snapshot of objects, to build transactions without access to the
+//! network, e.g. on an air-gapped machine holding the keys of a cold wallet.
+//!
+//! The snapshot is exported beforehand with `sui client export-objects`, and must contain every
+//! object the transactions refer to: the gas coins and owned objects of the sender, and the
+//! packages of the functions called. Transactions built from it are only valid as long as the
+//! objects in the snapshot were not modified since.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{anyhow, bail, Context};
+use async_trait::async_trait;
+use fastcrypto::encoding::Base64;
+use move_core_types::language_storage::StructTag;
+use mysten_common::fs::write_atomic;
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+use sui_json_rpc_types::{
+    SuiObjectData, SuiObjectDataOptions, SuiObjectResponse, SuiRawData, SuiRawMoveObject,
+    SuiRawMovePackage,
+};
+use sui_types::base_types::{ObjectID, ObjectInfo, SuiAddress};
+use sui_types::object::{Data, Object, Owner};
+
+use crate::DataReader;
+
+/// Objects exported for building transactions offline, stored as JSON.
+#[serde_as]
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ObjectSnapshot {
+    /// The reference gas price when the snapshot was taken.
+    pub reference_gas_price: u64,
+    /// BCS-serialized objects.
+    #[serde_as(as = "Vec<Base64>")]
+    objects: Vec<Vec<u8>>,
+}
+
+impl ObjectSnapshot {
+    pub fn new(reference_gas_price: u64, objects: &[Object]) -> Result<Self, anyhow::Error> {
+        Ok(Self {
+            reference_gas_price,
+            objects: objects
+                .iter()
+                .map(bcs::to_bytes)
+                .collect::<Result<_, _>>()?,
+        })
+    }
+
+    pub fn load(path: &Path) -> Result<Self, anyhow::Error> {
+        let bytes = fs::read(path)
+            .with_context(|| format!("Cannot read object snapshot {}", path.display()))?;
+        serde_json::from_slice(&bytes)
+            .with_context(|| format!("Cannot parse object snapshot {}", path.display()))
+    }
+
+    /// Write the snapshot to `path` atomically, so that an interruption cannot leave a partial
+    /// snapshot behind.
+    pub fn save(&self, path: &Path) -> Result<(), anyhow::Error> {
+        write_atomic(path, &serde_json::to_vec_pretty(self)?)
+            .with_context(|| format!("Cannot write object snapshot {}", path.display()))
+    }
+
+    pub fn objects(&self) -> Result<Vec<Object>, anyhow::Error> {
+        self.objects
+            .iter()
+            .map(|bytes| bcs::from_bytes(bytes).context("Invalid object in snapshot"))
+            .collect()
+    }
+}
+
+pub struct OfflineDataReader {
+    reference_gas_price: u64,
+    objects: BTreeMap<ObjectID, Object>,
+}
+
+impl OfflineDataReader {
+    pub fn new(snapshot: &ObjectSnapshot) -> Result<Self, anyhow::Error> {
+        Ok(Self {
+            reference_gas_price: snapshot.reference_gas_price,
+            objects: snapshot
+                .objects()?
+                .into_iter()
+                .map(|object| (object.id(), object))
+                .collect(),
+        })
+    }
+
+    pub fn load(path: &Path) -> Result<Self, anyhow::Error> {
+        Self::new(&ObjectSnapshot::load(path)?)
+    }
+}
+
+#[async_trait]
+impl DataReader for OfflineDataReader {
+    async fn get_owned_objects(
+        &self,
+        address: SuiAddress,
+        object_type: StructTag,
+    ) -> Result<Vec<ObjectInfo>, anyhow::Error> {
+        Ok(self
+            .objects
+            .values()
+            .filter(|object| {
+                object.owner == Owner::AddressOwner(address)
+                    && object.struct_tag().as_ref() == Some(&object_type)
+            })
+            .map(ObjectInfo::from_object)
+            .collect())
+    }
+
+    async fn get_object_with_options(
+        &self,
+        object_id: ObjectID,
+        options: SuiObjectDataOptions,
+    ) -> Result<SuiObjectResponse, anyhow::Error> {
+        let object = self
+            .objects
+            .get(&object_id)
+            .ok_or_else(|| anyhow!("Object {object_id} is not in the offline object snapshot"))?;
+        if options.show_content || options.show_display {
+            bail!("The content and display of objects are not available offline");
+        }
+
+        // Converting the BCS contents of Move objects normally requires their layout, which is not
+        // needed to return them as they are.
+        let show_bcs = options.show_bcs;
+        let mut data: SuiObjectData = (
+            object.compute_object_reference(),
+            object.clone(),
+            None,
+            SuiObjectDataOptions {
+                show_bcs: false,
+                ..options
+            },
+        )
+            .try_into()?;
+        if show_bcs {
+            data.bcs = Some(match &object.data {
+                Data::Move(o) => SuiRawData::MoveObject(SuiRawMoveObject::from(o.clone())),
+                Data::Package(p) => SuiRawData::Package(SuiRawMovePackage::from(p.clone())),
+            });
+        }
+        Ok(SuiObjectResponse::new_with_data(data))
+    }
+
+    async fn get_reference_gas_price(&self) -> Result<u64, anyhow::Error> {
+        Ok(self.reference_gas_price)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use sui_types::gas_coin::GasCoin;
+    use sui_types::transaction::TransactionDataAPI;
+
+    use crate::TransactionBuilder;
+
+    #[tokio::test]
+    async fn test_build_offline() {
+        let owner = SuiAddress::random_for_testing_only();
+        let coins = [
+            Object::new_gas_with_balance_and_owner_for_testing(1_000_000_000, owner),
+            Object::new_gas_with_balance_and_owner_for_testing(2_000, owner),
+            Object::new_gas_with_balance_and_owner_for_testing(1_000_000_000, SuiAddress::ZERO),
+        ];
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("objects.json");
+        ObjectSnapshot::new(1000, &coins)
+            .unwrap()
+            .save(&path)
+            .unwrap();
+        let reader = OfflineDataReader::load(&path).unwrap();
+
+        let owned = reader
+            .get_owned_objects(owner, GasCoin::type_())
+            .await
+            .unwrap();
+        assert_eq!(owned.len(), 2);
+        assert_eq!(reader.get_reference_gas_price().await.unwrap(), 1000);
+
+        let builder = TransactionBuilder::new(Arc::new(reader));
+        let gas = builder
+            .select_gas(owner, None, 10_000_000, vec![], 1000)
+            .await
+            .unwrap();
+        assert_eq!(gas, coins[0].compute_object_reference());
+
+        let tx = builder
+            .transfer_sui(
+                owner,
+                coins[0].id(),
+                10_000_000,
+                SuiAddress::ZERO,
+                Some(100),
+            )
+            .await
+            .unwrap();
+        assert_eq!(tx.gas(), &[coins[0].compute_object_reference()]);
+
+        // Objects missing from the snapshot
+        assert!(builder.get_object_ref(ObjectID::random()).await.is_err());
+    }
+}
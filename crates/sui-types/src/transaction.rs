@@ -3655,6 +3655,10 @@ impl InputObjects {
         self.objects.iter()
     }
 
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut ObjectReadResult> {
+        self.objects.iter_mut()
+    }
+
     pub fn iter_objects(&self) -> impl Iterator<Item = &Object> {
         self.objects.iter().filter_map(|o| o.as_object())
     }
@@ -4,6 +4,7 @@
 use std::collections::BTreeMap;
 
 use crate::base_types::ObjectID;
+use crate::committee::EpochId;
 use crate::effects::TransactionEffects;
 use crate::effects::TransactionEvents;
 use crate::error::ExecutionError;
@@ -29,10 +30,32 @@ pub trait TransactionExecutor: Send + Sync {
         &self,
         transaction: TransactionData,
         checks: TransactionChecks,
+        overrides: SimulateOverrides,
     ) -> Result<SimulateTransactionResult, SuiError>;
 }
 
+/// Changes to the state a transaction is simulated against. Overrides only apply to the simulation
+/// and are never persisted.
+#[derive(Default, Debug, Clone)]
+pub struct SimulateOverrides {
+    /// Replacement contents of input objects of the transaction, keyed by their ID. References to
+    /// owned objects in the transaction are updated to the replacements.
+    pub objects: BTreeMap<ObjectID, Object>,
+    /// Balances of gas coins owned by the gas owner which are created for the simulation and used
+    /// as the gas payment, in place of the gas payment of the transaction.
+    pub gas_coins: Vec<u64>,
+    /// Timestamp of the `Clock` object, if it is an input of the transaction.
+    pub clock_timestamp_ms: Option<u64>,
+    /// Epoch visible to the transaction through its `TxContext`.
+    pub epoch: Option<EpochId>,
+    /// Timestamp of the start of the epoch visible to the transaction through its `TxContext`.
+    pub epoch_timestamp_ms: Option<u64>,
+}
+
 pub struct SimulateTransactionResult {
+    /// The transaction that was simulated, after overrides were applied to it. The mock gas coin
+    /// that pays for transactions without a gas payment is not included.
+    pub transaction: TransactionData,
     pub effects: TransactionEffects,
     pub events: Option<TransactionEvents>,
     pub input_objects: BTreeMap<ObjectID, Object>,
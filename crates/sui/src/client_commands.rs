@@ -3,7 +3,13 @@
 
 use crate::{
     clever_error_rendering::render_clever_error_opt,
-    client_ptb::ptb::PTB,
+    client_ptb::{
+        emit::{program_args, to_command_line, to_script, transaction_to_program},
+        plan::{default_progress_path, run_plan, PlanProgress},
+        ptb::PTB,
+    },
+    client_simulate,
+    client_watch::Watcher,
     displays::Pretty,
     upgrade_compatibility::check_compatibility,
     verifier_meter::{AccumulatingMeter, Accumulator},
@@ -15,6 +21,7 @@ use std::{
     path::{Path, PathBuf},
     str::FromStr,
     sync::Arc,
+    time::Duration,
 };
 
 use anyhow::{anyhow, bail, ensure, Context};
@@ -67,6 +74,10 @@ use sui_sdk::{
     SuiClient, SUI_COIN_TYPE, SUI_DEVNET_URL, SUI_LOCAL_NETWORK_URL, SUI_LOCAL_NETWORK_URL_0,
     SUI_TESTNET_URL,
 };
+use sui_transaction_builder::{
+    offline::{ObjectSnapshot, OfflineDataReader},
+    TransactionBuilder,
+};
 use sui_types::{
     base_types::{ObjectID, ObjectRef, SequenceNumber, SuiAddress},
     crypto::{EmptySignInfo, SignatureScheme},
@@ -78,7 +89,7 @@ use sui_types::{
     metrics::BytecodeVerifierMetrics,
     move_package::{MovePackage, UpgradeCap},
     object::Owner,
-    parse_sui_type_tag,
+    parse_sui_struct_tag, parse_sui_type_tag,
     signature::GenericSignature,
     sui_serde,
     transaction::{
@@ -211,6 +222,34 @@ pub enum SuiClientCommands {
         signed_tx_bytes: String,
     },
 
+    /// Sign a transaction with keys from the keystore, as its sender or its gas sponsor. For a
+    /// sponsored transaction built with --gas-sponsor and --serialize-unsigned-transaction, the
+    /// sender and the sponsor each sign it with this command, and their signatures are combined
+    /// with `sui client combine-signed-tx`.
+    SignTx {
+        /// BCS serialized transaction data bytes without its type tag, as base64 encoded string. This is the output of sui client command using --serialize-unsigned-transaction.
+        #[clap(long)]
+        tx_bytes: String,
+
+        /// The address (or its alias) to sign as. If not provided, the transaction is signed by
+        /// each of its signers that has a key in the keystore.
+        #[clap(long)]
+        address: Option<KeyIdentity>,
+    },
+
+    /// Combine the signatures of a transaction into a serialized SenderSignedData string, that
+    /// can be executed with `sui client execute-combined-signed-tx`. The signatures must be by
+    /// exactly the sender of the transaction and its gas sponsor, if it has one.
+    CombineSignedTx {
+        /// BCS serialized transaction data bytes without its type tag, as base64 encoded string. This is the output of sui client command using --serialize-unsigned-transaction.
+        #[clap(long)]
+        tx_bytes: String,
+
+        /// A list of Base64 encoded signatures `flag || signature || pubkey`.
+        #[clap(long)]
+        signatures: Vec<String>,
+    },
+
     /// Request gas coin from faucet. By default, it will use the active address and the active network.
     #[clap[name = "faucet"]]
     Faucet {
@@ -297,6 +336,22 @@ pub enum SuiClientCommands {
         #[clap(name = "owner_address")]
         address: Option<KeyIdentity>,
     },
+    /// Export objects to a snapshot file, to build transactions from without network access,
+    /// e.g. on an air-gapped machine. The snapshot contains the objects owned by the addresses,
+    /// and the objects and packages listed. Transactions are built from it with
+    /// `--offline-snapshot`.
+    #[clap(name = "export-objects")]
+    ExportObjects {
+        /// Addresses whose owned objects are exported. Defaults to `sui client active-address`.
+        #[clap(long = "address", num_args(1..))]
+        addresses: Vec<KeyIdentity>,
+        /// Other objects to export, e.g. the packages of the functions to call.
+        #[clap(long = "object", num_args(1..))]
+        objects: Vec<ObjectID>,
+        /// Path of the snapshot file to write.
+        #[clap(long)]
+        output: PathBuf,
+    },
     /// Pay coins to recipients following specified amounts, with input coins.
     /// Length of recipients must be the same as that of amounts.
     #[clap(name = "pay")]
@@ -370,6 +425,21 @@ pub enum SuiClientCommands {
     #[clap(name = "ptb")]
     PTB(PTB),
 
+    /// Run a plan: a TOML file with an ordered list of PTB scripts, each executed as its own
+    /// transaction. Later steps can refer to the packages published and the objects created by
+    /// earlier steps. Running a plan that failed part way resumes it from the step that failed.
+    #[clap(name = "plan")]
+    Plan {
+        /// Path to the plan file.
+        plan: PathBuf,
+        /// File recording the steps that were executed. Defaults to `<PLAN>.progress.json`.
+        #[clap(long)]
+        progress: Option<PathBuf>,
+        /// Ignore the recorded progress and run the plan from its first step.
+        #[clap(long)]
+        restart: bool,
+    },
+
     /// Publish Move modules
     #[clap(name = "publish")]
     Publish {
@@ -473,6 +543,26 @@ pub enum SuiClientCommands {
         digest: TransactionDigest,
     },
 
+    /// Reconstruct the `sui client ptb` command of a programmable transaction, so that it can be
+    /// inspected, edited and run again.
+    #[clap(name = "tx-to-ptb")]
+    TxToPTB {
+        /// Digest of an executed transaction.
+        #[clap(
+            long,
+            required_unless_present = "tx_bytes",
+            conflicts_with = "tx_bytes"
+        )]
+        digest: Option<TransactionDigest>,
+        /// Base64-encoded BCS-serialized TransactionData, e.g. from
+        /// `--serialize-unsigned-transaction`.
+        #[clap(long)]
+        tx_bytes: Option<String>,
+        /// Output a script for `sui client ptb --file` instead of a command line.
+        #[clap(long)]
+        script: bool,
+    },
+
     /// Transfer object
     #[clap(name = "transfer")]
     Transfer {
@@ -668,6 +758,30 @@ pub enum SuiClientCommands {
         #[arg(long, default_value = "false")]
         overwrite_existing: bool,
     },
+    /// Follow objects, addresses and Move event types, printing each new object version,
+    /// transaction and event as checkpoints are produced, until interrupted. Use `--json` to print
+    /// one JSON object per line.
+    #[clap(name = "watch")]
+    Watch {
+        /// Objects whose current version, then every new version, is printed.
+        #[clap(long = "object", num_args(1..))]
+        objects: Vec<ObjectID>,
+        /// Addresses whose new transactions, sent or received, are printed.
+        #[clap(long = "address", num_args(1..))]
+        addresses: Vec<KeyIdentity>,
+        /// Move event types whose new events are printed, e.g. `0x2::display::VersionUpdated<..>`.
+        #[clap(long = "event-type", num_args(1..))]
+        event_types: Vec<String>,
+        /// How often to check for new checkpoints, in milliseconds.
+        #[clap(long, default_value = "1000")]
+        interval_ms: u64,
+        /// Stop after printing this many updates.
+        #[clap(long)]
+        count: Option<usize>,
+        /// Set from the global `--json` flag, as updates are printed while watching.
+        #[clap(skip)]
+        json: bool,
+    },
 }
 
 /// Arguments related to providing coins for gas payment
@@ -707,7 +821,7 @@ pub struct GasDataArgs {
 }
 
 /// Arguments related to what to do to a transaction after it has been built.
-#[derive(Args, Debug, Default)]
+#[derive(Args, Clone, Debug, Default)]
 pub struct TxProcessingArgs {
     /// Compute the transaction digest and print it out, but do not execute the transaction.
     #[arg(long)]
@@ -718,6 +832,16 @@ pub struct TxProcessingArgs {
     /// Perform a dev inspect
     #[arg(long)]
     pub dev_inspect: bool,
+    /// Execute the transaction locally against the latest state of the network, without
+    /// submitting it, and print its effects, events and return values. Unlike `--dry-run` and
+    /// `--dev-inspect`, the state can be changed with `--overrides`.
+    #[arg(long)]
+    pub simulate: bool,
+    /// A JSON file of changes to the state used by `--simulate`: replacement contents for
+    /// objects (`objects`), local packages replacing published ones (`packages`), MIST added to
+    /// the gas coin (`gasBalance`) and the clock timestamp (`clockTimestampMs`).
+    #[arg(long, requires = "simulate", value_hint = ValueHint::FilePath)]
+    pub overrides: Option<PathBuf>,
     /// Instead of executing the transaction, serialize the bcs bytes of the unsigned transaction data
     /// (TransactionData) using base64 encoding, and print out the string <TX_BYTES>. The string can
     /// be used to execute transaction with `sui client execute-signed-tx --tx-bytes <TX_BYTES>`.
@@ -736,6 +860,16 @@ pub struct TxProcessingArgs {
     /// private key corresponding to this address is not in keystore.
     #[arg(long, required = false, value_parser)]
     pub sender: Option<SuiAddress>,
+    /// Build the transaction without accessing the network, reading the objects it uses from a
+    /// snapshot exported with `sui client export-objects`. Requires
+    /// `--serialize-unsigned-transaction` and `--gas-budget`.
+    #[arg(
+        long,
+        requires = "serialize_unsigned_transaction",
+        conflicts_with_all = ["dry_run", "dev_inspect", "simulate"],
+        value_hint = ValueHint::FilePath,
+    )]
+    pub offline_snapshot: Option<PathBuf>,
 }
 
 #[derive(serde::Deserialize, Debug)]
@@ -762,8 +896,13 @@ impl SuiClientCommands {
                     digest: Some(tx_digest.clone()),
                     digests_path: None,
                     node,
+                    ingestion_path: None,
+                    object_cache: None,
                     trace,
                     terminate_early: false,
+                    workers: 1,
+                    summary_path: None,
+                    summary_format: SR2::batch::SummaryFormat::Json,
                     output_dir,
                     show_effects: false,
                     overwrite_existing,
@@ -794,8 +933,13 @@ impl SuiClientCommands {
                     digest: None,
                     digests_path: Some(path),
                     node,
+                    ingestion_path: None,
+                    object_cache: None,
                     trace,
                     terminate_early,
+                    workers: 1,
+                    summary_path: None,
+                    summary_format: SR2::batch::SummaryFormat::Json,
                     output_dir,
                     show_effects: false,
                     overwrite_existing,
@@ -812,6 +956,29 @@ impl SuiClientCommands {
                 // this will be displayed via trace info, so no output is needed here
                 SuiClientCommandResult::NoOutput
             }
+            SuiClientCommands::Watch {
+                objects,
+                addresses,
+                event_types,
+                interval_ms,
+                count,
+                json,
+            } => {
+                let addresses = addresses
+                    .into_iter()
+                    .map(|address| context.get_identity_address(Some(address)))
+                    .collect::<Result<_, _>>()?;
+                let event_types = event_types
+                    .iter()
+                    .map(|event_type| parse_sui_struct_tag(event_type))
+                    .collect::<Result<_, _>>()?;
+                let client = context.get_client().await?;
+                Watcher::new(client, objects, addresses, event_types)
+                    .await?
+                    .run(Duration::from_millis(interval_ms), count, json)
+                    .await?;
+                SuiClientCommandResult::NoOutput
+            }
             SuiClientCommands::Addresses { sort_by_alias } => {
                 let active_address = context.active_address()?;
                 let mut addresses: Vec<(String, SuiAddress)> = context
@@ -927,6 +1094,10 @@ impl SuiClientCommands {
                 gas_data,
                 processing,
             } => {
+                ensure!(
+                    processing.offline_snapshot.is_none(),
+                    "Upgrades cannot be built from an offline snapshot"
+                );
                 let sender = context.infer_sender(&payment.gas).await?;
                 let client = context.get_client().await?;
                 let read_api = client.read_api();
@@ -1029,6 +1200,7 @@ impl SuiClientCommands {
                     sender,
                     tx_kind,
                     context,
+                    client.transaction_builder(),
                     gas_payment,
                     gas_data,
                     processing,
@@ -1065,6 +1237,10 @@ impl SuiClientCommands {
                 gas_data,
                 processing,
             } => {
+                ensure!(
+                    processing.offline_snapshot.is_none(),
+                    "Packages cannot be published from an offline snapshot"
+                );
                 if build_config.test_mode {
                     return Err(SuiError::ModulePublishFailure {
                         error:
@@ -1143,6 +1319,7 @@ impl SuiClientCommands {
                     sender,
                     tx_kind,
                     context,
+                    client.transaction_builder(),
                     gas_payment,
                     gas_data,
                     processing,
@@ -1296,6 +1473,47 @@ impl SuiClientCommands {
                 SuiClientCommandResult::TransactionBlock(tx_read)
             }
 
+            SuiClientCommands::TxToPTB {
+                digest,
+                tx_bytes,
+                script,
+            } => {
+                let client = context.get_client().await?;
+                let tx_data = match (digest, tx_bytes) {
+                    (Some(digest), _) => {
+                        let response = client
+                            .read_api()
+                            .get_transaction_with_options(
+                                digest,
+                                SuiTransactionBlockResponseOptions::new().with_raw_input(),
+                            )
+                            .await?;
+                        let data: SenderSignedData = bcs::from_bytes(&response.raw_transaction)
+                            .context("Failed to parse the transaction")?;
+                        data.transaction_data().clone()
+                    }
+                    (None, Some(tx_bytes)) => {
+                        let Ok(bytes) = Base64::decode(&tx_bytes) else {
+                            bail!("Invalid Base64 encoding");
+                        };
+                        let Ok(tx_data) = bcs::from_bytes(&bytes) else {
+                            bail!("Failed to parse --tx-bytes as TransactionData");
+                        };
+                        tx_data
+                    }
+                    (None, None) => bail!("Either --digest or --tx-bytes must be provided"),
+                };
+
+                let program = transaction_to_program(&tx_data, client.read_api()).await?;
+                let args = program_args(&program);
+                let ptb = if script {
+                    format!("# Transaction {}\n{}", tx_data.digest(), to_script(&args))
+                } else {
+                    to_command_line(&args)
+                };
+                SuiClientCommandResult::EmittedPTB(ptb)
+            }
+
             SuiClientCommands::Call {
                 package,
                 module,
@@ -1318,23 +1536,20 @@ impl SuiClientCommands {
                     .map(|arg| arg.into())
                     .collect::<Vec<_>>();
 
-                let client = context.get_client().await?;
+                let builder = transaction_builder(context, &processing).await?;
 
-                let tx_kind = client
-                    .transaction_builder()
+                let tx_kind = builder
                     .move_call_tx_kind(package, &module, &function, type_args, args)
                     .await?;
 
-                let sender = context.infer_sender(&payment.gas).await?;
-                let gas_payment = client
-                    .transaction_builder()
-                    .input_refs(&payment.gas)
-                    .await?;
+                let sender = infer_sender(context, &builder, &payment.gas).await?;
+                let gas_payment = builder.input_refs(&payment.gas).await?;
 
                 dry_run_or_execute_or_serialize(
                     sender,
                     tx_kind,
                     context,
+                    &builder,
                     gas_payment,
                     gas_data,
                     processing,
@@ -1349,24 +1564,18 @@ impl SuiClientCommands {
                 gas_data,
                 processing,
             } => {
-                let signer = context.get_object_owner(&object_id).await?;
+                let builder = transaction_builder(context, &processing).await?;
+                let signer = builder.get_object_owner(object_id).await?;
                 let to = context.get_identity_address(Some(to))?;
-                let client = context.get_client().await?;
-
-                let tx_kind = client
-                    .transaction_builder()
-                    .transfer_object_tx_kind(object_id, to)
-                    .await?;
 
-                let gas_payment = client
-                    .transaction_builder()
-                    .input_refs(&payment.gas)
-                    .await?;
+                let tx_kind = builder.transfer_object_tx_kind(object_id, to).await?;
+                let gas_payment = builder.input_refs(&payment.gas).await?;
 
                 dry_run_or_execute_or_serialize(
                     signer,
                     tx_kind,
                     context,
+                    &builder,
                     gas_payment,
                     gas_data,
                     processing,
@@ -1381,23 +1590,18 @@ impl SuiClientCommands {
                 gas_data,
                 processing,
             } => {
-                let signer = context.get_object_owner(&object_id).await?;
+                let builder = transaction_builder(context, &processing).await?;
+                let signer = builder.get_object_owner(object_id).await?;
                 let to = context.get_identity_address(Some(to))?;
-                let client = context.get_client().await?;
 
-                let tx_kind = client
-                    .transaction_builder()
-                    .transfer_sui_tx_kind(to, amount);
-
-                let gas_payment = client
-                    .transaction_builder()
-                    .input_refs(&[object_id])
-                    .await?;
+                let tx_kind = builder.transfer_sui_tx_kind(to, amount);
+                let gas_payment = builder.input_refs(&[object_id]).await?;
 
                 dry_run_or_execute_or_serialize(
                     signer,
                     tx_kind,
                     context,
+                    &builder,
                     gas_payment,
                     gas_data,
                     processing,
@@ -1434,10 +1638,9 @@ impl SuiClientCommands {
                     .map(|x| context.get_identity_address(Some(x)))
                     .collect::<Result<Vec<SuiAddress>, anyhow::Error>>()
                     .map_err(|e| anyhow!("{e}"))?;
-                let signer = context.get_object_owner(&input_coins[0]).await?;
-                let client = context.get_client().await?;
-                let tx_kind = client
-                    .transaction_builder()
+                let builder = transaction_builder(context, &processing).await?;
+                let signer = builder.get_object_owner(input_coins[0]).await?;
+                let tx_kind = builder
                     .pay_tx_kind(input_coins.clone(), recipients.clone(), amounts.clone())
                     .await?;
 
@@ -1446,15 +1649,13 @@ impl SuiClientCommands {
                     "Gas coin is in input coins of Pay transaction, use PaySui transaction instead!"
                 );
 
-                let gas_payment = client
-                    .transaction_builder()
-                    .input_refs(&payment.gas)
-                    .await?;
+                let gas_payment = builder.input_refs(&payment.gas).await?;
 
                 dry_run_or_execute_or_serialize(
                     signer,
                     tx_kind,
                     context,
+                    &builder,
                     gas_payment,
                     gas_data,
                     processing,
@@ -1490,22 +1691,17 @@ impl SuiClientCommands {
                     .map(|x| context.get_identity_address(Some(x)))
                     .collect::<Result<Vec<SuiAddress>, anyhow::Error>>()
                     .map_err(|e| anyhow!("{e}"))?;
-                let signer = context.get_object_owner(&input_coins[0]).await?;
-                let client = context.get_client().await?;
+                let builder = transaction_builder(context, &processing).await?;
+                let signer = builder.get_object_owner(input_coins[0]).await?;
 
-                let tx_kind = client
-                    .transaction_builder()
-                    .pay_sui_tx_kind(recipients, amounts)?;
-
-                let gas_payment = client
-                    .transaction_builder()
-                    .input_refs(&input_coins)
-                    .await?;
+                let tx_kind = builder.pay_sui_tx_kind(recipients, amounts)?;
+                let gas_payment = builder.input_refs(&input_coins).await?;
 
                 dry_run_or_execute_or_serialize(
                     signer,
                     tx_kind,
                     context,
+                    &builder,
                     gas_payment,
                     gas_data,
                     processing,
@@ -1524,19 +1720,17 @@ impl SuiClientCommands {
                     "PayAllSui transaction requires a non-empty list of input coins"
                 );
                 let recipient = context.get_identity_address(Some(recipient))?;
-                let signer = context.get_object_owner(&input_coins[0]).await?;
-                let client = context.get_client().await?;
+                let builder = transaction_builder(context, &processing).await?;
+                let signer = builder.get_object_owner(input_coins[0]).await?;
 
-                let tx_kind = client.transaction_builder().pay_all_sui_tx_kind(recipient);
-                let gas_payment = client
-                    .transaction_builder()
-                    .input_refs(&input_coins)
-                    .await?;
+                let tx_kind = builder.pay_all_sui_tx_kind(recipient);
+                let gas_payment = builder.input_refs(&input_coins).await?;
 
                 dry_run_or_execute_or_serialize(
                     signer,
                     tx_kind,
                     context,
+                    &builder,
                     gas_payment,
                     gas_data,
                     processing,
@@ -1572,6 +1766,69 @@ impl SuiClientCommands {
                 SuiClientCommandResult::Objects(objects)
             }
 
+            SuiClientCommands::ExportObjects {
+                addresses,
+                objects: object_ids,
+                output,
+            } => {
+                let addresses = if addresses.is_empty() {
+                    vec![context.active_address()?]
+                } else {
+                    addresses
+                        .into_iter()
+                        .map(|address| context.get_identity_address(Some(address)))
+                        .collect::<Result<_, _>>()?
+                };
+                let client = context.get_client().await?;
+
+                let mut responses = vec![];
+                for address in addresses {
+                    let mut cursor = None;
+                    loop {
+                        let response = client
+                            .read_api()
+                            .get_owned_objects(
+                                address,
+                                Some(SuiObjectResponseQuery::new_with_options(
+                                    SuiObjectDataOptions::bcs_lossless(),
+                                )),
+                                cursor,
+                                None,
+                            )
+                            .await?;
+                        responses.extend(response.data);
+
+                        if response.has_next_page {
+                            cursor = response.next_cursor;
+                        } else {
+                            break;
+                        }
+                    }
+                }
+                for object_id in object_ids {
+                    responses.push(
+                        client
+                            .read_api()
+                            .get_object_with_options(
+                                object_id,
+                                SuiObjectDataOptions::bcs_lossless(),
+                            )
+                            .await?,
+                    );
+                }
+
+                let objects = responses
+                    .into_iter()
+                    .map(|response| response.into_object()?.try_into())
+                    .collect::<Result<Vec<sui_types::object::Object>, anyhow::Error>>()?;
+                let reference_gas_price = client.read_api().get_reference_gas_price().await?;
+                ObjectSnapshot::new(reference_gas_price, &objects)?.save(&output)?;
+                SuiClientCommandResult::ExportedObjects {
+                    path: output,
+                    num_objects: objects.len(),
+                }
+            }
+
             SuiClientCommands::NewAddress {
                 key_scheme,
                 alias,
@@ -1671,23 +1928,17 @@ impl SuiClientCommands {
                     _ => { /*no_op*/ }
                 }
 
-                let client = context.get_client().await?;
-                let signer = context.get_object_owner(&coin_id).await?;
+                let builder = transaction_builder(context, &processing).await?;
+                let signer = builder.get_object_owner(coin_id).await?;
 
-                let tx_kind = client
-                    .transaction_builder()
-                    .split_coin_tx_kind(coin_id, amounts, count)
-                    .await?;
-
-                let gas_payment = client
-                    .transaction_builder()
-                    .input_refs(&payment.gas)
-                    .await?;
+                let tx_kind = builder.split_coin_tx_kind(coin_id, amounts, count).await?;
+                let gas_payment = builder.input_refs(&payment.gas).await?;
 
                 dry_run_or_execute_or_serialize(
                     signer,
                     tx_kind,
                     context,
+                    &builder,
                     gas_payment,
                     gas_data,
                     processing,
@@ -1701,23 +1952,19 @@ impl SuiClientCommands {
                 gas_data,
                 processing,
             } => {
-                let client = context.get_client().await?;
-                let signer = context.get_object_owner(&primary_coin).await?;
+                let builder = transaction_builder(context, &processing).await?;
+                let signer = builder.get_object_owner(primary_coin).await?;
 
-                let tx_kind = client
-                    .transaction_builder()
+                let tx_kind = builder
                     .merge_coins_tx_kind(primary_coin, coin_to_merge)
                     .await?;
-
-                let gas_payment = client
-                    .transaction_builder()
-                    .input_refs(&payment.gas)
-                    .await?;
+                let gas_payment = builder.input_refs(&payment.gas).await?;
 
                 dry_run_or_execute_or_serialize(
                     signer,
                     tx_kind,
                     context,
+                    &builder,
                     gas_payment,
                     gas_data,
                     processing,
@@ -1744,11 +1991,13 @@ impl SuiClientCommands {
                     gas_sponsor: Some(tx_data.gas_owner()),
                 };
                 let tx_kind = tx_data.into_kind();
+                let builder = transaction_builder(context, &processing).await?;
 
                 dry_run_or_execute_or_serialize(
                     sender,
                     tx_kind,
                     context,
+                    &builder,
                     gas_payment,
                     gas_data,
                     processing,
@@ -1769,17 +2018,15 @@ impl SuiClientCommands {
                     bail!("Failed to parse --tx-bytes as TransactionKind");
                 };
 
-                let client = context.get_client().await?;
-                let sender = context.infer_sender(&payment.gas).await?;
-                let gas_payment = client
-                    .transaction_builder()
-                    .input_refs(&payment.gas)
-                    .await?;
+                let builder = transaction_builder(context, &processing).await?;
+                let sender = infer_sender(context, &builder, &payment.gas).await?;
+                let gas_payment = builder.input_refs(&payment.gas).await?;
 
                 dry_run_or_execute_or_serialize(
                     sender,
                     tx_kind,
                     context,
+                    &builder,
                     gas_payment,
                     gas_data,
                     processing,
@@ -1818,25 +2065,9 @@ impl SuiClientCommands {
                 tx_bytes,
                 signatures,
             } => {
-                let data = bcs::from_bytes(
-                    &Base64::try_from(tx_bytes)
-                    .map_err(|_| anyhow!("Invalid Base64 encoding"))?
-                    .to_vec()
-                    .map_err(|_| anyhow!("Invalid Base64 encoding"))?
-                ).map_err(|_| anyhow!("Failed to parse tx bytes, check if it matches the output of sui client commands with --serialize-unsigned-transaction"))?;
-
-                let mut sigs = Vec::new();
-                for sig in signatures {
-                    sigs.push(
-                        GenericSignature::from_bytes(
-                            &Base64::try_from(sig)
-                                .map_err(|_| anyhow!("Invalid Base64 encoding"))?
-                                .to_vec()
-                                .map_err(|e| anyhow!(e))?,
-                        )
-                        .map_err(|_| anyhow!("Invalid generic signature"))?,
-                    );
-                }
+                let data = parse_tx_bytes(tx_bytes)?;
+                let sigs = parse_signatures(signatures)?;
+                check_signers(&data, &sigs)?;
                 let transaction = Transaction::from_generic_sig_data(data, sigs);
 
                 let response = context.execute_transaction_may_fail(transaction).await?;
@@ -1849,10 +2080,63 @@ impl SuiClientCommands {
                         .to_vec()
                         .map_err(|_| anyhow!("Invalid Base64 encoding"))?
                 ).map_err(|_| anyhow!("Failed to parse SenderSignedData bytes, check if it matches the output of sui client commands with --serialize-signed-transaction"))?;
+                check_signers(data.transaction_data(), data.tx_signatures())?;
                 let transaction = Envelope::<SenderSignedData, EmptySignInfo>::new(data);
                 let response = context.execute_transaction_may_fail(transaction).await?;
                 SuiClientCommandResult::TransactionBlock(response)
             }
+            SuiClientCommands::SignTx { tx_bytes, address } => {
+                let data = parse_tx_bytes(tx_bytes)?;
+                let signers: Vec<_> = match address {
+                    Some(address) => {
+                        let address = context.get_identity_address(Some(address))?;
+                        ensure!(
+                            data.signers().iter().any(|signer| *signer == address),
+                            "{address} is neither the sender nor the gas sponsor of the transaction"
+                        );
+                        vec![address]
+                    }
+                    None => {
+                        let keys = context.config.keystore.addresses();
+                        let signers: Vec<_> = data
+                            .signers()
+                            .into_iter()
+                            .filter(|signer| keys.contains(signer))
+                            .collect();
+                        ensure!(
+                            !signers.is_empty(),
+                            "The keystore has no key for the sender or the gas sponsor of the transaction"
+                        );
+                        signers
+                    }
+                };
+
+                let mut signatures = vec![];
+                for signer in signers {
+                    let signature = context.config.keystore.sign_secure(
+                        &signer,
+                        &data,
+                        Intent::sui_transaction(),
+                    )?;
+                    signatures.push(TxSignatureOutput {
+                        role: signer_role(&data, signer),
+                        address: signer,
+                        signature: Base64::encode(GenericSignature::from(signature).as_ref()),
+                    });
+                }
+                SuiClientCommandResult::TxSignatures(signatures)
+            }
+            SuiClientCommands::CombineSignedTx {
+                tx_bytes,
+                signatures,
+            } => {
+                let data = parse_tx_bytes(tx_bytes)?;
+                let sigs = parse_signatures(signatures)?;
+                check_signers(&data, &sigs)?;
+                SuiClientCommandResult::SerializedSignedTransaction(SenderSignedData::new(
+                    data, sigs,
+                ))
+            }
             SuiClientCommands::NewEnv {
                 alias,
                 rpc,
@@ -1930,6 +2214,14 @@ impl SuiClientCommands {
                 ptb.execute(context).await?;
                 SuiClientCommandResult::NoOutput
             }
+            SuiClientCommands::Plan {
+                plan,
+                progress,
+                restart,
+            } => {
+                let progress = progress.unwrap_or_else(|| default_progress_path(&plan));
+                SuiClientCommandResult::Plan(run_plan(&plan, &progress, restart, context).await?)
+            }
         };
         Ok(ret.prerender_clever_errors(context).await)
     }
@@ -2397,6 +2689,9 @@ impl Display for SuiClientCommandResult {
                 }
                 Err(e) => writeln!(f, "Internal error, cannot read the object: {e}")?,
             },
+            SuiClientCommandResult::Plan(progress) => {
+                write!(writer, "{}", progress)?;
+            }
             SuiClientCommandResult::Objects(object_refs) => {
                 if object_refs.is_empty() {
                     writeln!(f, "This address has no owned objects.")?
@@ -2447,6 +2742,16 @@ impl Display for SuiClientCommandResult {
                     fastcrypto::encoding::Base64::encode(bcs::to_bytes(tx_data).unwrap())
                 )?;
             }
+            SuiClientCommandResult::TxSignatures(signatures) => {
+                for TxSignatureOutput {
+                    role,
+                    address,
+                    signature,
+                } in signatures
+                {
+                    writeln!(writer, "Signature of the {role} {address}:\n{signature}")?;
+                }
+            }
             SuiClientCommandResult::SerializedSignedTransaction(sender_signed_tx) => {
                 writeln!(
                     writer,
@@ -2460,6 +2765,16 @@ impl Display for SuiClientCommandResult {
             SuiClientCommandResult::ChainIdentifier(ci) => {
                 writeln!(writer, "{}", ci)?;
             }
+            SuiClientCommandResult::EmittedPTB(ptb) => {
+                writeln!(writer, "{}", ptb.trim_end())?;
+            }
+            SuiClientCommandResult::ExportedObjects { path, num_objects } => {
+                writeln!(
+                    writer,
+                    "Exported {num_objects} objects to {}",
+                    path.display()
+                )?;
+            }
             SuiClientCommandResult::Switch(response) => {
                 write!(writer, "{}", response)?;
             }
@@ -2693,19 +3008,23 @@ impl SuiClientCommandResult {
             | SuiClientCommandResult::ChainIdentifier(_)
             | SuiClientCommandResult::DynamicFieldQuery(_)
             | SuiClientCommandResult::DevInspect(_)
+            | SuiClientCommandResult::EmittedPTB(_)
             | SuiClientCommandResult::Envs(_, _)
+            | SuiClientCommandResult::ExportedObjects { .. }
             | SuiClientCommandResult::Gas(_)
             | SuiClientCommandResult::NewAddress(_)
             | SuiClientCommandResult::NewEnv(_)
             | SuiClientCommandResult::NoOutput
             | SuiClientCommandResult::Object(_)
             | SuiClientCommandResult::Objects(_)
+            | SuiClientCommandResult::Plan(_)
             | SuiClientCommandResult::RemoveAddress(_)
             | SuiClientCommandResult::RawObject(_)
             | SuiClientCommandResult::SerializedSignedTransaction(_)
             | SuiClientCommandResult::SerializedUnsignedTransaction(_)
             | SuiClientCommandResult::Switch(_)
             | SuiClientCommandResult::SyncClientState
+            | SuiClientCommandResult::TxSignatures(_)
             | SuiClientCommandResult::VerifyBytecodeMeter { .. }
             | SuiClientCommandResult::VerifySource => (),
         }
@@ -2851,13 +3170,19 @@ pub enum SuiClientCommandResult {
     DynamicFieldQuery(DynamicFieldPage),
     DryRun(DryRunTransactionBlockResponse),
     DevInspect(DevInspectResults),
+    EmittedPTB(String),
     Envs(Vec<SuiEnv>, Option<String>),
+    ExportedObjects {
+        path: PathBuf,
+        num_objects: usize,
+    },
     Gas(Vec<GasCoin>),
     NewAddress(NewAddressOutput),
     NewEnv(SuiEnv),
     NoOutput,
     Object(SuiObjectResponse),
     Objects(Vec<SuiObjectResponse>),
+    Plan(PlanProgress),
     RawObject(SuiObjectResponse),
     RemoveAddress(RemoveAddressOutput),
     SerializedSignedTransaction(SenderSignedData),
@@ -2865,6 +3190,7 @@ pub enum SuiClientCommandResult {
     Switch(SwitchResponse),
     SyncClientState,
     TransactionBlock(SuiTransactionBlockResponse),
+    TxSignatures(Vec<TxSignatureOutput>),
     VerifyBytecodeMeter {
         success: bool,
         max_package_ticks: Option<u128>,
@@ -2875,6 +3201,15 @@ pub enum SuiClientCommandResult {
     VerifySource,
 }
 
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TxSignatureOutput {
+    pub role: &'static str,
+    pub address: SuiAddress,
+    /// Base64 encoded `flag || signature || pubkey`.
+    pub signature: String,
+}
+
 #[derive(Serialize, Clone)]
 pub struct SwitchResponse {
     /// Active address
@@ -3173,6 +3508,44 @@ pub async fn max_gas_budget(client: &SuiClient) -> Result<u64, anyhow::Error> {
     })
 }
 
+/// The transaction builder for a command, reading objects from the snapshot set with
+/// `--offline-snapshot`, or from the network when there is none.
+async fn transaction_builder(
+    context: &WalletContext,
+    processing: &TxProcessingArgs,
+) -> Result<TransactionBuilder, anyhow::Error> {
+    match &processing.offline_snapshot {
+        Some(path) => Ok(TransactionBuilder::new(Arc::new(OfflineDataReader::load(
+            path,
+        )?))),
+        None => Ok(context.get_client().await?.transaction_builder().clone()),
+    }
+}
+
+/// Like [`WalletContext::infer_sender`], but reading the owners of the gas coins with `builder`.
+async fn infer_sender(
+    context: &mut WalletContext,
+    builder: &TransactionBuilder,
+    gas: &[ObjectID],
+) -> Result<SuiAddress, anyhow::Error> {
+    if gas.is_empty() {
+        return context.active_address();
+    }
+
+    let owners =
+        futures::future::try_join_all(gas.iter().map(|id| builder.get_object_owner(*id))).await?;
+
+    // SAFETY `gas` is non-empty.
+    let owner = owners.first().copied().unwrap();
+
+    ensure!(
+        owners.iter().all(|o| o == &owner),
+        "Cannot infer sender, not all gas objects have the same owner."
+    );
+
+    Ok(owner)
+}
+
 /// Dry run, execute, or serialize a transaction.
 ///
 /// This basically extracts the logical code for each command that deals with dry run, executing,
@@ -3181,6 +3554,7 @@ pub(crate) async fn dry_run_or_execute_or_serialize(
     signer: SuiAddress,
     tx_kind: TransactionKind,
     context: &mut WalletContext,
+    builder: &TransactionBuilder,
     gas_payment: Vec<ObjectRef>,
     gas_data: GasDataArgs,
     processing: TxProcessingArgs,
@@ -3195,23 +3569,32 @@ pub(crate) async fn dry_run_or_execute_or_serialize(
         tx_digest,
         dry_run,
         dev_inspect,
+        simulate,
+        overrides,
         serialize_unsigned_transaction,
         serialize_signed_transaction,
         sender,
+        offline_snapshot,
     } = processing;
 
     ensure!(
         !serialize_unsigned_transaction || !serialize_signed_transaction,
         "Cannot specify both flags: --serialize-unsigned-transaction and --serialize-signed-transaction."
     );
+    ensure!(
+        simulate || overrides.is_none(),
+        "State overrides can only be used with --simulate"
+    );
 
-    let gas_price = if let Some(gas_price) = gas_price {
-        gas_price
-    } else {
-        context.get_reference_gas_price().await?
-    };
+    ensure!(
+        offline_snapshot.is_none() || gas_budget.is_some(),
+        "The gas budget cannot be estimated offline, set it with --gas-budget"
+    );
 
-    let client = context.get_client().await?;
+    let gas_price = match gas_price {
+        Some(gas_price) => gas_price,
+        None => builder.get_reference_gas_price().await?,
+    };
 
     let signer = sender.unwrap_or(signer);
 
@@ -3237,11 +3620,27 @@ pub(crate) async fn dry_run_or_execute_or_serialize(
             gas_budget,
             gas_price,
             gas_payment.clone(),
-            None,
+            gas_sponsor,
         )
         .await;
     }
 
+    if simulate {
+        let client = context.get_client().await?;
+        let results = client_simulate::simulate(
+            &client,
+            signer,
+            tx_kind,
+            gas_payment,
+            gas_budget,
+            gas_price,
+            gas_sponsor,
+            overrides.as_deref(),
+        )
+        .await?;
+        return Ok(SuiClientCommandResult::DevInspect(results));
+    }
+
     let gas_budget = match gas_budget {
         Some(gas_budget) => gas_budget,
         None => {
@@ -3272,8 +3671,7 @@ pub(crate) async fn dry_run_or_execute_or_serialize(
             })
             .collect();
 
-        let gas_payment = client
-            .transaction_builder()
+        let gas_payment = builder
             .select_gas(
                 gas_sponsor.unwrap_or(signer),
                 None,
@@ -3316,7 +3714,15 @@ pub(crate) async fn dry_run_or_execute_or_serialize(
                     context
                         .config
                         .keystore
-                        .sign_secure(&gas_sponsor, &tx_data, Intent::sui_transaction())?
+                        .sign_secure(&gas_sponsor, &tx_data, Intent::sui_transaction())
+                        .with_context(|| {
+                            format!(
+                                "Cannot sign as the gas sponsor {gas_sponsor}. If the sponsor \
+                                 signs elsewhere, build the transaction with \
+                                 --serialize-unsigned-transaction and have the sender and the \
+                                 sponsor each sign it with `sui client sign-tx`"
+                            )
+                        })?
                         .into(),
                 );
             }
@@ -3335,6 +3741,7 @@ pub(crate) async fn dry_run_or_execute_or_serialize(
                 .await?;
             debug!("Transaction executed: {:?}", transaction);
             if let Some(effects) = response.effects.as_mut() {
+                let client = context.get_client().await?;
                 prerender_clever_errors(effects, client.read_api()).await;
             }
             let effects = response.effects.as_ref().ok_or_else(|| {
@@ -3527,3 +3934,65 @@ async fn get_replay_node(context: &mut WalletContext) -> Result<SR2::Node, anyho
         Chain::Unknown => bail!("Unsupported chain identifier for replay -- only testnet and mainnet are supported currently"),
     })
 }
+
+fn parse_tx_bytes(tx_bytes: String) -> Result<TransactionData, anyhow::Error> {
+    bcs::from_bytes(
+        &Base64::try_from(tx_bytes)
+            .map_err(|_| anyhow!("Invalid Base64 encoding"))?
+            .to_vec()
+            .map_err(|_| anyhow!("Invalid Base64 encoding"))?,
+    )
+    .map_err(|_| anyhow!("Failed to parse tx bytes, check if it matches the output of sui client commands with --serialize-unsigned-transaction"))
+}
+
+fn parse_signatures(signatures: Vec<String>) -> Result<Vec<GenericSignature>, anyhow::Error> {
+    signatures
+        .into_iter()
+        .map(|sig| {
+            GenericSignature::from_bytes(
+                &Base64::try_from(sig)
+                    .map_err(|_| anyhow!("Invalid Base64 encoding"))?
+                    .to_vec()
+                    .map_err(|e| anyhow!(e))?,
+            )
+            .map_err(|_| anyhow!("Invalid generic signature"))
+        })
+        .collect()
+}
+
+/// Whether `signer` signs `tx_data` as its sender or as its gas sponsor.
+fn signer_role(tx_data: &TransactionData, signer: SuiAddress) -> &'static str {
+    if signer == tx_data.sender() {
+        "sender"
+    } else {
+        "gas sponsor"
+    }
+}
+
+/// Check that `signatures` are by exactly the signers of `tx_data`: its sender, and its gas
+/// sponsor if it has one.
+fn check_signers(
+    tx_data: &TransactionData,
+    signatures: &[GenericSignature],
+) -> Result<(), anyhow::Error> {
+    let mut missing: BTreeSet<SuiAddress> = tx_data.signers().into_iter().collect();
+    for signature in signatures {
+        let signer = SuiAddress::try_from(signature)
+            .map_err(|e| anyhow!("Cannot derive the signer of a signature: {e}"))?;
+        ensure!(
+            tx_data.signers().iter().any(|s| *s == signer),
+            "Signature by {signer}, which is neither the sender nor the gas sponsor of the transaction"
+        );
+        ensure!(
+            missing.remove(&signer),
+            "More than one signature by {signer}"
+        );
+    }
+    if let Some(signer) = missing.first() {
+        bail!(
+            "Missing the signature of the {} {signer}",
+            signer_role(tx_data, *signer)
+        );
+    }
+    Ok(())
+}
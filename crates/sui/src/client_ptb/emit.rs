@@ -0,0 +1,958 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Reconstruct a PTB program from the `TransactionData` of a programmable transaction, and emit it
+//! as a `sui client ptb` command line or script, so that it can be inspected, edited and run
+//! again.
+//!
+//! Object inputs are emitted as their IDs, and pure inputs are decoded by the type of the first
+//! parameter they are passed to (fetching the signatures of called Move functions). Results that
+//! are used by later commands are bound to variables named `result_<command index>`.
+
+use std::collections::BTreeMap;
+
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use move_binary_format::{
+    binary_config::BinaryConfig,
+    file_format::{DatatypeHandleIndex, SignatureToken},
+    CompiledModule,
+};
+use move_core_types::{
+    account_address::AccountAddress,
+    language_storage::StructTag,
+    parsing::{
+        address::{NumericalAddress, ParsedAddress},
+        parser::NumberFormat,
+        types::{ParsedFqName, ParsedModuleId, ParsedStructType, ParsedType},
+    },
+    u256::U256,
+};
+use sui_sdk::apis::ReadApi;
+use sui_types::{
+    base_types::{
+        ObjectID, TxContext, TxContextKind, RESOLVED_ASCII_STR, RESOLVED_STD_OPTION,
+        RESOLVED_UTF8_STR,
+    },
+    id::RESOLVED_SUI_ID,
+    move_package::MovePackage,
+    transaction::{
+        self as Tx, CallArg, Command, ObjectArg, ProgrammableMoveCall, ProgrammableTransaction,
+        TransactionData, TransactionDataAPI, TransactionKind,
+    },
+    Identifier, TypeTag,
+};
+
+use super::{
+    ast::{
+        Argument, ModuleAccess, ParsedPTBCommand, ParsedProgram, Program, ProgramMetadata, ASSIGN,
+        DEV_INSPECT, DRY_RUN, GAS_BUDGET, GAS_COIN, GAS_PRICE, GAS_SPONSOR, JSON, MAKE_MOVE_VEC,
+        MERGE_COINS, MOVE_CALL, OVERRIDES, PREVIEW, PUBLISH, SENDER, SERIALIZE_SIGNED,
+        SERIALIZE_UNSIGNED, SIMULATE, SPLIT_COINS, SUMMARY, TRANSFER_OBJECTS, TX_DIGEST, UPGRADE,
+        WARN_SHADOWS,
+    },
+    builder::resolve_package,
+    error::{Span, Spanned},
+};
+
+/// Reconstruct a PTB program that builds a transaction equivalent to `tx_data`. The signatures of
+/// the Move functions it calls are fetched with `reader`, to decode the pure values passed to
+/// them.
+pub async fn transaction_to_program(
+    tx_data: &TransactionData,
+    reader: &ReadApi,
+) -> Result<ParsedProgram> {
+    let TransactionKind::ProgrammableTransaction(pt) = tx_data.kind() else {
+        bail!("Only programmable transactions can be converted to a PTB");
+    };
+
+    let mut packages = BTreeMap::new();
+    let mut param_types = BTreeMap::new();
+    for (ix, command) in pt.commands.iter().enumerate() {
+        let Command::MoveCall(call) = command else {
+            continue;
+        };
+        if !packages.contains_key(&call.package) {
+            let package = resolve_package(reader, call.package, Span { start: 0, end: 0 }).await?;
+            packages.insert(call.package, package);
+        }
+        let types = move_call_parameters(&packages[&call.package], call)
+            .with_context(|| format!("Cannot resolve the function called by command {ix}"))?;
+        param_types.insert(ix, types);
+    }
+
+    build_program(tx_data, pt, &param_types)
+}
+
+/// Build the PTB program of `tx_data`, given the types of the parameters of the Move functions
+/// called by each of its `MoveCall` commands (by command index).
+fn build_program(
+    tx_data: &TransactionData,
+    pt: &ProgrammableTransaction,
+    param_types: &BTreeMap<usize, Vec<Option<TypeTag>>>,
+) -> Result<ParsedProgram> {
+    let input_types = infer_input_types(pt, param_types)?;
+
+    // Results are only bound to variables if they are used.
+    let mut used_results = vec![false; pt.commands.len()];
+    for command in &pt.commands {
+        for arg in command_arguments(command) {
+            if let Tx::Argument::Result(ix) | Tx::Argument::NestedResult(ix, _) = arg {
+                *used_results
+                    .get_mut(*ix as usize)
+                    .ok_or_else(|| anyhow!("Reference to unknown result {ix}"))? = true;
+            }
+        }
+    }
+
+    let inputs = pt
+        .inputs
+        .iter()
+        .enumerate()
+        .map(|(ix, input)| input_argument(ix as u16, input, input_types.get(&(ix as u16))))
+        .collect::<Result<Vec<_>>>()?;
+    let arg = |arg: &Tx::Argument| -> Result<Spanned<Argument>> {
+        Ok(unspanned(match arg {
+            Tx::Argument::GasCoin => Argument::Gas,
+            Tx::Argument::Input(ix) => inputs
+                .get(*ix as usize)
+                .ok_or_else(|| anyhow!("Reference to unknown input {ix}"))?
+                .clone(),
+            Tx::Argument::Result(ix) => {
+                Argument::VariableAccess(unspanned(result_variable(*ix)), vec![])
+            }
+            Tx::Argument::NestedResult(ix, nested) => Argument::VariableAccess(
+                unspanned(result_variable(*ix)),
+                vec![unspanned(nested.to_string())],
+            ),
+        }))
+    };
+    let args = |args: &[Tx::Argument]| -> Result<Spanned<Vec<Spanned<Argument>>>> {
+        Ok(unspanned(args.iter().map(&arg).collect::<Result<_>>()?))
+    };
+
+    let mut commands = vec![];
+    for (ix, command) in pt.commands.iter().enumerate() {
+        let command = match command {
+            Command::MoveCall(call) => {
+                let type_args = call
+                    .type_arguments
+                    .iter()
+                    .map(|ty| Ok(parsed_type(&ty.to_type_tag()?)))
+                    .collect::<Result<Vec<_>>>()?;
+                ParsedPTBCommand::MoveCall(
+                    unspanned(ModuleAccess {
+                        address: unspanned(ParsedAddress::Numerical(numerical_address(
+                            *call.package,
+                        ))),
+                        module_name: unspanned(Identifier::new(call.module.as_str())?),
+                        function_name: unspanned(Identifier::new(call.function.as_str())?),
+                    }),
+                    (!type_args.is_empty()).then(|| unspanned(type_args)),
+                    call.arguments.iter().map(&arg).collect::<Result<_>>()?,
+                )
+            }
+            Command::TransferObjects(objects, recipient) => {
+                ParsedPTBCommand::TransferObjects(args(objects)?, arg(recipient)?)
+            }
+            Command::SplitCoins(coin, amounts) => {
+                ParsedPTBCommand::SplitCoins(arg(coin)?, args(amounts)?)
+            }
+            Command::MergeCoins(coin, coins) => {
+                ParsedPTBCommand::MergeCoins(arg(coin)?, args(coins)?)
+            }
+            Command::MakeMoveVec(Some(ty), elements) => ParsedPTBCommand::MakeMoveVec(
+                unspanned(parsed_type(&ty.to_type_tag()?)),
+                args(elements)?,
+            ),
+            Command::MakeMoveVec(None, _) => {
+                bail!("Command {ix} builds a vector without an element type, which PTBs require")
+            }
+            Command::Publish(..) | Command::Upgrade(..) => bail!(
+                "Command {ix} publishes or upgrades a package, which can only be done from the \
+                 package's source"
+            ),
+        };
+        commands.push(unspanned(command));
+        if used_results[ix] {
+            commands.push(unspanned(ParsedPTBCommand::Assign(
+                unspanned(result_variable(ix as u16)),
+                None,
+            )));
+        }
+    }
+
+    let sender = tx_data.sender();
+    let gas_owner = tx_data.gas_owner();
+    let gas = tx_data.gas();
+    let metadata = ProgramMetadata {
+        preview_set: false,
+        summary_set: false,
+        serialize_unsigned_set: false,
+        serialize_signed_set: false,
+        gas_object_ids: (!gas.is_empty())
+            .then(|| gas.iter().map(|(id, _, _)| unspanned(*id)).collect()),
+        json_set: false,
+        tx_digest_set: false,
+        dry_run_set: false,
+        dev_inspect_set: false,
+        simulate_set: false,
+        overrides: None,
+        gas_budget: Some(unspanned(tx_data.gas_budget())),
+        gas_price: Some(unspanned(tx_data.gas_price())),
+        gas_sponsor: (gas_owner != sender).then(|| unspanned(numerical_address(gas_owner.into()))),
+        mvr_names: BTreeMap::new(),
+        sender: Some(unspanned(numerical_address(sender.into()))),
+    };
+
+    Ok((
+        Program {
+            commands,
+            warn_shadows_set: false,
+        },
+        metadata,
+    ))
+}
+
+/// The `sui client ptb` arguments of `program`, grouped by command: each group starts with the
+/// command's flag, followed by its arguments.
+pub fn program_args((program, metadata): &ParsedProgram) -> Vec<Vec<String>> {
+    let flag = |name: &str| format!("--{name}");
+    let list = |args: &Spanned<Vec<Spanned<Argument>>>| {
+        format!(
+            "[{}]",
+            join(args.value.iter().map(|a| argument_str(&a.value)))
+        )
+    };
+
+    let mut lines = vec![];
+    if program.warn_shadows_set {
+        lines.push(vec![flag(WARN_SHADOWS)]);
+    }
+    for command in &program.commands {
+        lines.push(match &command.value {
+            ParsedPTBCommand::TransferObjects(objects, recipient) => vec![
+                flag(TRANSFER_OBJECTS),
+                list(objects),
+                argument_str(&recipient.value),
+            ],
+            ParsedPTBCommand::SplitCoins(coin, amounts) => {
+                vec![flag(SPLIT_COINS), argument_str(&coin.value), list(amounts)]
+            }
+            ParsedPTBCommand::MergeCoins(coin, coins) => {
+                vec![flag(MERGE_COINS), argument_str(&coin.value), list(coins)]
+            }
+            ParsedPTBCommand::MakeMoveVec(ty, elements) => vec![
+                flag(MAKE_MOVE_VEC),
+                format!("<{}>", type_str(&ty.value)),
+                list(elements),
+            ],
+            ParsedPTBCommand::MoveCall(access, type_args, args) => {
+                let ModuleAccess {
+                    address,
+                    module_name,
+                    function_name,
+                } = &access.value;
+                let mut line = vec![
+                    flag(MOVE_CALL),
+                    format!(
+                        "{}::{}::{}",
+                        address_str(&address.value),
+                        module_name.value,
+                        function_name.value
+                    ),
+                ];
+                if let Some(type_args) = type_args {
+                    line.push(format!("<{}>", join(type_args.value.iter().map(type_str))));
+                }
+                line.extend(args.iter().map(|a| argument_str(&a.value)));
+                line
+            }
+            ParsedPTBCommand::Assign(name, value) => {
+                let mut line = vec![flag(ASSIGN), name.value.clone()];
+                line.extend(value.iter().map(|v| argument_str(&v.value)));
+                line
+            }
+            ParsedPTBCommand::Publish(path) => vec![flag(PUBLISH), path.value.clone()],
+            ParsedPTBCommand::Upgrade(path, cap) => {
+                vec![flag(UPGRADE), path.value.clone(), argument_str(&cap.value)]
+            }
+            ParsedPTBCommand::WarnShadows => vec![flag(WARN_SHADOWS)],
+            ParsedPTBCommand::Preview => vec![flag(PREVIEW)],
+        });
+    }
+
+    if let Some(sender) = &metadata.sender {
+        lines.push(vec![flag(SENDER), format!("@{:#x}", sender.value)]);
+    }
+    if let Some(sponsor) = &metadata.gas_sponsor {
+        lines.push(vec![flag(GAS_SPONSOR), format!("@{:#x}", sponsor.value)]);
+    }
+    if let Some(coins) = &metadata.gas_object_ids {
+        let mut line = vec![flag(GAS_COIN)];
+        line.extend(
+            coins
+                .iter()
+                .map(|c| format!("@{:#x}", numerical_address(*c.value))),
+        );
+        lines.push(line);
+    }
+    if let Some(budget) = &metadata.gas_budget {
+        lines.push(vec![flag(GAS_BUDGET), budget.value.to_string()]);
+    }
+    if let Some(price) = &metadata.gas_price {
+        lines.push(vec![flag(GAS_PRICE), price.value.to_string()]);
+    }
+    for (set, name) in [
+        (metadata.preview_set, PREVIEW),
+        (metadata.summary_set, SUMMARY),
+        (metadata.json_set, JSON),
+        (metadata.tx_digest_set, TX_DIGEST),
+        (metadata.dry_run_set, DRY_RUN),
+        (metadata.dev_inspect_set, DEV_INSPECT),
+        (metadata.simulate_set, SIMULATE),
+        (metadata.serialize_unsigned_set, SERIALIZE_UNSIGNED),
+        (metadata.serialize_signed_set, SERIALIZE_SIGNED),
+    ] {
+        if set {
+            lines.push(vec![flag(name)]);
+        }
+    }
+    if let Some(path) = &metadata.overrides {
+        lines.push(vec![flag(OVERRIDES), path.value.clone()]);
+    }
+    lines
+}
+
+/// Render PTB arguments (as returned by `program_args`) as a `sui client ptb` shell command, one
+/// command per line.
+pub fn to_command_line(args: &[Vec<String>]) -> String {
+    let mut command = "sui client ptb".to_string();
+    for line in args {
+        command.push_str(" \\\n  ");
+        command.push_str(&join_with(line.iter().map(|a| shell_quote(a)), " "));
+    }
+    command
+}
+
+/// Render PTB arguments (as returned by `program_args`) as a script for `sui client ptb --file`,
+/// one command per line.
+pub fn to_script(args: &[Vec<String>]) -> String {
+    let mut script = String::new();
+    for line in args {
+        script.push_str(&join_with(line.iter().map(|a| script_quote(a)), " "));
+        script.push('\n');
+    }
+    script
+}
+
+/// Decode the types of the parameters of the function called by `call`, with its type arguments
+/// substituted in. Parameters that cannot receive pure values have no type.
+fn move_call_parameters(
+    package: &MovePackage,
+    call: &ProgrammableMoveCall,
+) -> Result<Vec<Option<TypeTag>>> {
+    let module = package.deserialize_module_by_str(&call.module, &BinaryConfig::standard())?;
+    let fdef = module
+        .function_defs
+        .iter()
+        .find(|fdef| {
+            module
+                .identifier_at(module.function_handle_at(fdef.function).name)
+                .as_str()
+                == call.function
+        })
+        .ok_or_else(|| {
+            anyhow!(
+                "Could not resolve function '{}' in module '{}'",
+                call.function,
+                call.module
+            )
+        })?;
+    let type_args = call
+        .type_arguments
+        .iter()
+        .map(|ty| ty.to_type_tag())
+        .collect::<Result<Vec<_>>>()?;
+
+    let function = module.function_handle_at(fdef.function);
+    Ok(module
+        .signature_at(function.parameters)
+        .0
+        .iter()
+        .filter(|tok| matches!(TxContext::kind(&module, tok), TxContextKind::None))
+        .map(|tok| signature_type(&module, &type_args, tok))
+        .collect())
+}
+
+/// The type of a value passed to a parameter of type `tok`, if it can be a pure value.
+fn signature_type(
+    view: &CompiledModule,
+    type_args: &[TypeTag],
+    tok: &SignatureToken,
+) -> Option<TypeTag> {
+    use SignatureToken as S;
+    Some(match tok {
+        S::Bool => TypeTag::Bool,
+        S::U8 => TypeTag::U8,
+        S::U16 => TypeTag::U16,
+        S::U32 => TypeTag::U32,
+        S::U64 => TypeTag::U64,
+        S::U128 => TypeTag::U128,
+        S::U256 => TypeTag::U256,
+        S::Address => TypeTag::Address,
+        S::Vector(inner) => TypeTag::Vector(Box::new(signature_type(view, type_args, inner)?)),
+        S::Datatype(idx) => struct_type(view, *idx, vec![]),
+        S::DatatypeInstantiation(inst) => {
+            let (idx, tys) = &**inst;
+            let tys = tys
+                .iter()
+                .map(|ty| signature_type(view, type_args, ty))
+                .collect::<Option<_>>()?;
+            struct_type(view, *idx, tys)
+        }
+        S::TypeParameter(idx) => type_args.get(*idx as usize)?.clone(),
+        // Pure values can be borrowed
+        S::Reference(inner) | S::MutableReference(inner) => signature_type(view, type_args, inner)?,
+        S::Signer => return None,
+    })
+}
+
+fn struct_type(
+    view: &CompiledModule,
+    idx: DatatypeHandleIndex,
+    type_params: Vec<TypeTag>,
+) -> TypeTag {
+    let handle = view.datatype_handle_at(idx);
+    let module = view.module_handle_at(handle.module);
+    TypeTag::Struct(Box::new(StructTag {
+        address: *view.address_identifier_at(module.address),
+        module: view.identifier_at(module.name).to_owned(),
+        name: view.identifier_at(handle.name).to_owned(),
+        type_params,
+    }))
+}
+
+/// Infer the type of each pure input from the first command that uses it.
+fn infer_input_types(
+    pt: &ProgrammableTransaction,
+    param_types: &BTreeMap<usize, Vec<Option<TypeTag>>>,
+) -> Result<BTreeMap<u16, TypeTag>> {
+    let mut types = BTreeMap::new();
+    let mut infer = |arg: &Tx::Argument, ty: Option<&TypeTag>| {
+        if let (Tx::Argument::Input(ix), Some(ty)) = (arg, ty) {
+            types.entry(*ix).or_insert_with(|| ty.clone());
+        }
+    };
+
+    for (ix, command) in pt.commands.iter().enumerate() {
+        match command {
+            Command::MoveCall(call) => {
+                let params = param_types.get(&ix).ok_or_else(|| {
+                    anyhow!("Missing the signature of the function called by command {ix}")
+                })?;
+                ensure!(
+                    params.len() == call.arguments.len(),
+                    "Command {ix} passes {} arguments to a function with {} parameters",
+                    call.arguments.len(),
+                    params.len()
+                );
+                for (arg, ty) in call.arguments.iter().zip(params) {
+                    infer(arg, ty.as_ref());
+                }
+            }
+            Command::TransferObjects(_, recipient) => infer(recipient, Some(&TypeTag::Address)),
+            Command::SplitCoins(_, amounts) => {
+                for amount in amounts {
+                    infer(amount, Some(&TypeTag::U64));
+                }
+            }
+            Command::MakeMoveVec(Some(ty), elements) => {
+                let ty = ty.to_type_tag()?;
+                for element in elements {
+                    infer(element, Some(&ty));
+                }
+            }
+            Command::MakeMoveVec(None, _)
+            | Command::MergeCoins(..)
+            | Command::Publish(..)
+            | Command::Upgrade(..) => {}
+        }
+    }
+    Ok(types)
+}
+
+fn command_arguments(command: &Command) -> Vec<&Tx::Argument> {
+    match command {
+        Command::MoveCall(call) => call.arguments.iter().collect(),
+        Command::TransferObjects(objects, recipient) => {
+            objects.iter().chain(std::iter::once(recipient)).collect()
+        }
+        Command::SplitCoins(coin, args) | Command::MergeCoins(coin, args) => {
+            std::iter::once(coin).chain(args).collect()
+        }
+        Command::MakeMoveVec(_, elements) => elements.iter().collect(),
+        Command::Publish(..) => vec![],
+        Command::Upgrade(_, _, _, ticket) => vec![ticket],
+    }
+}
+
+/// The PTB argument for an input of the transaction.
+fn input_argument(ix: u16, input: &CallArg, ty: Option<&TypeTag>) -> Result<Argument> {
+    Ok(match input {
+        CallArg::Pure(bytes) => {
+            let ty = ty.ok_or_else(|| anyhow!("Cannot infer the type of pure input {ix}"))?;
+            let mut decoder = PureDecoder { bytes };
+            let value = decoder
+                .decode(ty)
+                .with_context(|| format!("Cannot decode pure input {ix} as a {ty}"))?;
+            ensure!(
+                decoder.bytes.is_empty(),
+                "Cannot decode pure input {ix} as a {ty}: trailing bytes"
+            );
+            value
+        }
+        CallArg::Object(
+            ObjectArg::ImmOrOwnedObject((id, _, _))
+            | ObjectArg::Receiving((id, _, _))
+            | ObjectArg::SharedObject { id, .. },
+        ) => Argument::Address(numerical_address((*id).into())),
+        CallArg::BalanceWithdraw(_) => {
+            bail!("Input {ix} withdraws from an address balance, which PTBs do not support")
+        }
+    })
+}
+
+/// Decodes BCS encoded pure values into PTB arguments.
+struct PureDecoder<'b> {
+    bytes: &'b [u8],
+}
+
+impl PureDecoder<'_> {
+    fn decode(&mut self, ty: &TypeTag) -> Result<Argument> {
+        Ok(match ty {
+            TypeTag::Bool => match self.take::<1>()? {
+                [0] => Argument::Bool(false),
+                [1] => Argument::Bool(true),
+                [b] => bail!("Invalid bool {b}"),
+            },
+            TypeTag::U8 => number(u8::from_le_bytes(self.take()?)),
+            TypeTag::U16 => number(u16::from_le_bytes(self.take()?)),
+            TypeTag::U32 => number(u32::from_le_bytes(self.take()?)),
+            TypeTag::U64 => number(u64::from_le_bytes(self.take()?)),
+            TypeTag::U128 => number(u128::from_le_bytes(self.take()?)),
+            TypeTag::U256 => Argument::InferredNum(U256::from_le_bytes(&self.take()?)),
+            TypeTag::Address => self.address()?,
+            TypeTag::Vector(inner) if **inner == TypeTag::U8 => {
+                let bytes = self.bytes_vector()?;
+                match std::str::from_utf8(&bytes) {
+                    Ok(s) if is_string_literal(s) => Argument::String(s.to_owned()),
+                    _ => {
+                        Argument::Vector(bytes.into_iter().map(|b| unspanned(number(b))).collect())
+                    }
+                }
+            }
+            TypeTag::Vector(inner) => {
+                let len = self.uleb128()?;
+                Argument::Vector(
+                    (0..len)
+                        .map(|_| Ok(unspanned(self.decode(inner)?)))
+                        .collect::<Result<_>>()?,
+                )
+            }
+            TypeTag::Struct(tag) => {
+                let resolved = (
+                    &tag.address,
+                    tag.module.as_ident_str(),
+                    tag.name.as_ident_str(),
+                );
+                if resolved == RESOLVED_SUI_ID {
+                    self.address()?
+                } else if resolved == RESOLVED_ASCII_STR || resolved == RESOLVED_UTF8_STR {
+                    let bytes = self.bytes_vector()?;
+                    let s =
+                        String::from_utf8(bytes).map_err(|_| anyhow!("Invalid UTF-8 string"))?;
+                    ensure!(
+                        is_string_literal(&s),
+                        "String {s:?} cannot be written as a PTB string literal"
+                    );
+                    Argument::String(s)
+                } else if resolved == RESOLVED_STD_OPTION && tag.type_params.len() == 1 {
+                    let value = match self.uleb128()? {
+                        0 => None,
+                        1 => Some(Box::new(self.decode(&tag.type_params[0])?)),
+                        n => bail!("Invalid option with {n} elements"),
+                    };
+                    Argument::Option(unspanned(value))
+                } else {
+                    bail!("{ty} is not a pure type")
+                }
+            }
+            TypeTag::Signer => bail!("{ty} is not a pure type"),
+        })
+    }
+
+    fn take<const N: usize>(&mut self) -> Result<[u8; N]> {
+        ensure!(self.bytes.len() >= N, "Unexpected end of value");
+        let (taken, rest) = self.bytes.split_at(N);
+        self.bytes = rest;
+        Ok(taken.try_into().unwrap())
+    }
+
+    fn address(&mut self) -> Result<Argument> {
+        Ok(Argument::Address(NumericalAddress::new(
+            self.take()?,
+            NumberFormat::Hex,
+        )))
+    }
+
+    fn bytes_vector(&mut self) -> Result<Vec<u8>> {
+        let len = self.uleb128()?;
+        ensure!(self.bytes.len() >= len, "Unexpected end of value");
+        let (bytes, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(bytes.to_vec())
+    }
+
+    fn uleb128(&mut self) -> Result<usize> {
+        let mut value: u64 = 0;
+        for shift in (0..32).step_by(7) {
+            let [byte] = self.take()?;
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value as usize);
+            }
+        }
+        bail!("Invalid vector length")
+    }
+}
+
+fn number(n: impl Into<U256>) -> Argument {
+    Argument::InferredNum(n.into())
+}
+
+/// Whether `s` can be written as a PTB string literal: PTB strings do not support escape
+/// sequences, so `s` must be printable, contain no backslashes and at most one kind of quote.
+fn is_string_literal(s: &str) -> bool {
+    !s.chars().any(|c| c.is_control() || c == '\\') && !(s.contains('"') && s.contains('\''))
+}
+
+fn result_variable(ix: u16) -> String {
+    format!("result_{ix}")
+}
+
+fn numerical_address(address: AccountAddress) -> NumericalAddress {
+    NumericalAddress::new(address.into_bytes(), NumberFormat::Hex)
+}
+
+fn unspanned<T>(value: T) -> Spanned<T> {
+    Span { start: 0, end: 0 }.wrap(value)
+}
+
+fn parsed_type(ty: &TypeTag) -> ParsedType {
+    match ty {
+        TypeTag::Bool => ParsedType::Bool,
+        TypeTag::U8 => ParsedType::U8,
+        TypeTag::U16 => ParsedType::U16,
+        TypeTag::U32 => ParsedType::U32,
+        TypeTag::U64 => ParsedType::U64,
+        TypeTag::U128 => ParsedType::U128,
+        TypeTag::U256 => ParsedType::U256,
+        TypeTag::Address => ParsedType::Address,
+        TypeTag::Signer => ParsedType::Signer,
+        TypeTag::Vector(inner) => ParsedType::Vector(Box::new(parsed_type(inner))),
+        TypeTag::Struct(tag) => ParsedType::Struct(ParsedStructType {
+            fq_name: ParsedFqName {
+                module: ParsedModuleId {
+                    address: ParsedAddress::Numerical(numerical_address(tag.address)),
+                    name: tag.module.to_string(),
+                },
+                name: tag.name.to_string(),
+            },
+            type_args: tag.type_params.iter().map(parsed_type).collect(),
+        }),
+    }
+}
+
+fn join(items: impl Iterator<Item = String>) -> String {
+    join_with(items, ", ")
+}
+
+fn join_with(items: impl Iterator<Item = String>, sep: &str) -> String {
+    items.collect::<Vec<_>>().join(sep)
+}
+
+fn address_str(address: &ParsedAddress) -> String {
+    match address {
+        ParsedAddress::Named(name) => name.clone(),
+        ParsedAddress::Numerical(address) => format!("{address:#x}"),
+    }
+}
+
+fn type_str(ty: &ParsedType) -> String {
+    match ty {
+        ParsedType::Vector(inner) => format!("vector<{}>", type_str(inner)),
+        ParsedType::Struct(ParsedStructType {
+            fq_name:
+                ParsedFqName {
+                    module: ParsedModuleId { address, name },
+                    name: struct_name,
+                },
+            type_args,
+        }) => {
+            let address = address_str(address);
+            if type_args.is_empty() {
+                format!("{address}::{name}::{struct_name}")
+            } else {
+                let type_args = join(type_args.iter().map(type_str));
+                format!("{address}::{name}::{struct_name}<{type_args}>")
+            }
+        }
+        ParsedType::Address => "address".to_string(),
+        ParsedType::U8 => "u8".to_string(),
+        ParsedType::U16 => "u16".to_string(),
+        ParsedType::U32 => "u32".to_string(),
+        ParsedType::U64 => "u64".to_string(),
+        ParsedType::U128 => "u128".to_string(),
+        ParsedType::U256 => "u256".to_string(),
+        ParsedType::Bool => "bool".to_string(),
+        ParsedType::Signer => "signer".to_string(),
+    }
+}
+
+/// Render an argument the way the PTB parser reads it back. Unlike `Argument`'s `Display`, strings
+/// are not escaped (PTB strings do not support escapes), and addresses are written in lowercase.
+fn argument_str(arg: &Argument) -> String {
+    match arg {
+        Argument::Address(address) => format!("@{address:#x}"),
+        Argument::String(s) if s.contains('"') => format!("'{s}'"),
+        Argument::String(s) => format!("\"{s}\""),
+        Argument::Vector(elements) => {
+            format!(
+                "vector[{}]",
+                join(elements.iter().map(|e| argument_str(&e.value)))
+            )
+        }
+        Argument::Option(Spanned { value: Some(v), .. }) => format!("some({})", argument_str(v)),
+        Argument::Option(Spanned { value: None, .. }) => "none".to_string(),
+        arg => arg.to_string(),
+    }
+}
+
+/// Quote `arg` for a POSIX shell, if it contains characters the shell would interpret.
+fn shell_quote(arg: &str) -> String {
+    let safe = |c: char| c.is_ascii_alphanumeric() || "@_-.,:/=+%".contains(c);
+    if !arg.is_empty() && arg.chars().all(safe) {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', r"'\''"))
+    }
+}
+
+/// Quote `arg` for a PTB script, if it contains characters the script reader would interpret.
+/// Other arguments are split on whitespace when the script is read, which the PTB parser
+/// tolerates.
+fn script_quote(arg: &str) -> String {
+    if !arg.contains(['\'', '"', '$', '\\', '#']) {
+        arg.to_string()
+    } else if !arg.contains('\'') {
+        format!("'{arg}'")
+    } else {
+        let escaped = arg
+            .replace('\\', r"\\")
+            .replace('"', "\\\"")
+            .replace('$', r"\$");
+        format!("\"{escaped}\"")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client_ptb::{ptb::PTB, script::PTBSource};
+    use std::str::FromStr;
+    use sui_types::{
+        base_types::{random_object_ref, SuiAddress},
+        programmable_transaction_builder::ProgrammableTransactionBuilder,
+    };
+
+    fn transaction(build: impl FnOnce(&mut ProgrammableTransactionBuilder)) -> TransactionData {
+        let mut builder = ProgrammableTransactionBuilder::new();
+        build(&mut builder);
+        TransactionData::new_programmable(
+            SuiAddress::ZERO,
+            vec![random_object_ref()],
+            builder.finish(),
+            1_000_000,
+            1000,
+        )
+    }
+
+    fn emit(
+        tx_data: &TransactionData,
+        param_types: &BTreeMap<usize, Vec<Option<TypeTag>>>,
+    ) -> Vec<Vec<String>> {
+        let TransactionKind::ProgrammableTransaction(pt) = tx_data.kind() else {
+            unreachable!()
+        };
+        program_args(&build_program(tx_data, pt, param_types).unwrap())
+    }
+
+    #[test]
+    fn test_emit_split_and_transfer() {
+        let recipient = SuiAddress::from_str("0x42").unwrap();
+        let tx_data = transaction(|builder| {
+            let amounts = vec![
+                builder.pure(1000u64).unwrap(),
+                builder.pure(2000u64).unwrap(),
+            ];
+            let coins = builder.command(Command::SplitCoins(Tx::Argument::GasCoin, amounts));
+            let Tx::Argument::Result(coins) = coins else {
+                unreachable!()
+            };
+            let recipient = builder.pure(recipient).unwrap();
+            builder.command(Command::TransferObjects(
+                vec![
+                    Tx::Argument::NestedResult(coins, 0),
+                    Tx::Argument::NestedResult(coins, 1),
+                ],
+                recipient,
+            ));
+        });
+
+        let args = emit(&tx_data, &BTreeMap::new());
+        let gas_coin = format!("@{:#x}", numerical_address(*tx_data.gas()[0].0));
+        assert_eq!(
+            args,
+            vec![
+                vec!["--split-coins", "gas", "[1000, 2000]"],
+                vec!["--assign", "result_0"],
+                vec!["--transfer-objects", "[result_0.0, result_0.1]", "@0x42"],
+                vec!["--sender", "@0x0"],
+                vec!["--gas-coin", gas_coin.as_str()],
+                vec!["--gas-budget", "1000000"],
+                vec!["--gas-price", "1000"],
+            ]
+        );
+
+        // The emitted arguments parse back into a program.
+        PTB::parse_ptb_commands(args.concat()).unwrap();
+    }
+
+    #[test]
+    fn test_emit_move_call() {
+        let object = random_object_ref();
+        let tx_data = transaction(|builder| {
+            let name = builder.pure("a \"quoted\" name".as_bytes()).unwrap();
+            let data = builder.pure(vec![0xffu8, 0]).unwrap();
+            let limit = builder.pure(Some(5u32)).unwrap();
+            let object = builder.obj(ObjectArg::ImmOrOwnedObject(object)).unwrap();
+            builder.programmable_move_call(
+                ObjectID::from_hex_literal("0xabc").unwrap(),
+                Identifier::new("m").unwrap(),
+                Identifier::new("f").unwrap(),
+                vec![TypeTag::U64],
+                vec![object, name, data, limit],
+            );
+        });
+
+        let option_u32 = TypeTag::Struct(Box::new(StructTag {
+            address: AccountAddress::ONE,
+            module: Identifier::new("option").unwrap(),
+            name: Identifier::new("Option").unwrap(),
+            type_params: vec![TypeTag::U32],
+        }));
+        let vector_u8 = TypeTag::Vector(Box::new(TypeTag::U8));
+        let param_types = BTreeMap::from([(
+            0,
+            vec![
+                None,
+                Some(vector_u8.clone()),
+                Some(vector_u8),
+                Some(option_u32),
+            ],
+        )]);
+
+        let args = emit(&tx_data, &param_types);
+        let object = format!("@{:#x}", numerical_address(*object.0));
+        assert_eq!(
+            args[0],
+            vec![
+                "--move-call",
+                "0xabc::m::f",
+                "<u64>",
+                object.as_str(),
+                "'a \"quoted\" name'",
+                "vector[255, 0]",
+                "some(5)",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_emit_unsupported() {
+        let tx_data = transaction(|builder| {
+            builder.command(Command::MakeMoveVec(None, vec![]));
+        });
+        let TransactionKind::ProgrammableTransaction(pt) = tx_data.kind() else {
+            unreachable!()
+        };
+        assert!(build_program(&tx_data, pt, &BTreeMap::new()).is_err());
+
+        // Pure inputs whose type cannot be inferred
+        let tx_data = transaction(|builder| {
+            builder.pure(1u64).unwrap();
+        });
+        let TransactionKind::ProgrammableTransaction(pt) = tx_data.kind() else {
+            unreachable!()
+        };
+        assert!(build_program(&tx_data, pt, &BTreeMap::new()).is_err());
+    }
+
+    #[test]
+    fn test_quoting() {
+        assert_eq!(shell_quote("--split-coins"), "--split-coins");
+        assert_eq!(shell_quote("[1000, 2000]"), "'[1000, 2000]'");
+        assert_eq!(shell_quote("<u64>"), "'<u64>'");
+        assert_eq!(shell_quote("\"it's\""), r#"'"it'\''s"'"#);
+
+        assert_eq!(script_quote("[1000,"), "[1000,");
+        assert_eq!(script_quote("\"a b\""), "'\"a b\"'");
+        assert_eq!(script_quote("\"it's $x\""), r#""\"it's \$x\"""#);
+    }
+
+    #[test]
+    fn test_script_roundtrip() {
+        let args = vec![
+            vec![
+                "--assign".to_string(),
+                "s".to_string(),
+                "\"it's # $x\"".to_string(),
+            ],
+            vec![
+                "--split-coins".to_string(),
+                "gas".to_string(),
+                "[1, 2]".to_string(),
+            ],
+        ];
+        let path = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(path.path(), to_script(&args)).unwrap();
+        let source = PTBSource::new(vec![
+            "--file".to_string(),
+            path.path().to_string_lossy().into_owned(),
+        ])
+        .unwrap();
+        assert!(source.errors().is_empty());
+        assert_eq!(
+            source.args(),
+            [
+                "--assign",
+                "s",
+                "\"it's # $x\"",
+                "--split-coins",
+                "gas",
+                "[1,",
+                "2]"
+            ]
+        );
+    }
+}
@@ -319,6 +319,18 @@ impl<'l, I: Iterator<Item = &'l str>> Iterator for Lexer<'l, I> {
                         file.widen(c).map(|src| Lexeme(T::Upgrade, src))
                     }
 
+                    sp!(_, "overrides") => {
+                        if let Some(next) = self.peek() {
+                            break 'command self.unexpected(next);
+                        }
+
+                        let Some(file) = self.eat_token() else {
+                            break 'command self.done(T::EarlyEof);
+                        };
+
+                        file.widen(c).map(|src| Lexeme(T::Overrides, src))
+                    }
+
                     sp!(_, _) => ident.widen(c).map(|src| Lexeme(T::Command, src)),
                 }
             }
@@ -450,6 +462,12 @@ mod tests {
         insta::assert_debug_snapshot!(lex(args));
     }
 
+    #[test]
+    fn tokenize_overrides() {
+        let simulate = vec!["--simulate", "--overrides", "overrides.json"];
+        insta::assert_debug_snapshot!(lex(simulate));
+    }
+
     #[test]
     fn dotted_idents() {
         let idents = vec!["a", "a.b", "a.b.c", "a.b.c.d", "a.b.c.d.e"];
@@ -3,8 +3,11 @@
 
 pub mod ast;
 pub mod builder;
+pub mod emit;
 pub mod error;
 pub mod lexer;
 pub mod parser;
+pub mod plan;
 pub mod ptb;
+pub mod script;
 pub mod token;
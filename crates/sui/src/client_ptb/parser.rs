@@ -44,6 +44,8 @@ struct ProgramParsingState {
     tx_digest_set: bool,
     dry_run_set: bool,
     dev_inspect_set: bool,
+    simulate_set: bool,
+    overrides: Option<Spanned<String>>,
     gas_object_ids: Option<Vec<Spanned<ObjectID>>>,
     gas_budget: Option<Spanned<u64>>,
     gas_price: Option<Spanned<u64>>,
@@ -78,6 +80,8 @@ impl<'a, I: Iterator<Item = &'a str>> ProgramParser<'a, I> {
                 tx_digest_set: false,
                 dry_run_set: false,
                 dev_inspect_set: false,
+                simulate_set: false,
+                overrides: None,
                 gas_object_ids: None,
                 gas_budget: None,
                 gas_price: None,
@@ -130,6 +134,10 @@ impl<'a, I: Iterator<Item = &'a str>> ProgramParser<'a, I> {
                 L(T::Command, A::TX_DIGEST) => flag!(tx_digest_set),
                 L(T::Command, A::DRY_RUN) => flag!(dry_run_set),
                 L(T::Command, A::DEV_INSPECT) => flag!(dev_inspect_set),
+                L(T::Command, A::SIMULATE) => flag!(simulate_set),
+                L(T::Overrides, path) => {
+                    self.state.overrides = Some(sp.wrap(path.to_owned()));
+                }
                 L(T::Command, A::PREVIEW) => flag!(preview_set),
                 L(T::Command, A::WARN_SHADOWS) => flag!(warn_shadows_set),
                 L(T::Command, A::GAS_COIN) => {
@@ -257,6 +265,8 @@ impl<'a, I: Iterator<Item = &'a str>> ProgramParser<'a, I> {
                     tx_digest_set: self.state.tx_digest_set,
                     dry_run_set: self.state.dry_run_set,
                     dev_inspect_set: self.state.dev_inspect_set,
+                    simulate_set: self.state.simulate_set,
+                    overrides: self.state.overrides,
                     gas_budget: self.state.gas_budget,
                     gas_price: self.state.gas_price,
                     gas_sponsor: self.state.gas_sponsor,
@@ -931,6 +941,14 @@ mod tests {
         insta::assert_debug_snapshot!(parsed);
     }
 
+    #[test]
+    fn test_parse_simulate() {
+        let mut x = shlex::split("--simulate --overrides overrides.json").unwrap();
+        x.push("--gas-budget 1".to_owned());
+        let parser = ProgramParser::new(x.iter().map(|x| x.as_str())).unwrap();
+        insta::assert_debug_snapshot!(parser.parse().unwrap());
+    }
+
     #[test]
     fn test_parse_args() {
         let inputs = vec![
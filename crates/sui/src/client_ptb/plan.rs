@@ -0,0 +1,507 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Multi-step transaction plans, run with `sui client plan <FILE>`.
+//!
+//! A plan is a TOML file with an ordered list of steps, each of them a PTB script (see
+//! [`super::script`]) that is executed as its own transaction:
+//!
+//! ```toml
+//! [params]
+//! amount = "1000"
+//!
+//! [[step]]
+//! name = "publish"
+//! ptb = """
+//! --publish ./counter
+//! --assign cap
+//! --transfer-objects [cap] @$sender
+//! """
+//! outputs = { package = "published", counter = { created = "counter::Counter" } }
+//!
+//! [[step]]
+//! name = "increment"
+//! ptb = "--move-call $package::counter::increment @$counter $amount"
+//! ```
+//!
+//! Steps refer to the plan's `params`, to the active address as `$sender`, and to the outputs of
+//! earlier steps: the package a step published, or an object it created, selected by type (either
+//! a full type, or `module::Name` for types of packages published by the plan).
+//!
+//! Progress is recorded after each step in a file next to the plan, so that running a plan that
+//! failed part way resumes it from the step that failed.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use move_core_types::language_storage::StructTag;
+use mysten_common::fs::write_atomic;
+use serde::{Deserialize, Serialize};
+use sui_json_rpc_types::{ObjectChange, SuiTransactionBlockResponseOptions};
+use sui_sdk::wallet_context::WalletContext;
+use sui_types::{base_types::ObjectID, digests::TransactionDigest, parse_sui_struct_tag};
+
+use super::{
+    ptb::PTB,
+    script::{is_param_name, PTBSource},
+};
+
+/// Name of the parameter holding the active address, unless the plan sets it.
+const SENDER_PARAM: &str = "sender";
+
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct Plan {
+    /// Values substituted in the steps' scripts.
+    #[serde(default)]
+    params: BTreeMap<String, String>,
+    #[serde(rename = "step")]
+    steps: Vec<PlanStep>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct PlanStep {
+    name: String,
+    /// The PTB script of the step.
+    ptb: String,
+    /// Values from the effects of the step, available to later steps as parameters.
+    #[serde(default)]
+    outputs: BTreeMap<String, Output>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Output {
+    /// The ID of the package published by the step.
+    Published,
+    /// The ID of the object of this type created by the step.
+    Created(String),
+}
+
+/// The steps of a plan that were executed, in order.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct PlanProgress {
+    steps: Vec<CompletedStep>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CompletedStep {
+    step: PlanStep,
+    digest: TransactionDigest,
+    /// Missing if they could not be selected from the step's effects.
+    outputs: Option<BTreeMap<String, ObjectID>>,
+}
+
+impl Plan {
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("Cannot read plan {}", path.display()))?;
+        let plan: Plan = toml::from_str(&contents)
+            .with_context(|| format!("Cannot parse plan {}", path.display()))?;
+        plan.validate()?;
+        Ok(plan)
+    }
+
+    fn validate(&self) -> Result<()> {
+        ensure!(!self.steps.is_empty(), "The plan has no steps");
+
+        let mut names = BTreeSet::new();
+        for name in self.params.keys() {
+            ensure!(is_param_name(name), "Invalid parameter name '{name}'");
+            names.insert(name.as_str());
+        }
+
+        let mut steps = BTreeSet::new();
+        for step in &self.steps {
+            ensure!(
+                steps.insert(step.name.as_str()),
+                "Step name '{}' is used more than once",
+                step.name
+            );
+            for (name, output) in &step.outputs {
+                ensure!(
+                    is_param_name(name),
+                    "Invalid output name '{name}' in step '{}'",
+                    step.name
+                );
+                ensure!(
+                    name != SENDER_PARAM && names.insert(name.as_str()),
+                    "Output name '{name}' of step '{}' is already used by a parameter or output",
+                    step.name
+                );
+                if let Output::Created(pattern) = output {
+                    TypePattern::parse(pattern).with_context(|| {
+                        format!("Invalid type for output '{name}' of step '{}'", step.name)
+                    })?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl PlanStep {
+    /// Select the outputs of this step from the object changes of its transaction.
+    fn select_outputs(&self, changes: &[ObjectChange]) -> Result<BTreeMap<String, ObjectID>> {
+        let mut outputs = BTreeMap::new();
+        for (name, output) in &self.outputs {
+            let ids: Vec<_> = match output {
+                Output::Published => changes
+                    .iter()
+                    .filter_map(|change| match change {
+                        ObjectChange::Published { package_id, .. } => Some(*package_id),
+                        _ => None,
+                    })
+                    .collect(),
+                Output::Created(pattern) => {
+                    let pattern = TypePattern::parse(pattern)?;
+                    changes
+                        .iter()
+                        .filter_map(|change| match change {
+                            ObjectChange::Created {
+                                object_type,
+                                object_id,
+                                ..
+                            } if pattern.matches(object_type) => Some(*object_id),
+                            _ => None,
+                        })
+                        .collect()
+                }
+            };
+            let id = match ids[..] {
+                [id] => id,
+                [] => bail!("No object matches output '{name}' of step '{}'", self.name),
+                _ => bail!(
+                    "Output '{name}' of step '{}' is ambiguous: {} objects match it",
+                    self.name,
+                    ids.len()
+                ),
+            };
+            outputs.insert(name.clone(), id);
+        }
+        Ok(outputs)
+    }
+}
+
+/// The type of a created object to select as an output.
+enum TypePattern {
+    /// Matches any type with this module and name, whatever its address and type parameters.
+    Name { module: String, name: String },
+    /// Matches this type, and any instantiation of it if it has no type parameters.
+    Tag(StructTag),
+}
+
+impl TypePattern {
+    fn parse(pattern: &str) -> Result<Self> {
+        if let Some((module, name)) = pattern.split_once("::") {
+            if !name.contains("::") && !pattern.contains('<') {
+                return Ok(Self::Name {
+                    module: module.to_string(),
+                    name: name.to_string(),
+                });
+            }
+        }
+        Ok(Self::Tag(parse_sui_struct_tag(pattern)?))
+    }
+
+    fn matches(&self, ty: &StructTag) -> bool {
+        match self {
+            Self::Name { module, name } => ty.module.as_str() == module && ty.name.as_str() == name,
+            Self::Tag(tag) => {
+                tag.address == ty.address
+                    && tag.module == ty.module
+                    && tag.name == ty.name
+                    && (tag.type_params.is_empty() || tag.type_params == ty.type_params)
+            }
+        }
+    }
+}
+
+impl PlanProgress {
+    /// Load the progress recorded at `path`, if there is any.
+    pub fn load(path: &Path) -> Result<Self> {
+        match fs::read(path) {
+            Ok(bytes) => serde_json::from_slice(&bytes)
+                .with_context(|| format!("Cannot parse plan progress {}", path.display())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn save(&self, path: &Path) -> Result<()> {
+        write_atomic(path, &serde_json::to_vec_pretty(self)?)
+    }
+
+    /// Check that the completed steps are the first steps of `plan`, unchanged.
+    fn check(&self, plan: &Plan) -> Result<()> {
+        ensure!(
+            self.steps.len() <= plan.steps.len(),
+            "{} steps were executed, but the plan only has {}",
+            self.steps.len(),
+            plan.steps.len()
+        );
+        for (completed, step) in self.steps.iter().zip(&plan.steps) {
+            ensure!(
+                completed.step.name == step.name && completed.step.ptb == step.ptb,
+                "Step '{}' was changed since it was executed in transaction {}. Use --restart to \
+                 run the plan from the start",
+                completed.step.name,
+                completed.digest
+            );
+        }
+        Ok(())
+    }
+}
+
+/// The path progress is recorded at by default: next to the plan.
+pub fn default_progress_path(plan: &Path) -> PathBuf {
+    plan.with_extension("progress.json")
+}
+
+/// Run the steps of the plan at `path` that were not executed yet, as recorded at
+/// `progress_path`. If `restart` is set, any recorded progress is discarded.
+pub async fn run_plan(
+    path: &Path,
+    progress_path: &Path,
+    restart: bool,
+    context: &mut WalletContext,
+) -> Result<PlanProgress> {
+    let plan = Plan::load(path)?;
+    let mut progress = if restart {
+        PlanProgress::default()
+    } else {
+        PlanProgress::load(progress_path)?
+    };
+    progress.check(&plan)?;
+
+    let mut params = plan.params.clone();
+    if !params.contains_key(SENDER_PARAM) {
+        params.insert(
+            SENDER_PARAM.to_string(),
+            context.active_address()?.to_string(),
+        );
+    }
+
+    // Outputs of completed steps, selected again if their outputs were changed since.
+    for (ix, step) in plan.steps.iter().enumerate().take(progress.steps.len()) {
+        let completed = &progress.steps[ix];
+        if completed.outputs.is_none() || completed.step.outputs != step.outputs {
+            let client = context.get_client().await?;
+            let response = client
+                .read_api()
+                .get_transaction_with_options(
+                    completed.digest,
+                    SuiTransactionBlockResponseOptions::new().with_object_changes(),
+                )
+                .await?;
+            let outputs =
+                step.select_outputs(response.object_changes.as_deref().unwrap_or_default())?;
+            progress.steps[ix].step = step.clone();
+            progress.steps[ix].outputs = Some(outputs);
+            progress.save(progress_path)?;
+        }
+        params.extend(output_params(progress.steps[ix].outputs.as_ref()));
+    }
+
+    for step in &plan.steps[progress.steps.len()..] {
+        eprintln!("Running step '{}'", step.name);
+        let source = PTBSource::from_script(&step.ptb, &params);
+        let response = PTB::execute_source(source, context)
+            .await
+            .with_context(|| {
+                format!(
+                    "Step '{}' failed. Completed steps are recorded in {}, run the plan again to \
+                     resume from this step",
+                    step.name,
+                    progress_path.display()
+                )
+            })?
+            .ok_or_else(|| anyhow!("Step '{}' did not execute a transaction", step.name))?;
+
+        // Record the step before selecting its outputs, so that it is not executed again.
+        let outputs = step.select_outputs(response.object_changes.as_deref().unwrap_or_default());
+        progress.steps.push(CompletedStep {
+            step: step.clone(),
+            digest: response.digest,
+            outputs: outputs.as_ref().ok().cloned(),
+        });
+        progress.save(progress_path)?;
+        params.extend(output_params(Some(&outputs?)));
+    }
+
+    Ok(progress)
+}
+
+fn output_params(
+    outputs: Option<&BTreeMap<String, ObjectID>>,
+) -> impl Iterator<Item = (String, String)> + '_ {
+    outputs
+        .into_iter()
+        .flatten()
+        .map(|(name, id)| (name.clone(), id.to_string()))
+}
+
+impl fmt::Display for PlanProgress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for completed in &self.steps {
+            writeln!(f, "Step '{}': {}", completed.step.name, completed.digest)?;
+            for (name, id) in completed.outputs.iter().flatten() {
+                writeln!(f, "  {name}: {id}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sui_types::{
+        base_types::{random_object_ref, SuiAddress},
+        object::Owner,
+    };
+
+    fn created(object_type: &str) -> ObjectChange {
+        let (object_id, version, digest) = random_object_ref();
+        ObjectChange::Created {
+            sender: SuiAddress::ZERO,
+            owner: Owner::AddressOwner(SuiAddress::ZERO),
+            object_type: parse_sui_struct_tag(object_type).unwrap(),
+            object_id,
+            version,
+            digest,
+        }
+    }
+
+    fn published() -> ObjectChange {
+        let (package_id, version, digest) = random_object_ref();
+        ObjectChange::Published {
+            package_id,
+            version,
+            digest,
+            modules: vec!["counter".to_string()],
+        }
+    }
+
+    fn id(change: &ObjectChange) -> ObjectID {
+        match change {
+            ObjectChange::Created { object_id, .. } => *object_id,
+            ObjectChange::Published { package_id, .. } => *package_id,
+            _ => unreachable!(),
+        }
+    }
+
+    const PLAN: &str = r#"
+        [params]
+        amount = "1000"
+
+        [[step]]
+        name = "publish"
+        ptb = "--publish ./counter"
+        outputs = { package = "published", counter = { created = "counter::Counter" } }
+
+        [[step]]
+        name = "increment"
+        ptb = "--move-call $package::counter::increment @$counter $amount"
+    "#;
+
+    #[test]
+    fn test_parse_plan() {
+        let plan: Plan = toml::from_str(PLAN).unwrap();
+        plan.validate().unwrap();
+        assert_eq!(plan.steps.len(), 2);
+        assert_eq!(plan.steps[0].outputs["package"], Output::Published);
+        assert_eq!(
+            plan.steps[0].outputs["counter"],
+            Output::Created("counter::Counter".to_string())
+        );
+        assert!(plan.steps[1].outputs.is_empty());
+    }
+
+    #[test]
+    fn test_invalid_plans() {
+        let invalid = [
+            // No steps
+            "[params]\na = \"1\"",
+            // Duplicate step names
+            "[[step]]\nname = \"a\"\nptb = \"\"\n[[step]]\nname = \"a\"\nptb = \"\"",
+            // Output shadowing a parameter
+            "[params]\na = \"1\"\n[[step]]\nname = \"s\"\nptb = \"\"\noutputs = { a = \"published\" }",
+            // Output named after the sender
+            "[[step]]\nname = \"s\"\nptb = \"\"\noutputs = { sender = \"published\" }",
+            // Invalid type
+            "[[step]]\nname = \"s\"\nptb = \"\"\noutputs = { a = { created = \"x::y::z::w\" } }",
+        ];
+        for plan in invalid {
+            let plan: Result<Plan> = toml::from_str(plan).map_err(Into::into);
+            assert!(plan.and_then(|p| p.validate()).is_err());
+        }
+    }
+
+    #[test]
+    fn test_select_outputs() {
+        let plan: Plan = toml::from_str(PLAN).unwrap();
+        let step = &plan.steps[0];
+
+        let package = published();
+        let counter = created("0x42::counter::Counter");
+        let changes = vec![
+            package.clone(),
+            created("0x2::package::UpgradeCap"),
+            counter.clone(),
+        ];
+        let outputs = step.select_outputs(&changes).unwrap();
+        assert_eq!(outputs["package"], id(&package));
+        assert_eq!(outputs["counter"], id(&counter));
+
+        // Missing and ambiguous outputs
+        assert!(step.select_outputs(&[package.clone()]).is_err());
+        let ambiguous = vec![package, counter, created("0x43::counter::Counter")];
+        assert!(step.select_outputs(&ambiguous).is_err());
+    }
+
+    #[test]
+    fn test_type_patterns() {
+        let ty = parse_sui_struct_tag("0x2::coin::Coin<0x2::sui::SUI>").unwrap();
+        let matches = |pattern| TypePattern::parse(pattern).unwrap().matches(&ty);
+        assert!(matches("coin::Coin"));
+        assert!(matches("0x2::coin::Coin"));
+        assert!(matches("0x2::coin::Coin<0x2::sui::SUI>"));
+        assert!(!matches("0x2::coin::Coin<0x2::coin::COIN>"));
+        assert!(!matches("0x3::coin::Coin"));
+        assert!(!matches("coin::TreasuryCap"));
+    }
+
+    #[test]
+    fn test_progress() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = default_progress_path(&dir.path().join("plan.toml"));
+        assert_eq!(path, dir.path().join("plan.progress.json"));
+
+        let plan: Plan = toml::from_str(PLAN).unwrap();
+        let progress = PlanProgress::load(&path).unwrap();
+        assert!(progress.steps.is_empty());
+        progress.check(&plan).unwrap();
+
+        let progress = PlanProgress {
+            steps: vec![CompletedStep {
+                step: plan.steps[0].clone(),
+                digest: TransactionDigest::random(),
+                outputs: Some(BTreeMap::new()),
+            }],
+        };
+        progress.save(&path).unwrap();
+        let progress = PlanProgress::load(&path).unwrap();
+        progress.check(&plan).unwrap();
+
+        // A completed step was changed
+        let mut changed = plan;
+        changed.steps[0].ptb = "--publish ./other".to_string();
+        assert!(progress.check(&changed).is_err());
+    }
+}
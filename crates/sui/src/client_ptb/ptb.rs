@@ -9,6 +9,7 @@ use crate::{
         ast::{ParsedProgram, Program},
         builder::{resolve_package, PTBBuilder},
         error::{build_error_reports, PTBError, Span},
+        script::PTBSource,
         token::{Lexeme, Token},
     },
     displays::Pretty,
@@ -21,8 +22,10 @@ use anyhow::{anyhow, ensure, Error};
 use clap::{arg, Args, ValueHint};
 use move_core_types::account_address::AccountAddress;
 use serde::Serialize;
-use std::collections::BTreeMap;
-use sui_json_rpc_types::{SuiExecutionStatus, SuiTransactionBlockEffectsAPI};
+use std::{collections::BTreeMap, path::PathBuf};
+use sui_json_rpc_types::{
+    SuiExecutionStatus, SuiTransactionBlockEffectsAPI, SuiTransactionBlockResponse,
+};
 use sui_keys::keystore::AccountKeystore;
 use sui_sdk::{wallet_context::WalletContext, SuiClient};
 use sui_types::{
@@ -81,14 +84,43 @@ impl PTB {
             ptb_description().print_help().unwrap();
             return Ok(());
         }
-        let source_string = to_source_string(self.args.clone());
+
+        // Read scripts passed with --file, errors are reported against their text
+        let source = PTBSource::new(self.args)?;
+        Self::execute_source(source, context).await?;
+        Ok(())
+    }
+
+    /// Parses and executes the PTB read from `source`, printing its results. Returns the response
+    /// of the transaction if it was executed (rather than e.g. previewed or dry-run).
+    pub async fn execute_source(
+        source: PTBSource,
+        context: &mut WalletContext,
+    ) -> Result<Option<SuiTransactionBlockResponse>, Error> {
+        let source_string = source.text();
+        if !source.errors().is_empty() {
+            let errors = source.errors().to_vec();
+            let suffix = if errors.len() > 1 { "s" } else { "" };
+            let rendered = build_error_reports(source_string, errors);
+            eprintln!("Encountered error{suffix} when reading PTB script:");
+            for e in rendered.iter() {
+                eprintln!("{:?}", e);
+            }
+            anyhow::bail!("Could not build PTB due to previous error{suffix}");
+        }
 
         // Tokenize once to detect help flags
-        let tokens = self.args.iter().map(|s| s.as_str());
+        let tokens = source.args().iter().map(|s| s.as_str());
         for sp!(_, lexeme) in Lexer::new(tokens.clone()).into_iter().flatten() {
             match lexeme {
-                Lexeme(Token::Command, "help") => return Ok(ptb_description().print_long_help()?),
-                Lexeme(Token::Flag, "h") => return Ok(ptb_description().print_help()?),
+                Lexeme(Token::Command, "help") => {
+                    ptb_description().print_long_help()?;
+                    return Ok(None);
+                }
+                Lexeme(Token::Flag, "h") => {
+                    ptb_description().print_help()?;
+                    return Ok(None);
+                }
                 lexeme if lexeme.is_terminal() => break,
                 _ => continue,
             }
@@ -101,7 +133,7 @@ impl PTB {
         {
             Err(errors) => {
                 let suffix = if errors.len() > 1 { "s" } else { "" };
-                let rendered = build_error_reports(&source_string, errors);
+                let rendered = build_error_reports(source_string, source.map_errors(errors));
                 eprintln!("Encountered error{suffix} when parsing PTB:");
                 for e in rendered.iter() {
                     eprintln!("{:?}", e);
@@ -124,7 +156,7 @@ impl PTB {
                     program_metadata: &program_metadata
                 }
             );
-            return Ok(());
+            return Ok(None);
         }
 
         let client = context.get_client().await?;
@@ -165,7 +197,7 @@ impl PTB {
         if !warnings.is_empty() {
             let suffix = if warnings.len() > 1 { "s" } else { "" };
             eprintln!("Warning{suffix} produced when building PTB:");
-            let rendered = build_error_reports(&source_string, warnings);
+            let rendered = build_error_reports(source_string, source.map_errors(warnings));
             for e in rendered.iter() {
                 eprintln!("{:?}", e);
             }
@@ -174,7 +206,7 @@ impl PTB {
             Err(errors) => {
                 let suffix = if errors.len() > 1 { "s" } else { "" };
                 eprintln!("Encountered error{suffix} when building PTB:");
-                let rendered = build_error_reports(&source_string, errors);
+                let rendered = build_error_reports(source_string, source.map_errors(errors));
                 for e in rendered.iter() {
                     eprintln!("{:?}", e);
                 }
@@ -215,9 +247,14 @@ impl PTB {
             tx_digest: program_metadata.tx_digest_set,
             dry_run: program_metadata.dry_run_set,
             dev_inspect: program_metadata.dev_inspect_set,
+            simulate: program_metadata.simulate_set,
+            overrides: program_metadata
+                .overrides
+                .map(|path| PathBuf::from(path.value)),
             serialize_unsigned_transaction: program_metadata.serialize_unsigned_set,
             serialize_signed_transaction: program_metadata.serialize_signed_set,
             sender: program_metadata.sender.map(|x| x.value.into_inner().into()),
+            offline_snapshot: None,
         };
 
         let gas_payment = client.transaction_builder().input_refs(&gas).await?;
@@ -226,6 +263,7 @@ impl PTB {
             sender,
             tx_kind,
             context,
+            client.transaction_builder(),
             gas_payment,
             gas_data,
             processing,
@@ -238,12 +276,12 @@ impl PTB {
             | SuiClientCommandResult::SerializedUnsignedTransaction(_)
             | SuiClientCommandResult::SerializedSignedTransaction(_) => {
                 println!("{transaction_response}");
-                return Ok(());
+                return Ok(None);
             }
             SuiClientCommandResult::TransactionBlock(response) => response,
             SuiClientCommandResult::DevInspect(response) => {
                 println!("{}", Pretty(&response));
-                return Ok(());
+                return Ok(None);
             }
             _ => anyhow::bail!("Internal error, unexpected response from PTB execution."),
         };
@@ -284,7 +322,7 @@ impl PTB {
             println!("{}", transaction_response);
         }
 
-        Ok(())
+        Ok(Some(transaction_response))
     }
 
     // Also used in testing, thus public
@@ -365,6 +403,17 @@ pub fn ptb_description() -> clap::Command {
             --"dev-inspect"
             "Perform a dev-inspect of the PTB instead of executing it."
         ))
+        .arg(arg!(
+            --"simulate"
+            "Execute the PTB locally against the latest state of the network instead of \
+            submitting it, and print its effects, events and return values."
+        ))
+        .arg(arg!(
+            --"overrides" <FILE>
+            "A JSON file of changes to the state used by --simulate: replacement contents for \
+            objects, local packages replacing published ones, MIST added to the gas coin and the \
+            clock timestamp."
+        ))
         .arg(arg!(
             --"gas-coin" <ID> ...
             "The object ID of the gas coin to use. If not specified, it will try to use the first \
@@ -464,6 +513,27 @@ pub fn ptb_description() -> clap::Command {
             --"upgrade" <MOVE_PACKAGE_PATH>
             "Upgrade the Move package. It takes as input the folder where the package exists."
         ).value_hint(ValueHint::DirPath))
+        .arg(arg!(
+            --"file" <PATH>
+            "Read PTB commands from a script file. Can be combined with other commands and flags."
+        ).long_help(
+            "Read PTB commands from a script file. Can be combined with other commands and flags.\
+            \n Commands in the file are written as on the command line, and can span several lines.\
+            \n Arguments are split on whitespace, and can be quoted with '...' or \"...\".\
+            \n A # at the start of an argument starts a comment that runs to the end of the line.\
+            \n $NAME or ${NAME} is replaced by the value of a parameter passed with --param.\
+            \n\nExamples:\
+            \n --file transfer.ptb --param amount=1000 --param to=@0x42\
+            \n\nWhere transfer.ptb contains:\
+            \n # Send some SUI\
+            \n --split-coins gas [$amount]\
+            \n --assign coins\
+            \n --transfer-objects [coins.0] $to"
+        ).value_hint(ValueHint::FilePath))
+        .arg(arg!(
+            --"param" <NAME_VALUE>
+            "Set a parameter used by script files, as NAME=VALUE."
+        ).value_names(["NAME=VALUE"]))
         .arg(arg!(
             --"preview"
             "Instead of executing the transaction, preview its PTB commands."
@@ -0,0 +1,493 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! PTB script files, passed to `sui client ptb` with `--file <PATH>`.
+//!
+//! A script contains the same arguments that would be passed on the command line, split like a
+//! shell would split them: arguments are separated by whitespace (including newlines), can be
+//! quoted with single or double quotes, and a `#` at the start of an argument starts a comment
+//! that runs to the end of the line.
+//!
+//! Scripts can refer to parameters passed with `--param NAME=VALUE` as `$NAME` or `${NAME}`
+//! (except inside single quotes). The value is substituted as is, so it can be any PTB argument,
+//! e.g. a number, an address or a variable.
+//!
+//! The lexer and parser work on the arguments joined by spaces. Errors are reported against the
+//! original text instead, by mapping each byte of each argument to its location in that text.
+
+use std::collections::BTreeMap;
+use std::iter::Peekable;
+use std::str::CharIndices;
+
+use anyhow::{anyhow, bail, Context};
+
+use crate::err;
+
+use super::error::{PTBError, Span};
+
+pub const FILE_FLAG: &str = "--file";
+pub const PARAM_FLAG: &str = "--param";
+
+/// The arguments of a PTB, read from the command line and from script files, along with the text
+/// to report errors against.
+pub struct PTBSource {
+    args: Vec<String>,
+    text: String,
+    /// For each argument, the location in `text` of each of its bytes, followed by the location
+    /// of its end.
+    offsets: Vec<Vec<usize>>,
+    /// Errors found while reading scripts.
+    errors: Vec<PTBError>,
+}
+
+impl PTBSource {
+    /// Read the PTB arguments from the command line arguments `args`, replacing `--file <PATH>`
+    /// with the arguments in the script at `PATH`, and substituting parameters passed with
+    /// `--param NAME=VALUE` in scripts.
+    pub fn new(args: Vec<String>) -> Result<Self, anyhow::Error> {
+        let mut params = BTreeMap::new();
+        let mut files = vec![];
+        let mut cli_args = vec![];
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            if arg == PARAM_FLAG {
+                let param = args
+                    .next()
+                    .ok_or_else(|| anyhow!("Expected NAME=VALUE after {PARAM_FLAG}"))?;
+                let (name, value) = parse_param(&param)?;
+                if params.insert(name.to_string(), value.to_string()).is_some() {
+                    bail!("Parameter '{name}' is passed more than once");
+                }
+            } else if arg == FILE_FLAG {
+                let path = args
+                    .next()
+                    .ok_or_else(|| anyhow!("Expected a path after {FILE_FLAG}"))?;
+                files.push(cli_args.len());
+                cli_args.push(path);
+            } else {
+                cli_args.push(arg);
+            }
+        }
+        if files.is_empty() && !params.is_empty() {
+            bail!("{PARAM_FLAG} can only be used with {FILE_FLAG}");
+        }
+
+        let mut source = Self::empty();
+        for (i, arg) in cli_args.into_iter().enumerate() {
+            if files.contains(&i) {
+                let script = std::fs::read_to_string(&arg)
+                    .with_context(|| format!("Cannot read PTB script {arg}"))?;
+                source.push_script(&script, &params);
+            } else {
+                source.push_arg(arg);
+            }
+        }
+        // A trailing space, to point unexpected end of input errors at.
+        source.text.push(' ');
+        Ok(source)
+    }
+
+    /// Read the PTB arguments from the text of a script, substituting `params` in it.
+    pub fn from_script(script: &str, params: &BTreeMap<String, String>) -> Self {
+        let mut source = Self::empty();
+        source.push_script(script, params);
+        source.text.push(' ');
+        source
+    }
+
+    fn empty() -> Self {
+        PTBSource {
+            args: vec![],
+            text: String::new(),
+            offsets: vec![],
+            errors: vec![],
+        }
+    }
+
+    /// The PTB arguments, to lex and parse.
+    pub fn args(&self) -> &[String] {
+        &self.args
+    }
+
+    /// The text to report errors against.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Errors found while reading the scripts, with spans into `text`.
+    pub fn errors(&self) -> &[PTBError] {
+        &self.errors
+    }
+
+    /// Map the span of an error found by lexing or parsing `args` to a span in `text`.
+    pub fn map_error(&self, error: PTBError) -> PTBError {
+        let span = if error.span == Span::eof_span() {
+            error.span
+        } else {
+            let start = self.map_offset(error.span.start);
+            // Map the last byte of the span rather than its end, so that it does not cover a
+            // closing quote or the whitespace after the argument.
+            let end = if error.span.end > error.span.start {
+                self.map_offset(error.span.end - 1) + 1
+            } else {
+                start
+            };
+            Span { start, end }
+        };
+        PTBError { span, ..error }
+    }
+
+    pub fn map_errors(&self, errors: Vec<PTBError>) -> Vec<PTBError> {
+        errors.into_iter().map(|e| self.map_error(e)).collect()
+    }
+
+    /// Map an offset into the arguments joined by single spaces, to an offset in `text`.
+    fn map_offset(&self, offset: usize) -> usize {
+        let mut arg_start = 0;
+        for (arg, offsets) in self.args.iter().zip(&self.offsets) {
+            if offset <= arg_start + arg.len() {
+                return offsets[offset.saturating_sub(arg_start)];
+            }
+            arg_start += arg.len() + 1;
+        }
+        self.text.len() - 1
+    }
+
+    /// Add an argument from the command line, as `to_source_string` would render it.
+    fn push_arg(&mut self, arg: String) {
+        if !self.text.is_empty() {
+            self.text
+                .push(if arg.starts_with("--") { '\n' } else { ' ' });
+        }
+        let start = self.text.len();
+        self.text.push_str(&arg);
+        self.offsets.push((start..=start + arg.len()).collect());
+        self.args.push(arg);
+    }
+
+    /// Add the arguments of a script, keeping its text as is.
+    fn push_script(&mut self, script: &str, params: &BTreeMap<String, String>) {
+        if !self.text.is_empty() {
+            self.text.push('\n');
+        }
+        let base = self.text.len();
+        self.text.push_str(script);
+
+        let mut splitter = ScriptSplitter {
+            chars: script.char_indices().peekable(),
+            base,
+            end: base + script.len(),
+            params,
+            errors: vec![],
+        };
+        while let Some((arg, offsets)) = splitter.next_arg() {
+            self.args.push(arg);
+            self.offsets.push(offsets);
+        }
+        self.errors.extend(splitter.errors);
+    }
+}
+
+fn parse_param(param: &str) -> Result<(&str, &str), anyhow::Error> {
+    let Some((name, value)) = param.split_once('=') else {
+        bail!("Invalid parameter '{param}', expected NAME=VALUE");
+    };
+    if !is_param_name(name) {
+        bail!("Invalid parameter name '{name}', expected letters, digits and underscores");
+    }
+    Ok((name, value))
+}
+
+pub(crate) fn is_param_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with(|c: char| c.is_ascii_digit())
+        && name.chars().all(is_param_char)
+}
+
+fn is_param_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+/// Splits a script into arguments, recording where each byte of each argument comes from.
+struct ScriptSplitter<'s> {
+    chars: Peekable<CharIndices<'s>>,
+    /// Offset of the script in the source text.
+    base: usize,
+    /// Offset of the end of the script in the source text.
+    end: usize,
+    params: &'s BTreeMap<String, String>,
+    errors: Vec<PTBError>,
+}
+
+#[derive(Default)]
+struct Arg {
+    value: String,
+    offsets: Vec<usize>,
+}
+
+impl Arg {
+    fn push(&mut self, c: char, offset: usize) {
+        self.value.push(c);
+        self.offsets.extend((0..c.len_utf8()).map(|i| offset + i));
+    }
+
+    /// Push `value` in place of the text at `span`.
+    fn push_str(&mut self, value: &str, span: Span) {
+        self.value.push_str(value);
+        // Map the substituted bytes to the start of the reference, except for the last one, so that
+        // a span covering the whole value covers the whole reference.
+        let last = value.len().saturating_sub(1);
+        self.offsets.extend((0..value.len()).map(|i| {
+            if i == last {
+                span.end - 1
+            } else {
+                span.start
+            }
+        }));
+    }
+}
+
+impl ScriptSplitter<'_> {
+    fn next_arg(&mut self) -> Option<(String, Vec<usize>)> {
+        self.skip_whitespace_and_comments();
+        let (start, _) = *self.chars.peek()?;
+
+        let mut arg = Arg::default();
+        let mut end = self.base + start;
+        while let Some(&(i, c)) = self.chars.peek() {
+            if c.is_whitespace() {
+                break;
+            }
+            self.chars.next();
+            let offset = self.base + i;
+            end = offset + c.len_utf8();
+            match c {
+                '\'' => end = self.single_quoted(&mut arg, offset),
+                '"' => end = self.double_quoted(&mut arg, offset),
+                '\\' => match self.chars.next() {
+                    // Line continuation
+                    Some((_, '\n')) => end = offset + 2,
+                    Some((j, c)) => {
+                        arg.push(c, self.base + j);
+                        end = self.base + j + c.len_utf8();
+                    }
+                    None => arg.push(c, offset),
+                },
+                '$' => end = self.param(&mut arg, offset),
+                c => arg.push(c, offset),
+            }
+        }
+        arg.offsets.push(end);
+        Some((arg.value, arg.offsets))
+    }
+
+    fn skip_whitespace_and_comments(&mut self) {
+        while let Some(&(_, c)) = self.chars.peek() {
+            if c.is_whitespace() {
+                self.chars.next();
+            } else if c == '#' {
+                while self.chars.next_if(|(_, c)| *c != '\n').is_some() {}
+            } else {
+                break;
+            }
+        }
+    }
+
+    /// Consume a single quoted string, whose opening quote is at `start`. Returns the offset of
+    /// its end.
+    fn single_quoted(&mut self, arg: &mut Arg, start: usize) -> usize {
+        for (i, c) in self.chars.by_ref() {
+            if c == '\'' {
+                return self.base + i + 1;
+            }
+            arg.push(c, self.base + i);
+        }
+        self.unterminated(start)
+    }
+
+    /// Consume a double quoted string, whose opening quote is at `start`. Returns the offset of
+    /// its end.
+    fn double_quoted(&mut self, arg: &mut Arg, start: usize) -> usize {
+        while let Some((i, c)) = self.chars.next() {
+            let offset = self.base + i;
+            match c {
+                '"' => return offset + 1,
+                '\\' => match self
+                    .chars
+                    .next_if(|(_, c)| matches!(c, '"' | '\\' | '$' | '\n'))
+                {
+                    Some((_, '\n')) => {}
+                    Some((j, c)) => arg.push(c, self.base + j),
+                    None => arg.push(c, offset),
+                },
+                '$' => {
+                    self.param(arg, offset);
+                }
+                c => arg.push(c, offset),
+            }
+        }
+        self.unterminated(start)
+    }
+
+    fn unterminated(&mut self, start: usize) -> usize {
+        self.errors.push(err!(
+            Span {
+                start,
+                end: self.end,
+            },
+            "Unterminated quoted string"
+        ));
+        self.end
+    }
+
+    /// Substitute the parameter referred to by the `$` at `start`, as `$NAME` or `${NAME}`.
+    /// Returns the offset of the end of the reference.
+    fn param(&mut self, arg: &mut Arg, start: usize) -> usize {
+        let braced = self.chars.next_if(|(_, c)| *c == '{').is_some();
+        let mut name = String::new();
+        let mut end = start + 1 + braced as usize;
+        while let Some((i, c)) = self.chars.next_if(|(_, c)| is_param_char(*c)) {
+            name.push(c);
+            end = self.base + i + 1;
+        }
+        if braced {
+            match self.chars.next_if(|(_, c)| *c == '}') {
+                Some((i, _)) => end = self.base + i + 1,
+                None => {
+                    self.errors.push(err!(
+                        Span { start, end },
+                        "Expected '}}' to end the parameter reference"
+                    ));
+                    return end;
+                }
+            }
+        }
+
+        let span = Span { start, end };
+        if name.is_empty() {
+            if braced {
+                self.errors.push(err!(
+                    span,
+                    "Expected a parameter name between '${{' and '}}'"
+                ));
+            } else {
+                // Not a reference
+                arg.push('$', start);
+            }
+            return end;
+        }
+
+        match self.params.get(&name) {
+            Some(value) => arg.push_str(value, span),
+            None => self.errors.push(err!(
+                span => help: { "Pass a value for it with --param {name}=<VALUE>" },
+                "Unknown parameter '{name}'"
+            )),
+        }
+        end
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn split(script: &str, params: &[(&str, &str)]) -> PTBSource {
+        let params = params
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        PTBSource::from_script(script, &params)
+    }
+
+    #[test]
+    fn split_script() {
+        let source = split(
+            "# Split a coin\n\
+             --split-coins gas [1000, 2000] # trailing comment\n\
+             --assign coins\n\
+             --move-call 0x2::foo::bar '\"hello # world\"' \"a\\\"b\"\n",
+            &[],
+        );
+        assert!(source.errors().is_empty());
+        assert_eq!(
+            source.args(),
+            [
+                "--split-coins",
+                "gas",
+                "[1000,",
+                "2000]",
+                "--assign",
+                "coins",
+                "--move-call",
+                "0x2::foo::bar",
+                "\"hello # world\"",
+                "a\"b",
+            ]
+        );
+    }
+
+    #[test]
+    fn substitute_params() {
+        let source = split(
+            "--split-coins gas [$amount] --transfer-objects [coin] ${to} '$amount' x$",
+            &[("amount", "100"), ("to", "@0x42")],
+        );
+        assert!(source.errors().is_empty());
+        assert_eq!(
+            source.args(),
+            [
+                "--split-coins",
+                "gas",
+                "[100]",
+                "--transfer-objects",
+                "[coin]",
+                "@0x42",
+                "$amount",
+                "x$",
+            ]
+        );
+    }
+
+    #[test]
+    fn script_errors() {
+        let script = "--split-coins gas [$amount] '--assign";
+        let source = split(script, &[]);
+        let errors = source.errors();
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[0].message, "Unknown parameter 'amount'");
+        assert_eq!(&script[errors[0].span.start..errors[0].span.end], "$amount");
+        assert_eq!(errors[1].message, "Unterminated quoted string");
+        assert_eq!(
+            &script[errors[1].span.start..errors[1].span.end],
+            "'--assign"
+        );
+    }
+
+    #[test]
+    fn map_spans_to_script() {
+        let script = "# comment\n--split-coins   gas\n  'foo bar'   [${amount}]";
+        let source = split(script, &[("amount", "1000")]);
+        assert_eq!(source.args(), ["--split-coins", "gas", "foo bar", "[1000]"]);
+
+        // Offsets in "--split-coins gas foo bar [1000]"
+        let text_of = |start, end| {
+            let span = source.map_error(err!(Span { start, end }, "error")).span;
+            &script[span.start..span.end]
+        };
+        assert_eq!(text_of(0, 13), "--split-coins");
+        assert_eq!(text_of(14, 17), "gas");
+        assert_eq!(text_of(18, 25), "foo bar");
+        assert_eq!(text_of(26, 32), "[${amount}]");
+        assert_eq!(text_of(27, 31), "${amount}");
+    }
+
+    #[test]
+    fn parse_params() {
+        assert_eq!(parse_param("amount=100").unwrap(), ("amount", "100"));
+        assert_eq!(parse_param("to=@0x1=2").unwrap(), ("to", "@0x1=2"));
+        assert!(parse_param("amount").is_err());
+        assert!(parse_param("1x=2").is_err());
+        assert!(parse_param("a-b=2").is_err());
+    }
+}
@@ -57,11 +57,13 @@ pub enum Token {
     EarlyEof,
 
     // The following tokens are special -- they consume multiple shell tokens, to ensure we capture
-    // the path for a publish or an upgrade command.
+    // the path for a publish or an upgrade command, or for the overrides file.
     /// --publish \<shell-token\>
     Publish,
     /// --upgraded \<shell-token\>
     Upgrade,
+    /// --overrides \<shell-token\>
+    Overrides,
 }
 
 impl Lexeme<'_> {
@@ -78,7 +80,14 @@ impl Lexeme<'_> {
 
     /// Returns true if this lexeme signifies the end of the current command.
     pub fn is_command_end(&self) -> bool {
-        self.is_terminal() || [Token::Command, Token::Publish, Token::Upgrade].contains(&self.0)
+        self.is_terminal()
+            || [
+                Token::Command,
+                Token::Publish,
+                Token::Upgrade,
+                Token::Overrides,
+            ]
+            .contains(&self.0)
     }
 }
 
@@ -109,6 +118,7 @@ impl fmt::Display for Lexeme<'_> {
             T::EarlyEof | T::Eof => write!(f, "end of input"),
             T::Publish => write!(f, "command '--publish {:?}'", self.1),
             T::Upgrade => write!(f, "command '--upgrade {:?}'", self.1),
+            T::Overrides => write!(f, "command '--overrides {:?}'", self.1),
         }
     }
 }
@@ -140,6 +150,7 @@ impl fmt::Display for Token {
             T::EarlyEof => write!(f, "unexpected end of input"),
             T::Publish => write!(f, "a '--publish' command"),
             T::Upgrade => write!(f, "an '--upgrade' command"),
+            T::Overrides => write!(f, "an '--overrides' command"),
         }
     }
 }
@@ -0,0 +1,195 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Local simulation of transactions for `--simulate`.
+//!
+//! The transaction is executed by the replay tool's executor on this machine, against the latest
+//! state of the network read through JSON-RPC, with `StateOverrides` applied on top of it. Nothing
+//! is submitted to the network.
+
+use std::path::Path;
+
+use anyhow::{anyhow, bail, ensure};
+use sui_json_rpc_types::{DevInspectResults, SuiObjectDataOptions, SuiPastObjectResponse};
+use sui_protocol_config::{Chain, ProtocolConfig, ProtocolVersion};
+use sui_replay_2::replay_interface::{EpochData, EpochStore, ObjectKey, ObjectStore, VersionQuery};
+use sui_replay_2::simulate::{simulate_transaction, StateOverrides};
+use sui_sdk::SuiClient;
+use sui_types::base_types::{ObjectRef, SequenceNumber, SuiAddress};
+use sui_types::digests::ChainIdentifier;
+use sui_types::object::Object;
+use sui_types::transaction::{InputObjectKind, TransactionData, TransactionKind};
+use tokio::runtime::Handle;
+
+/// Reads the latest state of the network for the executor. Object queries at a checkpoint are
+/// answered with the latest version of the object.
+struct RpcStore {
+    client: SuiClient,
+    runtime: Handle,
+    chain: Chain,
+    epoch: EpochData,
+}
+
+impl EpochStore for RpcStore {
+    fn epoch_info(&self, epoch: u64) -> Result<EpochData, anyhow::Error> {
+        ensure!(
+            epoch == self.epoch.epoch_id,
+            "Only the current epoch {} can be simulated",
+            self.epoch.epoch_id
+        );
+        Ok(self.epoch.clone())
+    }
+
+    fn protocol_config(&self, epoch: u64) -> Result<ProtocolConfig, anyhow::Error> {
+        let epoch = self.epoch_info(epoch)?;
+        Ok(ProtocolConfig::get_for_version(
+            ProtocolVersion::new(epoch.protocol_version),
+            self.chain,
+        ))
+    }
+}
+
+impl ObjectStore for RpcStore {
+    fn get_objects(&self, keys: &[ObjectKey]) -> Result<Vec<Option<Object>>, anyhow::Error> {
+        self.runtime.block_on(async {
+            let read_api = self.client.read_api();
+            let mut objects = Vec::with_capacity(keys.len());
+            for key in keys {
+                let object = match key.version_query {
+                    VersionQuery::Version(version) => {
+                        match read_api
+                            .try_get_parsed_past_object(
+                                key.object_id,
+                                SequenceNumber::from_u64(version),
+                                SuiObjectDataOptions::bcs_lossless(),
+                            )
+                            .await?
+                        {
+                            SuiPastObjectResponse::VersionFound(data) => Some(data.try_into()?),
+                            _ => None,
+                        }
+                    }
+                    VersionQuery::RootVersion(_)
+                    | VersionQuery::AtCheckpoint(_)
+                    | VersionQuery::ImmutableOrLatest => read_api
+                        .get_object_with_options(
+                            key.object_id,
+                            SuiObjectDataOptions::bcs_lossless(),
+                        )
+                        .await?
+                        .data
+                        .map(|data| data.try_into())
+                        .transpose()?,
+                };
+                // Children are read at the latest version, which must not be newer than the bound
+                let object = match (&key.version_query, object) {
+                    (VersionQuery::RootVersion(bound), Some(object))
+                        if object.version().value() > *bound =>
+                    {
+                        None
+                    }
+                    (_, object) => object,
+                };
+                objects.push(object);
+            }
+            Ok(objects)
+        })
+    }
+}
+
+/// Simulate a transaction of `tx_kind` sent by `signer` against the latest state of the network
+/// with the overrides in the file at `overrides` applied.
+/// The budget defaults to the maximum allowed, and the gas payment to the largest SUI coin of the
+/// gas owner that is not an input of the transaction.
+#[allow(clippy::too_many_arguments)]
+pub async fn simulate(
+    client: &SuiClient,
+    signer: SuiAddress,
+    tx_kind: TransactionKind,
+    gas_payment: Vec<ObjectRef>,
+    gas_budget: Option<u64>,
+    gas_price: u64,
+    gas_sponsor: Option<SuiAddress>,
+    overrides: Option<&Path>,
+) -> Result<DevInspectResults, anyhow::Error> {
+    let overrides = overrides
+        .map(StateOverrides::from_file)
+        .transpose()?
+        .unwrap_or_default();
+
+    let chain_id = client.read_api().get_chain_identifier().await?;
+    let chain = ChainIdentifier::from_chain_short_id(&chain_id)
+        .map_or(Chain::Unknown, |chain_id| chain_id.chain());
+    let system_state = client
+        .governance_api()
+        .get_latest_sui_system_state()
+        .await?;
+    let checkpoint = client
+        .read_api()
+        .get_latest_checkpoint_sequence_number()
+        .await?;
+    let store = RpcStore {
+        client: client.clone(),
+        runtime: Handle::current(),
+        chain,
+        epoch: EpochData {
+            epoch_id: system_state.epoch,
+            protocol_version: system_state.protocol_version,
+            rgp: system_state.reference_gas_price,
+            start_timestamp: system_state.epoch_start_timestamp_ms,
+        },
+    };
+
+    let gas_owner = gas_sponsor.unwrap_or(signer);
+    let gas_budget = match gas_budget {
+        Some(gas_budget) => gas_budget,
+        None => store.protocol_config(system_state.epoch)?.max_tx_gas(),
+    };
+    let gas_payment = if !gas_payment.is_empty() {
+        gas_payment
+    } else {
+        let input_objects: Vec<_> = tx_kind
+            .input_objects()?
+            .iter()
+            .filter_map(|o| match o {
+                InputObjectKind::ImmOrOwnedMoveObject((id, _, _)) => Some(*id),
+                _ => None,
+            })
+            .collect();
+        let coin = client
+            .coin_read_api()
+            .get_coins(gas_owner, None, None, None)
+            .await?
+            .data
+            .into_iter()
+            .filter(|coin| !input_objects.contains(&coin.coin_object_id))
+            .max_by_key(|coin| coin.balance);
+        let Some(coin) = coin else {
+            bail!("No SUI coin owned by {gas_owner} can pay for the simulation, pass a gas coin");
+        };
+        vec![coin.object_ref()]
+    };
+
+    let tx_data = TransactionData::new_with_gas_coins_allow_sponsor(
+        tx_kind,
+        signer,
+        gas_payment,
+        gas_budget,
+        gas_price,
+        gas_owner,
+    );
+
+    // The executor reads objects synchronously, so it runs on a blocking thread from which the
+    // store can wait for the network.
+    tokio::task::spawn_blocking(move || {
+        simulate_transaction(
+            &store,
+            store.epoch.epoch_id,
+            checkpoint,
+            tx_data,
+            &overrides,
+        )
+    })
+    .await
+    .map_err(|e| anyhow!("Simulation failed: {e}"))?
+}
@@ -0,0 +1,686 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Following objects, addresses and Move events for `sui client watch`.
+//!
+//! The fullnode is polled through JSON-RPC: whenever the latest checkpoint advances, the
+//! transactions and events indexed since the last poll are fetched from cursors, so no update is
+//! missed between two polls. Every version of a watched object is read from the transactions that
+//! changed it, and its deletion from its current state.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{Display, Formatter};
+use std::time::Duration;
+
+use anyhow::{bail, ensure};
+use async_trait::async_trait;
+use move_core_types::language_storage::StructTag;
+use serde::Serialize;
+use sui_json_rpc_types::{
+    EventFilter, EventPage, SuiEvent, SuiObjectData, SuiObjectDataOptions, SuiObjectResponse,
+    SuiPastObjectResponse, SuiTransactionBlockEffectsAPI, SuiTransactionBlockResponseOptions,
+    SuiTransactionBlockResponseQuery, TransactionBlocksPage, TransactionFilter,
+};
+use sui_sdk::error::{Error as SdkError, SuiRpcResult};
+use sui_sdk::SuiClient;
+use sui_types::base_types::{ObjectDigest, ObjectID, SequenceNumber, SuiAddress};
+use sui_types::digests::TransactionDigest;
+use sui_types::error::SuiObjectResponseError;
+use sui_types::event::EventID;
+use sui_types::messages_checkpoint::CheckpointSequenceNumber;
+
+/// A change observed while watching.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct WatchUpdate {
+    /// The latest checkpoint when the change was observed.
+    pub checkpoint: CheckpointSequenceNumber,
+    #[serde(flatten)]
+    pub change: WatchChange,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum WatchChange {
+    /// A new version of a watched object, or its current version when watching starts.
+    Object { object: SuiObjectData },
+    /// A watched object was deleted or wrapped.
+    #[serde(rename_all = "camelCase")]
+    ObjectDeleted {
+        object_id: ObjectID,
+        version: SequenceNumber,
+        digest: ObjectDigest,
+    },
+    /// A transaction sent or received by a watched address.
+    #[serde(rename_all = "camelCase")]
+    Transaction {
+        address: SuiAddress,
+        digest: TransactionDigest,
+        tx_checkpoint: Option<CheckpointSequenceNumber>,
+        timestamp_ms: Option<u64>,
+    },
+    /// An event of a watched type.
+    Event { event: SuiEvent },
+}
+
+struct ObjectCursor {
+    /// The last version reported, live or deleted, or `None` before the first poll.
+    version: Option<SequenceNumber>,
+    /// The last transaction changing the object that was looked at.
+    cursor: Option<TransactionDigest>,
+}
+
+struct AddressCursors {
+    address: SuiAddress,
+    from: Option<TransactionDigest>,
+    to: Option<TransactionDigest>,
+}
+
+/// The fullnode queries made by a [`Watcher`].
+#[async_trait]
+pub trait WatchClient: Send + Sync {
+    async fn get_latest_checkpoint_sequence_number(&self)
+        -> SuiRpcResult<CheckpointSequenceNumber>;
+
+    async fn query_transaction_blocks(
+        &self,
+        query: SuiTransactionBlockResponseQuery,
+        cursor: Option<TransactionDigest>,
+        limit: Option<usize>,
+        descending_order: bool,
+    ) -> SuiRpcResult<TransactionBlocksPage>;
+
+    async fn query_events(
+        &self,
+        query: EventFilter,
+        cursor: Option<EventID>,
+        limit: Option<usize>,
+        descending_order: bool,
+    ) -> SuiRpcResult<EventPage>;
+
+    async fn get_object_with_options(
+        &self,
+        object_id: ObjectID,
+        options: SuiObjectDataOptions,
+    ) -> SuiRpcResult<SuiObjectResponse>;
+
+    async fn try_get_parsed_past_object(
+        &self,
+        object_id: ObjectID,
+        version: SequenceNumber,
+        options: SuiObjectDataOptions,
+    ) -> SuiRpcResult<SuiPastObjectResponse>;
+}
+
+#[async_trait]
+impl WatchClient for SuiClient {
+    async fn get_latest_checkpoint_sequence_number(
+        &self,
+    ) -> SuiRpcResult<CheckpointSequenceNumber> {
+        self.read_api()
+            .get_latest_checkpoint_sequence_number()
+            .await
+    }
+
+    async fn query_transaction_blocks(
+        &self,
+        query: SuiTransactionBlockResponseQuery,
+        cursor: Option<TransactionDigest>,
+        limit: Option<usize>,
+        descending_order: bool,
+    ) -> SuiRpcResult<TransactionBlocksPage> {
+        self.read_api()
+            .query_transaction_blocks(query, cursor, limit, descending_order)
+            .await
+    }
+
+    async fn query_events(
+        &self,
+        query: EventFilter,
+        cursor: Option<EventID>,
+        limit: Option<usize>,
+        descending_order: bool,
+    ) -> SuiRpcResult<EventPage> {
+        self.event_api()
+            .query_events(query, cursor, limit, descending_order)
+            .await
+    }
+
+    async fn get_object_with_options(
+        &self,
+        object_id: ObjectID,
+        options: SuiObjectDataOptions,
+    ) -> SuiRpcResult<SuiObjectResponse> {
+        self.read_api()
+            .get_object_with_options(object_id, options)
+            .await
+    }
+
+    async fn try_get_parsed_past_object(
+        &self,
+        object_id: ObjectID,
+        version: SequenceNumber,
+        options: SuiObjectDataOptions,
+    ) -> SuiRpcResult<SuiPastObjectResponse> {
+        self.read_api()
+            .try_get_parsed_past_object(object_id, version, options)
+            .await
+    }
+}
+
+pub struct Watcher<C = SuiClient> {
+    client: C,
+    objects: BTreeMap<ObjectID, ObjectCursor>,
+    addresses: Vec<AddressCursors>,
+    events: Vec<(StructTag, Option<EventID>)>,
+    checkpoint: Option<CheckpointSequenceNumber>,
+}
+
+impl<C: WatchClient> Watcher<C> {
+    /// Starts watching from the current state of the network: the current versions of the objects
+    /// are reported on the first poll, but only transactions and events that happen afterwards.
+    pub async fn new(
+        client: C,
+        objects: Vec<ObjectID>,
+        addresses: Vec<SuiAddress>,
+        event_types: Vec<StructTag>,
+    ) -> Result<Self, anyhow::Error> {
+        ensure!(
+            !objects.is_empty() || !addresses.is_empty() || !event_types.is_empty(),
+            "Nothing to watch: pass at least one --object, --address or --event-type"
+        );
+
+        let mut watched_objects = BTreeMap::new();
+        for id in objects {
+            watched_objects.insert(
+                id,
+                ObjectCursor {
+                    version: None,
+                    cursor: latest_transaction(&client, TransactionFilter::ChangedObject(id))
+                        .await?,
+                },
+            );
+        }
+
+        let mut watched_addresses = vec![];
+        for address in addresses.into_iter().collect::<BTreeSet<_>>() {
+            watched_addresses.push(AddressCursors {
+                address,
+                from: latest_transaction(&client, TransactionFilter::FromAddress(address)).await?,
+                to: latest_transaction(&client, TransactionFilter::ToAddress(address)).await?,
+            });
+        }
+
+        let mut events = vec![];
+        for event_type in event_types.into_iter().collect::<BTreeSet<_>>() {
+            let cursor = client
+                .query_events(
+                    EventFilter::MoveEventType(event_type.clone()),
+                    None,
+                    Some(1),
+                    true,
+                )
+                .await?
+                .data
+                .first()
+                .map(|event| event.id);
+            events.push((event_type, cursor));
+        }
+
+        Ok(Self {
+            client,
+            objects: watched_objects,
+            addresses: watched_addresses,
+            events,
+            checkpoint: None,
+        })
+    }
+
+    /// Appends the changes since the last poll to `updates`, or nothing if no checkpoint was
+    /// produced since. Changes found before an error are still appended, and are not reported
+    /// again by the next poll, except that the transactions of an address are only appended once
+    /// all of them were read: the next poll reads them again.
+    pub async fn poll(&mut self, updates: &mut Vec<WatchUpdate>) -> Result<(), anyhow::Error> {
+        let checkpoint = self.client.get_latest_checkpoint_sequence_number().await?;
+        if self.checkpoint == Some(checkpoint) {
+            return Ok(());
+        }
+
+        let mut changes = vec![];
+        let result = async {
+            self.poll_objects(&mut changes).await?;
+            self.poll_addresses(&mut changes).await?;
+            self.poll_events(&mut changes).await
+        }
+        .await;
+        updates.extend(
+            changes
+                .into_iter()
+                .map(|change| WatchUpdate { checkpoint, change }),
+        );
+        result?;
+
+        self.checkpoint = Some(checkpoint);
+        Ok(())
+    }
+
+    async fn poll_objects(&mut self, changes: &mut Vec<WatchChange>) -> Result<(), anyhow::Error> {
+        let options = SuiObjectDataOptions::new()
+            .with_type()
+            .with_owner()
+            .with_previous_transaction();
+        for (id, state) in self.objects.iter_mut() {
+            // The versions created since the last poll, read from the transactions that changed
+            // the object. The current version is reported on the first poll instead.
+            while state.version.is_some() {
+                let page = self
+                    .client
+                    .query_transaction_blocks(
+                        SuiTransactionBlockResponseQuery::new(
+                            Some(TransactionFilter::ChangedObject(*id)),
+                            Some(SuiTransactionBlockResponseOptions::new().with_effects()),
+                        ),
+                        state.cursor,
+                        None,
+                        false,
+                    )
+                    .await?;
+                for tx in page.data {
+                    let version = tx.effects.as_ref().and_then(|effects| {
+                        effects
+                            .created()
+                            .iter()
+                            .chain(effects.mutated())
+                            .chain(effects.unwrapped())
+                            .find(|oref| oref.reference.object_id == *id)
+                            .map(|oref| oref.reference.version)
+                    });
+                    if let Some(version) = version.filter(|v| Some(*v) > state.version) {
+                        // Versions pruned by the fullnode cannot be reported, and are skipped.
+                        if let SuiPastObjectResponse::VersionFound(object) = self
+                            .client
+                            .try_get_parsed_past_object(*id, version, options.clone())
+                            .await?
+                        {
+                            changes.push(WatchChange::Object { object });
+                        }
+                        state.version = Some(version);
+                    }
+                    state.cursor = Some(tx.digest);
+                }
+                if !page.has_next_page {
+                    break;
+                }
+            }
+
+            let response = self
+                .client
+                .get_object_with_options(*id, options.clone())
+                .await?;
+            match (response.data, response.error) {
+                (Some(object), _) => {
+                    // Versions not indexed yet are reported now, and skipped once indexed.
+                    if Some(object.version) > state.version {
+                        state.version = Some(object.version);
+                        changes.push(WatchChange::Object { object });
+                    }
+                }
+                (
+                    None,
+                    Some(SuiObjectResponseError::Deleted {
+                        object_id,
+                        version,
+                        digest,
+                    }),
+                ) => {
+                    if Some(version) > state.version {
+                        state.version = Some(version);
+                        changes.push(WatchChange::ObjectDeleted {
+                            object_id,
+                            version,
+                            digest,
+                        });
+                    }
+                }
+                (None, Some(SuiObjectResponseError::NotExists { .. }))
+                    if state.version.is_none() =>
+                {
+                    bail!("Object {id} does not exist");
+                }
+                (None, error) => {
+                    bail!("Cannot read object {id}: {error:?}");
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The cursors of an address only advance once both its sent and received transactions are
+    /// read, so that a failed poll reads them again instead of losing them.
+    async fn poll_addresses(
+        &mut self,
+        changes: &mut Vec<WatchChange>,
+    ) -> Result<(), anyhow::Error> {
+        for cursors in &mut self.addresses {
+            let address = cursors.address;
+            let mut transactions = BTreeMap::new();
+            let mut from = cursors.from;
+            let mut to = cursors.to;
+            for (filter, cursor) in [
+                (TransactionFilter::FromAddress(address), &mut from),
+                (TransactionFilter::ToAddress(address), &mut to),
+            ] {
+                loop {
+                    let page = self
+                        .client
+                        .query_transaction_blocks(
+                            SuiTransactionBlockResponseQuery::new(Some(filter.clone()), None),
+                            *cursor,
+                            None,
+                            false,
+                        )
+                        .await?;
+                    if let Some(next) = page.data.last() {
+                        *cursor = Some(next.digest);
+                    }
+                    // Transactions both sent and received by the address are only reported once.
+                    for tx in page.data {
+                        transactions.entry(tx.digest).or_insert(tx);
+                    }
+                    if !page.has_next_page {
+                        break;
+                    }
+                }
+            }
+            cursors.from = from;
+            cursors.to = to;
+
+            let mut transactions: Vec<_> = transactions.into_values().collect();
+            transactions.sort_by_key(|tx| (tx.checkpoint, tx.timestamp_ms));
+            changes.extend(transactions.into_iter().map(|tx| WatchChange::Transaction {
+                address,
+                digest: tx.digest,
+                tx_checkpoint: tx.checkpoint,
+                timestamp_ms: tx.timestamp_ms,
+            }));
+        }
+        Ok(())
+    }
+
+    async fn poll_events(&mut self, changes: &mut Vec<WatchChange>) -> Result<(), anyhow::Error> {
+        for (event_type, cursor) in &mut self.events {
+            loop {
+                let page = self
+                    .client
+                    .query_events(
+                        EventFilter::MoveEventType(event_type.clone()),
+                        *cursor,
+                        None,
+                        false,
+                    )
+                    .await?;
+                if let Some(next) = page.data.last() {
+                    *cursor = Some(next.id);
+                }
+                changes.extend(
+                    page.data
+                        .into_iter()
+                        .map(|event| WatchChange::Event { event }),
+                );
+                if !page.has_next_page {
+                    break;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Polls every `interval`, printing each update on its own line (as JSON if `json` is set),
+    /// until `count` updates were printed, or forever if no count is given. Polls failing to reach
+    /// the fullnode are retried on the next tick, other errors stop watching.
+    pub async fn run(
+        mut self,
+        interval: Duration,
+        count: Option<usize>,
+        json: bool,
+    ) -> Result<(), anyhow::Error> {
+        let mut printed = 0;
+        loop {
+            let mut updates = vec![];
+            let result = self.poll(&mut updates).await;
+            for update in updates {
+                if json {
+                    println!("{}", serde_json::to_string(&update)?);
+                } else {
+                    println!("{update}");
+                }
+                printed += 1;
+                if count.is_some_and(|count| printed >= count) {
+                    return Ok(());
+                }
+            }
+            match result {
+                Err(e) if is_transient(&e) => eprintln!("Cannot poll the fullnode, retrying: {e}"),
+                result => result?,
+            }
+            tokio::time::sleep(interval).await;
+        }
+    }
+}
+
+/// Whether `error` is a failure to reach the fullnode, which may succeed when retried.
+fn is_transient(error: &anyhow::Error) -> bool {
+    matches!(
+        error.downcast_ref::<SdkError>(),
+        Some(SdkError::RpcError(_) | SdkError::JsonRpcError(_))
+    )
+}
+
+/// The digest of the latest transaction matching `filter`, to only report transactions after it.
+async fn latest_transaction(
+    client: &impl WatchClient,
+    filter: TransactionFilter,
+) -> Result<Option<TransactionDigest>, anyhow::Error> {
+    Ok(client
+        .query_transaction_blocks(
+            SuiTransactionBlockResponseQuery::new(Some(filter), None),
+            None,
+            Some(1),
+            true,
+        )
+        .await?
+        .data
+        .first()
+        .map(|tx| tx.digest))
+}
+
+impl Display for WatchUpdate {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[checkpoint {}] ", self.checkpoint)?;
+        match &self.change {
+            WatchChange::Object { object } => {
+                write!(
+                    f,
+                    "object {} version {} digest {}",
+                    object.object_id,
+                    object.version.value(),
+                    object.digest
+                )?;
+                if let Some(owner) = &object.owner {
+                    write!(f, " owner {owner}")?;
+                }
+                if let Some(tx) = &object.previous_transaction {
+                    write!(f, " by transaction {tx}")?;
+                }
+                Ok(())
+            }
+            WatchChange::ObjectDeleted {
+                object_id, version, ..
+            } => write!(
+                f,
+                "object {object_id} deleted or wrapped at version {}",
+                version.value()
+            ),
+            WatchChange::Transaction {
+                address, digest, ..
+            } => write!(f, "transaction {digest} for address {address}"),
+            WatchChange::Event { event } => write!(
+                f,
+                "event {} from transaction {}: {}",
+                event.type_, event.id.tx_digest, event.parsed_json
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+    use sui_json_rpc_types::{Page, SuiTransactionBlockResponse};
+
+    /// A fullnode with transactions sent and received by an address, one per checkpoint, which
+    /// fails its first `ToAddress` queries.
+    struct FakeClient {
+        /// Digest, whether it was sent and whether it was received by the address.
+        transactions: Vec<(TransactionDigest, bool, bool)>,
+        checkpoint: AtomicU64,
+        failures: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl WatchClient for FakeClient {
+        async fn get_latest_checkpoint_sequence_number(
+            &self,
+        ) -> SuiRpcResult<CheckpointSequenceNumber> {
+            Ok(self.checkpoint.fetch_add(1, Ordering::Relaxed))
+        }
+
+        // One transaction per page
+        async fn query_transaction_blocks(
+            &self,
+            query: SuiTransactionBlockResponseQuery,
+            cursor: Option<TransactionDigest>,
+            _limit: Option<usize>,
+            descending_order: bool,
+        ) -> SuiRpcResult<TransactionBlocksPage> {
+            assert!(!descending_order);
+            let sent = match query.filter {
+                Some(TransactionFilter::FromAddress(_)) => true,
+                Some(TransactionFilter::ToAddress(_)) => {
+                    let failures = self.failures.load(Ordering::Relaxed);
+                    if failures > 0 {
+                        self.failures.store(failures - 1, Ordering::Relaxed);
+                        return Err(SdkError::DataError("fullnode unavailable".to_owned()));
+                    }
+                    false
+                }
+                filter => panic!("unexpected filter {filter:?}"),
+            };
+            let matching: Vec<_> = self
+                .transactions
+                .iter()
+                .enumerate()
+                .filter(|(_, (_, from, to))| if sent { *from } else { *to })
+                .collect();
+            let start = cursor.map_or(0, |cursor| {
+                matching
+                    .iter()
+                    .position(|(_, (digest, _, _))| *digest == cursor)
+                    .unwrap()
+                    + 1
+            });
+            let data: Vec<_> = matching[start..]
+                .iter()
+                .take(1)
+                .map(|(checkpoint, (digest, _, _))| SuiTransactionBlockResponse {
+                    checkpoint: Some(*checkpoint as CheckpointSequenceNumber),
+                    ..SuiTransactionBlockResponse::new(*digest)
+                })
+                .collect();
+            Ok(Page {
+                next_cursor: data.last().map(|tx| tx.digest),
+                has_next_page: start + 1 < matching.len(),
+                data,
+            })
+        }
+
+        async fn query_events(
+            &self,
+            _query: EventFilter,
+            _cursor: Option<EventID>,
+            _limit: Option<usize>,
+            _descending_order: bool,
+        ) -> SuiRpcResult<EventPage> {
+            unreachable!("no events are watched")
+        }
+
+        async fn get_object_with_options(
+            &self,
+            _object_id: ObjectID,
+            _options: SuiObjectDataOptions,
+        ) -> SuiRpcResult<SuiObjectResponse> {
+            unreachable!("no objects are watched")
+        }
+
+        async fn try_get_parsed_past_object(
+            &self,
+            _object_id: ObjectID,
+            _version: SequenceNumber,
+            _options: SuiObjectDataOptions,
+        ) -> SuiRpcResult<SuiPastObjectResponse> {
+            unreachable!("no objects are watched")
+        }
+    }
+
+    #[tokio::test]
+    async fn failed_address_poll_is_read_again() {
+        let address = SuiAddress::random_for_testing_only();
+        let digests: Vec<_> = (0..3).map(|_| TransactionDigest::random()).collect();
+        let client = FakeClient {
+            transactions: vec![
+                (digests[0], true, false),
+                (digests[1], true, true),
+                (digests[2], false, true),
+            ],
+            checkpoint: AtomicU64::new(1),
+            failures: AtomicUsize::new(1),
+        };
+        let mut watcher = Watcher {
+            client,
+            objects: BTreeMap::new(),
+            addresses: vec![AddressCursors {
+                address,
+                from: None,
+                to: None,
+            }],
+            events: vec![],
+            checkpoint: None,
+        };
+
+        // Sent transactions are read, but received ones fail
+        let mut updates = vec![];
+        watcher.poll(&mut updates).await.unwrap_err();
+        assert!(updates.is_empty());
+
+        // Every transaction is reported once by the next poll
+        watcher.poll(&mut updates).await.unwrap();
+        let reported: Vec<_> = updates
+            .iter()
+            .map(|update| match &update.change {
+                WatchChange::Transaction {
+                    address: a, digest, ..
+                } => {
+                    assert_eq!(*a, address);
+                    *digest
+                }
+                change => panic!("unexpected change {change:?}"),
+            })
+            .collect();
+        assert_eq!(reported, digests);
+
+        updates.clear();
+        watcher.poll(&mut updates).await.unwrap();
+        assert!(updates.is_empty());
+    }
+}
@@ -1,7 +1,7 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 use crate::zklogin_commands_util::{perform_zk_login_test_tx, read_cli_line};
-use anyhow::anyhow;
+use anyhow::{anyhow, bail, ensure, Context};
 use aws_sdk_kms::{
     primitives::Blob,
     types::{MessageType, SigningAlgorithmSpec},
@@ -23,30 +23,32 @@ use fastcrypto_zkp::bn254::zk_login::{fetch_jwks, OIDCProvider, ZkLoginInputs};
 use fastcrypto_zkp::bn254::zk_login::{JwkId, JWK};
 use fastcrypto_zkp::bn254::zk_login_api::ZkLoginEnv;
 use im::hashmap::HashMap as ImHashMap;
+use inquire::Password;
 use json_to_table::{json_to_table, Orientation};
 use num_bigint::BigUint;
 use rand::rngs::StdRng;
 use rand::Rng;
 use rand::SeedableRng;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use serde_json::json;
 use shared_crypto::intent::{Intent, IntentMessage, IntentScope, PersonalMessage};
 use std::fmt::{Debug, Display, Formatter};
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use sui_keys::encrypted::{passphrase_from_env, KEYSTORE_PASSPHRASE_ENV_VAR};
 use sui_keys::key_derive::generate_new_key;
 use sui_keys::key_identity::KeyIdentity;
 use sui_keys::keypair_file::{
     read_authority_keypair_from_file, read_keypair_from_file, write_authority_keypair_to_file,
     write_keypair_to_file,
 };
-use sui_keys::keystore::{AccountKeystore, Keystore};
+use sui_keys::keystore::{AccountKeystore, FileBasedKeystore, Keystore};
 use sui_types::base_types::SuiAddress;
 use sui_types::committee::EpochId;
 use sui_types::crypto::{
     get_authority_key_pair, EncodeDecodeBase64, Signature, SignatureScheme, SuiKeyPair,
-    ZkLoginPublicIdentifier,
+    SuiSignature, ZkLoginPublicIdentifier,
 };
 use sui_types::crypto::{DefaultHash, PublicKey};
 use sui_types::error::SuiResult;
@@ -102,6 +104,13 @@ pub enum KeyToolCommand {
         #[clap(long, default_value = "0")]
         cur_epoch: u64,
     },
+    /// Remove the passphrase of an encrypted Sui CLI Keystore, saving its keys in plaintext.
+    Decrypt,
+    /// Encrypt Sui CLI Keystore with a passphrase, or change the passphrase of an encrypted
+    /// keystore. The passphrase is read from the SUI_KEYSTORE_PASSPHRASE environment variable if
+    /// it is set, and prompted for otherwise. Once encrypted, the keystore is unlocked with the
+    /// passphrase in SUI_KEYSTORE_PASSPHRASE, or with a prompt for it.
+    Encrypt,
     /// Generate a new keypair with key scheme flag {ed25519 | secp256k1 | secp256r1}
     /// with optional derivation path, default to m/44'/784'/0'/0'/0' for ed25519 or
     /// m/54'/784'/0'/0/0 for secp256k1 or m/74'/784'/0'/0/0 for secp256r1. Word
@@ -189,6 +198,49 @@ pub enum KeyToolCommand {
         #[clap(long)]
         threshold: ThresholdUnit,
     },
+    /// Create a MultiSig proposal file, to collect the signatures of a transaction signed by a
+    /// MultiSig address. The file holds the transaction, the public keys, weights and threshold
+    /// defining the address, and the signatures added so far with
+    /// `keytool multi-sig-sign-proposal`. Once their weights meet the threshold,
+    /// `keytool multi-sig-combine-proposal` combines them.
+    MultiSigPropose {
+        /// Path of the proposal file to create.
+        #[clap(long)]
+        file: PathBuf,
+        /// Base64 encoded BCS serialized TransactionData, e.g. the output of a sui client command
+        /// with --serialize-unsigned-transaction. The MultiSig address must be its sender or its
+        /// gas sponsor.
+        #[clap(long)]
+        tx_bytes: String,
+        #[clap(long)]
+        threshold: ThresholdUnit,
+        #[clap(long, num_args(1..))]
+        pks: Vec<PublicKey>,
+        #[clap(long, num_args(1..))]
+        weights: Vec<WeightUnit>,
+    },
+    /// Add a signature to a MultiSig proposal file: either signed here with the key of an
+    /// address in the keystore, or a signature `flag || sig || pk` in Base64 produced elsewhere,
+    /// e.g. with `keytool sign`. The signature replaces any previous one by the same key.
+    MultiSigSignProposal {
+        #[clap(long)]
+        file: PathBuf,
+        #[clap(
+            long,
+            required_unless_present = "signature",
+            conflicts_with = "signature"
+        )]
+        address: Option<KeyIdentity>,
+        #[clap(long)]
+        signature: Option<GenericSignature>,
+    },
+    /// Combine the signatures of a MultiSig proposal file into a MultiSig signature, once their
+    /// weights meet the threshold. The result can be used with the proposal's transaction bytes
+    /// for `sui client execute-signed-tx`.
+    MultiSigCombineProposal {
+        #[clap(long)]
+        file: PathBuf,
+    },
 
     /// Read the content at the provided file path. The accepted format can be
     /// [enum SuiKeyPair] (Base64 encoded of 33-byte `flag || privkey`) or `type AuthorityKeyPair`
@@ -304,6 +356,13 @@ pub struct AliasUpdate {
     new_alias: String,
 }
 
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeystoreEncryption {
+    keystore_path: Option<PathBuf>,
+    encrypted: bool,
+}
+
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct DecodedMultiSig {
@@ -389,6 +448,171 @@ pub struct MultiSigOutput {
     weight: u8,
 }
 
+/// A transaction to be signed by a MultiSig address, and the signatures collected for it.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct MultiSigProposal {
+    /// Base64 encoded BCS serialized TransactionData.
+    tx_bytes: String,
+    multisig_address: SuiAddress,
+    threshold: ThresholdUnit,
+    /// Base64 encoded `flag || pk` of the keys defining the address, with their weights.
+    public_keys: Vec<(String, WeightUnit)>,
+    signatures: Vec<GenericSignature>,
+}
+
+impl MultiSigProposal {
+    pub fn new(tx_bytes: String, multisig_pk: &MultiSigPublicKey) -> Result<Self, anyhow::Error> {
+        let multisig_address = SuiAddress::from(multisig_pk);
+        let proposal = Self {
+            tx_bytes,
+            multisig_address,
+            threshold: *multisig_pk.threshold(),
+            public_keys: multisig_pk
+                .pubkeys()
+                .iter()
+                .map(|(pk, weight)| (pk.encode_base64(), *weight))
+                .collect(),
+            signatures: vec![],
+        };
+        let tx_data = proposal.tx_data()?;
+        ensure!(
+            tx_data.signers().iter().any(|s| *s == multisig_address),
+            "MultiSig address {multisig_address} is neither the sender nor the gas sponsor of the \
+             transaction"
+        );
+        Ok(proposal)
+    }
+
+    pub fn load(path: &Path) -> Result<Self, anyhow::Error> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("Cannot read MultiSig proposal {}", path.display()))?;
+        let proposal: Self = serde_json::from_str(&contents)
+            .with_context(|| format!("Cannot parse MultiSig proposal {}", path.display()))?;
+        ensure!(
+            SuiAddress::from(&proposal.multisig_pk()?) == proposal.multisig_address,
+            "The public keys of MultiSig proposal {} do not define its address {}",
+            path.display(),
+            proposal.multisig_address
+        );
+        Ok(proposal)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), anyhow::Error> {
+        fs::write(path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Cannot write MultiSig proposal {}", path.display()))
+    }
+
+    pub fn tx_data(&self) -> Result<TransactionData, anyhow::Error> {
+        Ok(bcs::from_bytes(&Base64::decode(&self.tx_bytes).map_err(
+            |e| anyhow!("Cannot deserialize data as TransactionData {:?}", e),
+        )?)?)
+    }
+
+    pub fn multisig_pk(&self) -> Result<MultiSigPublicKey, anyhow::Error> {
+        let (pks, weights) = self
+            .public_keys
+            .iter()
+            .map(|(pk, weight)| {
+                PublicKey::decode_base64(pk)
+                    .map(|pk| (pk, *weight))
+                    .map_err(|e| anyhow!("Invalid public key {pk}: {e:?}"))
+            })
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .unzip();
+        Ok(MultiSigPublicKey::new(pks, weights, self.threshold)?)
+    }
+
+    /// Add `signature` to the proposal, after checking that it is a valid signature of the
+    /// transaction by one of the MultiSig's keys.
+    pub fn add_signature(&mut self, signature: GenericSignature) -> Result<(), anyhow::Error> {
+        let multisig_pk = self.multisig_pk()?;
+        let pk = signature.to_public_key()?;
+        ensure!(
+            multisig_pk.get_index(&pk).is_some(),
+            "Public key {} is not one of the keys of MultiSig address {}",
+            pk.encode_base64(),
+            self.multisig_address
+        );
+        if let GenericSignature::Signature(s) = &signature {
+            let intent_msg = IntentMessage::new(Intent::sui_transaction(), self.tx_data()?);
+            s.verify_secure(&intent_msg, SuiAddress::from(&pk), s.scheme())
+                .map_err(|e| anyhow!("Invalid signature of the transaction: {e}"))?;
+        }
+
+        self.signatures
+            .retain(|s| s.to_public_key().is_ok_and(|p| p != pk));
+        self.signatures.push(signature);
+        Ok(())
+    }
+
+    /// The total weight of the keys that signed the proposal.
+    pub fn weight(&self) -> Result<u16, anyhow::Error> {
+        let multisig_pk = self.multisig_pk()?;
+        let mut weight = 0u16;
+        for signature in &self.signatures {
+            let index = multisig_pk
+                .get_index(&signature.to_public_key()?)
+                .ok_or_else(|| anyhow!("Signature by a key that is not part of the MultiSig"))?;
+            weight += multisig_pk.pubkeys()[index as usize].1 as u16;
+        }
+        Ok(weight)
+    }
+
+    /// Combine the signatures into a MultiSig signature, if their weights meet the threshold.
+    pub fn combine(&self) -> Result<GenericSignature, anyhow::Error> {
+        let multisig_pk = self.multisig_pk()?;
+        let weight = self.weight()?;
+        ensure!(
+            weight >= self.threshold,
+            "The signatures have a total weight of {weight}, below the threshold of {}",
+            self.threshold
+        );
+
+        // The signatures must be in the same order as the keys.
+        let mut signatures = self.signatures.clone();
+        signatures.sort_by_key(|s| {
+            s.to_public_key()
+                .ok()
+                .and_then(|pk| multisig_pk.get_index(&pk))
+        });
+        Ok(MultiSig::combine(signatures, multisig_pk)?.into())
+    }
+
+    fn output(&self, path: &Path) -> Result<MultiSigProposalOutput, anyhow::Error> {
+        Ok(MultiSigProposalOutput {
+            file: path.to_path_buf(),
+            multisig_address: self.multisig_address,
+            signers: self
+                .signatures
+                .iter()
+                .map(SuiAddress::try_from)
+                .collect::<Result<_, _>>()?,
+            weight: self.weight()?,
+            threshold: self.threshold,
+        })
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MultiSigProposalOutput {
+    file: PathBuf,
+    multisig_address: SuiAddress,
+    signers: Vec<SuiAddress>,
+    weight: u16,
+    threshold: ThresholdUnit,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MultiSigCombineProposalOutput {
+    multisig_address: SuiAddress,
+    tx_bytes: String,
+    multisig_serialized: String,
+}
+
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ConvertOutput {
@@ -461,6 +685,7 @@ pub enum CommandOutput {
     Convert(ConvertOutput),
     DecodeMultiSig(DecodedMultiSigOutput),
     DecodeOrVerifyTx(DecodeOrVerifyTxOutput),
+    Encryption(KeystoreEncryption),
     Error(String),
     Generate(Key),
     Import(Key),
@@ -470,6 +695,8 @@ pub enum CommandOutput {
     MultiSigAddress(MultiSigAddress),
     MultiSigCombinePartialSig(MultiSigCombinePartialSig),
     MultiSigCombinePartialSigLegacy(MultiSigCombinePartialSigLegacyOutput),
+    MultiSigProposal(MultiSigProposalOutput),
+    MultiSigCombineProposal(MultiSigCombineProposalOutput),
     PrivateKeyBase64(PrivateKeyBase64),
     Show(Key),
     Sign(SignData),
@@ -497,6 +724,27 @@ impl KeyToolCommand {
                 CommandOutput::Convert(result)
             }
 
+            KeyToolCommand::Decrypt => {
+                let keystore = file_keystore(keystore)?;
+                keystore.set_passphrase(None)?;
+                CommandOutput::Encryption(KeystoreEncryption::from(&*keystore))
+            }
+
+            KeyToolCommand::Encrypt => {
+                let keystore = file_keystore(keystore)?;
+                let passphrase = match passphrase_from_env() {
+                    Some(passphrase) => passphrase,
+                    None => Password::new("Enter a passphrase for the keystore:")
+                        .prompt()?
+                        .into(),
+                };
+                if passphrase.is_empty() {
+                    return Err(anyhow!("The keystore passphrase cannot be empty"));
+                }
+                keystore.set_passphrase(Some(passphrase.as_str()))?;
+                CommandOutput::Encryption(KeystoreEncryption::from(&*keystore))
+            }
+
             KeyToolCommand::DecodeMultiSig {
                 multisig,
                 tx_bytes,
@@ -797,6 +1045,55 @@ impl KeyToolCommand {
                 )
             }
 
+            KeyToolCommand::MultiSigPropose {
+                file,
+                tx_bytes,
+                threshold,
+                pks,
+                weights,
+            } => {
+                ensure!(
+                    !file.exists(),
+                    "MultiSig proposal {} already exists",
+                    file.display()
+                );
+                let multisig_pk = MultiSigPublicKey::new(pks, weights, threshold)?;
+                let proposal = MultiSigProposal::new(tx_bytes, &multisig_pk)?;
+                proposal.save(&file)?;
+                CommandOutput::MultiSigProposal(proposal.output(&file)?)
+            }
+
+            KeyToolCommand::MultiSigSignProposal {
+                file,
+                address,
+                signature,
+            } => {
+                let mut proposal = MultiSigProposal::load(&file)?;
+                let signature = match (address, signature) {
+                    (_, Some(signature)) => signature,
+                    (Some(address), None) => {
+                        let address = keystore.get_by_identity(address)?;
+                        keystore
+                            .sign_secure(&address, &proposal.tx_data()?, Intent::sui_transaction())?
+                            .into()
+                    }
+                    (None, None) => bail!("Either --address or --signature must be provided"),
+                };
+                proposal.add_signature(signature)?;
+                proposal.save(&file)?;
+                CommandOutput::MultiSigProposal(proposal.output(&file)?)
+            }
+
+            KeyToolCommand::MultiSigCombineProposal { file } => {
+                let proposal = MultiSigProposal::load(&file)?;
+                let multisig = proposal.combine()?;
+                CommandOutput::MultiSigCombineProposal(MultiSigCombineProposalOutput {
+                    multisig_address: proposal.multisig_address,
+                    tx_bytes: proposal.tx_bytes,
+                    multisig_serialized: multisig.encode_base64(),
+                })
+            }
+
             KeyToolCommand::Show { file } => {
                 let res = read_keypair_from_file(&file);
                 match res {
@@ -1402,6 +1699,41 @@ impl Debug for CommandOutput {
     }
 }
 
+impl From<&FileBasedKeystore> for KeystoreEncryption {
+    fn from(keystore: &FileBasedKeystore) -> Self {
+        Self {
+            keystore_path: keystore.path().map(Path::to_path_buf),
+            encrypted: keystore.is_encrypted(),
+        }
+    }
+}
+
+/// Prompt for the passphrase of an encrypted keystore that could not be unlocked with the
+/// passphrase in SUI_KEYSTORE_PASSPHRASE, the first time its keys are used.
+pub fn prompt_keystore_passphrase(keystore: &mut Keystore) {
+    if let Keystore::File(keystore) = keystore {
+        keystore.set_passphrase_prompt(Arc::new(|| {
+            let passphrase = Password::new("Enter the keystore passphrase:")
+                .without_confirmation()
+                .prompt()
+                .map_err(|e| {
+                    anyhow!(
+                        "Cannot read the keystore passphrase: {e}. Set \
+                        {KEYSTORE_PASSPHRASE_ENV_VAR} to unlock the keystore non-interactively"
+                    )
+                })?;
+            Ok(passphrase)
+        }));
+    }
+}
+
+fn file_keystore(keystore: &mut Keystore) -> Result<&mut FileBasedKeystore, anyhow::Error> {
+    match keystore {
+        Keystore::File(keystore) => Ok(keystore),
+        _ => Err(anyhow!("Only file based keystores can be encrypted")),
+    }
+}
+
 /// Converts legacy formatted private key to 33 bytes bech32 encoded private key or vice versa.
 /// It can handle:
 /// 1) Hex encoded 32 byte private key (assumes scheme is Ed25519), this is the legacy wallet format
@@ -6,6 +6,8 @@ pub mod client_commands;
 #[macro_use]
 pub mod client_ptb;
 mod clever_error_rendering;
+pub mod client_simulate;
+pub mod client_watch;
 pub mod displays;
 pub mod fire_drill;
 pub mod genesis_ceremony;
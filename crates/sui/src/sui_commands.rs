@@ -2,11 +2,12 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::client_commands::{
-    implicit_deps_for_protocol_version, pkg_tree_shake, SuiClientCommands,
+    compile_package, implicit_deps_for_protocol_version, pkg_tree_shake, SuiClientCommands,
 };
 use crate::fire_drill::{run_fire_drill, FireDrill};
 use crate::genesis_ceremony::{run, Ceremony};
-use crate::keytool::KeyToolCommand;
+use crate::keytool::{prompt_keystore_passphrase, KeyToolCommand};
+use crate::upgrade_compatibility::{check_compatibility, compatibility_report, policy_name};
 use crate::validator_commands::SuiValidatorCommand;
 use anyhow::{anyhow, bail, ensure, Context};
 use clap::*;
@@ -43,10 +44,13 @@ use sui_indexer::test_utils::{
     start_indexer_jsonrpc_for_testing, start_indexer_writer_for_testing,
 };
 use sui_json_rpc_types::{SuiObjectDataOptions, SuiRawData};
+use sui_move::check_upgrade::CheckUpgrade;
 use sui_move::summary::PackageSummaryMetadata;
+use sui_protocol_config::{Chain, ProtocolConfig};
 use sui_sdk::apis::ReadApi;
 use sui_sdk::SuiClient;
-use sui_types::move_package::MovePackage;
+use sui_types::digests::ChainIdentifier;
+use sui_types::move_package::{MovePackage, UpgradePolicy};
 
 use sui_graphql_rpc::{
     config::{ConnectionConfig, ServiceConfig},
@@ -449,6 +453,7 @@ impl SuiCommand {
                 let keystore_path =
                     keystore_path.unwrap_or(sui_config_dir()?.join(SUI_KEYSTORE_FILENAME));
                 let mut keystore = Keystore::from(FileBasedKeystore::new(&keystore_path)?);
+                prompt_keystore_passphrase(&mut keystore);
                 cmd.execute(&mut keystore).await?.print(!json);
                 Ok(())
             }
@@ -462,8 +467,9 @@ impl SuiCommand {
                     .config
                     .unwrap_or(sui_config_dir()?.join(SUI_CLIENT_CONFIG));
                 prompt_if_no_config(&config_path, accept_defaults).await?;
-                if let Some(cmd) = cmd {
+                if let Some(mut cmd) = cmd {
                     let mut context = WalletContext::new(&config_path)?;
+                    prompt_keystore_passphrase(&mut context.config.keystore);
                     if let Some(env_override) = config.env {
                         context = context.with_env_override(env_override);
                     }
@@ -472,6 +478,12 @@ impl SuiCommand {
                             eprintln!("{}", format!("[warning] {e}").yellow().bold());
                         }
                     }
+                    if let SuiClientCommands::Watch {
+                        json: watch_json, ..
+                    } = &mut cmd
+                    {
+                        *watch_json = json;
+                    }
                     cmd.execute(&mut context).await?.print(!json);
                 } else {
                     // Print help
@@ -490,6 +502,7 @@ impl SuiCommand {
                 let config_path = config.unwrap_or(sui_config_dir()?.join(SUI_CLIENT_CONFIG));
                 prompt_if_no_config(&config_path, accept_defaults).await?;
                 let mut context = WalletContext::new(&config_path)?;
+                prompt_keystore_passphrase(&mut context.config.keystore);
                 if let Some(cmd) = cmd {
                     if let Ok(client) = context.get_client().await {
                         if let Err(e) = client.check_api_version() {
@@ -642,6 +655,10 @@ impl SuiCommand {
                         );
                         return Ok(());
                     }
+                    sui_move::Command::CheckUpgrade(check) => {
+                        return check_upgrade(client_config, package_path, build_config, check)
+                            .await;
+                    }
                     _ => (),
                 };
 
@@ -687,6 +704,7 @@ impl SuiCommand {
                 let config_path =
                     client_config.unwrap_or(sui_config_dir()?.join(SUI_CLIENT_CONFIG));
                 let mut context = WalletContext::new(&config_path)?;
+                prompt_keystore_passphrase(&mut context.config.keystore);
                 if let Ok(client) = context.get_client().await {
                     if let Err(e) = client.check_api_version() {
                         eprintln!("{}", format!("[warning] {e}").yellow().bold());
@@ -1450,6 +1468,102 @@ fn read_line() -> Result<String, anyhow::Error> {
     Ok(s.trim_end().to_string())
 }
 
+/// Check that a local package can upgrade an on-chain package, for `sui move check-upgrade`.
+async fn check_upgrade(
+    client_config: SuiEnvConfig,
+    package_path: Option<PathBuf>,
+    build_config: BuildConfig,
+    check: CheckUpgrade,
+) -> anyhow::Result<()> {
+    let (chain_id, client) =
+        get_chain_id_and_client(client_config, "sui move check-upgrade").await?;
+    let Some(client) = client else {
+        bail!("`sui move check-upgrade` requires a configured network");
+    };
+    let read_api = client.read_api();
+
+    let protocol_version = read_api.get_protocol_config(None).await?.protocol_version;
+    let protocol_config = ProtocolConfig::get_for_version(
+        protocol_version,
+        match chain_id
+            .as_ref()
+            .and_then(ChainIdentifier::from_chain_short_id)
+        {
+            Some(chain_id) => chain_id.chain(),
+            None => Chain::Unknown,
+        },
+    );
+
+    // Build the package as `sui client upgrade` does, with its own address unset.
+    let package_path = move_cli::base::reroot_path(package_path.as_deref())?;
+    let build_config = resolve_lock_file_path(build_config, Some(&package_path))?;
+    let previous_id = if let Some(ref chain_id) = chain_id {
+        sui_package_management::set_package_id(
+            &package_path,
+            build_config.install_dir.clone(),
+            chain_id,
+            AccountAddress::ZERO,
+        )?
+    } else {
+        None
+    };
+
+    let compiled_package = compile_package(
+        read_api,
+        build_config.clone(),
+        &package_path,
+        check.with_unpublished_dependencies,
+        /* skip_dependency_verification */ true,
+    )
+    .await;
+
+    // Restore original ID, then check result.
+    if let (Some(chain_id), Some(previous_id)) = (chain_id, previous_id) {
+        let _ = sui_package_management::set_package_id(
+            &package_path,
+            build_config.install_dir.clone(),
+            &chain_id,
+            previous_id,
+        )?;
+    }
+    let compiled_package = compiled_package?;
+
+    let policy = UpgradePolicy::from(check.policy);
+    if check.json {
+        let report = compatibility_report(
+            read_api,
+            check.package_id,
+            &compiled_package,
+            policy as u8,
+            protocol_config,
+        )
+        .await?;
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        ensure!(
+            report.compatible,
+            "The package cannot upgrade {} under the '{}' upgrade policy",
+            check.package_id,
+            report.policy,
+        );
+    } else {
+        check_compatibility(
+            read_api,
+            check.package_id,
+            compiled_package,
+            package_path,
+            policy as u8,
+            protocol_config,
+        )
+        .await?;
+        println!(
+            "The package can upgrade {} under the '{}' upgrade policy",
+            check.package_id,
+            policy_name(policy),
+        );
+    }
+    Ok(())
+}
+
 /// Get the currently configured client, and the chain ID for that client.
 async fn get_chain_id_and_client(
     client_config: SuiEnvConfig,
@@ -36,6 +36,8 @@ use sui_types::crypto::Signature;
 use sui_types::crypto::SignatureScheme;
 use sui_types::crypto::SuiKeyPair;
 use sui_types::crypto::SuiSignatureInner;
+use sui_types::multisig::MultiSigPublicKey;
+use sui_types::signature::GenericSignature;
 use sui_types::transaction::TransactionData;
 use sui_types::transaction::TEST_ONLY_GAS_UNIT_FOR_TRANSFER;
 use tempfile::TempDir;
@@ -565,3 +567,72 @@ async fn test_sign_command() -> Result<(), anyhow::Error> {
     .await?;
     Ok(())
 }
+
+#[test]
+async fn test_multisig_proposal() -> Result<(), anyhow::Error> {
+    let mut keystore = Keystore::from(InMemKeystore::new_insecure_for_tests(3));
+    let addresses = keystore.addresses();
+    let pks: Vec<_> = addresses
+        .iter()
+        .map(|address| keystore.export(address).unwrap().public())
+        .collect();
+
+    // A 2-of-3 MultiSig of the first two keys and an outside one.
+    let outsider = SuiKeyPair::Ed25519(get_key_pair().1);
+    let multisig_pks = vec![pks[0].clone(), pks[1].clone(), outsider.public()];
+    let multisig_pk = MultiSigPublicKey::new(multisig_pks.clone(), vec![1, 1, 1], 2)?;
+    let multisig_address = SuiAddress::from(&multisig_pk);
+
+    let gas = (
+        ObjectID::random(),
+        SequenceNumber::new(),
+        ObjectDigest::random(),
+    );
+    let tx_data = TransactionData::new_pay_sui(
+        multisig_address,
+        vec![gas],
+        vec![SuiAddress::random_for_testing_only()],
+        vec![10000],
+        gas,
+        TEST_ONLY_GAS_UNIT_FOR_TRANSFER,
+        1,
+    )
+    .unwrap();
+
+    let dir = TempDir::new().unwrap();
+    let file = dir.path().join("proposal.json");
+    KeyToolCommand::MultiSigPropose {
+        file: file.clone(),
+        tx_bytes: Base64::encode(bcs::to_bytes(&tx_data)?),
+        threshold: 2,
+        pks: multisig_pks,
+        weights: vec![1, 1, 1],
+    }
+    .execute(&mut keystore)
+    .await?;
+
+    // The third key of the keystore is not part of the MultiSig.
+    let sign = |address: SuiAddress| KeyToolCommand::MultiSigSignProposal {
+        file: file.clone(),
+        address: Some(KeyIdentity::Address(address)),
+        signature: None,
+    };
+    assert!(sign(addresses[2]).execute(&mut keystore).await.is_err());
+
+    // Signing twice with the same key does not add to the weight.
+    sign(addresses[0]).execute(&mut keystore).await?;
+    sign(addresses[0]).execute(&mut keystore).await?;
+    let combine = || KeyToolCommand::MultiSigCombineProposal { file: file.clone() };
+    assert!(combine().execute(&mut keystore).await.is_err());
+
+    sign(addresses[1]).execute(&mut keystore).await?;
+    let CommandOutput::MultiSigCombineProposal(output) = combine().execute(&mut keystore).await?
+    else {
+        panic!("Expected MultiSigCombineProposal output");
+    };
+    assert_eq!(output.multisig_address, multisig_address);
+
+    let multisig = GenericSignature::decode_base64(&output.multisig_serialized).unwrap();
+    assert_eq!(SuiAddress::try_from(&multisig)?, multisig_address);
+    Ok(())
+}
@@ -6,7 +6,9 @@ use std::path::PathBuf;
 use std::str::FromStr;
 use std::sync::Arc;
 
-use crate::upgrade_compatibility::{compare_packages, missing_module_diag, FormattedField};
+use crate::upgrade_compatibility::{
+    compare_packages, missing_module_diag, package_incompatibilities, FormattedField,
+};
 
 use move_binary_format::normalized::{Field, Type};
 use move_binary_format::CompiledModule;
@@ -267,6 +269,69 @@ fn test_address_change() {
     assert!(result.is_ok());
 }
 
+#[test]
+fn test_incompatibilities() {
+    let (pkg_v1, pkg_v2, _) = get_packages("declaration_errors");
+    let mut incompatibilities = package_incompatibilities(
+        AccountAddress::ZERO,
+        &pkg_v1,
+        &pkg_v2,
+        UpgradePolicy::Compatible,
+    );
+    incompatibilities.sort();
+    let summary: Vec<_> = incompatibilities
+        .iter()
+        .map(|i| {
+            (
+                i.module.as_str(),
+                i.name.as_deref(),
+                i.kind,
+                i.required_policy,
+            )
+        })
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            ("enum_", Some("EnumToBeRemoved"), "enum-missing", None),
+            (
+                "func_",
+                Some("fun_to_be_removed"),
+                "function-missing-public",
+                None
+            ),
+            (
+                "func_",
+                Some("fun_to_lose_public"),
+                "function-lost-public-visibility",
+                None
+            ),
+            ("missing_module", None, "module-missing", None),
+            ("struct_", Some("StructToBeRemoved"), "struct-missing", None),
+        ]
+    );
+
+    // Changes to private functions are allowed by the 'compatible' policy.
+    let (pkg_v1, pkg_v2, _) = get_packages("additive_errors");
+    let incompatibilities = package_incompatibilities(
+        AccountAddress::ZERO,
+        &pkg_v1,
+        &pkg_v2,
+        UpgradePolicy::Additive,
+    );
+    let required_policy = |name: &str| {
+        incompatibilities
+            .iter()
+            .find(|i| i.name.as_deref() == Some(name))
+            .unwrap()
+            .required_policy
+    };
+    assert_eq!(required_policy("function_to_remove"), Some("compatible"));
+    assert_eq!(required_policy("function_to_change"), Some("compatible"));
+    assert_eq!(required_policy("StructToRemove"), None);
+    assert_eq!(required_policy("StructToChange"), None);
+}
+
 #[test]
 fn positional_formatting() {
     let name = Identifier::new("pos999").unwrap();
@@ -42,6 +42,7 @@ use move_core_types::{
 };
 use move_ir_types::location::{ByteIndex, Loc};
 use move_package::compilation::compiled_package::CompiledUnitWithSource;
+use serde::Serialize;
 use sui_json_rpc_types::{SuiObjectDataOptions, SuiRawData};
 use sui_move_build::CompiledPackage;
 use sui_protocol_config::ProtocolConfig;
@@ -255,6 +256,155 @@ fn breaks_inclusion_check(
     }
 }
 
+impl UpgradeCompatibilityModeError {
+    /// A stable name for the kind of incompatibility, used in machine-readable reports.
+    fn kind(&self) -> &'static str {
+        match self {
+            UpgradeCompatibilityModeError::StructMissing { .. } => "struct-missing",
+            UpgradeCompatibilityModeError::StructAbilityMismatch { .. } => {
+                "struct-ability-mismatch"
+            }
+            UpgradeCompatibilityModeError::StructTypeParamMismatch { .. } => {
+                "struct-type-param-mismatch"
+            }
+            UpgradeCompatibilityModeError::StructFieldMismatch { .. } => "struct-field-mismatch",
+            UpgradeCompatibilityModeError::EnumMissing { .. } => "enum-missing",
+            UpgradeCompatibilityModeError::EnumAbilityMismatch { .. } => "enum-ability-mismatch",
+            UpgradeCompatibilityModeError::EnumTypeParamMismatch { .. } => {
+                "enum-type-param-mismatch"
+            }
+            UpgradeCompatibilityModeError::EnumNewVariant { .. } => "enum-new-variant",
+            UpgradeCompatibilityModeError::EnumVariantMissing { .. } => "enum-variant-missing",
+            UpgradeCompatibilityModeError::EnumVariantMismatch { .. } => "enum-variant-mismatch",
+            UpgradeCompatibilityModeError::FunctionMissingPublic { .. } => {
+                "function-missing-public"
+            }
+            UpgradeCompatibilityModeError::FunctionMissingEntry { .. } => "function-missing-entry",
+            UpgradeCompatibilityModeError::FunctionSignatureMismatch { .. } => {
+                "function-signature-mismatch"
+            }
+            UpgradeCompatibilityModeError::FunctionLostPublicVisibility { .. } => {
+                "function-lost-public-visibility"
+            }
+            UpgradeCompatibilityModeError::FunctionEntryCompatibility { .. } => {
+                "function-entry-mismatch"
+            }
+            UpgradeCompatibilityModeError::FileFormatVersionDowngrade { .. } => {
+                "file-format-version-downgrade"
+            }
+            UpgradeCompatibilityModeError::StructNew { .. } => "struct-new",
+            UpgradeCompatibilityModeError::StructChange { .. } => "struct-change",
+            UpgradeCompatibilityModeError::EnumNew { .. } => "enum-new",
+            UpgradeCompatibilityModeError::EnumChange { .. } => "enum-change",
+            UpgradeCompatibilityModeError::FunctionNew { .. } => "function-new",
+            UpgradeCompatibilityModeError::FunctionChange { .. } => "function-change",
+            UpgradeCompatibilityModeError::FunctionMissing { .. } => "function-missing",
+            UpgradeCompatibilityModeError::FriendNew => "friend-new",
+            UpgradeCompatibilityModeError::FriendMissing => "friend-missing",
+        }
+    }
+
+    /// The kind and name of the declaration affected, or `module` and no name for changes to the
+    /// module as a whole.
+    fn declaration(&self) -> (&'static str, Option<&Identifier>) {
+        match self {
+            UpgradeCompatibilityModeError::StructMissing { name }
+            | UpgradeCompatibilityModeError::StructAbilityMismatch { name, .. }
+            | UpgradeCompatibilityModeError::StructTypeParamMismatch { name, .. }
+            | UpgradeCompatibilityModeError::StructFieldMismatch { name, .. }
+            | UpgradeCompatibilityModeError::StructNew { name }
+            | UpgradeCompatibilityModeError::StructChange { name, .. } => ("struct", Some(name)),
+
+            UpgradeCompatibilityModeError::EnumMissing { name }
+            | UpgradeCompatibilityModeError::EnumAbilityMismatch { name, .. }
+            | UpgradeCompatibilityModeError::EnumTypeParamMismatch { name, .. }
+            | UpgradeCompatibilityModeError::EnumNewVariant { name, .. }
+            | UpgradeCompatibilityModeError::EnumVariantMissing { name, .. }
+            | UpgradeCompatibilityModeError::EnumVariantMismatch { name, .. }
+            | UpgradeCompatibilityModeError::EnumNew { name }
+            | UpgradeCompatibilityModeError::EnumChange { name, .. } => ("enum", Some(name)),
+
+            UpgradeCompatibilityModeError::FunctionMissingPublic { name }
+            | UpgradeCompatibilityModeError::FunctionMissingEntry { name }
+            | UpgradeCompatibilityModeError::FunctionSignatureMismatch { name, .. }
+            | UpgradeCompatibilityModeError::FunctionLostPublicVisibility { name }
+            | UpgradeCompatibilityModeError::FunctionEntryCompatibility { name, .. }
+            | UpgradeCompatibilityModeError::FunctionNew { name }
+            | UpgradeCompatibilityModeError::FunctionChange { name, .. }
+            | UpgradeCompatibilityModeError::FunctionMissing { name } => ("function", Some(name)),
+
+            UpgradeCompatibilityModeError::FileFormatVersionDowngrade { .. }
+            | UpgradeCompatibilityModeError::FriendNew
+            | UpgradeCompatibilityModeError::FriendMissing => ("module", None),
+        }
+    }
+}
+
+/// A machine-readable report of an upgrade compatibility check, see [`compatibility_report`].
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CompatibilityReport {
+    /// The on-chain package the new package was compared with.
+    pub package_id: ObjectID,
+    /// The upgrade policy checked against.
+    pub policy: &'static str,
+    /// Whether the upgrade is allowed by the policy.
+    pub compatible: bool,
+    pub incompatibilities: Vec<Incompatibility>,
+}
+
+/// A change the upgrade policy does not allow.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "camelCase")]
+pub struct Incompatibility {
+    pub module: String,
+    /// `struct`, `enum`, `function`, or `module` for changes to the module as a whole.
+    pub declaration: &'static str,
+    /// The name of the declaration, unless it is the module.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// What changed, e.g. `struct-ability-mismatch`.
+    pub kind: &'static str,
+    /// The most restrictive policy allowing the change, or `None` if no policy allows it.
+    pub required_policy: Option<&'static str>,
+}
+
+impl Incompatibility {
+    fn new(
+        module: &Identifier,
+        error: &UpgradeCompatibilityModeError,
+        required_policy: Option<UpgradePolicy>,
+    ) -> Self {
+        let (declaration, name) = error.declaration();
+        Self {
+            module: module.to_string(),
+            declaration,
+            name: name.map(|name| name.to_string()),
+            kind: error.kind(),
+            required_policy: required_policy.map(policy_name),
+        }
+    }
+
+    fn missing_module(module: &Identifier) -> Self {
+        Self {
+            module: module.to_string(),
+            declaration: "module",
+            name: None,
+            kind: "module-missing",
+            required_policy: None,
+        }
+    }
+}
+
+/// The name of an upgrade policy in machine-readable reports.
+pub(crate) fn policy_name(policy: UpgradePolicy) -> &'static str {
+    match policy {
+        UpgradePolicy::Compatible => "compatible",
+        UpgradePolicy::Additive => "additive",
+        UpgradePolicy::DepOnly => "dep-only",
+    }
+}
+
 /// Compatibility mode used for 'compatible' upgrades, collects all errors and returns them as a single error.
 #[derive(Default)]
 pub(crate) struct CliCompatibilityMode {
@@ -689,6 +839,52 @@ pub(crate) async fn check_compatibility(
     upgrade_policy: u8,
     protocol_config: ProtocolConfig,
 ) -> Result<(), Error> {
+    let (original_id, existing_modules) =
+        fetch_existing_package(read_api, package_id, &protocol_config).await?;
+
+    let policy =
+        UpgradePolicy::try_from(upgrade_policy).map_err(|_| anyhow!("Invalid upgrade policy"))?;
+
+    compare_packages(
+        original_id,
+        existing_modules,
+        new_package,
+        package_path,
+        policy,
+    )
+}
+
+/// Like [`check_compatibility`], but lists every incompatibility in a report instead of rendering
+/// them as diagnostics.
+pub(crate) async fn compatibility_report(
+    read_api: &ReadApi,
+    package_id: ObjectID,
+    new_package: &CompiledPackage,
+    upgrade_policy: u8,
+    protocol_config: ProtocolConfig,
+) -> Result<CompatibilityReport, Error> {
+    let (original_id, existing_modules) =
+        fetch_existing_package(read_api, package_id, &protocol_config).await?;
+
+    let policy =
+        UpgradePolicy::try_from(upgrade_policy).map_err(|_| anyhow!("Invalid upgrade policy"))?;
+
+    let incompatibilities =
+        package_incompatibilities(original_id, &existing_modules, new_package, policy);
+    Ok(CompatibilityReport {
+        package_id,
+        policy: policy_name(policy),
+        compatible: incompatibilities.is_empty(),
+        incompatibilities,
+    })
+}
+
+/// Fetch the modules of an on-chain package, along with its original ID.
+async fn fetch_existing_package(
+    read_api: &ReadApi,
+    package_id: ObjectID,
+    protocol_config: &ProtocolConfig,
+) -> Result<(AccountAddress, Vec<CompiledModule>), Error> {
     let existing_obj_read = read_api
         .get_object_with_options(package_id, SuiObjectDataOptions::new().with_bcs())
         .await
@@ -708,22 +904,79 @@ pub(crate) async fn check_compatibility(
     let existing_modules = existing_package
         .module_map
         .iter()
-        .map(|m| CompiledModule::deserialize_with_config(m.1, &to_binary_config(&protocol_config)))
+        .map(|m| CompiledModule::deserialize_with_config(m.1, &to_binary_config(protocol_config)))
         .collect::<Result<Vec<_>, _>>()
         .context("Unable to get existing package")?;
 
-    let policy =
-        UpgradePolicy::try_from(upgrade_policy).map_err(|_| anyhow!("Invalid upgrade policy"))?;
+    let original_id = *existing_package
+        .to_move_package(u64::MAX /* safe as this pkg comes from the network */)?
+        .original_package_id();
 
-    compare_packages(
-        *existing_package
-            .to_move_package(u64::MAX /* safe as this pkg comes from the network */)?
-            .original_package_id(),
-        existing_modules,
-        new_package,
-        package_path,
-        policy,
-    )
+    Ok((original_id, existing_modules))
+}
+
+/// Map the modules of the new package by name, with the on-chain address in place of the zero
+/// address of unpublished modules.
+fn new_modules_by_name(
+    package_id: AccountAddress,
+    new_package: &CompiledPackage,
+) -> HashMap<Identifier, CompiledModule> {
+    new_package
+        .get_modules()
+        .map(|m| {
+            let mut new_module = m.clone();
+            let new_module_address_idx = new_module.self_handle().address;
+            let addrs = &mut new_module.address_identifiers;
+            if let Some(address_mut) = addrs.get_mut(new_module_address_idx.0 as usize) {
+                if *address_mut == AccountAddress::ZERO {
+                    // if the new module address is zero, set it to the on-chain address
+                    *address_mut = package_id;
+                }
+            }
+            (new_module.self_id().name().to_owned(), new_module)
+        })
+        .collect()
+}
+
+/// List the changes between the existing and new modules that the policy does not allow.
+fn package_incompatibilities(
+    package_id: AccountAddress,
+    existing_modules: &[CompiledModule],
+    new_package: &CompiledPackage,
+    policy: UpgradePolicy,
+) -> Vec<Incompatibility> {
+    let new_modules_map = new_modules_by_name(package_id, new_package);
+
+    let mut incompatibilities = vec![];
+    for existing_module in existing_modules {
+        let name = existing_module.self_id().name().to_owned();
+        let Some(new_module) = new_modules_map.get(&name) else {
+            incompatibilities.push(Incompatibility::missing_module(&name));
+            continue;
+        };
+
+        // A change is allowed by the most restrictive policy whose check reports no error for
+        // the same declaration.
+        let errors_by_policy = [
+            UpgradePolicy::DepOnly,
+            UpgradePolicy::Additive,
+            UpgradePolicy::Compatible,
+        ]
+        .map(|p| (p, module_errors(existing_module, new_module, p)));
+
+        for error in module_errors(existing_module, new_module, policy) {
+            let required_policy = errors_by_policy
+                .iter()
+                .find(|(_, errors)| {
+                    !errors
+                        .iter()
+                        .any(|other| other.declaration() == error.declaration())
+                })
+                .map(|(p, _)| *p);
+            incompatibilities.push(Incompatibility::new(&name, &error, required_policy));
+        }
+    }
+    incompatibilities
 }
 
 /// Collect all the errors into a single error message.
@@ -735,10 +988,7 @@ fn compare_packages(
     policy: UpgradePolicy,
 ) -> Result<(), Error> {
     // create a map from the new modules
-    let mut new_modules_map: HashMap<Identifier, CompiledModule> = new_package
-        .get_modules()
-        .map(|m| (m.self_id().name().to_owned(), m.clone()))
-        .collect();
+    let new_modules_map = new_modules_by_name(package_id, &new_package);
 
     let lookup: HashMap<Identifier, IdentifierTableLookup> = new_modules_map
         .values()
@@ -764,17 +1014,8 @@ fn compare_packages(
 
     for existing_module in existing_modules {
         let name = existing_module.self_id().name().to_owned();
-        match new_modules_map.get_mut(&name) {
+        match new_modules_map.get(&name) {
             Some(new_module) => {
-                let new_module_address_idx = new_module.self_handle().address;
-                let addrs = &mut new_module.address_identifiers;
-                if let Some(address_mut) = addrs.get_mut(new_module_address_idx.0 as usize) {
-                    if *address_mut == AccountAddress::ZERO {
-                        // if the new module address is zero, set it to the on-chain address
-                        *address_mut = package_id;
-                    }
-                }
-
                 let compiled_unit_with_source = new_package
                     .package
                     .get_module_by_name_from_root(name.as_str())
@@ -828,8 +1069,36 @@ fn modules_into_diags(
     lookup: &IdentifierTableLookup,
     policy: UpgradePolicy,
 ) -> Result<Diagnostics, Error> {
+    let diags_list = module_errors(existing_module, new_module, policy)
+        .iter()
+        .map(|e| {
+            compatibility_diag_from_error(
+                e,
+                compiled_unit_with_source,
+                matches!(policy, UpgradePolicy::Compatible),
+                lookup,
+            )
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut diags = Diagnostics::new();
+
+    for diag in diags_list {
+        diags.extend(diag);
+    }
+
+    Ok(diags)
+}
+
+/// Runs the compatibility check between two modules, returning the changes the policy does not
+/// allow.
+fn module_errors(
+    existing_module: &CompiledModule,
+    new_module: &CompiledModule,
+    policy: UpgradePolicy,
+) -> Vec<UpgradeCompatibilityModeError> {
     let pool = &mut normalized::RcPool::new();
-    let diags_list = match policy {
+    match policy {
         UpgradePolicy::DepOnly => InclusionCheck::Equal.check_with_mode::<CliInclusionCheckMode>(
             &Module::new(pool, existing_module, /* include code */ true),
             &Module::new(pool, new_module, /* include code */ true),
@@ -845,24 +1114,6 @@ fn modules_into_diags(
     }
     .err()
     .unwrap_or_default()
-    .into_iter()
-    .map(|e| {
-        compatibility_diag_from_error(
-            &e,
-            compiled_unit_with_source,
-            matches!(policy, UpgradePolicy::Compatible),
-            lookup,
-        )
-    })
-    .collect::<Result<Vec<_>, _>>()?;
-
-    let mut diags = Diagnostics::new();
-
-    for diag in diags_list {
-        diags.extend(diag);
-    }
-
-    Ok(diags)
 }
 
 /// Convert an error to a vector of diagnostics using the error specific function.
@@ -17,6 +17,7 @@ use move_package::{lock_file::schema::ManagedPackage, BuildConfig as MoveBuildCo
 use serde_json::json;
 use sui::client_commands::{GasDataArgs, PaymentArgs, TxProcessingArgs};
 use sui::client_ptb::ptb::PTB;
+use sui::client_watch::{WatchChange, Watcher};
 use sui::sui_commands::IndexerArgs;
 use sui_keys::key_identity::KeyIdentity;
 use sui_sdk::SuiClient;
@@ -3439,6 +3440,91 @@ async fn test_execute_signed_tx() -> Result<(), anyhow::Error> {
     Ok(())
 }
 
+#[sim_test]
+async fn test_offline_snapshot() -> Result<(), anyhow::Error> {
+    let mut test_cluster = TestClusterBuilder::new().build().await;
+    let rgp = test_cluster.get_reference_gas_price().await;
+    let address = test_cluster.get_address_0();
+    let address1 = test_cluster.get_address_1();
+    let context = &mut test_cluster.wallet;
+    let temp_dir = tempfile::tempdir()?;
+    let snapshot = temp_dir.path().join("objects.json");
+
+    SuiClientCommands::ExportObjects {
+        addresses: vec![KeyIdentity::Address(address)],
+        objects: vec![],
+        output: snapshot.clone(),
+    }
+    .execute(context)
+    .await?;
+    assert!(!temp_dir.path().join("objects.tmp").exists());
+
+    let coins = context
+        .get_all_gas_objects_owned_by_address(address)
+        .await?;
+    let offline = TxProcessingArgs {
+        serialize_unsigned_transaction: true,
+        offline_snapshot: Some(snapshot.clone()),
+        ..Default::default()
+    };
+
+    let transfer = SuiClientCommands::TransferSui {
+        to: KeyIdentity::Address(address1),
+        sui_coin_object_id: coins[0].0,
+        amount: Some(1),
+        gas_data: GasDataArgs {
+            gas_budget: Some(rgp * TEST_ONLY_GAS_UNIT_FOR_TRANSFER),
+            ..Default::default()
+        },
+        processing: offline.clone(),
+    }
+    .execute(context)
+    .await?;
+    let SuiClientCommandResult::SerializedUnsignedTransaction(tx_data) = transfer else {
+        panic!("Expected SerializedUnsignedTransaction result");
+    };
+    assert_eq!(tx_data.sender(), address);
+    assert_eq!(tx_data.gas(), &[coins[0]]);
+    assert_eq!(tx_data.gas_price(), rgp);
+
+    // The gas budget cannot be estimated without the network.
+    let no_budget = SuiClientCommands::TransferSui {
+        to: KeyIdentity::Address(address1),
+        sui_coin_object_id: coins[0].0,
+        amount: Some(1),
+        gas_data: GasDataArgs::default(),
+        processing: offline.clone(),
+    }
+    .execute(context)
+    .await;
+    assert!(no_budget.is_err());
+
+    // Objects of other addresses are not in the snapshot.
+    let other_coin = context
+        .get_all_gas_objects_owned_by_address(address1)
+        .await?[0];
+    let missing = SuiClientCommands::TransferSui {
+        to: KeyIdentity::Address(address),
+        sui_coin_object_id: other_coin.0,
+        amount: Some(1),
+        gas_data: GasDataArgs {
+            gas_budget: Some(rgp * TEST_ONLY_GAS_UNIT_FOR_TRANSFER),
+            ..Default::default()
+        },
+        processing: offline,
+    }
+    .execute(context)
+    .await;
+    assert!(missing.is_err());
+
+    // The transaction built offline is valid while its objects are unchanged.
+    let response = context
+        .execute_transaction_must_succeed(context.sign_transaction(&tx_data))
+        .await;
+    assert!(response.status_ok().unwrap());
+    Ok(())
+}
+
 #[sim_test]
 async fn test_serialize_tx() -> Result<(), anyhow::Error> {
     let mut test_cluster = TestClusterBuilder::new().build().await;
@@ -3538,6 +3624,73 @@ async fn test_serialize_tx() -> Result<(), anyhow::Error> {
     Ok(())
 }
 
+#[sim_test]
+async fn test_ptb_simulate() -> Result<(), anyhow::Error> {
+    let mut test_cluster = TestClusterBuilder::new().build().await;
+    let address = test_cluster.get_address_0();
+    let address1 = test_cluster.get_address_1();
+    let context = &mut test_cluster.wallet;
+    let coins = context
+        .get_all_gas_objects_owned_by_address(address)
+        .await?;
+
+    let temp_dir = tempfile::tempdir()?;
+    let overrides = temp_dir.path().join("overrides.json");
+    fs::write(
+        &overrides,
+        json!({ "gasBalance": 1_000_000_000u64, "clockTimestampMs": 1_000 }).to_string(),
+    )?;
+    let invalid = temp_dir.path().join("invalid.json");
+    fs::write(&invalid, json!({ "gasBudget": 1 }).to_string())?;
+
+    let ptb_args = vec![
+        "--split-coins".to_string(),
+        "gas".to_string(),
+        "[1000]".to_string(),
+        "--assign".to_string(),
+        "new_coin".to_string(),
+        "--transfer-objects".to_string(),
+        "[new_coin]".to_string(),
+        format!("@{}", address1),
+    ];
+    let with = |extra: &[&str]| {
+        let mut args = ptb_args.clone();
+        args.extend(extra.iter().map(|arg| arg.to_string()));
+        PTB { args }
+    };
+    let overrides = overrides.to_str().unwrap();
+    let invalid = invalid.to_str().unwrap();
+
+    SuiClientCommands::PTB(with(&["--simulate"]))
+        .execute(context)
+        .await?;
+    SuiClientCommands::PTB(with(&["--simulate", "--overrides", overrides]))
+        .execute(context)
+        .await?;
+
+    // Nothing was executed.
+    assert_eq!(
+        context
+            .get_all_gas_objects_owned_by_address(address)
+            .await?,
+        coins
+    );
+
+    // Overrides are only accepted with --simulate, and must be valid.
+    assert!(SuiClientCommands::PTB(with(&["--overrides", overrides]))
+        .execute(context)
+        .await
+        .is_err());
+    assert!(
+        SuiClientCommands::PTB(with(&["--simulate", "--overrides", invalid]))
+            .execute(context)
+            .await
+            .is_err()
+    );
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_stake_with_none_amount() -> Result<(), anyhow::Error> {
     let mut test_cluster = TestClusterBuilder::new().build().await;
@@ -4550,6 +4703,105 @@ async fn test_transfer_sponsored() -> Result<(), anyhow::Error> {
     Ok(())
 }
 
+#[sim_test]
+async fn test_sign_and_combine_sponsored_tx() -> Result<(), anyhow::Error> {
+    // Like `test_transfer_sponsored`, but the sender and the sponsor sign separately.
+    let (mut cluster, _, rgp, o, _, _) = test_cluster_helper().await;
+    let a0 = cluster.get_address_0();
+    let a1 = cluster.get_address_1();
+    let context = &mut cluster.wallet;
+
+    SuiClientCommands::TransferSui {
+        to: KeyIdentity::Address(a1),
+        sui_coin_object_id: o[1],
+        amount: None,
+        gas_data: GasDataArgs {
+            gas_budget: Some(rgp * TEST_ONLY_GAS_UNIT_FOR_TRANSFER),
+            ..Default::default()
+        },
+        processing: TxProcessingArgs::default(),
+    }
+    .execute(context)
+    .await?;
+
+    // A1 sends O1 back to A0, sponsored by A0.
+    let transfer_back = SuiClientCommands::Transfer {
+        to: KeyIdentity::Address(a0),
+        object_id: o[1],
+        payment: PaymentArgs::default(),
+        gas_data: GasDataArgs {
+            gas_budget: Some(rgp * TEST_ONLY_GAS_UNIT_FOR_TRANSFER),
+            gas_sponsor: Some(a0),
+            ..Default::default()
+        },
+        processing: TxProcessingArgs {
+            serialize_unsigned_transaction: true,
+            ..Default::default()
+        },
+    }
+    .execute(context)
+    .await?;
+
+    let SuiClientCommandResult::SerializedUnsignedTransaction(tx_data) = transfer_back else {
+        panic!("Expected SerializedUnsignedTransaction result");
+    };
+    let tx_bytes = Base64::encode(bcs::to_bytes(&tx_data)?);
+
+    let mut signatures = vec![];
+    for address in [a1, a0] {
+        let SuiClientCommandResult::TxSignatures(mut signed) = SuiClientCommands::SignTx {
+            tx_bytes: tx_bytes.clone(),
+            address: Some(KeyIdentity::Address(address)),
+        }
+        .execute(context)
+        .await?
+        else {
+            panic!("Expected TxSignatures result");
+        };
+        assert_eq!(signed.len(), 1);
+        signatures.push(signed.remove(0).signature);
+    }
+
+    // Both signatures are needed.
+    let combined = SuiClientCommands::CombineSignedTx {
+        tx_bytes: tx_bytes.clone(),
+        signatures: signatures[..1].to_vec(),
+    }
+    .execute(context)
+    .await;
+    assert!(combined.is_err());
+
+    let SuiClientCommandResult::SerializedSignedTransaction(signed_data) =
+        SuiClientCommands::CombineSignedTx {
+            tx_bytes,
+            signatures,
+        }
+        .execute(context)
+        .await?
+    else {
+        panic!("Expected SerializedSignedTransaction result");
+    };
+
+    let SuiClientCommandResult::TransactionBlock(response) =
+        SuiClientCommands::ExecuteCombinedSignedTx {
+            signed_tx_bytes: Base64::encode(bcs::to_bytes(&signed_data)?),
+        }
+        .execute(context)
+        .await?
+    else {
+        panic!("Expected TransactionBlock result");
+    };
+
+    let Some(tx) = &response.transaction else {
+        panic!("TransactionBlock response should contain a transaction");
+    };
+    assert_eq!(response.status_ok(), Some(true));
+    assert_eq!(tx.data.gas_data().owner, a0);
+    assert_eq!(tx.data.sender(), &a1);
+
+    Ok(())
+}
+
 #[sim_test]
 async fn test_transfer_serialized_data() -> Result<(), anyhow::Error> {
     // Like `test_transfer` but the transaction is pre-generated and serialized into a
@@ -5404,3 +5656,114 @@ async fn test_tree_shaking_package_system_deps() -> Result<(), anyhow::Error> {
 
     Ok(())
 }
+
+#[sim_test]
+async fn test_watch() -> Result<(), anyhow::Error> {
+    let (mut cluster, client, rgp, o, _, _) = test_cluster_helper().await;
+    let a1 = cluster.get_address_1();
+    let context = &mut cluster.wallet;
+
+    let mut watcher = Watcher::new(client, vec![o[1]], vec![a1], vec![]).await?;
+
+    // Only the current version of the object is reported at first.
+    let mut updates = vec![];
+    watcher.poll(&mut updates).await?;
+    assert_eq!(updates.len(), 1);
+    let WatchChange::Object { object } = &updates[0].change else {
+        panic!("Expected an object update, got {:?}", updates[0]);
+    };
+    assert_eq!(object.object_id, o[1]);
+    let version = object.version;
+
+    let response = SuiClientCommands::TransferSui {
+        to: KeyIdentity::Address(a1),
+        sui_coin_object_id: o[1],
+        amount: None,
+        gas_data: GasDataArgs {
+            gas_budget: Some(rgp * TEST_ONLY_GAS_UNIT_FOR_TRANSFER),
+            ..Default::default()
+        },
+        processing: TxProcessingArgs::default(),
+    }
+    .execute(context)
+    .await?;
+    let digest = response.tx_block_response().unwrap().digest;
+
+    let mut updates = vec![];
+    for _ in 0..50 {
+        watcher.poll(&mut updates).await?;
+        if updates.len() >= 2 {
+            break;
+        }
+        sleep(Duration::from_millis(200)).await;
+    }
+
+    assert_eq!(updates.len(), 2, "Unexpected updates: {updates:?}");
+    assert!(updates.iter().any(|update| matches!(
+        &update.change,
+        WatchChange::Object { object }
+            if object.version > version && object.owner == Some(Owner::AddressOwner(a1))
+    )));
+    assert!(updates.iter().any(|update| matches!(
+        &update.change,
+        WatchChange::Transaction { address, digest: d, .. } if *address == a1 && *d == digest
+    )));
+
+    Ok(())
+}
+
+#[sim_test]
+async fn test_watch_every_object_version() -> Result<(), anyhow::Error> {
+    let (mut cluster, client, rgp, o, _, _) = test_cluster_helper().await;
+    let a1 = cluster.get_address_1();
+    let context = &mut cluster.wallet;
+
+    let mut watcher = Watcher::new(client, vec![o[1]], vec![], vec![]).await?;
+    let mut updates = vec![];
+    watcher.poll(&mut updates).await?;
+    assert_eq!(updates.len(), 1);
+
+    // Both versions are reported, even if the object changes twice between two polls.
+    let mut versions = vec![];
+    for _ in 0..2 {
+        let response = SuiClientCommands::TransferSui {
+            to: KeyIdentity::Address(a1),
+            sui_coin_object_id: o[1],
+            amount: Some(1),
+            gas_data: GasDataArgs {
+                gas_budget: Some(rgp * TEST_ONLY_GAS_UNIT_FOR_TRANSFER),
+                ..Default::default()
+            },
+            processing: TxProcessingArgs::default(),
+        }
+        .execute(context)
+        .await?;
+        let effects = response
+            .tx_block_response()
+            .unwrap()
+            .effects
+            .as_ref()
+            .unwrap();
+        versions.push(effects.gas_object().reference.version);
+    }
+
+    let mut updates = vec![];
+    for _ in 0..50 {
+        watcher.poll(&mut updates).await?;
+        if updates.len() >= 2 {
+            break;
+        }
+        sleep(Duration::from_millis(200)).await;
+    }
+
+    let reported: Vec<_> = updates
+        .iter()
+        .map(|update| match &update.change {
+            WatchChange::Object { object } => object.version,
+            change => panic!("Expected an object update, got {change:?}"),
+        })
+        .collect();
+    assert_eq!(reported, versions);
+
+    Ok(())
+}